@@ -9,6 +9,22 @@ const chartConfig = {
 // promise which resolves
 let chartPromise = Graphima.createMain(params, chartConfig);
 
+// the promise rejects with "failed to get canvas 2d context" where canvases
+// are unavailable, leaving the container as it was; a chart whose canvas
+// loses its context later (e.g. a GPU reset) pauses and redraws itself once
+// the browser restores it
+
+// params without data sets (or with empty ones only, which wait for
+// appendData) render a "no data" placeholder, see strings.noData of the config; it has no
+// axes, preview or legend and ignores pointer and keyboard input
+
+// event listeners and callbacks may call the API synchronously; calls from
+// renderTooltip and describeChart, which run while the chart draws, are
+// applied right after the frame: their promises resolve at once and errors
+// come as DEFERRED_CALL_FAILED warnings of the chart (see getWarnings),
+// while createMain, createFromDefinition, exportDefinition, getLayout,
+// dataToPixel and pixelToData reject there
+
 // destroy chart
 chartPromise.then(function () {
   Graphima.destroyMain(chartId); // promise
@@ -17,3 +33,244 @@ chartPromise.then(function () {
 // OPTIONAL: if you want to run all the initialization code before createMain
 // to minimize latency of the first call
 Graphima.init(); // promise
+
+// build params from CSV text: the header row holds data set names, the first
+// column is x (numbers, dates or datetimes), an empty cell is a gap (null) in
+// its data set and a column with no values is rejected
+Graphima.paramsFromCsv(csvText, {
+  delimiter: ",", // optional, defaults to ","
+  decimalComma: false, // optional, parse "1,5" as 1.5
+  columns: ["Foo", "Bar"], // optional, data sets to include
+}).then(function (params) {
+  params.selector = "#chart-1";
+  return Graphima.createMain(params, chartConfig);
+});
+
+// charts dispatch bubbling DOM events on the wrapper element (its selector is
+// what createMain resolves to); event.detail always contains chartId:
+//  * "graphima:ready" - the first frame is drawn; a container with zero size
+//    (e.g. a hidden tab) defers it until the container gets a size
+//  * "graphima:rangechange" - the zoom window changed; detail: {from, to}
+//  * "graphima:hover" - the hovered point changed; detail: {x, series, nearest},
+//    where series is a list of {name, value}; x is null (and there is no
+//    series) when the pointer leaves; with hoverSettleMs, only once the
+//    point stays hovered that long
+//  * "graphima:pointclick" - a point was clicked, within 12px of it (scaled by
+//    hitScale); detail is the same as hover
+//  * "graphima:followchange" - the window stopped or resumed following
+//    appended data (see follow of the config); detail: {following}
+document.addEventListener("graphima:rangechange", function (event) {
+  console.log(event.detail.chartId, event.detail.from, event.detail.to);
+});
+// events are delivered once per frame, at most one range change and one
+// hover per chart (the latest), and also go to the callbacks of the config
+// (onRangeChange, onHover, ...); eventThrottleMs spaces deliveries further
+Graphima.createMain(params, {
+  ...chartConfig,
+  domEvents: false,
+  eventThrottleMs: { rangechange: 250 },
+  onRangeChange: function (detail) {
+    console.log(detail.chartId, detail.from, detail.to);
+  },
+});
+
+// a second createMain (or createFromDefinition) on the same container
+// rejects with {code: "CONTAINER_ALREADY_USED", message, path: null, chartId}
+// unless containerReuse of the config is "replace", which destroys the
+// existing chart first; other errors are strings
+Graphima.createMain(params, chartConfig).catch(function (error) {
+  if (error.code === "CONTAINER_ALREADY_USED") {
+    var existingChartId = error.chartId;
+  }
+});
+
+// non-fatal issues (a clamped initialRange, dropped non-finite points, a
+// container which stays without size, data sets of a restored definition
+// which no longer exist, callbacks which threw) are kept as warnings of the
+// chart, the latest 100, until getWarnings takes them: [{code, message,
+// path}] (path is e.g. "dataSets[1]" or null); a CALLBACK_FAILED warning has
+// the key of the callback as its path (e.g. "onHover") and what it threw as
+// error
+chartPromise.then(function (chartId) {
+  // warnings since creation or the previous call
+  Graphima.getWarnings(chartId); // promise, [{code, message, path}]
+});
+// with returnWarnings of the config createMain and createFromDefinition
+// resolve to {chartId, warnings} instead of the chart id, warnings being the
+// ones of the creation. Callers which read the chart id switch to
+// result.chartId when opting in
+Graphima.createMain(params, { ...chartConfig, returnWarnings: true }).then(
+  function (result) {
+    for (const warning of result.warnings) {
+      console.log(warning.code, warning.message, warning.path);
+    }
+  },
+);
+
+// a chart whose container is removed from the document without destroyMain
+// (e.g. by a framework) is destroyed on the next frame and reported to
+// onAutoDestroy of the config; moving the container keeps the chart. As the
+// chart is gone by then, a CALLBACK_FAILED warning of onAutoDestroy is
+// logged to the console
+Graphima.createMain(params, {
+  ...chartConfig,
+  onAutoDestroy: function (chartId) {
+    console.log("chart destroyed with its container", chartId);
+  },
+});
+
+// html tooltip content: renderTooltip receives
+// {x, formattedX, series: [{name, value, formattedValue, color, nearest}],
+// more} (more is the "+N more" line of tooltipMaxEntries or null) and returns either an html string or a DOM Node; graphima positions the
+// element, keeps it inside the chart and removes it on destroyMain
+Graphima.createMain(params, {
+  ...chartConfig,
+  tooltipMode: "html",
+  renderTooltip: function (payload) {
+    const node = document.createElement("div");
+    node.textContent = payload.formattedX;
+    return node;
+  },
+});
+
+// show or hide a data set by name; the legend reflects the change and the
+// chart dispatches "graphima:visibilitychange" with detail.visible being
+// {name: bool}. Hiding the last visible data set is rejected.
+Graphima.setDataSetVisible(chartId, "Foo", false); // promise
+
+// show only the data set, or for the isolated one restore the visibility it
+// had before; the same as isolating it from the legend
+Graphima.isolateDataSet(chartId, "Foo"); // promise
+
+// change the paint order of a data set, see dataSets[].zIndex of the params;
+// only repaints
+Graphima.setDataSetZIndex(chartId, "Foo", 2); // promise
+
+// change which interactions the chart reacts to, see config.interactions;
+// gestures in progress are dropped
+Graphima.setInteractions(chartId, { zoom: false, pan: false }); // promise
+
+// replace the point markers of a data set, see dataSets[].markers of the
+// params; the data stays as is, [] removes them
+Graphima.setPointMarkers(chartId, "Foo", [{ coord: "2020-01-02" }]); // promise
+
+// dim the chart and show a spinner with an optional message while new data
+// loads (a still one with prefers-reduced-motion); hover is off meanwhile,
+// see also initialLoading of the config
+Graphima.setLoading(chartId, true, "Loading…"); // promise
+Graphima.setLoading(chartId, false);
+
+// add points past the ends of data sets, by name; x and values are parsed as
+// in params and each x should be after the last one of its data set, else
+// nothing is added. The window follows the data with follow of the config,
+// a zoomed-in window stays put otherwise. The first points of a data set
+// declared without any create the chart anew under the same chart id, e.g.
+// a chart of only such data sets turns from "no data" into a real one
+Graphima.appendData(chartId, [
+  { name: "Foo", coords: ["2020-01-04"], values: [3] },
+]); // promise
+// after the user zoomed or panned, move the window back to the latest data
+// and follow it again; rejects unless follow is enabled
+Graphima.resumeFollow(chartId); // promise
+
+// a JSON document describing the chart, e.g. for a bug report or a
+// rendering service: {version, params, config, state}; params carry the data
+// with appended points at full precision (dates as ms) and no selector,
+// config is the one passed to createMain without functions, state is
+// {window: [from, to], hiddenDataSets: [...], following, previewHeight}, where
+// previewHeight is set once previewSplitter was moved
+const definition = await Graphima.exportDefinition(chartId); // promise
+// recreate it in another container; validated as createMain does, the
+// window goes to initialRange of the params and hidden data sets which are
+// missing are warned of
+Graphima.createFromDefinition("#chart-2", definition); // promise, as createMain
+
+// debug overlay for bug reports: frames per second and the last frame time,
+// the scale, the window, visible points per series, how often the main and
+// preview frames were reused, and outlines of the layout; it is not part of
+// exportDefinition
+Graphima.setDebug(chartId, true); // promise
+
+// hover an x (a number, or a date for datetime coords) as the pointer would,
+// e.g. for a guided tour: the tooltip, hover events and the shared tooltip
+// of the link group follow; the x is clamped to the data, the optional
+// duration in ms tweens from the x hovered this way before; the pointer
+// entering the plot takes over, null clears it
+Graphima.setHoverX(chartId, new Date(2024, 0, 1), 600); // promise
+Graphima.setHoverX(chartId, null); // promise
+
+// zoom to [from, to] of coordType type as a selection would (it stops
+// following appended data); a range out of the data is fitted as
+// initialRange is
+Graphima.setVisibleRange(chartId, ["2020-01-02", "2020-01-03"]); // promise
+// draw every part of the chart anew with the next frame
+Graphima.redraw(chartId); // promise
+
+// move a chart to another link group (see linkGroup of the config), null
+// takes it out; shared tooltips follow on the next frame
+Graphima.setLinkGroup(chartId, "dashboard"); // promise
+
+// turns alignY of the config on or off, each chart of the link group
+// animates from its own y domain to the shared one and back
+Graphima.setAlignY(chartId, true); // promise
+
+// rectangles {x, y, width, height} of the last drawn frame in CSS pixels
+// relative to the wrapper, for aligning DOM overlays: plot (inside the axes),
+// main, preview and legend, plus the gutters {top, right, bottom, left}
+// between main and plot; redraws after a resize update them; a preview in
+// previewContainer is relative to the wrapper injected there
+Graphima.getLayout(chartId); // {plot: {x: 40, y: 8, width: 600, height: 300}, ...}
+// map between data and the same pixels through the current scales (log
+// ones included); x takes what params accept, e.g. a date string, and comes
+// back as a number (ms for dates, like event details)
+Graphima.dataToPixel(chartId, "2020-01-02", 150); // {x, y}
+Graphima.pixelToData(chartId, 120, 80); // {x, y}
+
+// built-in strings by key, e.g. {previewZoomOut: "Click to zoom out", ...};
+// placeholders like {count} are substituted, override them via
+// config.strings
+Graphima.defaultStrings();
+
+// release canvases and caches of charts which are off screen or in a hidden
+// tab right away, e.g. on memory pressure; they are rebuilt once shown (see
+// memoryTrimDelay of the config for doing it automatically)
+Graphima.trimMemory();
+
+// leak check: with graphima built with the debug-leak-check cargo feature,
+// getResourceCounts returns {charts, listeners, closures, canvases,
+// observations} held by the charts (it fails otherwise); everything but
+// charts drops to 0 once the last chart is destroyed and the closures are
+// released a task later. E.g. after 100 create/destroy cycles:
+async function checkLeaks(params, chartConfig) {
+  for (let i = 0; i < 100; i++) {
+    await Graphima.destroyMain(await Graphima.createMain(params, chartConfig));
+  }
+  await new Promise((resolve) => setTimeout(resolve, 0));
+  console.log(await Graphima.getResourceCounts());
+}
+
+// legend: click toggles a data set; double click, Ctrl/Cmd+click or a long
+// press isolates it (the entry gets underlined) and doing it again restores
+// the previous visibility, toggles made meanwhile included; hovering an
+// entry (holding it on touch) highlights its series as hoverHighlight does
+// for the nearest one; the legend is focusable: Tab and arrow keys move
+// between entries, Enter or Space toggles the focused one, Ctrl/Cmd+Enter
+// isolates it
+
+// keyboard: the tab order is the chart, the legend, then the preview window.
+// On the chart arrow keys pan, "+" and "-" zoom, "0" resets the zoom; on the
+// preview window arrow keys move it, Shift+arrow keys resize it; Escape
+// returns focus from the legend or the preview window to the chart
+
+// accessibility: the chart wrapper carries an aria-label summarizing the
+// visible series and range; it is updated on zoom and visibility changes.
+// describeChart receives {xMin, xMax, formattedXMin, formattedXMax, summary,
+// series: [{name, min, max, latest, formattedMin, formattedMax,
+// formattedLatest}]} and returns the label text; when it throws or returns
+// something else the summary is used and a CALLBACK_FAILED warning added
+Graphima.createMain(params, {
+  ...chartConfig,
+  describeChart: function (payload) {
+    return "Sales, " + payload.formattedXMin + " to " + payload.formattedXMax;
+  },
+});
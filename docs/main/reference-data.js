@@ -7,7 +7,7 @@ const params = {
 
   // one of 3 supported data types:
   //  * "date"
-  //  * "datetime"
+  //  * "datetime" (shown in timeZone of the config)
   //  * "number"
   coordType: "date",
   valueType: "number",
@@ -18,12 +18,71 @@ const params = {
   //     "coords"; list of coordinates of coordType type
   //     "values": list of values of valueType type
   // }
+  // every series has coords of its own (e.g. one every minute next to one
+  // every 5 minutes), they only have to be as many as its values; the
+  // tooltip shows the nearest point of each series whose data spans the
+  // hovered coord. A series with empty coords and values waits for the first
+  // points of appendData, until then its markers and comparisons are left
+  // out (markers stay so, see setPointMarkers). A null value is a gap: the
+  // line and its fill break there, e.g. values: [10, null, 30] (also in
+  // appendData)
   dataSets: [
     {
       name: "Foo",
       // all the below are valid dates
       coords: ["2020-01-01", 1577923200000, new Date("2020-01-03")],
       values: [10, "20", 30.0],
+      // optional markers of single points, drawn above the line and picked
+      // by hover first; coord should match a point, shape is "circle"
+      // (default), "square", "triangle" or "diamond", color and size (apx)
+      // default to the series color and pointMarkerSize of the config
+      // markers: [{ coord: "2020-01-02", shape: "diamond", color: [255, 0, 0], size: 10 }],
+      // optional paint order, higher is drawn above (0 by default, ties keep
+      // the data set order); the legend and the tooltip keep their order and
+      // the hovered series is drawn on top regardless
+      // zIndex: 1,
+      // optional opacity of the line, its fill and markers, 0 to 1
+      // opacity: 0.8,
+      // optional, false leaves a contextual series (e.g. a baseline band) out
+      // of the hover: it is never the nearest, has no tooltip line and is
+      // not dimmed while another series is hovered (true by default)
+      // hoverable: false,
+      // optional, false leaves the series out of the tooltip only (true by
+      // default)
+      // showInTooltip: false,
+      // optional, false leaves the series out of the legend (true by default)
+      // showInLegend: false,
+      // optional unit shown after the values in tooltips and on the y axis
+      // title while all visible series share it; comparisons inherit it
+      // unit: "ms",
+      // optional, shown with the name and the unit as the title of the
+      // legend item on hover
+      // description: "95th percentile of the response time",
+      // optional area between the line and y = 0 (or the nearer plot edge);
+      // "solid" takes color ([r, g, b] or [r, g, b, alpha]), "gradient"
+      // takes stops from the plot edge away from the baseline (offset 0) to
+      // the baseline (offset 1); without them the series color fades out
+      // towards the baseline
+      // fill: {
+      //   type: "gradient",
+      //   stops: [
+      //     { offset: 0, color: [0, 114, 178, 0.6] },
+      //     { offset: 1, color: [0, 114, 178, 0] },
+      //   ],
+      // },
+      // optional copy of the series shifted by offset (x units, ms for dates)
+      // to overlay e.g. the previous period; drawn muted and dashed, the
+      // tooltip shows how the current value differs from it; it stays out
+      // of the y range unless includeInDomain is set, the default name is
+      // strings.comparisonName
+      // compare: { offset: 7 * 24 * 3600 * 1000, name: "Foo last week" },
     },
   ],
+
+  // optional window to open the chart with, [from, to] of coordType type; a
+  // range out of the data is clamped or moved to the nearest edge with a
+  // console warning
+  // initialRange: ["2020-01-02", "2020-01-03"],
+  // or the last n x units (ms for dates), e.g. the last 24 hours
+  // initialWindow: 24 * 3600 * 1000,
 };
@@ -15,6 +15,27 @@ const CONFIG = {
   colorPreviewHint: [255, 255, 255, 1],
   colorTooltip: [255, 255, 255, 1],
   colorTooltipFont: [0, 0, 0, 1],
+  // optional, used with previewMask and previewHoverMarker
+  // colorPreviewMask: [0, 0, 0, 0.15],
+  // colorPreviewWindow: [0, 0, 255, 0.5],
+  // optional, used with zeroLineShow and negativeRegionFill
+  // colorZeroLine: [142, 142, 142, 1],
+  // colorNegativeRegion: [255, 0, 0, 0.05],
+  // optional, dims the chart while it is loading
+  // colorLoadingOverlay: [255, 255, 255, 0.7],
+  // optional "light" or "dark" preset overriding the colors above (except
+  // colorPalette); "auto" follows prefers-color-scheme and switches live
+  // theme: "auto",
+  // "high" thickens lines and markers and strengthens the grid and ticks; by
+  // default it follows prefers-contrast: more and forced colors (Windows High
+  // Contrast) and switches live
+  // contrast: "high",
+  highContrastLineWidthCoeff: 2,
+  // optional "ltr" or "rtl"; detected from the container by default. The rtl
+  // layout puts the y axis on the other side and mirrors the legend and
+  // tooltips; rtlReverseXAxis also puts newer values on the left
+  // direction: "rtl",
+  rtlReverseXAxis: false,
 
   // defines how series should be sorted (order in tooltip & legend)
   // one of:
@@ -32,7 +53,102 @@ const CONFIG = {
   layoutContentHeight: 5,
   layoutPreviewHeight: 1,
   layoutLegendHeight: 1.5,
+  // "bottom" or "top": where the preview goes relative to the main plot
+  previewPosition: "bottom",
+  // a bar between the main plot and the preview, dragged (or moved with the
+  // arrow keys when focused) to trade their heights; the height it sets is
+  // kept by exportDefinition
+  previewSplitter: false,
+  // optional selector of an element the preview goes into instead, e.g. a
+  // fixed strip while the main plot scrolls with the page; it fills that
+  // element and destroyMain removes it too. It cannot be combined with
+  // previewSplitter, previewPosition "top" or a "preview" step of
+  // compactLayout (the default steps leave the preview be)
+  // previewContainer: "#overview",
+  // optional, parts dropped from small containers: a step applies while the
+  // container is narrower than minWidth or lower than minHeight (CSS px)
+  // and the steps before it apply too; part is "legend", "axisLabels",
+  // "preview" or "sparkline" (only the series are left); [] keeps every
+  // part at any size
+  // compactLayout: [
+  //   { part: "legend", minWidth: 240, minHeight: 160 },
+  //   { part: "axisLabels", minWidth: 180, minHeight: 120 },
+  //   { part: "preview", minWidth: 150, minHeight: 100 },
+  //   { part: "sparkline", minWidth: 100, minHeight: 50 },
+  // ],
 
+  // optional: "default" uses colorPalette, "colorblind_safe" uses the
+  // Okabe-Ito colors, an array of colors replaces colorPalette
+  // palette: "colorblind_safe",
+  // distinct line dashes per series, with matching hatches on legend and
+  // tooltip swatches, so series remain distinguishable in grayscale
+  seriesPatterns: false,
+  // optional factor for invisible hit areas: point clicks, legend items and
+  // the preview window; 2 for coarse pointers (touch), 1 otherwise
+  // hitScale: 1.5,
+  // the series nearest to the pointer is drawn thicker on top of the others,
+  // which fade to hoverDimOpacity
+  hoverHighlight: true,
+  hoverDimOpacity: 0.3,
+  // ms series take to fade in or out when shown or hidden, 0 switches them
+  // at once, as does prefers-reduced-motion
+  seriesAnimationDuration: 300,
+  // series fading in also grow from the baseline, on creation too
+  seriesEnterGrow: false,
+  // the preview dims what is outside of the zoom window (colorPreviewMask)
+  // and outlines the window with handles (colorPreviewWindow) instead of
+  // the overlay with the hint
+  previewMask: false,
+  // mark the hovered x of the main chart in the preview
+  previewHoverMarker: false,
+  // optional watermark in a corner of the plot: a text or an image URL (an
+  // image appears once loaded, a broken one is skipped with a warning)
+  // watermarkText: "ACME Corp.",
+  // watermarkImage: "/logo.png",
+  // "top-left", "top-right", "bottom-left" or "bottom-right"
+  watermarkPosition: "bottom-right",
+  watermarkOpacity: 0.3,
+  // of the font size for a text, of the natural size for an image
+  watermarkScale: 1,
+  // "below" or "above" the series
+  watermarkLayer: "below",
+  // a line at y = 0 above the grid and below the series, and a tint of the
+  // plot below it; both are skipped while 0 is out of the y range (always on
+  // a log scale)
+  zeroLineShow: false,
+  zeroLineWidth: 1.5,
+  negativeRegionFill: false,
+  // optional, which interactions the chart reacts to; missing flags are on:
+  //  * hover - the tooltip, clicks on points and the hover highlight
+  //  * zoom - selecting a range, pinching, zooming out and zoom keys
+  //  * pan - dragging the preview window and arrow keys
+  //  * legendToggle - toggling and isolating data sets from the legend
+  // with all off the chart skips pointer and key events altogether; change
+  // them later with Graphima.setInteractions
+  // interactions: { hover: false, zoom: false, pan: false, legendToggle: false },
+  // optional, a live chart: the window shows the last spanX x units (ms for
+  // dates; the whole data without it) and moves along as Graphima.appendData
+  // adds points; zooming or panning stops it until Graphima.resumeFollow.
+  // spanX replaces initialWindow of the params unless initialRange is given
+  // follow: { enabled: true, spanX: 3600000 },
+  // what the plot shows where a window from initialRange or follow goes past
+  // the data: "clip" keeps such windows within the data, "extendLast" draws
+  // the last value of each series on as a dashed line, "fade" hatches the
+  // empty space; with the last two the tooltip says "no data" there
+  edgeBehavior: "clip",
+  // the zone "datetime" coords are shown in, on the x axis and in tooltips:
+  // "local" (the browser's), "utc" or an IANA name like "America/New_York";
+  // day and month ticks stay on local boundaries across DST changes. An
+  // unknown name fails createMain. "date" coords are calendar days, shown as
+  // they are
+  timeZone: "local",
+  // optional caps of the canvas resolution to save memory and fill rate on
+  // large high-density displays: the device pixel ratio to render at and the
+  // longer canvas side in physical pixels
+  // maxRenderPixelRatio: 1.5,
+  // maxCanvasDimensionPx: 4096,
+  // size of point markers (dataSets[].markers of the params) in apx
+  pointMarkerSize: 8,
   // palette to be used
   colorPalette: [
     // first 5 are color-blind friendly
@@ -62,4 +178,147 @@ const CONFIG = {
   // number of significant digits when fallen back to scientific notation:
   //  1.234e6
   expFmtSignificantDigits: 5,
+
+  // OPTIONAL: what to do when x axis labels don't fit:
+  //  * "skip" - show every Nth label
+  //  * "rotate" - rotate labels by xLabelRotation degrees
+  xLabelOverlap: "skip",
+  // NaN and infinite x or y values in data sets:
+  //  * "error" - reject the data set, reporting its name and the index
+  //  * "drop" - leave such points out
+  //  * "clamp" - replace infinite y values with the min or max of the data
+  //    set, leave the other points out
+  nonFiniteValues: "error",
+  // minimum gap between x axis labels in px
+  xLabelMinGap: 8,
+  // degrees, within (0, 90]
+  xLabelRotation: 45,
+  // side of the y axis labels: "left", "right" or "both"
+  yAxisPosition: "left",
+  // optional format of y axis labels: "plain", "compact" (1.2K), "si" (1.2k)
+  // or "bytes" (1.2KiB)
+  // yLabelFormat: "compact",
+  // apply yLabelFormat to tooltip values too
+  yLabelFormatInTooltip: false,
+  // optional unit the values are stored in: "duration_seconds" (ms, s, min,
+  // h, d), "bytes" (B, KiB, MiB, ...) or a ladder of [factor, suffix] pairs;
+  // the axis maximum picks the unit shared by all y labels, the tooltip picks
+  // one per value. Takes precedence over yLabelFormat
+  // yUnit: [[1, "m"], [1000, "km"]],
+  // where the unit of the y labels goes: "labels" or the axis "title"
+  yUnitPlacement: "labels",
+  // optional locale for decimal and group separators, the browser one by default
+  // locale: "de-DE",
+  // "canvas" draws the tooltip, "html" renders it as an element on top of the
+  // chart; defaults to "html" when renderTooltip is given
+  tooltipMode: "canvas",
+  // html tooltip placement: "pointer" follows the pointer, "point" snaps to the
+  // nearest point
+  tooltipPosition: "pointer",
+  // optional html tooltip content, see reference-api.js
+  // renderTooltip: function (payload) { return "<b>" + payload.formattedX + "</b>"; },
+  // show the change of every value in the tooltip
+  tooltipShowChange: false,
+  // what the change is computed against: "previous" point of the data set or
+  // the "first" visible one
+  tooltipChangeBase: "previous",
+  // order of the tooltip rows: "seriesOrder", "valueDesc" or "valueAsc"
+  tooltipSort: "seriesOrder",
+  // optional cap on the rows; the rest are collapsed into one with their sum
+  // (strings.tooltipMoreLines), the nearest series is always listed
+  // tooltipMaxEntries: 8,
+  // leave out series whose value is 0, except the nearest one
+  tooltipHideZero: false,
+  // optional axis titles, no space is reserved when empty
+  // xAxisTitle: "time UTC",
+  // yAxisTitle: "requests/sec",
+  // optional target distance between x ticks in px; by default it is derived
+  // from the label length
+  // xTickSpacing: 80,
+  // fainter y gridlines between the major ones: gridMinorCount lines on a
+  // linear scale, 2x..9x of every decade on a log scale
+  gridMinorShow: false,
+  gridMinorCount: 4,
+  gridMinorLineWidth: 0.5,
+  // optional, colorGrid with 0.4 opacity by default
+  // colorGridMinor: [200, 200, 200, 0.4],
+  // what to do when legend items don't fit:
+  //  * "page" - show them page by page within layoutLegendHeight
+  //  * "wrap" - show all rows, the legend grows and the plot shrinks
+  //  * "scroll" - a single row scrolled by dragging or the arrows
+  legendOverflow: "page",
+  // values printed next to points: "always", "never" or "auto" - only when
+  // neighbouring points are at least pointLabelsMinSpacing px apart
+  pointLabelsShow: "never",
+  pointLabelsMinSpacing: 40,
+  // a visually hidden table with the data for screen readers; disable it for
+  // huge data sets, otherwise rows are thinned out to accessibleTableMaxRows
+  accessibleTable: true,
+  accessibleTableMaxRows: 500,
+  // optional text of the chart aria-label, see reference-api.js
+  // describeChart: function (payload) { return payload.summary; },
+  // optional, called with the chart id when a chart is destroyed because its
+  // container left the document without destroyMain
+  // onAutoDestroy: function (chartId) {},
+  // optional, called with the detail of the chart events (see
+  // reference-api.js), after the DOM event: onReady, onRangeChange, onHover,
+  // onPointClick, onVisibilityChange, onFollowChange
+  // onRangeChange: function (detail) {},
+  // whether events are also dispatched on the wrapper as DOM events; with
+  // false, events without a callback cost nothing
+  domEvents: true,
+  // optional min ms between two deliveries of an event kind; the latest
+  // range change and hover are delivered, a hover leave is never dropped
+  // eventThrottleMs: { rangechange: 200, hover: 50 },
+  // ms the hovered point has to stay the same before onHover and the hover
+  // event fire, for expensive listeners; the tooltip follows at once and
+  // the leave (x: null) is sent as soon as the pointer leaves
+  hoverSettleMs: 0,
+  // optional overrides of built-in strings by key, missing ones stay in
+  // English; see Graphima.defaultStrings() for the keys
+  // strings: { previewZoomOut: "Cliquez pour dézoomer" },
+  // rendering waits while the container has zero size (e.g. display: none);
+  // a console warning is logged after this many ms, 0 disables it
+  zeroSizeWarningTimeout: 10000,
+  // ms a chart stays off screen or in a hidden tab before its canvases and
+  // caches are released (rebuilt once shown), 0 keeps them
+  memoryTrimDelay: 60000,
+  // min and max values of blocks of points are computed while the browser is
+  // idle (requestIdleCallback, a timeout where missing), so that zooming and
+  // panning long data sets does not scan every point of the window
+  idlePrecompute: true,
+  // createMain resolves to {chartId, warnings} instead of the chart id, see
+  // getWarnings in the API
+  returnWarnings: false,
+  // wrapper ids (chart ids) are numbered in creation order instead of being
+  // random, so that identical inputs produce identical DOM, e.g. for
+  // screenshot tests
+  deterministic: false,
+  // the chart starts with the loading overlay (see setLoading), e.g. when
+  // it is created before its data arrives
+  initialLoading: false,
+  // createMain on a container which already holds a chart of this page:
+  // "error" rejects with {code: "CONTAINER_ALREADY_USED", chartId} (the id of
+  // the existing chart), "replace" destroys the existing chart first
+  containerReuse: "error",
+  // optional, charts with the same link group are coordinated, see
+  // sharedTooltip and Graphima.setLinkGroup
+  // linkGroup: "dashboard",
+  // the tooltip of the hovered chart also lists the values of the other
+  // charts of its link group at the same x, under their names from params
+  sharedTooltip: false,
+  // charts of the link group with alignY share one y axis spanning the data
+  // of all of them, which follows data updates and charts joining or leaving;
+  // a chart whose scale (log or linear) differs from the one of the first
+  // chart of the group keeps its own with a Y_SCALE_MISMATCH warning, see
+  // Graphima.setAlignY
+  alignY: false,
+  // optional, while a series is hovered, elements with
+  // data-graphima-series="<series name>" get this class and the chart
+  // wrapper gets data-graphima-hover-x/-y with the hovered values (ms for
+  // dates); both are removed on hover end and on destroyMain
+  // hoverLinkClass: "is-hovered",
+  // optional, selector of the element those are looked up in, the whole
+  // document by default
+  // hoverLinkRoot: "#my-legend",
 };
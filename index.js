@@ -18,9 +18,129 @@ async function destroyMain(chartId) {
   const exports = await init();
   return exports.destroyMain(chartId);
 }
+async function setDataSetVisible(chartId, name, visible) {
+  const exports = await init();
+  return exports.setDataSetVisible(chartId, name, visible);
+}
+async function paramsFromCsv(csv, options) {
+  const exports = await init();
+  return exports.paramsFromCsv(csv, options);
+}
+async function defaultStrings() {
+  const exports = await init();
+  return exports.defaultStrings();
+}
+async function trimMemory() {
+  const exports = await init();
+  return exports.trimMemory();
+}
+async function isolateDataSet(chartId, name) {
+  const exports = await init();
+  return exports.isolateDataSet(chartId, name);
+}
+async function setPointMarkers(chartId, name, markers) {
+  const exports = await init();
+  return exports.setPointMarkers(chartId, name, markers);
+}
+async function setInteractions(chartId, interactions) {
+  const exports = await init();
+  return exports.setInteractions(chartId, interactions);
+}
+async function setLoading(chartId, loading, message) {
+  const exports = await init();
+  return exports.setLoading(chartId, loading, message);
+}
+async function getLayout(chartId) {
+  const exports = await init();
+  return exports.getLayout(chartId);
+}
+async function dataToPixel(chartId, x, y) {
+  const exports = await init();
+  return exports.dataToPixel(chartId, x, y);
+}
+async function pixelToData(chartId, x, y) {
+  const exports = await init();
+  return exports.pixelToData(chartId, x, y);
+}
+async function setLinkGroup(chartId, linkGroup) {
+  const exports = await init();
+  return exports.setLinkGroup(chartId, linkGroup);
+}
+async function setDataSetZIndex(chartId, name, zIndex) {
+  const exports = await init();
+  return exports.setDataSetZIndex(chartId, name, zIndex);
+}
+async function appendData(chartId, dataSets) {
+  const exports = await init();
+  return exports.appendData(chartId, dataSets);
+}
+async function resumeFollow(chartId) {
+  const exports = await init();
+  return exports.resumeFollow(chartId);
+}
+async function exportDefinition(chartId) {
+  const exports = await init();
+  return exports.exportDefinition(chartId);
+}
+async function createFromDefinition(selector, definition) {
+  const exports = await init();
+  return exports.createFromDefinition(selector, definition);
+}
+async function setDebug(chartId, enabled) {
+  const exports = await init();
+  return exports.setDebug(chartId, enabled);
+}
+async function getWarnings(chartId) {
+  const exports = await init();
+  return exports.getWarnings(chartId);
+}
+async function getResourceCounts() {
+  const exports = await init();
+  return exports.getResourceCounts();
+}
+async function setAlignY(chartId, enabled) {
+  const exports = await init();
+  return exports.setAlignY(chartId, enabled);
+}
+async function setHoverX(chartId, x, durationMs) {
+  const exports = await init();
+  return exports.setHoverX(chartId, x, durationMs);
+}
+async function setVisibleRange(chartId, range) {
+  const exports = await init();
+  return exports.setVisibleRange(chartId, range);
+}
+async function redraw(chartId) {
+  const exports = await init();
+  return exports.redraw(chartId);
+}
 
 export default {
   init, // optional
   createMain,
   destroyMain,
+  setDataSetVisible,
+  paramsFromCsv,
+  defaultStrings,
+  trimMemory,
+  isolateDataSet,
+  setPointMarkers,
+  setInteractions,
+  setLoading,
+  getLayout,
+  dataToPixel,
+  pixelToData,
+  setLinkGroup,
+  setDataSetZIndex,
+  appendData,
+  resumeFollow,
+  exportDefinition,
+  createFromDefinition,
+  setDebug,
+  getWarnings,
+  getResourceCounts,
+  setAlignY,
+  setHoverX,
+  setVisibleRange,
+  redraw,
 };
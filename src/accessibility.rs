@@ -0,0 +1,334 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use crate::params::{ChartConfig, Content};
+use crate::warnings::{ChartWarning, Warnings};
+use js_sys::{Array, Object, Reflect};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+const CSS_VISUALLY_HIDDEN: &str = "position: absolute; width: 1px; height: 1px; margin: -1px; \
+     padding: 0; border: 0; overflow: hidden; clip: rect(0 0 0 0); clip-path: inset(50%); \
+     white-space: nowrap";
+
+struct SeriesSummary {
+    name: String,
+    min: f64,
+    max: f64,
+    latest: f64,
+    formatted: [String; 3],
+}
+
+/// Keeps the aria-label of the chart wrapper and the visually hidden data
+/// table in sync with the content.
+pub struct ChartDescription {
+    chart_config: Rc<ChartConfig>,
+    wrapper: web_sys::Element,
+    table: Option<web_sys::Element>,
+    label: String,
+    data_signature: (usize, usize),
+    /// Of the chart, for describeChart failures.
+    warnings: Warnings,
+}
+
+impl ChartDescription {
+    pub fn new(
+        chart_config: Rc<ChartConfig>,
+        wrapper: &web_sys::Element,
+        warnings: Warnings,
+    ) -> Self {
+        // an img hides its children from screen readers, a figure keeps the
+        // table navigable
+        wrapper
+            .set_attribute(
+                "role",
+                if chart_config.accessible_table {
+                    "figure"
+                } else {
+                    "img"
+                },
+            )
+            .unwrap();
+        Self {
+            chart_config,
+            wrapper: wrapper.clone(),
+            table: None,
+            label: String::new(),
+            data_signature: (0, 0),
+            warnings,
+        }
+    }
+
+    pub fn set_chart_config(&mut self, chart_config: Rc<ChartConfig>) {
+        self.chart_config = chart_config;
+    }
+
+    /// Updates the label for the visible range and data sets; rebuilds the
+    /// table when the data changes.
+    pub fn update(&mut self, content: &Content, range: (f64, f64), visibility: &[bool]) {
+        let data_signature = (
+            content.data_sets.len(),
+            content
+                .data_sets
+                .iter()
+                .map(|data_set| data_set.data_points.len())
+                .sum(),
+        );
+        if self.chart_config.accessible_table && self.data_signature != data_signature {
+            self.data_signature = data_signature;
+            self.render_table(content);
+        }
+
+        let label = self.get_label(content, range, visibility);
+        if label != self.label {
+            self.wrapper
+                .set_attribute("aria-label", label.as_str())
+                .unwrap();
+            self.label = label;
+        }
+    }
+
+    fn get_label(&self, content: &Content, range: (f64, f64), visibility: &[bool]) -> String {
+        let formatted_range = content.coord_verbose_format.format_values(
+            [range.0, range.1].into_iter(),
+            |x| x,
+            range.0,
+            range.1,
+        );
+        let series: Vec<SeriesSummary> = content
+            .data_sets
+            .iter()
+            .zip(visibility.iter())
+            .filter(|(_, visible)| **visible)
+            .filter_map(|(data_set, _)| {
+                let data_points = data_set.slice_by_coord(range.0, range.1)?;
+                let min = data_points.iter().map(|p| p.value).fold(f64::MAX, f64::min);
+                let max = data_points.iter().map(|p| p.value).fold(f64::MIN, f64::max);
+                let latest = data_points.last()?.value;
+                let formatted = content.value_verbose_format.format_values(
+                    [min, max, latest].into_iter(),
+                    |v| v,
+                    min,
+                    max,
+                );
+                Some(SeriesSummary {
+                    name: data_set.name.clone(),
+                    min,
+                    max,
+                    latest,
+                    formatted: [
+                        formatted[0].clone(),
+                        formatted[1].clone(),
+                        formatted[2].clone(),
+                    ],
+                })
+            })
+            .collect();
+
+        let strings = &self.chart_config.strings;
+        let count = series.len().to_string();
+        let mut args = vec![
+            ("count", count.as_str()),
+            ("from", formatted_range[0].as_str()),
+            ("to", formatted_range[1].as_str()),
+        ];
+        let mut summary = match &content.name {
+            Some(name) => {
+                args.push(("name", name.as_str()));
+                strings.format("summaryWithName", &args)
+            }
+            None => strings.format("summary", &args),
+        };
+        for item in series.iter() {
+            summary.push(' ');
+            summary.push_str(
+                strings
+                    .format(
+                        "summarySeries",
+                        &[
+                            ("name", item.name.as_str()),
+                            ("min", item.formatted[0].as_str()),
+                            ("max", item.formatted[1].as_str()),
+                            ("latest", item.formatted[2].as_str()),
+                        ],
+                    )
+                    .as_str(),
+            );
+        }
+
+        if let Some(describe_chart) = &self.chart_config.describe_chart {
+            let payload = Self::get_payload(range, &formatted_range, &series, summary.as_str());
+            match describe_chart.call1(&JsValue::NULL, &payload) {
+                Ok(result) => match result.as_string() {
+                    Some(label) => return label,
+                    None => self.warnings.push(ChartWarning::new(
+                        "CALLBACK_FAILED",
+                        "describeChart should return a string, using the default summary"
+                            .to_string(),
+                        Some("describeChart".to_string()),
+                    )),
+                },
+                Err(error) => self
+                    .warnings
+                    .push(ChartWarning::callback_failed("describeChart", error)),
+            }
+        }
+        summary
+    }
+
+    fn get_payload(
+        range: (f64, f64),
+        formatted_range: &[String],
+        series: &[SeriesSummary],
+        summary: &str,
+    ) -> JsValue {
+        let items = Array::new();
+        for item in series.iter() {
+            let object = Object::new();
+            Reflect::set(&object, &"name".into(), &item.name.as_str().into()).unwrap();
+            Reflect::set(&object, &"min".into(), &item.min.into()).unwrap();
+            Reflect::set(&object, &"max".into(), &item.max.into()).unwrap();
+            Reflect::set(&object, &"latest".into(), &item.latest.into()).unwrap();
+            for (key, formatted) in ["formattedMin", "formattedMax", "formattedLatest"]
+                .iter()
+                .zip(item.formatted.iter())
+            {
+                Reflect::set(&object, &(*key).into(), &formatted.as_str().into()).unwrap();
+            }
+            items.push(&object);
+        }
+        let payload = Object::new();
+        Reflect::set(&payload, &"xMin".into(), &range.0.into()).unwrap();
+        Reflect::set(&payload, &"xMax".into(), &range.1.into()).unwrap();
+        Reflect::set(
+            &payload,
+            &"formattedXMin".into(),
+            &formatted_range[0].as_str().into(),
+        )
+        .unwrap();
+        Reflect::set(
+            &payload,
+            &"formattedXMax".into(),
+            &formatted_range[1].as_str().into(),
+        )
+        .unwrap();
+        Reflect::set(&payload, &"summary".into(), &summary.into()).unwrap();
+        Reflect::set(&payload, &"series".into(), &items).unwrap();
+        payload.into()
+    }
+
+    /// Renders a row per coordinate, thinned out evenly down to
+    /// `accessible_table_max_rows`.
+    fn render_table(&mut self, content: &Content) {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let table = match &self.table {
+            Some(table) => table.clone(),
+            None => {
+                let table = document.create_element("table").unwrap();
+                table.set_attribute("style", CSS_VISUALLY_HIDDEN).unwrap();
+                self.wrapper.append_child(&table).unwrap();
+                self.table = Some(table.clone());
+                table
+            }
+        };
+        table.set_inner_html("");
+
+        let mut coords: Vec<f64> = content
+            .data_sets
+            .iter()
+            .flat_map(|data_set| data_set.data_points.iter().map(|p| p.coord))
+            .collect();
+        coords.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        coords.dedup();
+        let step = coords
+            .len()
+            .div_ceil(self.chart_config.accessible_table_max_rows)
+            .max(1);
+        let coords: Vec<f64> = coords.into_iter().step_by(step).collect();
+
+        if let Some(name) = &content.name {
+            let caption = document.create_element("caption").unwrap();
+            caption.set_text_content(Some(name.as_str()));
+            table.append_child(&caption).unwrap();
+        }
+        let header = document.create_element("tr").unwrap();
+        let x_title = self
+            .chart_config
+            .x_axis_title
+            .as_deref()
+            .unwrap_or(self.chart_config.strings.get("tableCoordHeader"));
+        for title in Some(x_title).into_iter().chain(
+            content
+                .data_sets
+                .iter()
+                .map(|data_set| data_set.name.as_str()),
+        ) {
+            let cell = document.create_element("th").unwrap();
+            cell.set_attribute("scope", "col").unwrap();
+            cell.set_text_content(Some(title));
+            header.append_child(&cell).unwrap();
+        }
+        table.append_child(&header).unwrap();
+
+        let formatted_coords = content.coord_verbose_format.format_values(
+            coords.iter(),
+            |x| *x,
+            content.global_coord_min,
+            content.global_coord_max,
+        );
+        let columns: Vec<Vec<String>> = content
+            .data_sets
+            .iter()
+            .map(|data_set| {
+                let values: Vec<Option<f64>> = coords
+                    .iter()
+                    .map(|coord| {
+                        data_set
+                            .bin_search_left_bound(*coord)
+                            .map(|index| &data_set.data_points[index])
+                            .filter(|p| p.coord == *coord)
+                            .map(|p| p.value)
+                    })
+                    .collect();
+                let formatted = content.value_verbose_format.format_values(
+                    values.iter().flatten(),
+                    |v| *v,
+                    content.global_value_min,
+                    content.global_value_max,
+                );
+                let mut formatted = formatted.into_iter();
+                values
+                    .iter()
+                    .map(|value| match value {
+                        Some(_) => formatted.next().unwrap(),
+                        None => String::new(),
+                    })
+                    .collect()
+            })
+            .collect();
+        for (index, formatted_coord) in formatted_coords.iter().enumerate() {
+            let row = document.create_element("tr").unwrap();
+            let cell = document.create_element("th").unwrap();
+            cell.set_attribute("scope", "row").unwrap();
+            cell.set_text_content(Some(formatted_coord.as_str()));
+            row.append_child(&cell).unwrap();
+            for column in columns.iter() {
+                let cell = document.create_element("td").unwrap();
+                cell.set_text_content(Some(column[index].as_str()));
+                row.append_child(&cell).unwrap();
+            }
+            table.append_child(&row).unwrap();
+        }
+    }
+}
+impl Drop for ChartDescription {
+    fn drop(&mut self) {
+        if let Some(table) = &self.table {
+            table.remove();
+        }
+    }
+}
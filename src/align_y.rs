@@ -0,0 +1,89 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+
+/// What a chart aligning its y axis offers to its link group, see
+/// `ChartConfig::align_y`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct YAlignment {
+    pub link_group: String,
+    /// Min and max of its data.
+    pub domain: (f64, f64),
+    pub log_scale: bool,
+}
+
+/// The y domain a chart gets from its link group.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SharedYDomain {
+    /// The chart fits the y axis to its own data.
+    Own,
+    Shared(f64, f64),
+    /// The chart has another kind of scale than the group, so it keeps its
+    /// own domain.
+    ScaleMismatch,
+}
+
+/// Unites the domains of the charts of each link group; the first chart of
+/// a group decides the kind of scale, charts of the other kind are left
+/// out.
+pub fn get_shared_y_domains(alignments: &[Option<YAlignment>]) -> Vec<SharedYDomain> {
+    alignments
+        .iter()
+        .map(|alignment| {
+            let alignment = match alignment {
+                Some(alignment) => alignment,
+                None => return SharedYDomain::Own,
+            };
+            let group = alignments
+                .iter()
+                .flatten()
+                .filter(|other| other.link_group == alignment.link_group);
+            let log_scale = group.clone().next().unwrap().log_scale;
+            if alignment.log_scale != log_scale {
+                return SharedYDomain::ScaleMismatch;
+            }
+            let (min, max) = group
+                .filter(|other| other.log_scale == log_scale)
+                .fold((f64::MAX, f64::MIN), |(min, max), other| {
+                    (min.min(other.domain.0), max.max(other.domain.1))
+                });
+            SharedYDomain::Shared(min, max)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::align_y::{get_shared_y_domains, SharedYDomain, YAlignment};
+
+    #[test]
+    fn test_shared_y_domains() {
+        let alignment = |link_group: &str, min: f64, max: f64, log_scale: bool| {
+            Some(YAlignment {
+                link_group: link_group.to_string(),
+                domain: (min, max),
+                log_scale,
+            })
+        };
+        assert_eq!(
+            get_shared_y_domains(&[
+                alignment("a", 0.0, 10.0, false),
+                None,
+                alignment("a", -5.0, 5.0, false),
+                alignment("b", 1.0, 100.0, true),
+                alignment("a", -100.0, 100.0, true),
+            ]),
+            vec![
+                SharedYDomain::Shared(-5.0, 10.0),
+                SharedYDomain::Own,
+                SharedYDomain::Shared(-5.0, 10.0),
+                SharedYDomain::Shared(1.0, 100.0),
+                SharedYDomain::ScaleMismatch,
+            ]
+        );
+    }
+}
@@ -6,11 +6,26 @@
  * Copyright (C) 2023, Nikita Almakov
  */
 use crate::animate::AnimatedNumber;
+use crate::data_set::{get_paint_order, get_shared_unit, AreaFill, DataPoint};
+use crate::date_format::format_date_ticks;
 use crate::grid::{Grid, Tick};
 use crate::params::Content;
-use crate::params::{ChartConfig, VerboseFormat};
+use crate::params::{
+    ChartConfig, DataType, EdgeBehavior, LabelOverlap, PointLabelsShow, VerboseFormat,
+    WatermarkLayer,
+};
+use crate::pattern::{
+    create_no_data_pattern, get_comparison_line_dash, get_line_dash, trace_marker,
+};
 use crate::scale::Scale;
 use crate::screen::{Screen, ScreenArea};
+use crate::time_zone::TimeZone;
+use crate::utils::{
+    dedup_labels, get_label_step, place_labels, snap_line_width, snap_rect, snap_to_pixel,
+    truncate_with_ellipsis, LabelBox,
+};
+use crate::watermark::Watermark;
+use std::f64::consts::FRAC_PI_2;
 use std::rc::Rc;
 use wasm_bindgen::JsValue;
 
@@ -24,6 +39,10 @@ pub enum Axis {
 
 const COORD_TICKS_DUTY_FACTOR: f64 = 1.5;
 const VALUE_TICKS_DUTY_FACTOR: f64 = 5.0;
+const AXIS_TITLE_HEIGHT_FACTOR: f64 = 1.5;
+const MINOR_GRID_MIN_SPACING: f64 = 3.0;
+const POINT_LABEL_GAP: f64 = 4.0;
+const HIGHLIGHT_LINE_WIDTH_COEFF: f64 = 1.5;
 
 pub struct Camera<T>
 where
@@ -35,11 +54,28 @@ where
     pub coord: AnimatedNumber,
     pub coord_range: AnimatedNumber,
     pub coord_ticks_height: f64,
+    base_coord_ticks_height: f64,
+    padding: [f64; 4],
+    base_padding_top: f64,
+    /// Tick labels and axis titles are left out, see `DroppedParts`.
+    axis_labels_dropped: bool,
+    /// Grid lines and paddings are left out too.
+    sparkline: bool,
     pub value: AnimatedNumber,
     pub value_range: AnimatedNumber,
+    /// Fixed y domain, e.g. one shared by the link group; the y axis fits
+    /// the data in view when None.
+    value_domain: Option<(f64, f64)>,
     pub value_ticks_width: f64,
     pub coord_grid: Grid,
     pub value_grid: Grid,
+    /// Index of the data set drawn thicker on top of the others, which are
+    /// dimmed.
+    highlighted: Option<usize>,
+    /// The unit of the y ticks when it goes to the title.
+    y_unit_suffix: Option<String>,
+    /// The unit of the visible data sets when they share one.
+    series_unit: Option<String>,
     pub dirty: bool,
 }
 impl<T> Camera<T>
@@ -55,8 +91,9 @@ where
         screen: &Screen,
         padding: [f64; 4],
     ) -> Camera<T> {
-        let screen_area = ScreenArea::new(scale, screen, padding);
-        let coord_grid = Grid::new(
+        let mut screen_area = ScreenArea::new(scale, screen, padding);
+        screen_area.reversed_x = chart_config.is_x_axis_reversed();
+        let coord_grid = Grid::with_nice_steps(
             content.coord_type,
             content.global_coord_min,
             content.global_coord_max,
@@ -73,24 +110,65 @@ where
             coord: AnimatedNumber::new(0.0),
             coord_range: AnimatedNumber::new(0.0),
             coord_ticks_height,
+            base_coord_ticks_height: coord_ticks_height,
+            padding,
+            base_padding_top: padding[0],
+            axis_labels_dropped: false,
+            sparkline: false,
             value: AnimatedNumber::new(0.0),
             value_range: AnimatedNumber::new(0.0),
+            value_domain: None,
             value_ticks_width,
             coord_grid,
             value_grid,
+            highlighted: None,
+            y_unit_suffix: None,
+            series_unit: None,
             dirty: false,
         };
         camera.update_by_content(content, None);
         camera
     }
+    pub fn set_compact(&mut self, axis_labels_dropped: bool, sparkline: bool) {
+        if self.axis_labels_dropped != axis_labels_dropped || self.sparkline != sparkline {
+            self.axis_labels_dropped = axis_labels_dropped;
+            self.sparkline = sparkline;
+            self.dirty = true;
+        }
+    }
+    pub fn set_highlighted(&mut self, highlighted: Option<usize>) {
+        if self.highlighted != highlighted {
+            self.highlighted = highlighted;
+            self.dirty = true;
+        }
+    }
+    pub fn is_log_scale(&self) -> bool {
+        self.screen_area.scale.is_log()
+    }
+    /// Takes effect with the next update of the value range; the origin is
+    /// that of log scales, see `Scale::set_value_origin`.
+    pub fn set_value_domain(&mut self, value_domain: Option<(f64, f64)>, value_origin: f64) {
+        self.value_domain = value_domain;
+        self.screen_area.scale.set_value_origin(value_origin);
+        self.screen_area.global_scale.set_value_origin(value_origin);
+        self.dirty = true;
+    }
+    fn set_value_bounds(&mut self, value_min: f64, value_max: f64, time_us: Option<f64>) {
+        let (value_min, value_max) = self.value_domain.unwrap_or((value_min, value_max));
+        self.value.set_value((value_max + value_min) * 0.5, time_us);
+        self.value_range.set_value(value_max - value_min, time_us);
+    }
     pub fn update_by_content(&mut self, content: &mut Content, time_us: Option<f64>) {
         self.dirty = true;
         let mut coord_min: f64 = f64::MAX;
         let mut coord_max: f64 = f64::MIN;
         let mut value_min: f64 = f64::MAX;
         let mut value_max: f64 = f64::MIN;
+        let only_comparisons_visible = content.only_comparisons_visible();
         for data_set in content.data_sets.iter_mut() {
-            if data_set.alpha.get_end_value() > 0.0 {
+            if data_set.alpha.get_end_value() > 0.0
+                && data_set.is_in_value_domain(only_comparisons_visible)
+            {
                 coord_min = coord_min.min(data_set.data_points[0].coord);
                 coord_max =
                     coord_min.max(data_set.data_points[data_set.data_points.len() - 1].coord);
@@ -102,8 +180,7 @@ where
         }
         self.coord.set_value((coord_max + coord_min) * 0.5, time_us);
         self.coord_range.set_value(coord_max - coord_min, time_us);
-        self.value.set_value((value_max + value_min) * 0.5, time_us);
-        self.value_range.set_value(value_max - value_min, time_us);
+        self.set_value_bounds(value_min, value_max, time_us);
     }
     pub fn zoom_by_coords(
         &mut self,
@@ -119,14 +196,17 @@ where
         let mut value_min: f64 = f64::MAX;
         let mut value_max: f64 = f64::MIN;
         let mut number_of_points: usize = 0;
+        let only_comparisons_visible = content.only_comparisons_visible();
         for data_set in content.data_sets.iter_mut() {
-            if data_set.alpha.get_end_value() > 0.0 {
-                if let Some(data_points) = data_set.slice_by_coord(coord_start, coord_end) {
-                    number_of_points = number_of_points.max(data_points.len());
-                    for data_point in data_points.iter() {
-                        value_min = value_min.min(data_point.value);
-                        value_max = value_max.max(data_point.value);
-                    }
+            if data_set.alpha.get_end_value() > 0.0
+                && data_set.is_in_value_domain(only_comparisons_visible)
+            {
+                if let Some((min, max, count)) =
+                    data_set.get_value_range_by_coord(coord_start, coord_end)
+                {
+                    number_of_points = number_of_points.max(count);
+                    value_min = value_min.min(min);
+                    value_max = value_max.max(max);
                 }
             }
         }
@@ -135,8 +215,7 @@ where
             self.coord
                 .set_value((coord_start + coord_end) * 0.5, time_us);
             self.coord_range.set_value(coord_end - coord_start, time_us);
-            self.value_range.set_value(value_max - value_min, time_us);
-            self.value.set_value((value_min + value_max) * 0.5, time_us);
+            self.set_value_bounds(value_min, value_max, time_us);
         }
     }
     pub fn move_to(&mut self, content: &mut Content, coord_center: f64, time_us: Option<f64>) {
@@ -147,18 +226,20 @@ where
         let coord_end = coord_center + coord_half_range;
         let mut value_min: f64 = f64::MAX;
         let mut value_max: f64 = f64::MIN;
+        let only_comparisons_visible = content.only_comparisons_visible();
         for data_set in content.data_sets.iter_mut() {
-            if data_set.alpha.get_end_value() > 0.0 {
-                if let Some(data_points) = data_set.slice_by_coord(coord_start, coord_end) {
-                    for data_point in data_points.iter() {
-                        value_min = value_min.min(data_point.value);
-                        value_max = value_max.max(data_point.value);
-                    }
+            if data_set.alpha.get_end_value() > 0.0
+                && data_set.is_in_value_domain(only_comparisons_visible)
+            {
+                if let Some((min, max, _)) =
+                    data_set.get_value_range_by_coord(coord_start, coord_end)
+                {
+                    value_min = value_min.min(min);
+                    value_max = value_max.max(max);
                 }
             }
         }
-        self.value_range.set_value(value_max - value_min, time_us);
-        self.value.set_value((value_min + value_max) * 0.5, time_us);
+        self.set_value_bounds(value_min, value_max, time_us);
     }
     pub fn sync_screen_area(&mut self, screen: &mut Screen, time_us: f64) {
         if self.scale_time_us != time_us {
@@ -181,13 +262,23 @@ where
             );
         }
     }
+    /// The area as of the last shoot, for queries between frames.
+    pub fn get_last_screen_area(&self) -> &ScreenArea<T> {
+        &self.screen_area
+    }
     pub fn get_content_screen_area(&self, time_us: f64) -> &ScreenArea<T> {
         if self.scale_time_us != time_us {
             panic!("screen area out of sync");
         }
         &self.screen_area
     }
-    pub fn shoot(&mut self, content: &mut Content, screen: &mut Screen, time_us: f64) {
+    pub fn shoot(
+        &mut self,
+        content: &mut Content,
+        screen: &mut Screen,
+        watermark: Option<&Watermark>,
+        time_us: f64,
+    ) {
         if !self.dirty {
             self.scale_time_us = time_us;
             return;
@@ -195,19 +286,56 @@ where
         let animated_numbers_before = ANIMATED_NUMBERS_COUNT.load(Ordering::Relaxed);
 
         self.sync_screen_area(screen, time_us);
+        self.series_unit = get_shared_unit(
+            content
+                .data_sets
+                .iter()
+                .filter(|data_set| data_set.alpha.get_end_value() > 0.0),
+        )
+        .map(|unit| unit.to_string());
         screen.clear();
+        if self.base_coord_ticks_height > 0.0 && self.chart_config.negative_region_fill {
+            self.draw_negative_region(screen, time_us);
+        }
 
         if self.coord_ticks_height > 0.0 {
-            let ticks = self.get_coord_ticks(
-                self.get_content_screen_area(time_us).get_content_cwidth()
-                    / (screen.apx_to_cpx(
+            let c_tick_spacing = match self.chart_config.x_tick_spacing {
+                Some(x_tick_spacing) => screen.apx_to_cpx(x_tick_spacing),
+                None => {
+                    screen.apx_to_cpx(
                         self.chart_config.font_size_small * self.chart_config.font_width_coeff,
                     ) * content.coord_short_verbose_len as f64
-                        * COORD_TICKS_DUTY_FACTOR),
+                        * COORD_TICKS_DUTY_FACTOR
+                }
+            };
+            let ticks = self.get_coord_ticks(
+                self.get_content_screen_area(time_us).get_content_cwidth() / c_tick_spacing,
                 time_us,
             );
-            self.draw_grid(screen, ticks.as_slice(), Axis::X, time_us);
-            self.draw_ticks(screen, content, ticks.as_slice(), Axis::X, time_us);
+            let step = ticks
+                .iter()
+                .filter(|tick| tick.end_alpha == 1.0)
+                .map(|tick| tick.value)
+                .collect::<Vec<f64>>()
+                .windows(2)
+                .map(|pair| pair[1] - pair[0])
+                .fold(f64::MAX, f64::min);
+            if step != f64::MAX {
+                content.update_coord_granularity(step);
+            }
+            let (ticks, labels) = self.format_ticks(content, ticks, &Axis::X);
+            if !self.sparkline {
+                self.draw_grid(screen, ticks.as_slice(), Axis::X, time_us);
+            }
+            if !self.axis_labels_dropped {
+                self.draw_ticks(
+                    screen,
+                    ticks.as_slice(),
+                    labels.as_slice(),
+                    Axis::X,
+                    time_us,
+                );
+            }
         }
         if self.value_ticks_width > 0.0 {
             let ticks = self.get_value_ticks(
@@ -216,55 +344,314 @@ where
                         * VALUE_TICKS_DUTY_FACTOR),
                 time_us,
             );
-            self.draw_grid(screen, ticks.as_slice(), Axis::Y, time_us);
-            self.draw_ticks(screen, content, ticks.as_slice(), Axis::Y, time_us);
+            let (ticks, labels) = self.format_ticks(content, ticks, &Axis::Y);
+            if !self.sparkline {
+                if self.chart_config.grid_minor_show {
+                    self.draw_minor_grid(screen, ticks.as_slice(), time_us);
+                }
+                self.draw_grid(screen, ticks.as_slice(), Axis::Y, time_us);
+            }
+            if !self.axis_labels_dropped {
+                self.draw_ticks(
+                    screen,
+                    ticks.as_slice(),
+                    labels.as_slice(),
+                    Axis::Y,
+                    time_us,
+                );
+            }
+        }
+        if self.base_coord_ticks_height > 0.0 && self.chart_config.zero_line_show && !self.sparkline
+        {
+            self.draw_zero_line(screen, time_us);
+        }
+        if self.base_coord_ticks_height > 0.0 && !self.axis_labels_dropped {
+            self.draw_titles(screen, time_us);
         }
 
         let context = &screen.context;
         let content_screen_area = self.get_content_screen_area(time_us);
+        let watermark_rect = [
+            content_screen_area.left_cx(),
+            content_screen_area.top_cy(),
+            content_screen_area.right_cx(),
+            content_screen_area.bottom_cy(),
+        ];
+        if let Some(watermark) = watermark {
+            if self.chart_config.watermark_layer == WatermarkLayer::BelowSeries {
+                watermark.draw(&self.chart_config, screen, watermark_rect);
+            }
+        }
+        // the preview always shows the whole data
+        let past_edges = self.base_coord_ticks_height > 0.0;
+        if past_edges && self.chart_config.edge_behavior == EdgeBehavior::Fade {
+            self.draw_no_data_regions(screen, content, time_us);
+        }
         let mut alpha: f64;
-        for data_set in content.data_sets.iter_mut() {
-            alpha = data_set.alpha.get_value(time_us);
+        let mut c_line_width: f64;
+        let has_comparisons = content
+            .data_sets
+            .iter()
+            .any(|data_set| data_set.comparison.is_some());
+        let highlighted = self
+            .highlighted
+            .filter(|index| *index < content.data_sets.len());
+        for index in get_paint_order(&content.data_sets, highlighted) {
+            let data_set = &mut content.data_sets[index];
+            let shown = data_set.alpha.get_value(time_us);
+            alpha = shown * data_set.opacity;
             if alpha == 0.0 {
                 continue;
             }
+            if data_set.entering && shown == 1.0 {
+                data_set.entering = false;
+            }
+            let grow = (data_set.entering && self.chart_config.series_enter_grow)
+                .then(|| (shown, self.get_baseline_cy(time_us)));
+            c_line_width = screen.apx_to_cpx(self.chart_config.line_width);
+            match highlighted {
+                Some(highlighted) if highlighted == index => {
+                    c_line_width *= HIGHLIGHT_LINE_WIDTH_COEFF;
+                }
+                Some(_) if data_set.hoverable => alpha *= self.chart_config.hover_dim_opacity,
+                Some(_) => {}
+                None => {}
+            }
             if let Some(data_points) = data_set.slice_by_coord(
                 content_screen_area.scale.get_coord_min(),
                 content_screen_area.scale.get_coord_max(),
             ) {
-                let mut it = data_points.iter();
-                let data_point = it.next().unwrap();
-                context.begin_path();
+                let runs = data_set.split_at_gaps(data_points);
+                if let Some(fill) = &data_set.fill {
+                    let baseline_cy = self.get_baseline_cy(time_us);
+                    context.begin_path();
+                    for run in runs.iter() {
+                        let (first_cx, last_cx) =
+                            Self::trace_line(context, content_screen_area, run, grow);
+                        context.line_to(last_cx, baseline_cy);
+                        context.line_to(first_cx, baseline_cy);
+                        context.close_path();
+                    }
+                    match fill {
+                        AreaFill::Solid(v) => {
+                            context.set_fill_style_str(
+                                format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3 * alpha)
+                                    .as_str(),
+                            );
+                        }
+                        // created for every shot, so it follows the plot height
+                        AreaFill::Gradient(stops) => {
+                            // from the plot edge away from the baseline
+                            let start_cy = if baseline_cy > content_screen_area.top_cy() {
+                                content_screen_area.top_cy()
+                            } else {
+                                content_screen_area.bottom_cy()
+                            };
+                            let gradient =
+                                context.create_linear_gradient(0.0, start_cy, 0.0, baseline_cy);
+                            for (offset, v) in stops.iter() {
+                                gradient
+                                    .add_color_stop(
+                                        *offset as f32,
+                                        format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3 * alpha)
+                                            .as_str(),
+                                    )
+                                    .unwrap();
+                            }
+                            context.set_fill_style_canvas_gradient(&gradient);
+                        }
+                    }
+                    context.fill();
+                }
                 context.set_stroke_style(&JsValue::from_str(data_set.to_css_color(alpha).as_str()));
-                context.set_line_width(screen.apx_to_cpx(self.chart_config.line_width));
+                context.set_line_width(c_line_width);
+                if data_set.comparison.is_some() {
+                    context
+                        .set_line_dash(&get_comparison_line_dash(screen.apx_to_cpx(1.0)))
+                        .unwrap();
+                } else if self.chart_config.series_patterns {
+                    context
+                        .set_line_dash(&get_line_dash(data_set.pattern, screen.apx_to_cpx(1.0)))
+                        .unwrap();
+                } else if has_comparisons {
+                    context.set_line_dash(&js_sys::Array::new()).unwrap();
+                }
 
-                let mut prev_x = content_screen_area.get_cx(data_point.coord);
-                let mut prev_y = content_screen_area.get_cy(data_point.value);
-                context.move_to(prev_x, prev_y);
-                let mut x: f64;
-                let mut y: f64;
-                for data_point in it {
-                    x = content_screen_area.get_cx(data_point.coord);
-                    y = content_screen_area.get_cy(data_point.value);
-                    if x - prev_x >= 1.0 || (y - prev_y).abs() >= 1.0 {
-                        context.line_to(x, y);
-                        prev_x = x;
-                        prev_y = y;
-                    }
+                context.begin_path();
+                for run in runs.iter() {
+                    Self::trace_line(context, content_screen_area, run, grow);
                 }
                 context.stroke();
             }
+            if past_edges
+                && self.chart_config.edge_behavior == EdgeBehavior::ExtendLast
+                && data_set.comparison.is_none()
+            {
+                let last = &data_set.data_points[data_set.data_points.len() - 1];
+                if last.coord < content_screen_area.scale.get_coord_max() {
+                    let cy = content_screen_area.get_cy(last.value);
+                    context
+                        .set_line_dash(&get_comparison_line_dash(screen.apx_to_cpx(1.0)))
+                        .unwrap();
+                    context.begin_path();
+                    context.move_to(
+                        content_screen_area
+                            .get_cx(last.coord)
+                            .max(content_screen_area.left_cx()),
+                        cy,
+                    );
+                    context.line_to(content_screen_area.right_cx(), cy);
+                    context.stroke();
+                    context.set_line_dash(&js_sys::Array::new()).unwrap();
+                }
+            }
+        }
+        if self.chart_config.series_patterns || has_comparisons {
+            context.set_line_dash(&js_sys::Array::new()).unwrap();
+        }
+        // the preview has no axes and no markers
+        if self.base_coord_ticks_height > 0.0 {
+            self.draw_point_markers(screen, content, highlighted, time_us);
+        }
+        if let Some(watermark) = watermark {
+            if self.chart_config.watermark_layer == WatermarkLayer::AboveSeries {
+                watermark.draw(&self.chart_config, screen, watermark_rect);
+            }
+        }
+
+        if self.base_coord_ticks_height > 0.0
+            && self.chart_config.point_labels_show != PointLabelsShow::Never
+        {
+            self.draw_point_labels(screen, content, time_us);
         }
 
-        if ANIMATED_NUMBERS_COUNT.load(Ordering::Relaxed) == animated_numbers_before {
+        // measured tick labels may need a different padding; the next frame
+        // is drawn with the updated layout
+        let padding = self.get_axes_padding();
+        if ANIMATED_NUMBERS_COUNT.load(Ordering::Relaxed) == animated_numbers_before
+            && padding == self.padding
+        {
             self.dirty = false;
         }
+        if padding != self.padding {
+            self.padding = padding;
+            self.screen_area.set_padding(screen, padding);
+        }
+    }
+    fn get_axes_padding(&self) -> [f64; 4] {
+        let mut padding = self.padding;
+        // the measured tick sizes are kept for when the labels are back
+        if self.axis_labels_dropped {
+            let top = if self.sparkline {
+                0.0
+            } else {
+                self.base_padding_top
+            };
+            return [top, 0.0, 0.0, 0.0];
+        }
+        padding[0] = self.base_padding_top;
+        if self.base_coord_ticks_height > 0.0 {
+            padding[2] = self.coord_ticks_height;
+        }
+        if self.value_ticks_width > 0.0 {
+            let position = &self.chart_config.y_axis_position;
+            padding[1] = if position.has_right() {
+                self.value_ticks_width
+            } else {
+                0.0
+            };
+            padding[3] = if position.has_left() {
+                self.value_ticks_width
+            } else {
+                0.0
+            };
+        }
+        if self.base_coord_ticks_height > 0.0 {
+            let title_height = self.chart_config.font_size_normal * AXIS_TITLE_HEIGHT_FACTOR;
+            if self.chart_config.x_axis_title.is_some() {
+                padding[2] += title_height;
+            }
+            if self.chart_config.has_y_axis_title() || self.series_unit.is_some() {
+                if self.chart_config.y_axis_position.has_left() {
+                    padding[3] += title_height;
+                } else {
+                    padding[1] += title_height;
+                }
+            }
+        }
+        padding
+    }
+    fn draw_titles(&self, screen: &Screen, time_us: f64) {
+        let screen_area = self.get_content_screen_area(time_us);
+        let context = &screen.context;
+        let c_title_height =
+            screen.apx_to_cpx(self.chart_config.font_size_normal * AXIS_TITLE_HEIGHT_FACTOR);
+        let v = &self.chart_config.color_tick;
+        context.set_fill_style_str(format!("rgb({}, {}, {})", v.0, v.1, v.2).as_str());
+        context.set_font(
+            format!(
+                "{}px {}",
+                screen.apx_to_cpx(self.chart_config.font_size_normal),
+                self.chart_config.font_standard.as_str()
+            )
+            .as_str(),
+        );
+        context.set_text_align("center");
+        context.set_text_baseline("middle");
+        let measure = |text: &str| context.measure_text(text).unwrap().width();
+
+        if let Some(title) = &self.chart_config.x_axis_title {
+            let title = truncate_with_ellipsis(title, screen_area.get_content_cwidth(), measure);
+            context
+                .fill_text(
+                    title.as_str(),
+                    (screen_area.left_cx() + screen_area.right_cx()) * 0.5,
+                    screen_area.bottom_cy()
+                        + screen.apx_to_cpx(self.coord_ticks_height)
+                        + c_title_height * 0.5,
+                )
+                .unwrap();
+        }
+        let unit = self.y_unit_suffix.as_ref().or(self.series_unit.as_ref());
+        let y_title = match (&self.chart_config.y_axis_title, unit) {
+            (Some(title), Some(suffix)) => Some(format!("{} ({})", title, suffix)),
+            (Some(title), None) => Some(title.clone()),
+            (None, suffix) => suffix.cloned(),
+        };
+        if let Some(title) = &y_title {
+            let title = truncate_with_ellipsis(title, screen_area.get_content_cheight(), measure);
+            let c_offset = screen.apx_to_cpx(self.value_ticks_width) + c_title_height * 0.5;
+            let (cx, angle) = if self.chart_config.y_axis_position.has_left() {
+                (screen_area.left_cx() - c_offset, -FRAC_PI_2)
+            } else {
+                (screen_area.right_cx() + c_offset, FRAC_PI_2)
+            };
+            context.save();
+            context
+                .translate(cx, (screen_area.top_cy() + screen_area.bottom_cy()) * 0.5)
+                .unwrap();
+            context.rotate(angle).unwrap();
+            context.fill_text(title.as_str(), 0.0, 0.0).unwrap();
+            context.restore();
+        }
+    }
+    pub fn get_padding(&self) -> [f64; 4] {
+        self.padding
+    }
+    pub fn set_horizontal_padding(&mut self, screen: &Screen, left: f64, right: f64) {
+        if self.padding[1] != right || self.padding[3] != left {
+            self.padding[1] = right;
+            self.padding[3] = left;
+            self.screen_area.set_padding(screen, self.padding);
+            self.dirty = true;
+        }
     }
     fn draw_grid(&mut self, screen: &mut Screen, ticks: &[Tick], axis: Axis, time_us: f64) {
         let screen_area = self.get_content_screen_area(time_us);
         let context = &screen.context;
         let mut alpha: f64 = -1.0;
-        context.set_line_width(1.0);
+        let c_line_width = snap_line_width(self.chart_config.stroke_width_coeff);
+        context.set_line_width(c_line_width);
         let v = &self.chart_config.color_grid;
         match axis {
             Axis::X => {
@@ -276,8 +663,9 @@ where
                         ));
                         alpha = tick.alpha;
                     }
-                    context.move_to(screen_area.get_cx(tick.value), screen_area.bottom_cy());
-                    context.line_to(screen_area.get_cx(tick.value), screen_area.top_cy());
+                    let cx = snap_to_pixel(screen_area.get_cx(tick.value), c_line_width);
+                    context.move_to(cx, screen_area.bottom_cy());
+                    context.line_to(cx, screen_area.top_cy());
                     context.stroke();
                 }
             }
@@ -290,22 +678,324 @@ where
                         ));
                         alpha = tick.alpha;
                     }
-                    context.move_to(screen_area.left_cx(), screen_area.get_cy(tick.value));
-                    context.line_to(screen_area.right_cx(), screen_area.get_cy(tick.value));
+                    let cy = snap_to_pixel(screen_area.get_cy(tick.value), c_line_width);
+                    context.move_to(screen_area.left_cx(), cy);
+                    context.line_to(screen_area.right_cx(), cy);
                     context.stroke();
                 }
             }
         }
     }
-    fn draw_ticks(
-        &mut self,
-        screen: &mut Screen,
-        content: &Content,
-        ticks: &[Tick],
-        axis: Axis,
+    /// Hatches the parts of the plot before the first and after the last
+    /// point of the visible data sets.
+    fn draw_no_data_regions(&self, screen: &Screen, content: &Content, time_us: f64) {
+        let screen_area = self.get_content_screen_area(time_us);
+        let (first_coord, last_coord) = content
+            .data_sets
+            .iter()
+            .filter(|data_set| data_set.alpha.get_end_value() > 0.0)
+            .fold((f64::MAX, f64::MIN), |(first, last), data_set| {
+                (
+                    first.min(data_set.data_points[0].coord),
+                    last.max(data_set.data_points[data_set.data_points.len() - 1].coord),
+                )
+            });
+        let left_cx = screen_area.left_cx();
+        let right_cx = screen_area.right_cx();
+        let regions = [
+            (left_cx, screen_area.get_cx(first_coord).min(right_cx)),
+            (screen_area.get_cx(last_coord).max(left_cx), right_cx),
+        ];
+        if regions.iter().all(|(from, to)| from >= to) {
+            return;
+        }
+        let context = &screen.context;
+        if let Some(pattern) = create_no_data_pattern(
+            context,
+            self.chart_config.color_tick,
+            screen.apx_to_cpx(1.0),
+        ) {
+            context.set_fill_style_canvas_pattern(&pattern);
+            for (from, to) in regions.into_iter().filter(|(from, to)| from < to) {
+                context.fill_rect(
+                    from,
+                    screen_area.top_cy(),
+                    to - from,
+                    screen_area.get_content_cheight(),
+                );
+            }
+        }
+    }
+    /// Adds a subpath through the points, skipping those less than a canvas
+    /// pixel apart and scaled towards the baseline cy by the factor of `grow`,
+    /// if any; returns the cx of the first and the last point.
+    fn trace_line(
+        context: &web_sys::CanvasRenderingContext2d,
+        screen_area: &ScreenArea<T>,
+        data_points: &[DataPoint],
+        grow: Option<(f64, f64)>,
+    ) -> (f64, f64) {
+        let get_cy = |value: f64| match grow {
+            Some((factor, baseline_cy)) => {
+                baseline_cy + (screen_area.get_cy(value) - baseline_cy) * factor
+            }
+            None => screen_area.get_cy(value),
+        };
+        let mut it = data_points.iter();
+        let data_point = it.next().unwrap();
+        let first_x = screen_area.get_cx(data_point.coord);
+        let mut prev_x = first_x;
+        let mut prev_y = get_cy(data_point.value);
+        context.move_to(prev_x, prev_y);
+        let mut x: f64;
+        let mut y: f64;
+        for data_point in it {
+            x = screen_area.get_cx(data_point.coord);
+            y = get_cy(data_point.value);
+            if (x - prev_x).abs() >= 1.0 || (y - prev_y).abs() >= 1.0 {
+                context.line_to(x, y);
+                prev_x = x;
+                prev_y = y;
+            }
+        }
+        (first_x, prev_x)
+    }
+    /// Returns the cy area fills go down to: y = 0 when in view, otherwise
+    /// the edge of the plot nearer to it.
+    fn get_baseline_cy(&self, time_us: f64) -> f64 {
+        let screen_area = self.get_content_screen_area(time_us);
+        match self.get_zero_cy(time_us) {
+            Some(zero_cy) => zero_cy,
+            None if screen_area.scale.get_value_max() < 0.0 => screen_area.top_cy(),
+            None => screen_area.bottom_cy(),
+        }
+    }
+    /// Returns the cy of y = 0, None when 0 is out of the y domain (always so
+    /// on a log scale).
+    fn get_zero_cy(&self, time_us: f64) -> Option<f64> {
+        let screen_area = self.get_content_screen_area(time_us);
+        let (value_min, value_max) = (
+            screen_area.scale.get_value_min(),
+            screen_area.scale.get_value_max(),
+        );
+        if value_min <= 0.0 && 0.0 <= value_max && value_min < value_max {
+            Some(screen_area.get_cy(0.0))
+        } else {
+            None
+        }
+    }
+    fn draw_negative_region(&self, screen: &Screen, time_us: f64) {
+        if let Some(zero_cy) = self.get_zero_cy(time_us) {
+            let screen_area = self.get_content_screen_area(time_us);
+            let v = &self.chart_config.color_negative_region;
+            let context = &screen.context;
+            context
+                .set_fill_style_str(format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3).as_str());
+            context.fill_rect(
+                screen_area.left_cx(),
+                zero_cy,
+                screen_area.right_cx() - screen_area.left_cx(),
+                screen_area.bottom_cy() - zero_cy,
+            );
+        }
+    }
+    fn draw_zero_line(&self, screen: &Screen, time_us: f64) {
+        if let Some(zero_cy) = self.get_zero_cy(time_us) {
+            let screen_area = self.get_content_screen_area(time_us);
+            let v = &self.chart_config.color_zero_line;
+            let context = &screen.context;
+            context
+                .set_stroke_style_str(format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3).as_str());
+            let c_line_width =
+                snap_line_width(screen.apx_to_cpx(self.chart_config.zero_line_width));
+            let zero_cy = snap_to_pixel(zero_cy, c_line_width);
+            context.set_line_width(c_line_width);
+            context.begin_path();
+            context.move_to(screen_area.left_cx(), zero_cy);
+            context.line_to(screen_area.right_cx(), zero_cy);
+            context.stroke();
+        }
+    }
+    /// Draws the markers of points in view above all the lines.
+    fn draw_point_markers(
+        &self,
+        screen: &Screen,
+        content: &mut Content,
+        highlighted: Option<usize>,
         time_us: f64,
     ) {
         let screen_area = self.get_content_screen_area(time_us);
+        let context = &screen.context;
+        let (coord_min, coord_max) = (
+            screen_area.scale.get_coord_min(),
+            screen_area.scale.get_coord_max(),
+        );
+        for index in get_paint_order(&content.data_sets, highlighted) {
+            let data_set = &mut content.data_sets[index];
+            if data_set.markers.is_empty() {
+                continue;
+            }
+            let mut alpha = data_set.alpha.get_value(time_us) * data_set.opacity;
+            if data_set.hoverable && highlighted.is_some_and(|highlighted| highlighted != index) {
+                alpha *= self.chart_config.hover_dim_opacity;
+            }
+            if alpha == 0.0 {
+                continue;
+            }
+            for (point_index, marker) in data_set.markers.iter() {
+                if marker.coord < coord_min || marker.coord > coord_max {
+                    continue;
+                }
+                let (r, g, b) = marker.rgb.unwrap_or(data_set.rgb);
+                context
+                    .set_fill_style_str(format!("rgba({}, {}, {}, {})", r, g, b, alpha).as_str());
+                trace_marker(
+                    context,
+                    marker.shape,
+                    screen_area.get_cx(marker.coord),
+                    screen_area.get_cy(data_set.data_points[*point_index].value),
+                    screen.apx_to_cpx(marker.size.unwrap_or(self.chart_config.point_marker_size)),
+                );
+                context.fill();
+            }
+        }
+    }
+
+    /// Prints values next to points; in the "auto" mode only for data sets
+    /// which points are far enough apart. Overlapping labels are nudged apart
+    /// or dropped, larger values win.
+    fn draw_point_labels(&self, screen: &Screen, content: &mut Content, time_us: f64) {
+        let screen_area = self.get_content_screen_area(time_us);
+        let context = &screen.context;
+        let c_font_size = screen.apx_to_cpx(self.chart_config.font_size_small);
+        let c_gap = screen.apx_to_cpx(POINT_LABEL_GAP);
+        let c_min_spacing = screen.apx_to_cpx(self.chart_config.point_labels_min_spacing);
+        context.set_font(
+            format!(
+                "{}px {}",
+                c_font_size,
+                self.chart_config.font_standard.as_str()
+            )
+            .as_str(),
+        );
+
+        // (value, text, color, box)
+        let mut labels: Vec<(f64, String, String, LabelBox)> = Vec::new();
+        for data_set in content.data_sets.iter_mut() {
+            let alpha = data_set.alpha.get_value(time_us);
+            if alpha == 0.0 {
+                continue;
+            }
+            let data_points = match data_set.slice_by_coord(
+                screen_area.scale.get_coord_min(),
+                screen_area.scale.get_coord_max(),
+            ) {
+                Some(data_points) => data_points,
+                None => continue,
+            };
+            let positions: Vec<(f64, f64)> = data_points
+                .iter()
+                .map(|p| (screen_area.get_cx(p.coord), screen_area.get_cy(p.value)))
+                .collect();
+            if self.chart_config.point_labels_show == PointLabelsShow::Auto
+                && positions
+                    .windows(2)
+                    .any(|pair| (pair[1].0 - pair[0].0).abs() < c_min_spacing)
+            {
+                continue;
+            }
+            let texts = content.value_verbose_format_short.format_values(
+                data_points.iter(),
+                |p| p.value,
+                screen_area.scale.get_value_min(),
+                screen_area.scale.get_value_max(),
+            );
+            for ((data_point, (cx, cy)), text) in data_points.iter().zip(positions).zip(texts) {
+                if cx < screen_area.left_cx() || cx > screen_area.right_cx() {
+                    continue;
+                }
+                let width = context.measure_text(text.as_str()).unwrap().width();
+                let above = cy - c_gap - c_font_size >= screen_area.top_cy();
+                labels.push((
+                    data_point.value,
+                    text,
+                    data_set.to_css_color(alpha),
+                    LabelBox {
+                        x: cx - width * 0.5,
+                        y: if above {
+                            cy - c_gap - c_font_size
+                        } else {
+                            cy + c_gap
+                        },
+                        width,
+                        height: c_font_size,
+                        direction: if above { -1.0 } else { 1.0 },
+                    },
+                ));
+            }
+        }
+        labels.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+        let boxes: Vec<LabelBox> = labels.iter().map(|label| label.3.clone()).collect();
+        let placed = place_labels(
+            boxes.as_slice(),
+            screen_area.top_cy(),
+            screen_area.bottom_cy(),
+            c_font_size * 2.0,
+        );
+
+        context.set_text_align("left");
+        context.set_text_baseline("top");
+        for ((_, text, color, label_box), y) in labels.iter().zip(placed) {
+            if let Some(y) = y {
+                context.set_fill_style_str(color.as_str());
+                context.fill_text(text.as_str(), label_box.x, y).unwrap();
+            }
+        }
+    }
+    fn draw_minor_grid(&self, screen: &Screen, ticks: &[Tick], time_us: f64) {
+        let screen_area = self.get_content_screen_area(time_us);
+        // fading generations of major ticks would mix periods
+        let major: Vec<f64> = ticks
+            .iter()
+            .filter(|tick| tick.end_alpha == 1.0)
+            .map(|tick| tick.value)
+            .collect();
+        let positions: Vec<f64> = screen_area
+            .scale
+            .minor_ticks(major.as_slice(), self.chart_config.grid_minor_count)
+            .into_iter()
+            .map(|value| screen_area.get_cy(value))
+            .collect();
+        let c_min_spacing = screen.apx_to_cpx(MINOR_GRID_MIN_SPACING);
+        if positions
+            .windows(2)
+            .any(|pair| (pair[1] - pair[0]).abs() < c_min_spacing)
+        {
+            return;
+        }
+        let context = &screen.context;
+        let v = &self.chart_config.color_grid_minor;
+        context.set_stroke_style_str(format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3).as_str());
+        let c_line_width =
+            snap_line_width(screen.apx_to_cpx(self.chart_config.grid_minor_line_width));
+        context.set_line_width(c_line_width);
+        context.begin_path();
+        for cy in positions {
+            let cy = snap_to_pixel(cy, c_line_width);
+            context.move_to(screen_area.left_cx(), cy);
+            context.line_to(screen_area.right_cx(), cy);
+        }
+        context.stroke();
+    }
+    /// Formats the labels of the ticks. Ticks whose label repeats the one
+    /// before lose it, and their gridline, unless more decimals tell them
+    /// apart, see `dedup_labels`.
+    fn format_ticks(
+        &self,
+        content: &Content,
+        ticks: Vec<Tick>,
+        axis: &Axis,
+    ) -> (Vec<Tick>, Vec<String>) {
         let verbose_format: &VerboseFormat;
         let min_value: f64;
         let max_value: f64;
@@ -321,12 +1011,62 @@ where
                 max_value = self.screen_area.scale.get_value_max();
             }
         }
-        let formatted_ticks = verbose_format.format_values(
-            ticks.iter(),
-            |tick: &Tick| tick.value,
-            min_value,
-            max_value,
-        );
+        let formatted_ticks = match (axis, content.coord_type, content.coord_granularity) {
+            (Axis::X, DataType::Date, Some(granularity)) => {
+                let values: Vec<f64> = ticks.iter().map(|tick| tick.value).collect();
+                format_date_ticks(&values, granularity, &TimeZone::Utc)
+            }
+            (Axis::X, DataType::DateTime { time_zone }, Some(granularity)) => {
+                let values: Vec<f64> = ticks.iter().map(|tick| tick.value).collect();
+                format_date_ticks(&values, granularity, &time_zone)
+            }
+            _ => verbose_format.format_values(
+                ticks.iter(),
+                |tick: &Tick| tick.value,
+                min_value,
+                max_value,
+            ),
+        };
+        // dates get no extra decimals, so their repeated labels are dropped
+        let kept = dedup_labels(formatted_ticks, |index, extra_decimals| {
+            verbose_format.format_with_extra_decimals(
+                ticks[index].value,
+                extra_decimals,
+                min_value,
+                max_value,
+            )
+        });
+        let mut kept_ticks = Vec::with_capacity(kept.len());
+        let mut labels = Vec::with_capacity(kept.len());
+        let mut kept = kept.into_iter().peekable();
+        for (index, tick) in ticks.into_iter().enumerate() {
+            if let Some((_, label)) = kept.next_if(|(kept_index, _)| *kept_index == index) {
+                kept_ticks.push(tick);
+                labels.push(label);
+            }
+        }
+        (kept_ticks, labels)
+    }
+    fn draw_ticks(
+        &mut self,
+        screen: &mut Screen,
+        ticks: &[Tick],
+        formatted_ticks: &[String],
+        axis: Axis,
+        time_us: f64,
+    ) {
+        if let (Axis::Y, Some(ladder), true) = (
+            &axis,
+            &self.chart_config.y_unit,
+            self.chart_config.y_unit_in_title,
+        ) {
+            let max_abs = ticks
+                .iter()
+                .map(|tick| tick.value.abs())
+                .fold(0.0, f64::max);
+            self.y_unit_suffix = Some(ladder.pick(max_abs).1.clone());
+        }
+        let screen_area = self.get_content_screen_area(time_us);
         let context = &screen.context;
 
         context.set_font(
@@ -339,14 +1079,67 @@ where
         );
 
         let tick_color = &self.chart_config.color_tick;
+        let mut coord_ticks_height = self.coord_ticks_height;
+        let mut value_ticks_width = self.value_ticks_width;
 
         match axis {
             Axis::X => {
                 let mut alpha: f64 = -1.0;
-                let y = screen_area.bottom_cy() + screen.apx_to_cpx(self.coord_ticks_height * 0.5);
-                context.set_text_align("center");
+                let c_font_size = screen.apx_to_cpx(self.chart_config.font_size_small);
+                let c_min_gap = screen.apx_to_cpx(self.chart_config.x_label_min_gap);
+                let positions: Vec<f64> = ticks
+                    .iter()
+                    .map(|tick| screen_area.get_cx(tick.value))
+                    .collect();
+                let widths: Vec<f64> = formatted_ticks
+                    .iter()
+                    .map(|formatted_tick| {
+                        context
+                            .measure_text(formatted_tick.as_str())
+                            .unwrap()
+                            .width()
+                    })
+                    .collect();
+                let mut step = get_label_step(&positions, &widths, c_min_gap);
+                let rotation = match self.chart_config.x_label_overlap {
+                    LabelOverlap::Rotate if step > 1 => self.chart_config.x_label_rotation,
+                    _ => 0.0,
+                };
+                if rotation != 0.0 {
+                    // rotated labels are spaced by their height across the slant
+                    let rotated_widths = vec![c_font_size / rotation.sin(); widths.len()];
+                    step = get_label_step(&positions, &rotated_widths, c_min_gap);
+                    let max_width = widths.iter().cloned().fold(0.0, f64::max);
+                    coord_ticks_height = self.base_coord_ticks_height.max(
+                        (max_width * rotation.sin() + c_font_size * (rotation.cos() + 1.0))
+                            / screen.apx_to_cpx(1.0),
+                    );
+                } else {
+                    coord_ticks_height = self.base_coord_ticks_height;
+                }
+                // skipping by tick ordinal keeps the same labels while panning
+                let ordinal_period = ticks
+                    .windows(2)
+                    .map(|pair| pair[1].normalized_value - pair[0].normalized_value)
+                    .fold(f64::MAX, f64::min);
+
+                let y = if rotation != 0.0 {
+                    screen_area.bottom_cy() + c_font_size * 0.5
+                } else {
+                    screen_area.bottom_cy() + screen.apx_to_cpx(self.base_coord_ticks_height * 0.5)
+                };
+                context.set_text_align(if rotation != 0.0 { "right" } else { "center" });
                 context.set_text_baseline("middle");
-                for (tick, formatted_tick) in ticks.iter().zip(formatted_ticks.iter()) {
+                for ((tick, formatted_tick), x) in
+                    ticks.iter().zip(formatted_ticks.iter()).zip(positions)
+                {
+                    if step > 1
+                        && ((tick.normalized_value / ordinal_period).round() as i64)
+                            .rem_euclid(step as i64)
+                            != 0
+                    {
+                        continue;
+                    }
                     if tick.alpha != alpha {
                         context.set_fill_style(&JsValue::from_str(
                             format!(
@@ -357,18 +1150,44 @@ where
                         ));
                         alpha = tick.alpha;
                     }
-                    context
-                        .fill_text(formatted_tick.as_str(), screen_area.get_cx(tick.value), y)
-                        .unwrap();
+                    if rotation != 0.0 {
+                        context.save();
+                        context.translate(x, y).unwrap();
+                        context.rotate(-rotation).unwrap();
+                        context
+                            .fill_text(formatted_tick.as_str(), 0.0, 0.0)
+                            .unwrap();
+                        context.restore();
+                    } else {
+                        context.fill_text(formatted_tick.as_str(), x, y).unwrap();
+                    }
                 }
             }
             Axis::Y => {
                 let mut alpha: f64 = -1.0;
-                let x = screen_area.left_cx()
-                    - screen.apx_to_cpx(
-                        self.chart_config.font_size_small * self.chart_config.font_width_coeff,
-                    ) * 0.5;
-                context.set_text_align("right");
+                let c_gap = screen.apx_to_cpx(
+                    self.chart_config.font_size_small * self.chart_config.font_width_coeff,
+                ) * 0.5;
+                let max_width = formatted_ticks
+                    .iter()
+                    .map(|formatted_tick| {
+                        context
+                            .measure_text(formatted_tick.as_str())
+                            .unwrap()
+                            .width()
+                    })
+                    .fold(0.0, f64::max);
+                if max_width > 0.0 {
+                    let apx = screen.apx_to_cpx(1.0);
+                    let required_width = (max_width + c_gap * 2.0) / apx;
+                    // shrinking lazily keeps the plot from jittering while zooming
+                    if required_width > value_ticks_width
+                        || required_width < value_ticks_width - c_gap * 4.0 / apx
+                    {
+                        value_ticks_width = required_width;
+                    }
+                }
+                let position = &self.chart_config.y_axis_position;
                 context.set_text_baseline("middle");
                 for (tick, formatted_tick) in ticks.iter().zip(formatted_ticks.iter()) {
                     if tick.alpha != alpha {
@@ -381,22 +1200,36 @@ where
                         ));
                         alpha = tick.alpha;
                     }
-                    context
-                        .fill_text(formatted_tick.as_str(), x, screen_area.get_cy(tick.value))
-                        .unwrap();
+                    let y = screen_area.get_cy(tick.value);
+                    if position.has_left() {
+                        context.set_text_align("right");
+                        context
+                            .fill_text(formatted_tick.as_str(), screen_area.left_cx() - c_gap, y)
+                            .unwrap();
+                    }
+                    if position.has_right() {
+                        context.set_text_align("left");
+                        context
+                            .fill_text(formatted_tick.as_str(), screen_area.right_cx() + c_gap, y)
+                            .unwrap();
+                    }
                 }
             }
         }
+        self.coord_ticks_height = coord_ticks_height;
+        self.value_ticks_width = value_ticks_width;
     }
 
+    /// Returns the horizontal bounds of the window in canvas pixels.
     pub fn draw_grip(
         &mut self,
         screen: &mut Screen,
         screen_area: &ScreenArea<T>,
         zoomed_in: bool,
         slide_in_progress: bool,
+        focused: bool,
         time_us: f64,
-    ) {
+    ) -> (f64, f64) {
         screen.clear();
         let context = &screen.context;
 
@@ -407,6 +1240,7 @@ where
         let left_x = screen_area.left_cx();
         let right_x = screen_area.right_cx();
         let width = right_x - left_x;
+        let (mut window_x_start, mut window_x_end) = (left_x, right_x);
 
         if zoomed_in {
             let (coord, coord_range) = if slide_in_progress {
@@ -417,47 +1251,60 @@ where
                     self.coord_range.get_value(time_us),
                 )
             };
-            let grip_x_start = screen_area.get_cx(coord - coord_range * 0.5);
-            let grip_x_end = screen_area.get_cx(coord + coord_range * 0.5);
+            let grip_cx_1 = screen_area.get_cx(coord - coord_range * 0.5);
+            let grip_cx_2 = screen_area.get_cx(coord + coord_range * 0.5);
+            // a window past the edges of the data is cut at the preview edges
+            let (grip_x_start, grip_x_end) = (
+                grip_cx_1.min(grip_cx_2).max(left_x),
+                grip_cx_1.max(grip_cx_2).min(right_x),
+            );
             let v = &self.chart_config.color_camera_grip;
             context.set_fill_style(&JsValue::from_str(
                 format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3).as_str(),
             ));
             context.fill_rect(grip_x_start, top_y, grip_x_end - grip_x_start, height);
+            (window_x_start, window_x_end) = (grip_x_start, grip_x_end);
         }
 
         if !slide_in_progress {
-            let v = &self.chart_config.color_preview_overlay;
-            context.set_fill_style(&JsValue::from_str(
-                format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3,).as_str(),
-            ));
-            context.fill_rect(left_x, top_y, width, height);
+            if self.chart_config.preview_mask {
+                // the mask is drawn by the chart, the hint would not be
+                // readable without the overlay
+                let v = &self.chart_config.color_tick;
+                context.set_fill_style_str(format!("rgb({}, {}, {})", v.0, v.1, v.2).as_str());
+            } else {
+                let v = &self.chart_config.color_preview_overlay;
+                context.set_fill_style(&JsValue::from_str(
+                    format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3,).as_str(),
+                ));
+                context.fill_rect(left_x, top_y, width, height);
 
-            context.set_text_align("center");
-            context.set_text_baseline("middle");
-            context.set_font(
-                format!(
-                    "{}px {}",
-                    screen.apx_to_cpx(self.chart_config.font_size_large),
-                    self.chart_config.font_standard.as_str()
-                )
-                .as_str(),
-            );
-            let v = &self.chart_config.color_preview_hint;
-            context.set_fill_style(&JsValue::from_str(
-                format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3,).as_str(),
-            ));
-            context
-                .fill_text(
-                    if zoomed_in {
-                        "Click to zoom out"
-                    } else {
-                        "Drag here or above to zoom in"
-                    },
-                    (left_x + right_x) * 0.5,
-                    (top_y + bottom_y) * 0.5,
-                )
-                .unwrap();
+                context.set_text_align("center");
+                context.set_text_baseline("middle");
+                context.set_font(
+                    format!(
+                        "{}px {}",
+                        screen.apx_to_cpx(self.chart_config.font_size_large),
+                        self.chart_config.font_standard.as_str()
+                    )
+                    .as_str(),
+                );
+                let v = &self.chart_config.color_preview_hint;
+                context.set_fill_style(&JsValue::from_str(
+                    format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3,).as_str(),
+                ));
+                context
+                    .fill_text(
+                        self.chart_config.strings.get(if zoomed_in {
+                            "previewZoomOut"
+                        } else {
+                            "previewZoomIn"
+                        }),
+                        (left_x + right_x) * 0.5,
+                        (top_y + bottom_y) * 0.5,
+                    )
+                    .unwrap();
+            }
 
             let font_cpx_size = screen.apx_to_cpx(self.chart_config.font_size_small);
             context.set_font(
@@ -478,6 +1325,25 @@ where
                 )
                 .unwrap();
         }
+
+        if focused {
+            let c_line_width = snap_line_width(screen.apx_to_cpx(2.0));
+            let v = &self.chart_config.color_tooltip_font;
+            context
+                .set_stroke_style_str(format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3).as_str());
+            context.set_line_width(c_line_width);
+            let (x, y, width, height) = snap_rect(
+                (
+                    window_x_start + c_line_width * 0.5,
+                    top_y + c_line_width * 0.5,
+                    window_x_end - window_x_start - c_line_width,
+                    height - c_line_width,
+                ),
+                c_line_width,
+            );
+            context.stroke_rect(x, y, width, height);
+        }
+        (window_x_start, window_x_end)
     }
     pub fn get_coord_ticks(&mut self, max_ticks: f64, time_us: f64) -> Vec<Tick> {
         let screen_area = self.get_content_screen_area(time_us);
@@ -0,0 +1,137 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use std::str::FromStr;
+
+/// Parts of the chart dropped as its container shrinks.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CompactPart {
+    Legend,
+    AxisLabels,
+    Preview,
+    /// Drops everything but the series, grid lines and paddings included.
+    Sparkline,
+}
+impl FromStr for CompactPart {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "legend" => Ok(Self::Legend),
+            "axisLabels" => Ok(Self::AxisLabels),
+            "preview" => Ok(Self::Preview),
+            "sparkline" => Ok(Self::Sparkline),
+            _ => Err(format!("unsupported compact part: {}", s)),
+        }
+    }
+}
+
+/// Drops the part while the container is narrower than min_width or lower
+/// than min_height, in CSS px.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CompactStep {
+    pub part: CompactPart,
+    pub min_width: f64,
+    pub min_height: f64,
+}
+impl CompactStep {
+    pub const fn new(part: CompactPart, min_width: f64, min_height: f64) -> Self {
+        Self {
+            part,
+            min_width,
+            min_height,
+        }
+    }
+}
+
+pub const DEFAULT_COMPACT_STEPS: [CompactStep; 4] = [
+    CompactStep::new(CompactPart::Legend, 240.0, 160.0),
+    CompactStep::new(CompactPart::AxisLabels, 180.0, 120.0),
+    CompactStep::new(CompactPart::Preview, 150.0, 100.0),
+    CompactStep::new(CompactPart::Sparkline, 100.0, 50.0),
+];
+
+/// What is left out of the layout at the current size.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct DroppedParts {
+    pub legend: bool,
+    pub axis_labels: bool,
+    pub preview: bool,
+    pub sparkline: bool,
+}
+impl DroppedParts {
+    /// Steps are taken in order and each one only along with the ones
+    /// before it, so the order of the steps is the order parts go in; the
+    /// sparkline drops the rest too.
+    pub fn from_steps(steps: &[CompactStep], width: f64, height: f64) -> Self {
+        let mut dropped = Self::default();
+        for step in steps
+            .iter()
+            .take_while(|step| width < step.min_width || height < step.min_height)
+        {
+            match step.part {
+                CompactPart::Legend => dropped.legend = true,
+                CompactPart::AxisLabels => dropped.axis_labels = true,
+                CompactPart::Preview => dropped.preview = true,
+                CompactPart::Sparkline => {
+                    dropped = Self {
+                        legend: true,
+                        axis_labels: true,
+                        preview: true,
+                        sparkline: true,
+                    }
+                }
+            }
+        }
+        dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compact::{CompactPart, CompactStep, DroppedParts, DEFAULT_COMPACT_STEPS};
+
+    #[test]
+    fn test_dropped_parts() {
+        let get = |width, height| DroppedParts::from_steps(&DEFAULT_COMPACT_STEPS, width, height);
+        assert_eq!(get(600.0, 400.0), DroppedParts::default());
+        assert_eq!(
+            get(200.0, 400.0),
+            DroppedParts {
+                legend: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            get(600.0, 90.0),
+            DroppedParts {
+                legend: true,
+                axis_labels: true,
+                preview: true,
+                sparkline: false,
+            }
+        );
+        assert!(get(120.0, 40.0).sparkline);
+
+        // a step is only taken along with the ones before it
+        let steps = [
+            CompactStep::new(CompactPart::Preview, 100.0, 100.0),
+            CompactStep::new(CompactPart::Legend, 300.0, 300.0),
+        ];
+        assert_eq!(
+            DroppedParts::from_steps(&steps, 200.0, 200.0),
+            DroppedParts::default()
+        );
+        assert_eq!(
+            DroppedParts::from_steps(&steps, 50.0, 200.0),
+            DroppedParts {
+                legend: true,
+                preview: true,
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -10,12 +10,27 @@ use js_sys::Reflect;
 use wasm_bindgen::prelude::*;
 
 pub enum ControlEvent {
-    PointerDown { pos: (f64, f64) },
-    PointerMoved { pos: (f64, f64) },
-    PointerUp,
-    PinchStarted { pos1: (f64, f64), pos2: (f64, f64) },
-    PinchUpdated { pos1: (f64, f64), pos2: (f64, f64) },
+    PointerDown {
+        pos: (f64, f64),
+    },
+    PointerMoved {
+        pos: (f64, f64),
+    },
+    /// `modifier` is whether ctrl or meta was held.
+    PointerUp {
+        modifier: bool,
+    },
+    PinchStarted {
+        pos1: (f64, f64),
+        pos2: (f64, f64),
+    },
+    PinchUpdated {
+        pos1: (f64, f64),
+        pos2: (f64, f64),
+    },
     PinchFinished,
+    KeyDown(web_sys::KeyboardEvent),
+    FocusChanged,
 }
 pub trait WatchControls {
     fn down(&mut self, event: &JsValue) -> Option<ControlEvent>;
@@ -60,10 +75,13 @@ impl WatchControls for MouseControls {
         self.primary_moved = Some(pos);
         Some(ControlEvent::PointerMoved { pos })
     }
-    fn up(&mut self, _event: &JsValue) -> Option<ControlEvent> {
+    fn up(&mut self, event: &JsValue) -> Option<ControlEvent> {
         self.primary_down = None;
         self.primary_moved = None;
-        Some(ControlEvent::PointerUp)
+        let modifier = ["ctrlKey", "metaKey"]
+            .iter()
+            .any(|key| Reflect::get(event, &JsValue::from_str(key)).unwrap() == JsValue::TRUE);
+        Some(ControlEvent::PointerUp { modifier })
     }
     fn left(&mut self, _event: &JsValue) -> Option<ControlEvent> {
         self.primary_down = None;
@@ -219,7 +237,7 @@ impl WatchControls for TouchControls {
                     self.secondary_just_left = false;
                     Some(ControlEvent::PinchFinished)
                 } else if self.secondary.is_none() {
-                    Some(ControlEvent::PointerUp)
+                    Some(ControlEvent::PointerUp { modifier: false })
                 } else {
                     None
                 };
@@ -0,0 +1,358 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use crate::params::{
+    get_optional_bool_by_str_key, get_optional_by_str_key, get_optional_string_by_str_key,
+};
+use js_sys::{Array, Object, Reflect};
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+const MS_PER_DAY: f64 = 86400000.0;
+
+pub struct CsvOptions {
+    pub delimiter: char,
+    pub decimal_comma: bool,
+    pub columns: Option<Vec<String>>,
+}
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            decimal_comma: false,
+            columns: None,
+        }
+    }
+}
+impl CsvOptions {
+    pub fn from_raw(raw_options: &JsValue) -> Result<Self, String> {
+        let mut options = Self::default();
+        if let Some(delimiter) =
+            get_optional_string_by_str_key(raw_options, "delimiter", &|| "delimiter".to_string())?
+        {
+            let mut chars = delimiter.chars();
+            options.delimiter = match (chars.next(), chars.next()) {
+                (Some(c), None) if c != '"' => c,
+                _ => return Err("delimiter should be a single character".to_string()),
+            };
+        }
+        if let Some(decimal_comma) =
+            get_optional_bool_by_str_key(raw_options, "decimalComma", &|| {
+                "decimalComma".to_string()
+            })?
+        {
+            options.decimal_comma = decimal_comma;
+        }
+        if let Some(columns) =
+            get_optional_by_str_key(raw_options, "columns", &|| "columns".to_string())?
+        {
+            let columns: Result<Vec<String>, String> = columns
+                .dyn_into::<Array>()
+                .map_err(|_| "not an array: columns".to_string())?
+                .iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    item.as_string()
+                        .ok_or_else(|| format!("not a string: columns.{}", index))
+                })
+                .collect();
+            options.columns = Some(columns?);
+        }
+        Ok(options)
+    }
+}
+
+pub struct CsvRow {
+    pub line: usize,
+    pub coord: String,
+    pub values: Vec<Option<f64>>,
+}
+
+pub struct CsvTable {
+    pub names: Vec<String>,
+    pub rows: Vec<CsvRow>,
+}
+
+fn split_line(line: &str, delimiter: char) -> Option<Vec<String>> {
+    let mut cells: Vec<String> = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    cell.push('"');
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            } else {
+                cell.push(c);
+            }
+        } else if c == '"' {
+            quoted = true;
+        } else if c == delimiter {
+            cells.push(cell.trim().to_string());
+            cell = String::new();
+        } else {
+            cell.push(c);
+        }
+    }
+    if quoted {
+        return None;
+    }
+    cells.push(cell.trim().to_string());
+    Some(cells)
+}
+
+pub fn parse_number(cell: &str, decimal_comma: bool) -> Option<f64> {
+    let value = if decimal_comma {
+        f64::from_str(cell.replace(['.', ' '], "").replace(',', ".").as_str())
+    } else {
+        f64::from_str(cell)
+    };
+    value.ok().filter(|v| v.is_finite())
+}
+
+pub fn parse_csv(text: &str, options: &CsvOptions) -> Result<CsvTable, String> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    let (header_line, header) = lines.next().ok_or_else(|| "csv is empty".to_string())?;
+    let header = split_line(header, options.delimiter)
+        .ok_or_else(|| format!("line {}: unterminated quote", header_line))?;
+    if header.len() < 2 {
+        return Err(format!(
+            "line {}: header should contain an x column and at least one data set",
+            header_line
+        ));
+    }
+
+    let column_indices: Vec<usize> = match &options.columns {
+        None => (1..header.len()).collect(),
+        Some(columns) => {
+            let mut indices = Vec::with_capacity(columns.len());
+            for column in columns.iter() {
+                indices.push(
+                    header
+                        .iter()
+                        .skip(1)
+                        .position(|name| name == column)
+                        .ok_or_else(|| format!("column not found: {}", column))?
+                        + 1,
+                );
+            }
+            indices
+        }
+    };
+    let names: Vec<String> = column_indices.iter().map(|i| header[*i].clone()).collect();
+    if let Some(index) = names.iter().position(|name| name.is_empty()) {
+        return Err(format!(
+            "line {}: column {} has an empty name",
+            header_line,
+            column_indices[index] + 1
+        ));
+    }
+
+    let mut rows: Vec<CsvRow> = Vec::new();
+    for (line_number, line) in lines {
+        let cells = split_line(line, options.delimiter)
+            .ok_or_else(|| format!("line {}: unterminated quote", line_number))?;
+        if cells.len() != header.len() {
+            return Err(format!(
+                "line {}: expected {} cells, got {}",
+                line_number,
+                header.len(),
+                cells.len()
+            ));
+        }
+        if cells[0].is_empty() {
+            return Err(format!("line {}: x is missing", line_number));
+        }
+        let mut values: Vec<Option<f64>> = Vec::with_capacity(column_indices.len());
+        for index in column_indices.iter() {
+            let cell = cells[*index].as_str();
+            if cell.is_empty() {
+                values.push(None);
+            } else {
+                values.push(Some(parse_number(cell, options.decimal_comma).ok_or_else(
+                    || {
+                        format!(
+                            "line {}: failed to parse '{}' in column '{}'",
+                            line_number, cell, header[*index]
+                        )
+                    },
+                )?));
+            }
+        }
+        rows.push(CsvRow {
+            line: line_number,
+            coord: cells[0].clone(),
+            values,
+        });
+    }
+    for (index, name) in names.iter().enumerate() {
+        if rows.iter().all(|row| row.values[index].is_none()) {
+            return Err(format!("column '{}' has no values", name));
+        }
+    }
+    Ok(CsvTable { names, rows })
+}
+
+fn parse_coords(
+    table: &CsvTable,
+    options: &CsvOptions,
+) -> Result<(&'static str, Vec<f64>), String> {
+    let numbers: Option<Vec<f64>> = table
+        .rows
+        .iter()
+        .map(|row| parse_number(row.coord.as_str(), options.decimal_comma))
+        .collect();
+    if let Some(numbers) = numbers {
+        return Ok(("number", numbers));
+    }
+    let mut dates: Vec<f64> = Vec::with_capacity(table.rows.len());
+    for row in table.rows.iter() {
+        let value = js_sys::Date::new(&JsValue::from_str(row.coord.as_str())).value_of();
+        if !value.is_finite() {
+            return Err(format!(
+                "line {}: neither a number nor a date: '{}'",
+                row.line, row.coord
+            ));
+        }
+        dates.push(value);
+    }
+    if dates.iter().all(|v| v % MS_PER_DAY == 0.0) {
+        Ok(("date", dates))
+    } else {
+        Ok(("datetime", dates))
+    }
+}
+
+/// Builds {coordType, valueType, dataSets}, an empty cell is a gap (null).
+pub fn params_from_csv(csv: &str, raw_options: &JsValue) -> Result<JsValue, String> {
+    let options = CsvOptions::from_raw(raw_options)?;
+    let table = parse_csv(csv, &options)?;
+    let (coord_type, coords) = parse_coords(&table, &options)?;
+
+    let data_sets = Array::new();
+    for (index, name) in table.names.iter().enumerate() {
+        let data_set_coords: Array = coords.iter().map(|c| JsValue::from_f64(*c)).collect();
+        let data_set_values: Array = table
+            .rows
+            .iter()
+            .map(|row| row.values[index].map_or(JsValue::NULL, JsValue::from_f64))
+            .collect();
+        let data_set = Object::new();
+        Reflect::set(&data_set, &"name".into(), &JsValue::from_str(name)).unwrap();
+        Reflect::set(&data_set, &"coords".into(), &data_set_coords).unwrap();
+        Reflect::set(&data_set, &"values".into(), &data_set_values).unwrap();
+        data_sets.push(&data_set);
+    }
+
+    let params = Object::new();
+    Reflect::set(&params, &"coordType".into(), &JsValue::from_str(coord_type)).unwrap();
+    Reflect::set(&params, &"valueType".into(), &JsValue::from_str("number")).unwrap();
+    Reflect::set(&params, &"dataSets".into(), &data_sets).unwrap();
+    Ok(params.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::csv::{parse_coords, parse_csv, parse_number, CsvOptions};
+
+    #[test]
+    fn test_parse_csv() {
+        let table = parse_csv(
+            "x,foo,\"bar, baz\"\n1,10,20\n\n2,,\"21\"\n",
+            &CsvOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(table.names, vec!["foo", "bar, baz"]);
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(table.rows[0].values, vec![Some(10.0), Some(20.0)]);
+        assert_eq!(table.rows[1].line, 4);
+        assert_eq!(table.rows[1].coord, "2");
+        assert_eq!(table.rows[1].values, vec![None, Some(21.0)]);
+        // an empty cell is kept as a gap
+        let table = parse_csv("x,a\n1,1\n2,\n3,3", &CsvOptions::default()).unwrap();
+        let values: Vec<Option<f64>> = table.rows.iter().map(|row| row.values[0]).collect();
+        assert_eq!(values, vec![Some(1.0), None, Some(3.0)]);
+    }
+
+    #[test]
+    fn test_parse_coords() {
+        // dates are detected with js Date, see tests/web.rs
+        let options = CsvOptions {
+            delimiter: ';',
+            decimal_comma: true,
+            columns: None,
+        };
+        let table = parse_csv("x;a\n1,5;1\n-2;2\n1e3;3", &options).unwrap();
+        assert_eq!(
+            parse_coords(&table, &options),
+            Ok(("number", vec![1.5, -2.0, 1000.0]))
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_options() {
+        let table = parse_csv(
+            "x;a;b;c\n2020-01-01;1,5;2;3\n2020-01-02;1.000,25;;4",
+            &CsvOptions {
+                delimiter: ';',
+                decimal_comma: true,
+                columns: Some(vec!["c".to_string(), "a".to_string()]),
+            },
+        )
+        .unwrap();
+        assert_eq!(table.names, vec!["c", "a"]);
+        assert_eq!(table.rows[0].values, vec![Some(3.0), Some(1.5)]);
+        assert_eq!(table.rows[1].values, vec![Some(4.0), Some(1000.25)]);
+        assert_eq!(parse_number("1 234,5", true), Some(1234.5));
+        assert_eq!(parse_number("inf", false), None);
+    }
+
+    #[test]
+    fn test_parse_csv_errors() {
+        let options = CsvOptions::default();
+        assert_eq!(parse_csv("", &options).err().unwrap(), "csv is empty");
+        assert_eq!(
+            parse_csv("x,a\n1,2\n2,3,4", &options).err().unwrap(),
+            "line 3: expected 2 cells, got 3"
+        );
+        assert_eq!(
+            parse_csv("x,a\n1,2\n2,abc", &options).err().unwrap(),
+            "line 3: failed to parse 'abc' in column 'a'"
+        );
+        assert_eq!(
+            parse_csv("x,a\n1,\"2", &options).err().unwrap(),
+            "line 2: unterminated quote"
+        );
+        assert_eq!(
+            parse_csv(
+                "x,a\n1,2",
+                &CsvOptions {
+                    columns: Some(vec!["b".to_string()]),
+                    ..CsvOptions::default()
+                }
+            )
+            .err()
+            .unwrap(),
+            "column not found: b"
+        );
+        assert_eq!(
+            parse_csv("x,a,b\n1,,2\n2, ,3", &options).err().unwrap(),
+            "column 'a' has no values"
+        );
+    }
+}
@@ -6,6 +6,7 @@
  * Copyright (C) 2023, Nikita Almakov
  */
 use crate::animate::AnimatedNumber;
+use crate::params::NonFiniteValues;
 
 #[derive(Debug, PartialEq)]
 pub struct DataPoint {
@@ -31,6 +32,16 @@ impl DataSetMeta {
             })
             .collect();
         values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        // a data set declared without points, see `Content::pending_data_sets`
+        if values.is_empty() {
+            return Self {
+                min: f64::MAX,
+                p25: 0.0,
+                p50: 0.0,
+                p75: 0.0,
+                max: f64::MIN,
+            };
+        }
         let max_index = values.len() - 1;
         Self {
             min: *values.get(0).unwrap(),
@@ -53,12 +64,125 @@ impl DataSetMeta {
     }
 }
 
+/// Points per block of `DataSet::value_blocks`.
+const VALUE_BLOCK_SIZE: usize = 64;
+
+/// Marks a copy of another data set shifted along x, drawn dashed in a
+/// muted color.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    /// Name of the compared data set.
+    pub of: String,
+    pub include_in_domain: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MarkerShape {
+    Circle,
+    Square,
+    Triangle,
+    Diamond,
+}
+impl MarkerShape {
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "circle" => Ok(Self::Circle),
+            "square" => Ok(Self::Square),
+            "triangle" => Ok(Self::Triangle),
+            "diamond" => Ok(Self::Diamond),
+            _ => Err(format!("unsupported marker shape: {}", s)),
+        }
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Circle => "circle",
+            Self::Square => "square",
+            Self::Triangle => "triangle",
+            Self::Diamond => "diamond",
+        }
+    }
+}
+
+/// Fills the area between a line and the baseline: y = 0 when in view,
+/// otherwise the nearer edge of the plot.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AreaFill {
+    Solid((u8, u8, u8, f64)),
+    /// Color stops by offset, from the plot edge away from the baseline (0)
+    /// to the baseline (1).
+    Gradient(Vec<(f64, (u8, u8, u8, f64))>),
+}
+impl AreaFill {
+    /// Checks that there are at least 2 stops with offsets in [0, 1] in
+    /// ascending order.
+    pub fn gradient(stops: Vec<(f64, (u8, u8, u8, f64))>) -> Result<Self, String> {
+        if stops.len() < 2 {
+            return Err("a gradient needs at least 2 stops".to_string());
+        }
+        if let Some(index) = stops
+            .iter()
+            .position(|(offset, _)| !(0.0..=1.0).contains(offset))
+        {
+            return Err(format!(
+                "stop offset should be within [0, 1] at index: {}",
+                index
+            ));
+        }
+        if let Some(index) = stops.windows(2).position(|pair| pair[1].0 < pair[0].0) {
+            return Err(format!(
+                "stop offsets should be ascending at index: {}",
+                index + 1
+            ));
+        }
+        Ok(Self::Gradient(stops))
+    }
+}
+
+/// A marker of a single point; unset color and size fall back to the series
+/// color and `pointMarkerSize`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointMarker {
+    pub coord: f64,
+    pub shape: MarkerShape,
+    pub rgb: Option<(u8, u8, u8)>,
+    pub size: Option<f64>,
+}
+
 pub struct DataSet {
     pub name: String,
     pub data_points: Vec<DataPoint>,
     pub meta: DataSetMeta,
     pub rgb: (u8, u8, u8),
+    /// Index of the line dash and the hatch, see `pattern.rs`.
+    pub pattern: usize,
     pub alpha: AnimatedNumber,
+    /// Grows from the baseline along with its alpha until it is fully
+    /// shown, see `ChartConfig::series_enter_grow`.
+    pub entering: bool,
+    pub comparison: Option<Comparison>,
+    /// Markers with the index of their point, ordered by it.
+    pub markers: Vec<(usize, PointMarker)>,
+    /// Data sets with a higher one are painted above, see `get_paint_order`.
+    pub z_index: i32,
+    /// Multiplies the alpha of the line, its fill and markers.
+    pub opacity: f64,
+    pub fill: Option<AreaFill>,
+    /// Takes part in the hover: nearest point picking, the tooltip and the
+    /// dimming of the other data sets.
+    pub hoverable: bool,
+    pub show_in_tooltip: bool,
+    pub show_in_legend: bool,
+    /// E.g. "ms", shown after the values and on the y axis when the visible
+    /// data sets share it, see `get_shared_unit`.
+    pub unit: Option<String>,
+    /// A longer description for the title of the legend item.
+    pub description: Option<String>,
+    /// Min and max values of consecutive blocks of `VALUE_BLOCK_SIZE` points,
+    /// filled in by `precompute_value_blocks` while the browser is idle.
+    pub value_blocks: Vec<(f64, f64)>,
+    /// Sorted coords of null values, the line breaks at each, see
+    /// `split_at_gaps`.
+    pub gaps: Vec<f64>,
 }
 
 impl DataSet {
@@ -69,9 +193,216 @@ impl DataSet {
             data_points,
             meta,
             rgb,
+            pattern: 0,
             alpha: AnimatedNumber::new(1.0),
+            entering: false,
+            comparison: None,
+            markers: Vec::new(),
+            z_index: 0,
+            opacity: 1.0,
+            fill: None,
+            hoverable: true,
+            show_in_tooltip: true,
+            show_in_legend: true,
+            unit: None,
+            description: None,
+            value_blocks: Vec::new(),
+            gaps: Vec::new(),
+        }
+    }
+    /// Fades the data set in or out over the animation from the time, at
+    /// once without one; it enters when fading in.
+    pub fn set_visible(&mut self, visible: bool, time_us: Option<f64>) {
+        let alpha = if visible { 1.0 } else { 0.0 };
+        if self.alpha.get_end_value() == alpha {
+            return;
+        }
+        self.entering = visible && time_us.is_some();
+        self.alpha.set_value(alpha, time_us);
+    }
+    /// Sets the duration of the fades in ms, keeping the alpha.
+    pub fn set_fade_duration(&mut self, duration_ms: f64) {
+        if duration_ms > 0.0 {
+            // the split of AnimatedNumber::new
+            self.alpha = AnimatedNumber::custom(
+                self.alpha.get_end_value(),
+                duration_ms * 1000.0 / 3.0,
+                duration_ms * 1000.0 * 2.0 / 3.0,
+            );
+        }
+    }
+    pub fn with_unit(&self, formatted_value: String) -> String {
+        match &self.unit {
+            Some(unit) => format!("{} {}", formatted_value, unit),
+            None => formatted_value,
+        }
+    }
+    /// The name with the unit and the description below, None when there is
+    /// nothing to add to the name.
+    pub fn get_legend_title(&self) -> Option<String> {
+        if self.unit.is_none() && self.description.is_none() {
+            return None;
+        }
+        let mut title = self.name.clone();
+        if let Some(unit) = &self.unit {
+            title.push_str(format!(" ({})", unit).as_str());
+        }
+        if let Some(description) = &self.description {
+            title.push('\n');
+            title.push_str(description);
+        }
+        Some(title)
+    }
+    /// Replaces the markers; each one should match the coord of a point.
+    pub fn set_markers(&mut self, markers: Vec<PointMarker>) -> Result<(), String> {
+        let mut indexed = Vec::with_capacity(markers.len());
+        for marker in markers.into_iter() {
+            let index = self
+                .bin_search_left_bound(marker.coord)
+                .filter(|index| self.data_points[*index].coord == marker.coord)
+                .ok_or_else(|| {
+                    format!(
+                        "data set '{}' - no point to mark at: {}",
+                        self.name, marker.coord
+                    )
+                })?;
+            indexed.push((index, marker));
+        }
+        indexed.sort_by_key(|(index, _)| *index);
+        indexed.dedup_by_key(|(index, _)| *index);
+        self.markers = indexed;
+        Ok(())
+    }
+    /// Checks that points can be appended: each coord should be after its
+    /// predecessor.
+    pub fn check_append(&self, coords: &[f64], values: &[f64]) -> Result<(), String> {
+        if coords.len() != values.len() {
+            return Err(format!(
+                "coords and values have different lengths: {}",
+                self.name
+            ));
+        }
+        let mut last_coord = self.data_points.last().map(|p| p.coord);
+        for (index, coord) in coords.iter().enumerate() {
+            if last_coord.is_some_and(|last_coord| *coord <= last_coord) {
+                return Err(format!(
+                    "data set '{}' - appended coord is not after the last one at index: {}",
+                    self.name, index
+                ));
+            }
+            last_coord = Some(*coord);
+        }
+        Ok(())
+    }
+    /// Adds points past the last one, nothing is added if `check_append`
+    /// fails.
+    pub fn append_points(&mut self, coords: Vec<f64>, values: Vec<f64>) -> Result<(), String> {
+        self.check_append(coords.as_slice(), values.as_slice())?;
+        if coords.is_empty() {
+            return Ok(());
+        }
+        self.data_points.extend(
+            coords
+                .into_iter()
+                .zip(values)
+                .map(|(coord, value)| DataPoint { coord, value }),
+        );
+        self.meta = DataSetMeta::from_data_points(self.data_points.as_slice());
+        Ok(())
+    }
+    pub fn add_gaps(&mut self, gaps: Vec<f64>) {
+        if gaps.is_empty() {
+            return;
+        }
+        self.gaps.extend(gaps);
+        self.gaps.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        self.gaps.dedup();
+    }
+    /// Splits consecutive points into the runs drawn as separate lines, a gap
+    /// between two points ends a run.
+    pub fn split_at_gaps<'a>(&self, data_points: &'a [DataPoint]) -> Vec<&'a [DataPoint]> {
+        let mut runs = Vec::new();
+        let mut start = 0;
+        for index in 1..data_points.len() {
+            let prev_coord = data_points[index - 1].coord;
+            let next_gap = self.gaps.partition_point(|gap| *gap <= prev_coord);
+            if self
+                .gaps
+                .get(next_gap)
+                .is_some_and(|gap| *gap < data_points[index].coord)
+            {
+                runs.push(&data_points[start..index]);
+                start = index;
+            }
+        }
+        runs.push(&data_points[start..]);
+        runs
+    }
+    /// The points and the gaps ordered by coord, gaps with no value.
+    pub fn get_values_with_gaps(&self) -> Vec<(f64, Option<f64>)> {
+        let mut result: Vec<(f64, Option<f64>)> = self
+            .data_points
+            .iter()
+            .map(|p| (p.coord, Some(p.value)))
+            .chain(self.gaps.iter().map(|gap| (*gap, None)))
+            .collect();
+        result.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        result
+    }
+    pub fn has_missing_value_blocks(&self) -> bool {
+        self.value_blocks.len() < self.data_points.len() / VALUE_BLOCK_SIZE
+    }
+    /// Adds up to `max_blocks` of the missing value blocks; appended points
+    /// leave the computed ones valid.
+    pub fn precompute_value_blocks(&mut self, max_blocks: usize) {
+        let end =
+            (self.data_points.len() / VALUE_BLOCK_SIZE).min(self.value_blocks.len() + max_blocks);
+        for block in self.value_blocks.len()..end {
+            let points =
+                &self.data_points[block * VALUE_BLOCK_SIZE..(block + 1) * VALUE_BLOCK_SIZE];
+            self.value_blocks
+                .push(points.iter().fold((f64::MAX, f64::MIN), |range, point| {
+                    (range.0.min(point.value), range.1.max(point.value))
+                }));
         }
     }
+    /// Min and max values of the points within the coords and their number;
+    /// whole blocks computed so far are not scanned point by point.
+    pub fn get_value_range_by_coord(
+        &self,
+        coord_start: f64,
+        coord_end: f64,
+    ) -> Option<(f64, f64, usize)> {
+        let first = self.bin_search_left_bound(coord_start)?;
+        let last = self.bin_search_right_bound(coord_end)?;
+        let (mut min, mut max) = (f64::MAX, f64::MIN);
+        let mut index = first;
+        while index <= last {
+            let block = index / VALUE_BLOCK_SIZE;
+            if index % VALUE_BLOCK_SIZE == 0
+                && block < self.value_blocks.len()
+                && index + VALUE_BLOCK_SIZE <= last + 1
+            {
+                min = min.min(self.value_blocks[block].0);
+                max = max.max(self.value_blocks[block].1);
+                index += VALUE_BLOCK_SIZE;
+            } else {
+                min = min.min(self.data_points[index].value);
+                max = max.max(self.data_points[index].value);
+                index += 1;
+            }
+        }
+        Some((min, max, last + 1 - first))
+    }
+    /// Whether the data set sets the y domain; comparisons are left out
+    /// unless asked for or nothing else is visible.
+    pub fn is_in_value_domain(&self, only_comparisons_visible: bool) -> bool {
+        only_comparisons_visible
+            || self
+                .comparison
+                .as_ref()
+                .is_none_or(|comparison| comparison.include_in_domain)
+    }
     pub fn slice_by_coord(&self, coord_start: f64, coord_end: f64) -> Option<&[DataPoint]> {
         if let Some(left_idx) = self.bin_search_left_bound(coord_start) {
             if let Some(right_idx) = self.bin_search_right_bound(coord_end) {
@@ -152,6 +483,29 @@ impl DataSet {
         }
         None
     }
+    /// Index of the point closest to coord, the earlier one on a tie; series
+    /// need not share coords, so each one is looked up on its own.
+    pub fn get_nearest_index(&self, coord: f64) -> Option<usize> {
+        [
+            self.bin_search_right_bound(coord),
+            self.bin_search_left_bound(coord),
+        ]
+        .into_iter()
+        .flatten()
+        .min_by(|a, b| {
+            (self.data_points[*a].coord - coord)
+                .abs()
+                .partial_cmp(&(self.data_points[*b].coord - coord).abs())
+                .unwrap()
+        })
+    }
+    /// Whether coord is between the first and the last point.
+    pub fn covers(&self, coord: f64) -> bool {
+        match (self.data_points.first(), self.data_points.last()) {
+            (Some(first), Some(last)) => first.coord <= coord && coord <= last.coord,
+            _ => false,
+        }
+    }
     #[allow(dead_code)]
     pub fn bin_search(&self, x: f64) -> Option<usize> {
         let data = self.data_points.as_slice();
@@ -184,9 +538,479 @@ impl DataSet {
     }
 }
 
+/// Each data set has coords of its own, which only have to match its values
+/// in length.
+pub fn check_lengths<V>(coords: &[f64], values: &[V], path: String) -> Result<(), String> {
+    if coords.len() != values.len() {
+        return Err(format!(
+            "coords and values have different lengths ({} and {}): {}",
+            coords.len(),
+            values.len(),
+            path
+        ));
+    }
+    Ok(())
+}
+
+/// Handles NaN and infinite coordinates and values as the policy says, so
+/// that they never reach the scales; indices in errors are of the input.
+/// Null values (None) are gaps and kept as long as their coords are finite.
+pub fn sanitize_non_finite(
+    name: &str,
+    coords: Vec<f64>,
+    values: Vec<Option<f64>>,
+    policy: NonFiniteValues,
+) -> Result<(Vec<f64>, Vec<Option<f64>>), String> {
+    let is_finite = |(coord, value): (&f64, &Option<f64>)| {
+        coord.is_finite() && value.is_none_or(|value| value.is_finite())
+    };
+    // different lengths are reported by the caller
+    if coords.len() != values.len() || coords.iter().zip(values.iter()).all(is_finite) {
+        return Ok((coords, values));
+    }
+    if policy == NonFiniteValues::Error {
+        let index = coords.iter().zip(values.iter()).position(|p| !is_finite(p));
+        return Err(format!(
+            "data set '{}' - non-finite value found at index: {}",
+            name,
+            index.unwrap()
+        ));
+    }
+    let finite_values = values.iter().flatten().filter(|value| value.is_finite());
+    let value_min = finite_values.clone().fold(f64::INFINITY, |a, b| a.min(*b));
+    let value_max = finite_values.fold(f64::NEG_INFINITY, |a, b| a.max(*b));
+    Ok(coords
+        .into_iter()
+        .zip(values)
+        .filter_map(|(coord, value)| {
+            let value = match value {
+                _ if !coord.is_finite() => return None,
+                None => return Some((coord, None)),
+                Some(value) if value.is_nan() => return None,
+                Some(value) => value,
+            };
+            match policy {
+                NonFiniteValues::Clamp if value == f64::INFINITY && value_max.is_finite() => {
+                    Some((coord, Some(value_max)))
+                }
+                NonFiniteValues::Clamp if value == f64::NEG_INFINITY && value_min.is_finite() => {
+                    Some((coord, Some(value_min)))
+                }
+                _ if value.is_finite() => Some((coord, Some(value))),
+                _ => None,
+            }
+        })
+        .unzip())
+}
+
+/// Separates null values from the points, returns the coords and the values
+/// of the points and the coords of the gaps.
+pub fn split_gaps(coords: Vec<f64>, values: Vec<Option<f64>>) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let mut gaps = Vec::new();
+    let (coords, values) = coords
+        .into_iter()
+        .zip(values)
+        .filter_map(|(coord, value)| {
+            if value.is_none() {
+                gaps.push(coord);
+            }
+            value.map(|value| (coord, value))
+        })
+        .unzip();
+    (coords, values, gaps)
+}
+
+/// Appends the points by data set name, moving data sets declared without
+/// points from pending to data_sets with their first ones; either all data
+/// sets get their points or none. Returns whether any data set was moved.
+pub fn append_by_name(
+    data_sets: &mut Vec<DataSet>,
+    pending: &mut Vec<DataSet>,
+    appends: Vec<(String, Vec<f64>, Vec<f64>)>,
+) -> Result<bool, String> {
+    let find = |data_sets: &[DataSet], name: &str| {
+        data_sets
+            .iter()
+            .position(|data_set| data_set.name == name && data_set.comparison.is_none())
+    };
+    for (index, (name, coords, values)) in appends.iter().enumerate() {
+        if appends[..index].iter().any(|(other, _, _)| other == name) {
+            return Err(format!("duplicate data set name: {}", name));
+        }
+        let data_set = match (find(data_sets, name), find(pending, name)) {
+            (Some(index), _) => &data_sets[index],
+            (None, Some(index)) => &pending[index],
+            (None, None) => return Err(format!("data set not found: {}", name)),
+        };
+        data_set.check_append(coords.as_slice(), values.as_slice())?;
+    }
+    let mut moved = false;
+    for (name, coords, values) in appends.into_iter() {
+        let index = match find(data_sets, name.as_str()) {
+            Some(index) => index,
+            // stays pending until it gets points
+            None if coords.is_empty() => continue,
+            None => {
+                let pending_index = find(pending, name.as_str()).unwrap();
+                data_sets.push(pending.remove(pending_index));
+                moved = true;
+                data_sets.len() - 1
+            }
+        };
+        data_sets[index].append_points(coords, values)?;
+    }
+    Ok(moved)
+}
+
+/// The unit of the data sets when all of them have the same one.
+pub fn get_shared_unit<'a>(mut data_sets: impl Iterator<Item = &'a DataSet>) -> Option<&'a str> {
+    let unit = data_sets.next()?.unit.as_deref()?;
+    data_sets
+        .all(|data_set| data_set.unit.as_deref() == Some(unit))
+        .then_some(unit)
+}
+
+/// Indices of the data sets in the order to paint them: by z-index, keeping
+/// the order of equal ones, with the highlighted one on top.
+pub fn get_paint_order(data_sets: &[DataSet], highlighted: Option<usize>) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..data_sets.len())
+        .filter(|index| Some(*index) != highlighted)
+        .collect();
+    order.sort_by_key(|index| data_sets[*index].z_index);
+    order.extend(highlighted.filter(|index| *index < data_sets.len()));
+    order
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::data_set::{DataPoint, DataSet};
+    use crate::data_set::{
+        append_by_name, check_lengths, get_paint_order, get_shared_unit, sanitize_non_finite,
+        split_gaps, AreaFill, Comparison, DataPoint, DataSet, MarkerShape, PointMarker,
+    };
+    use crate::params::NonFiniteValues;
+
+    #[test]
+    fn test_sanitize_non_finite() {
+        let some = |values: Vec<f64>| values.into_iter().map(Some).collect::<Vec<_>>();
+        let coords = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let values = some(vec![1.0, f64::INFINITY, 3.0, f64::NAN, f64::NEG_INFINITY]);
+        assert_eq!(
+            sanitize_non_finite("a", coords.clone(), values.clone(), NonFiniteValues::Error),
+            Err("data set 'a' - non-finite value found at index: 1".to_string())
+        );
+        assert_eq!(
+            sanitize_non_finite("a", coords.clone(), values.clone(), NonFiniteValues::Drop),
+            Ok((vec![1.0, 3.0], some(vec![1.0, 3.0])))
+        );
+        assert_eq!(
+            sanitize_non_finite("a", coords, values, NonFiniteValues::Clamp),
+            Ok((vec![1.0, 2.0, 3.0, 5.0], some(vec![1.0, 3.0, 3.0, 1.0])))
+        );
+        // non-finite coordinates are never kept
+        let coords = vec![1.0, f64::NAN, f64::INFINITY];
+        let values = some(vec![1.0, 2.0, 3.0]);
+        assert_eq!(
+            sanitize_non_finite("b", coords.clone(), values.clone(), NonFiniteValues::Error),
+            Err("data set 'b' - non-finite value found at index: 1".to_string())
+        );
+        for policy in [NonFiniteValues::Drop, NonFiniteValues::Clamp] {
+            assert_eq!(
+                sanitize_non_finite("b", coords.clone(), values.clone(), policy),
+                Ok((vec![1.0], some(vec![1.0])))
+            );
+        }
+        // finite input is returned as is
+        assert_eq!(
+            sanitize_non_finite("c", vec![1.0], some(vec![2.0]), NonFiniteValues::Error),
+            Ok((vec![1.0], some(vec![2.0])))
+        );
+        // gaps are kept, unless at a non-finite coord
+        let coords = vec![1.0, 2.0, f64::NAN, 4.0];
+        let values = vec![None, Some(f64::NAN), None, Some(4.0)];
+        assert_eq!(
+            sanitize_non_finite("d", coords.clone(), values.clone(), NonFiniteValues::Error),
+            Err("data set 'd' - non-finite value found at index: 1".to_string())
+        );
+        assert_eq!(
+            sanitize_non_finite("d", coords, values, NonFiniteValues::Drop),
+            Ok((vec![1.0, 4.0], vec![None, Some(4.0)]))
+        );
+    }
+
+    #[test]
+    fn test_gaps() {
+        assert_eq!(
+            split_gaps(
+                vec![1.0, 2.0, 3.0, 4.0],
+                vec![Some(1.0), None, Some(3.0), Some(4.0)]
+            ),
+            (vec![1.0, 3.0, 4.0], vec![1.0, 3.0, 4.0], vec![2.0])
+        );
+        let get_data_points = || -> Vec<DataPoint> {
+            [1.0, 3.0, 4.0, 6.0, 7.0]
+                .into_iter()
+                .map(|coord| DataPoint { coord, value: 0.0 })
+                .collect()
+        };
+        let data_points = get_data_points();
+        let mut data_set = DataSet::new("a", (0, 0, 0), get_data_points());
+        assert_eq!(data_set.split_at_gaps(&data_points), vec![&data_points[..]]);
+
+        data_set.add_gaps(vec![5.0, 2.0, 0.0]);
+        data_set.add_gaps(vec![2.0, 8.0]);
+        assert_eq!(data_set.gaps, vec![0.0, 2.0, 5.0, 8.0]);
+        assert_eq!(
+            data_set.split_at_gaps(&data_points),
+            vec![&data_points[..1], &data_points[1..3], &data_points[3..]]
+        );
+        assert_eq!(
+            data_set.split_at_gaps(&data_points[2..]),
+            vec![&data_points[2..3], &data_points[3..]]
+        );
+        assert_eq!(
+            data_set.get_values_with_gaps(),
+            vec![
+                (0.0, None),
+                (1.0, Some(0.0)),
+                (2.0, None),
+                (3.0, Some(0.0)),
+                (4.0, Some(0.0)),
+                (5.0, None),
+                (6.0, Some(0.0)),
+                (7.0, Some(0.0)),
+                (8.0, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_in_value_domain() {
+        let point = DataPoint {
+            coord: 1.0,
+            value: 2.0,
+        };
+        let mut data_set = DataSet::new("a", (255, 255, 255), vec![point]);
+        assert!(data_set.is_in_value_domain(false));
+        data_set.comparison = Some(Comparison {
+            of: "b".to_string(),
+            include_in_domain: false,
+        });
+        assert!(!data_set.is_in_value_domain(false));
+        // the range still fits comparisons shown alone
+        assert!(data_set.is_in_value_domain(true));
+        data_set.comparison.as_mut().unwrap().include_in_domain = true;
+        assert!(data_set.is_in_value_domain(false));
+    }
+
+    #[test]
+    fn test_set_markers() {
+        let mut data_set = DataSet::new(
+            "a",
+            (255, 255, 255),
+            (1..5)
+                .map(|i| DataPoint {
+                    coord: i as f64,
+                    value: 0.0,
+                })
+                .collect(),
+        );
+        let marker = |coord: f64| PointMarker {
+            coord,
+            shape: MarkerShape::Circle,
+            rgb: None,
+            size: None,
+        };
+        assert_eq!(data_set.set_markers(vec![marker(3.0), marker(1.0)]), Ok(()));
+        assert_eq!(data_set.markers, vec![(0, marker(1.0)), (2, marker(3.0))]);
+        assert_eq!(
+            data_set.set_markers(vec![marker(2.5)]),
+            Err("data set 'a' - no point to mark at: 2.5".to_string())
+        );
+        assert_eq!(data_set.markers.len(), 2);
+    }
+
+    #[test]
+    fn test_area_fill_gradient() {
+        let color = (0, 0, 0, 1.0);
+        assert_eq!(
+            AreaFill::gradient(vec![(0.0, color), (0.5, color), (1.0, color)]),
+            Ok(AreaFill::Gradient(vec![
+                (0.0, color),
+                (0.5, color),
+                (1.0, color)
+            ]))
+        );
+        assert_eq!(
+            AreaFill::gradient(vec![(0.0, color)]),
+            Err("a gradient needs at least 2 stops".to_string())
+        );
+        assert_eq!(
+            AreaFill::gradient(vec![(0.0, color), (1.5, color)]),
+            Err("stop offset should be within [0, 1] at index: 1".to_string())
+        );
+        assert_eq!(
+            AreaFill::gradient(vec![(0.5, color), (0.2, color), (1.0, color)]),
+            Err("stop offsets should be ascending at index: 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_append_points() {
+        let point = |coord: f64, value: f64| DataPoint { coord, value };
+        let mut data_set = DataSet::new("a", (255, 255, 255), vec![point(1.0, 5.0)]);
+        assert_eq!(
+            data_set.append_points(vec![2.0, 4.0], vec![7.0, 3.0]),
+            Ok(())
+        );
+        assert_eq!(
+            data_set.data_points,
+            vec![point(1.0, 5.0), point(2.0, 7.0), point(4.0, 3.0)]
+        );
+        assert_eq!((data_set.meta.min, data_set.meta.max), (3.0, 7.0));
+        assert_eq!(
+            data_set.append_points(vec![5.0, 5.0], vec![1.0, 1.0]),
+            Err("data set 'a' - appended coord is not after the last one at index: 1".to_string())
+        );
+        assert_eq!(
+            data_set.append_points(vec![4.0], vec![1.0]),
+            Err("data set 'a' - appended coord is not after the last one at index: 0".to_string())
+        );
+        assert_eq!(data_set.data_points.len(), 3);
+
+        let mut data_set = DataSet::new("b", (255, 255, 255), vec![]);
+        assert_eq!(data_set.append_points(vec![2.0], vec![7.0]), Ok(()));
+        assert_eq!(data_set.data_points, vec![point(2.0, 7.0)]);
+        assert_eq!((data_set.meta.min, data_set.meta.max), (7.0, 7.0));
+    }
+
+    #[test]
+    fn test_append_by_name() {
+        let point = |coord: f64, value: f64| DataPoint { coord, value };
+        let mut data_sets = vec![DataSet::new("a", (255, 255, 255), vec![point(1.0, 5.0)])];
+        let mut pending = vec![
+            DataSet::new("b", (255, 255, 255), vec![]),
+            DataSet::new("c", (255, 255, 255), vec![]),
+        ];
+        let append =
+            |name: &str, coords: Vec<f64>, values: Vec<f64>| (name.to_string(), coords, values);
+        assert_eq!(
+            append_by_name(
+                &mut data_sets,
+                &mut pending,
+                vec![
+                    append("a", vec![2.0], vec![6.0]),
+                    append("c", vec![], vec![])
+                ],
+            ),
+            Ok(false)
+        );
+        assert_eq!(data_sets.len(), 1);
+        assert_eq!(pending.len(), 2);
+        assert_eq!(
+            append_by_name(
+                &mut data_sets,
+                &mut pending,
+                vec![
+                    append("a", vec![3.0], vec![1.0]),
+                    append("d", vec![1.0], vec![1.0])
+                ],
+            ),
+            Err("data set not found: d".to_string())
+        );
+        assert_eq!(
+            append_by_name(
+                &mut data_sets,
+                &mut pending,
+                vec![
+                    append("b", vec![1.0], vec![1.0]),
+                    append("b", vec![2.0], vec![1.0])
+                ],
+            ),
+            Err("duplicate data set name: b".to_string())
+        );
+        // nothing is appended when one of the data sets fails
+        assert_eq!(data_sets[0].data_points.len(), 2);
+        assert!(pending
+            .iter()
+            .all(|data_set| data_set.data_points.is_empty()));
+
+        assert_eq!(
+            append_by_name(
+                &mut data_sets,
+                &mut pending,
+                vec![append("b", vec![1.0, 2.0], vec![4.0, 8.0])],
+            ),
+            Ok(true)
+        );
+        assert_eq!(
+            data_sets
+                .iter()
+                .map(|data_set| data_set.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a", "b"]
+        );
+        assert_eq!(
+            data_sets[1].data_points,
+            vec![point(1.0, 4.0), point(2.0, 8.0)]
+        );
+        assert_eq!((data_sets[1].meta.min, data_sets[1].meta.max), (4.0, 8.0));
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].name, "c");
+    }
+
+    #[test]
+    fn test_value_blocks() {
+        let value = |index: usize| ((index * 37) % 101) as f64;
+        let points = |range: std::ops::Range<usize>| {
+            range
+                .map(|index| DataPoint {
+                    coord: index as f64,
+                    value: value(index),
+                })
+                .collect::<Vec<_>>()
+        };
+        let scan = |data_set: &DataSet, start: f64, end: f64| {
+            data_set.slice_by_coord(start, end).map(|data_points| {
+                let (min, max) = data_points
+                    .iter()
+                    .fold((f64::MAX, f64::MIN), |range, point| {
+                        (range.0.min(point.value), range.1.max(point.value))
+                    });
+                (min, max, data_points.len())
+            })
+        };
+        let mut data_set = DataSet::new("a", (255, 255, 255), points(0..300));
+        assert!(data_set.has_missing_value_blocks());
+        data_set.precompute_value_blocks(2);
+        assert_eq!(data_set.value_blocks.len(), 2);
+        data_set.precompute_value_blocks(10);
+        // the last 44 points make no whole block
+        assert_eq!(data_set.value_blocks.len(), 4);
+        assert!(!data_set.has_missing_value_blocks());
+
+        data_set
+            .append_points(
+                (300..400).map(|index| index as f64).collect(),
+                (300..400).map(value).collect(),
+            )
+            .unwrap();
+        assert!(data_set.has_missing_value_blocks());
+        for (start, end) in [
+            (0.0, 399.0),
+            (10.5, 270.0),
+            (64.0, 127.0),
+            (63.0, 200.0),
+            (-5.0, 3.0),
+            (120.2, 120.8),
+        ] {
+            assert_eq!(
+                data_set.get_value_range_by_coord(start, end),
+                scan(&data_set, start, end)
+            );
+        }
+        assert_eq!(data_set.get_value_range_by_coord(500.0, 600.0), None);
+    }
+
     #[test]
     fn test_bin_search_empty() {
         let empty_data = DataSet::new("test", (255, 255, 255), vec![]);
@@ -389,4 +1213,139 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_get_paint_order() {
+        let mut data_sets: Vec<DataSet> = (0..4)
+            .map(|index| {
+                DataSet::new(
+                    index.to_string().as_str(),
+                    (0, 0, 0),
+                    vec![DataPoint {
+                        coord: 0.0,
+                        value: 0.0,
+                    }],
+                )
+            })
+            .collect();
+        assert_eq!(get_paint_order(&data_sets, None), vec![0, 1, 2, 3]);
+        data_sets[0].z_index = 1;
+        data_sets[2].z_index = -1;
+        assert_eq!(get_paint_order(&data_sets, None), vec![2, 1, 3, 0]);
+        assert_eq!(get_paint_order(&data_sets, Some(2)), vec![1, 3, 0, 2]);
+    }
+
+    #[test]
+    fn test_units() {
+        let data_set = |unit: Option<&str>| {
+            let mut data_set = DataSet::new(
+                "a",
+                (0, 0, 0),
+                vec![DataPoint {
+                    coord: 0.0,
+                    value: 0.0,
+                }],
+            );
+            data_set.unit = unit.map(|unit| unit.to_string());
+            data_set
+        };
+        let ms = [data_set(Some("ms")), data_set(Some("ms"))];
+        assert_eq!(get_shared_unit(ms.iter()), Some("ms"));
+        let mixed = [data_set(Some("ms")), data_set(Some("req/s"))];
+        assert_eq!(get_shared_unit(mixed.iter()), None);
+        let partial = [data_set(Some("ms")), data_set(None)];
+        assert_eq!(get_shared_unit(partial.iter()), None);
+        assert_eq!(get_shared_unit([].iter()), None);
+
+        assert_eq!(ms[0].with_unit("5".to_string()), "5 ms");
+        assert_eq!(data_set(None).get_legend_title(), None);
+        let mut described = data_set(Some("ms"));
+        described.description = Some("p95 latency".to_string());
+        assert_eq!(
+            described.get_legend_title(),
+            Some("a (ms)\np95 latency".to_string())
+        );
+    }
+
+    #[test]
+    fn test_set_visible() {
+        let mut data_set = DataSet::new(
+            "a",
+            (0, 0, 0),
+            vec![DataPoint {
+                coord: 0.0,
+                value: 0.0,
+            }],
+        );
+        data_set.set_fade_duration(300.0);
+        // five quick legend clicks end hidden
+        for click in 0..5 {
+            let visible = data_set.alpha.get_end_value() == 0.0;
+            data_set.set_visible(visible, Some(click as f64 * 1000.0));
+        }
+        assert_eq!(data_set.alpha.get_end_value(), 0.0);
+        assert!(!data_set.entering);
+        data_set.set_visible(true, Some(5000.0));
+        assert!(data_set.entering);
+        assert_eq!(data_set.alpha.get_value(1e6), 1.0);
+        // shown at once without the animation
+        data_set.set_visible(false, None);
+        data_set.set_visible(true, None);
+        assert!(!data_set.entering);
+    }
+
+    #[test]
+    fn test_unaligned_data_sets() {
+        let minute = 60_000.0;
+        let series = |name: &str, step: usize, value: &dyn Fn(usize) -> f64| {
+            let points = (0..=60)
+                .step_by(step)
+                .map(|index| DataPoint {
+                    coord: index as f64 * minute,
+                    value: value(index),
+                })
+                .collect();
+            DataSet::new(name, (0, 0, 0), points)
+        };
+        let one_minute = series("1m", 1, &|index| index as f64);
+        let five_minutes = series("5m", 5, &|index| -(index as f64));
+        assert_eq!(one_minute.data_points.len(), 61);
+        assert_eq!(five_minutes.data_points.len(), 13);
+
+        // each series is looked up on its own coords
+        assert_eq!(one_minute.get_nearest_index(3.0 * minute), Some(3));
+        assert_eq!(five_minutes.get_nearest_index(3.0 * minute), Some(1));
+        assert_eq!(five_minutes.get_nearest_index(2.0 * minute), Some(0));
+        // the earlier point on a tie
+        assert_eq!(five_minutes.get_nearest_index(7.5 * minute), Some(1));
+        assert_eq!(five_minutes.get_nearest_index(-minute), Some(0));
+        assert_eq!(five_minutes.get_nearest_index(90.0 * minute), Some(12));
+        assert!(five_minutes.covers(3.0 * minute));
+        assert!(!five_minutes.covers(61.0 * minute));
+
+        // a window between 5 minute points has no points of that series
+        assert_eq!(
+            one_minute.get_value_range_by_coord(6.0 * minute, 9.0 * minute),
+            Some((6.0, 9.0, 4))
+        );
+        assert_eq!(
+            five_minutes
+                .get_value_range_by_coord(6.0 * minute, 9.0 * minute)
+                .map(|range| range.2),
+            Some(0)
+        );
+        assert_eq!(
+            five_minutes.get_value_range_by_coord(4.0 * minute, 11.0 * minute),
+            Some((-10.0, -5.0, 2))
+        );
+
+        assert_eq!(
+            check_lengths(&[1.0, 2.0], &[3.0, 4.0], "dataSets[0]".to_string()),
+            Ok(())
+        );
+        assert_eq!(
+            check_lengths(&[1.0, 2.0, 3.0], &[3.0], "dataSets[1]".to_string()),
+            Err("coords and values have different lengths (3 and 1): dataSets[1]".to_string())
+        );
+    }
 }
@@ -0,0 +1,187 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use crate::time_zone::TimeZone;
+use chrono::prelude::*;
+
+const MS_PER_MINUTE: f64 = 6e4;
+const MS_PER_DAY: f64 = 864e5;
+// a granularity is left only when the tick step is this far past the boundary,
+// so animations around a boundary do not switch formats back and forth
+const HYSTERESIS_FACTOR: f64 = 1.5;
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub enum DateGranularity {
+    Second,
+    Minute,
+    Day,
+    Month,
+    Year,
+}
+impl DateGranularity {
+    /// Tick steps below the bound belong to the granularity.
+    fn get_upper_bound(&self) -> f64 {
+        match self {
+            Self::Second => MS_PER_MINUTE,
+            Self::Minute => MS_PER_DAY,
+            Self::Day => MS_PER_DAY * 28.0,
+            Self::Month => MS_PER_DAY * 365.0,
+            Self::Year => f64::INFINITY,
+        }
+    }
+    fn get_lower_bound(&self) -> f64 {
+        match self {
+            Self::Second => 0.0,
+            Self::Minute => Self::Second.get_upper_bound(),
+            Self::Day => Self::Minute.get_upper_bound(),
+            Self::Month => Self::Day.get_upper_bound(),
+            Self::Year => Self::Month.get_upper_bound(),
+        }
+    }
+    fn from_step_exact(step_ms: f64) -> Self {
+        [
+            Self::Second,
+            Self::Minute,
+            Self::Day,
+            Self::Month,
+            Self::Year,
+        ]
+        .into_iter()
+        .find(|granularity| step_ms < granularity.get_upper_bound())
+        .unwrap()
+    }
+    pub fn from_step(step_ms: f64, current: Option<Self>) -> Self {
+        let granularity = Self::from_step_exact(step_ms);
+        match current {
+            Some(current) if granularity > current => {
+                if step_ms < current.get_upper_bound() * HYSTERESIS_FACTOR {
+                    current
+                } else {
+                    granularity
+                }
+            }
+            Some(current) if granularity < current => {
+                if step_ms >= current.get_lower_bound() / HYSTERESIS_FACTOR {
+                    current
+                } else {
+                    granularity
+                }
+            }
+            _ => granularity,
+        }
+    }
+    /// The full format of a single value, e.g. in the tooltip.
+    pub fn get_verbose_fmt(&self) -> &'static str {
+        match self {
+            Self::Second => "%b %d, %Y %H:%M:%S",
+            _ => "%b %d, %Y %H:%M",
+        }
+    }
+    /// Returns the label format and the anchor format, which is used for the
+    /// first label and whenever the enclosing unit (day or year) changes.
+    fn get_label_fmts(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::Second => ("%H:%M:%S", "%b %d %H:%M:%S"),
+            Self::Minute => ("%H:%M", "%b %d %H:%M"),
+            Self::Day => ("%b %d", "%b %d, %Y"),
+            Self::Month => ("%b", "%b %Y"),
+            Self::Year => ("%Y", "%Y"),
+        }
+    }
+    fn get_anchor_key(&self, date_time: &NaiveDateTime) -> i32 {
+        match self {
+            Self::Second | Self::Minute => date_time.num_days_from_ce(),
+            Self::Day | Self::Month | Self::Year => date_time.year(),
+        }
+    }
+}
+
+pub fn to_local_date_time(value: f64, time_zone: &TimeZone) -> NaiveDateTime {
+    DateTime::from_timestamp_millis(value as i64)
+        .unwrap()
+        .with_timezone(&time_zone.get_fixed_offset(value))
+        .naive_local()
+}
+
+/// Formats tick labels of a temporal axis, e.g. "Mar 12 14:00", "14:05",
+/// "14:10"; a label repeats the date (or the year) when it changes.
+pub fn format_date_ticks(
+    values: &[f64],
+    granularity: DateGranularity,
+    time_zone: &TimeZone,
+) -> Vec<String> {
+    let (label_fmt, anchor_fmt) = granularity.get_label_fmts();
+    let mut prev_key: Option<i32> = None;
+    values
+        .iter()
+        .map(|value| {
+            let date_time = to_local_date_time(*value, time_zone);
+            let key = granularity.get_anchor_key(&date_time);
+            let fmt = if prev_key == Some(key) {
+                label_fmt
+            } else {
+                anchor_fmt
+            };
+            prev_key = Some(key);
+            date_time.format(fmt).to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::date_format::{format_date_ticks, DateGranularity};
+    use crate::time_zone::TimeZone;
+
+    #[test]
+    fn test_date_granularity() {
+        assert_eq!(
+            DateGranularity::from_step(3e5, None),
+            DateGranularity::Minute
+        );
+        assert_eq!(
+            DateGranularity::from_step(864e5, None),
+            DateGranularity::Day
+        );
+        // a step just past the boundary keeps the current granularity
+        assert_eq!(
+            DateGranularity::from_step(864e5, Some(DateGranularity::Minute)),
+            DateGranularity::Minute
+        );
+        assert_eq!(
+            DateGranularity::from_step(1728e5, Some(DateGranularity::Minute)),
+            DateGranularity::Day
+        );
+        assert_eq!(
+            DateGranularity::from_step(648e5, Some(DateGranularity::Day)),
+            DateGranularity::Day
+        );
+        assert_eq!(
+            DateGranularity::from_step(432e5, Some(DateGranularity::Day)),
+            DateGranularity::Minute
+        );
+    }
+
+    #[test]
+    fn test_format_date_ticks() {
+        let utc = TimeZone::Utc;
+        // 2024-03-11 23:00, 2024-03-12 00:00, 2024-03-12 01:00
+        let values = [1710198000000.0, 1710201600000.0, 1710205200000.0];
+        assert_eq!(
+            format_date_ticks(&values, DateGranularity::Minute, &utc),
+            vec!["Mar 11 23:00", "Mar 12 00:00", "01:00"]
+        );
+        assert_eq!(
+            format_date_ticks(&values[1..], DateGranularity::Second, &utc),
+            vec!["Mar 12 00:00:00", "01:00:00"]
+        );
+        assert_eq!(
+            format_date_ticks(&values[1..], DateGranularity::Day, &utc),
+            vec!["Mar 12, 2024", "Mar 12"]
+        );
+    }
+}
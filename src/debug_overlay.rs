@@ -0,0 +1,135 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use crate::params::ChartConfig;
+use crate::screen::Screen;
+use std::collections::VecDeque;
+
+const FPS_PERIOD_US: f64 = 1_000_000.0;
+// in apx
+const LINE_HEIGHT: f64 = 14.0;
+const PADDING: f64 = 6.0;
+
+/// Camera frames which were drawn again or kept as they were, see
+/// `Camera::dirty`.
+#[derive(Default, Clone, Copy)]
+pub struct FrameReuse {
+    pub reused: u32,
+    pub total: u32,
+}
+impl FrameReuse {
+    pub fn count(&mut self, reused: bool) {
+        self.total += 1;
+        self.reused += reused as u32;
+    }
+    /// Percentage of the reused frames, None before the first frame.
+    pub fn get_rate(&self) -> Option<f64> {
+        (self.total > 0).then(|| self.reused as f64 / self.total as f64 * 100.0)
+    }
+}
+
+/// What the chart thinks is going on, drawn over the plot while enabled with
+/// `setDebug`; a chart without it pays nothing.
+#[derive(Default)]
+pub struct DebugOverlay {
+    frame_times_us: VecDeque<f64>,
+    /// How long the last frame took to draw.
+    pub last_frame_ms: f64,
+    pub main_frames: FrameReuse,
+    pub preview_frames: FrameReuse,
+}
+impl DebugOverlay {
+    pub fn on_frame(&mut self, time_us: f64, frame_ms: f64) {
+        self.last_frame_ms = frame_ms;
+        self.frame_times_us.push_back(time_us);
+        while self
+            .frame_times_us
+            .front()
+            .is_some_and(|front| *front <= time_us - FPS_PERIOD_US)
+        {
+            self.frame_times_us.pop_front();
+        }
+    }
+    /// Frames drawn within the last second; an idle chart draws none.
+    pub fn get_fps(&self) -> usize {
+        self.frame_times_us.len()
+    }
+    /// Draws the lines in the top left corner of the overlay and outlines the
+    /// rectangles, given in canvas pixels.
+    pub fn draw(
+        &self,
+        screen: &Screen,
+        config: &ChartConfig,
+        lines: &[String],
+        rects: &[(f64, f64, f64, f64)],
+    ) {
+        let context = &screen.context;
+        context.set_line_width(screen.apx_to_cpx(1.0));
+        context.set_stroke_style_str("rgba(255, 0, 255, 0.8)");
+        for (x, y, width, height) in rects.iter() {
+            context.stroke_rect(*x, *y, *width, *height);
+        }
+        if lines.is_empty() {
+            return;
+        }
+
+        let c_line_height = screen.apx_to_cpx(LINE_HEIGHT);
+        let c_padding = screen.apx_to_cpx(PADDING);
+        context.set_font(
+            format!(
+                "{:.0}px {}",
+                screen.apx_to_cpx(config.font_size_small),
+                config.font_monospace.as_str()
+            )
+            .as_str(),
+        );
+        let width = lines
+            .iter()
+            .map(|line| context.measure_text(line.as_str()).unwrap().width())
+            .fold(0.0, f64::max)
+            + c_padding * 2.0;
+        let height = lines.len() as f64 * c_line_height + c_padding * 2.0;
+        context.set_fill_style_str("rgba(0, 0, 0, 0.7)");
+        context.fill_rect(0.0, 0.0, width, height);
+        context.set_fill_style_str("rgb(255, 255, 255)");
+        context.set_text_align("left");
+        context.set_text_baseline("top");
+        for (index, line) in lines.iter().enumerate() {
+            context
+                .fill_text(
+                    line.as_str(),
+                    c_padding,
+                    c_padding + index as f64 * c_line_height,
+                )
+                .unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::debug_overlay::{DebugOverlay, FrameReuse};
+
+    #[test]
+    fn test_debug_overlay_counters() {
+        let mut overlay = DebugOverlay::default();
+        for time_us in [0.0, 400_000.0, 800_000.0, 1_200_000.0] {
+            overlay.on_frame(time_us, 2.0);
+        }
+        // the first frame is a second old
+        assert_eq!(overlay.get_fps(), 3);
+        assert_eq!(overlay.last_frame_ms, 2.0);
+
+        let mut frames = FrameReuse::default();
+        assert_eq!(frames.get_rate(), None);
+        frames.count(true);
+        frames.count(false);
+        frames.count(true);
+        frames.count(true);
+        assert_eq!(frames.get_rate(), Some(75.0));
+    }
+}
@@ -0,0 +1,163 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use crate::params::{get_optional_bool_by_str_key, get_optional_by_str_key};
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Bumped when a definition changes in a way older versions cannot import.
+pub const DEFINITION_VERSION: u32 = 1;
+
+fn is_plain_object(value: &JsValue) -> bool {
+    value.is_object() && !Array::is_array(value)
+}
+
+/// What a definition restores besides the params and the config.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChartState {
+    /// Coords of the window, passed on as initialRange.
+    pub window: Option<(f64, f64)>,
+    pub hidden_data_sets: Vec<String>,
+    /// Whether the window follows appended data; false detaches it.
+    pub following: Option<bool>,
+    /// Set once previewSplitter was moved.
+    pub preview_height: Option<f64>,
+}
+impl ChartState {
+    pub fn to_js(&self) -> JsValue {
+        let state = Object::new();
+        let set = |key: &str, value: &JsValue| {
+            Reflect::set(&state, &key.into(), value).unwrap();
+        };
+        if let Some((from, to)) = self.window {
+            set("window", &Array::of2(&from.into(), &to.into()));
+        }
+        let hidden: Array = self
+            .hidden_data_sets
+            .iter()
+            .map(|name| JsValue::from_str(name))
+            .collect();
+        set("hiddenDataSets", &hidden);
+        if let Some(following) = self.following {
+            set("following", &following.into());
+        }
+        if let Some(preview_height) = self.preview_height {
+            set("previewHeight", &preview_height.into());
+        }
+        state.into()
+    }
+    pub fn from_js(raw_state: &JsValue) -> Result<Self, String> {
+        let mut state = Self::default();
+        if let Some(window) =
+            get_optional_by_str_key(raw_state, "window", &|| "state.window".to_string())?
+        {
+            let window = window
+                .dyn_into::<Array>()
+                .map_err(|_| "not an array: state.window".to_string())?;
+            state.window = match (
+                window.length(),
+                window.get(0).as_f64(),
+                window.get(1).as_f64(),
+            ) {
+                (2, Some(from), Some(to)) => Some((from, to)),
+                _ => return Err("should be [from, to]: state.window".to_string()),
+            };
+        }
+        if let Some(hidden) = get_optional_by_str_key(raw_state, "hiddenDataSets", &|| {
+            "state.hiddenDataSets".to_string()
+        })? {
+            let hidden = hidden
+                .dyn_into::<Array>()
+                .map_err(|_| "not an array: state.hiddenDataSets".to_string())?;
+            for (index, name) in hidden.iter().enumerate() {
+                state.hidden_data_sets.push(
+                    name.as_string()
+                        .ok_or_else(|| format!("not a string: state.hiddenDataSets[{}]", index))?,
+                );
+            }
+        }
+        state.following = get_optional_bool_by_str_key(raw_state, "following", &|| {
+            "state.following".to_string()
+        })?;
+        state.preview_height = match get_optional_by_str_key(raw_state, "previewHeight", &|| {
+            "state.previewHeight".to_string()
+        })?
+        .map(|height| height.as_f64())
+        {
+            None => None,
+            Some(Some(height)) if height.is_finite() && height >= 0.0 => Some(height),
+            Some(_) => {
+                return Err("should be a non-negative number: state.previewHeight".to_string())
+            }
+        };
+        Ok(state)
+    }
+}
+
+/// {version, params, config, state}, see `ChartManager::export_definition`.
+#[derive(Debug, Clone)]
+pub struct Definition {
+    /// Params with the current data and without a selector, as made by
+    /// `export_params`.
+    pub params: JsValue,
+    /// The config as passed to createMain, without functions; undefined when
+    /// there was none.
+    pub config: JsValue,
+    pub state: ChartState,
+}
+impl Definition {
+    pub fn to_json(&self) -> String {
+        let definition = Object::new();
+        let set = |key: &str, value: &JsValue| {
+            Reflect::set(&definition, &key.into(), value).unwrap();
+        };
+        set("version", &DEFINITION_VERSION.into());
+        set("params", &self.params);
+        set("config", &self.config);
+        set("state", &self.state.to_js());
+        js_sys::JSON::stringify(&definition).unwrap().into()
+    }
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let definition = js_sys::JSON::parse(text).map_err(|error| {
+            let message = error
+                .dyn_ref::<js_sys::Error>()
+                .map_or_else(|| format!("{:?}", error), |error| error.message().into());
+            format!("definition is not valid JSON: {}", message)
+        })?;
+        if !is_plain_object(&definition) {
+            return Err("not an object: definition".to_string());
+        }
+        let get = |key: &str| Reflect::get(&definition, &key.into()).unwrap();
+        let version = get("version");
+        match version.as_f64() {
+            Some(version) if version == DEFINITION_VERSION as f64 => {}
+            Some(version) => return Err(format!("unsupported definition version: {}", version)),
+            None if version.is_undefined() => {
+                return Err("definition.version is missing".to_string())
+            }
+            None => return Err("not a number: definition.version".to_string()),
+        }
+        let params = get("params");
+        if !is_plain_object(&params) {
+            return Err("not an object: definition.params".to_string());
+        }
+        let raw_state = get("state");
+        let state = if raw_state.is_undefined() || raw_state.is_null() {
+            ChartState::default()
+        } else if is_plain_object(&raw_state) {
+            ChartState::from_js(&raw_state)?
+        } else {
+            return Err("not an object: definition.state".to_string());
+        };
+        Ok(Self {
+            params,
+            config: get("config"),
+            state,
+        })
+    }
+}
@@ -0,0 +1,330 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use crate::align_y::{SharedYDomain, YAlignment};
+use crate::controls::ControlEvent;
+use crate::definition::{ChartState, Definition};
+use crate::events::{ChartEvent, PendingChartEvents};
+use crate::loading::LoadingOverlay;
+use crate::main_chart::{ChartRebuild, DrawChart};
+use crate::params::{
+    export_params, params_with_points, ChartConfig, ChartParams, ClientCaps, ColorScheme, Content,
+    Contrast, Interactions,
+};
+use crate::screen::Screen;
+use crate::tooltip::TooltipSection;
+use crate::warnings::{ChartWarning, Warnings};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// Stands in for a chart without data: draws the "no data" message only,
+/// there are no axes, preview or legend and control events are ignored.
+pub struct EmptyChart {
+    pub container_selector: String,
+    pub wrapper: web_sys::Element,
+    pub client_caps: Rc<RefCell<ClientCaps>>,
+    pub config: ChartConfig,
+    pub base_config: ChartConfig,
+    pub color_scheme: Option<ColorScheme>,
+    pub contrast: Contrast,
+    pub screen: Screen,
+    pub dirty: bool,
+    pub events: Vec<ChartEvent>,
+    pub ready: bool,
+    /// Shown instead of the message.
+    pub loading: Option<LoadingOverlay>,
+    /// See `ChartParams::raw`.
+    pub raw_params: JsValue,
+    /// Holds the data sets declared without points until their first ones.
+    pub content: Content,
+    pub warnings: Warnings,
+}
+impl EmptyChart {
+    pub fn new(
+        params: ChartParams,
+        config: ChartConfig,
+        client_caps: Rc<RefCell<ClientCaps>>,
+    ) -> Result<Self, String> {
+        let color_scheme = config.resolve_color_scheme(&client_caps.borrow());
+        let contrast = config.resolve_contrast(&client_caps.borrow());
+        let base_config = config;
+        let config = base_config.resolve(&client_caps.borrow());
+        let wrapper = web_sys::window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .query_selector(params.selector.as_str())
+            .unwrap()
+            .ok_or_else(|| "container not found".to_string())?;
+        let screen = Screen::new(
+            params.selector.as_str(),
+            Rc::clone(&client_caps),
+            "display: block; width: 100%; height: 100%",
+            config.get_resolution_cap(),
+        )?;
+        let message = config.strings.get("noData");
+        wrapper.set_attribute("role", "img").unwrap();
+        wrapper.set_attribute("aria-label", message).unwrap();
+        let initial_loading = config.initial_loading;
+        let warnings = Warnings::default();
+        // e.g. all the points were non-finite
+        warnings.extend(params.warnings);
+        let mut chart = Self {
+            container_selector: params.selector,
+            wrapper,
+            client_caps,
+            config,
+            base_config,
+            color_scheme,
+            contrast,
+            screen,
+            dirty: true,
+            events: Vec::new(),
+            ready: false,
+            loading: None,
+            raw_params: params.raw,
+            content: params.content,
+            warnings,
+        };
+        if initial_loading {
+            chart.set_loading(true, None);
+        }
+        Ok(chart)
+    }
+}
+impl DrawChart for EmptyChart {
+    fn on_control_event(&mut self, _event: &ControlEvent, _time_us: f64) {}
+    fn accepts_control_events(&self) -> bool {
+        false
+    }
+    fn on_resize(&mut self) {
+        self.dirty = true;
+        self.screen.schedule_canvas_size_sync();
+    }
+    fn on_element_resize(&mut self, element: &web_sys::Element) {
+        if self.wrapper == *element {
+            self.on_resize();
+        }
+    }
+    fn on_client_caps_change(&mut self) {
+        self.dirty = true;
+        let client_caps = self.client_caps.borrow();
+        let color_scheme = self.base_config.resolve_color_scheme(&client_caps);
+        let contrast = self.base_config.resolve_contrast(&client_caps);
+        if color_scheme != self.color_scheme || contrast != self.contrast {
+            self.config = self.base_config.resolve(&client_caps);
+            self.color_scheme = color_scheme;
+            self.contrast = contrast;
+        }
+    }
+    fn draw(&mut self, time_us: f64) -> usize {
+        if !self.dirty {
+            return 0;
+        }
+        self.dirty = false;
+        self.screen.sync_canvas_size();
+        self.screen.clear();
+        if let Some(loading) = &self.loading {
+            self.dirty = loading.draw(
+                &self.screen,
+                &self.config,
+                false,
+                self.client_caps.borrow().reduced_motion,
+                time_us,
+            );
+            return 1;
+        }
+        let context = &self.screen.context;
+        let v = &self.config.color_tick;
+        context.set_fill_style_str(format!("rgb({}, {}, {})", v.0, v.1, v.2).as_str());
+        context.set_font(
+            format!(
+                "{}px {}",
+                self.screen.apx_to_cpx(self.config.font_size_large),
+                self.config.font_standard.as_str()
+            )
+            .as_str(),
+        );
+        context.set_text_align("center");
+        context.set_text_baseline("middle");
+        context
+            .fill_text(
+                self.config.strings.get("noData"),
+                self.screen.canvas_width * 0.5,
+                self.screen.canvas_height * 0.5,
+            )
+            .unwrap();
+        if !self.ready {
+            self.ready = true;
+            self.events.push(ChartEvent::Ready);
+        }
+        1
+    }
+    fn take_events(&mut self) -> Option<PendingChartEvents> {
+        if self.events.is_empty() {
+            return None;
+        }
+        Some(PendingChartEvents {
+            chart_id: self.container_selector.clone(),
+            target: self.wrapper.clone().into(),
+            sinks: self.config.get_event_sinks(),
+            events: std::mem::take(&mut self.events),
+            warnings: self.warnings.clone(),
+        })
+    }
+    fn set_data_set_visible(
+        &mut self,
+        name: &str,
+        _visible: bool,
+        _time_us: f64,
+    ) -> Result<(), String> {
+        Err(format!("data set not found: {}", name))
+    }
+    fn isolate_data_set(&mut self, name: &str, _time_us: f64) -> Result<(), String> {
+        Err(format!("data set not found: {}", name))
+    }
+    fn set_point_markers(&mut self, name: &str, _raw_markers: &JsValue) -> Result<(), String> {
+        Err(format!("data set not found: {}", name))
+    }
+    fn set_data_set_z_index(&mut self, name: &str, _z_index: i32) -> Result<(), String> {
+        Err(format!("data set not found: {}", name))
+    }
+    fn set_interactions(&mut self, raw_interactions: &JsValue) -> Result<(), String> {
+        self.config.interactions = Interactions::from_raw(raw_interactions)?;
+        self.base_config.interactions = self.config.interactions;
+        Ok(())
+    }
+    fn set_loading(&mut self, loading: bool, message: Option<String>) {
+        self.loading = loading.then_some(LoadingOverlay { message });
+        if loading {
+            self.wrapper.set_attribute("aria-busy", "true").unwrap();
+        } else {
+            self.wrapper.remove_attribute("aria-busy").unwrap();
+        }
+        self.dirty = true;
+    }
+    fn append_data(&mut self, raw_data_sets: &JsValue, _time_us: f64) -> Result<bool, String> {
+        let (warnings, first_points) = self
+            .content
+            .append_data(raw_data_sets, self.config.non_finite_values)?;
+        self.warnings.extend(warnings);
+        Ok(first_points)
+    }
+    fn take_rebuild(&mut self) -> Result<ChartRebuild, String> {
+        Ok(ChartRebuild {
+            raw_params: params_with_points(&self.raw_params, Some(&self.content))?,
+            config: self.base_config.clone(),
+            state: ChartState::default(),
+            warnings: self.warnings.take(),
+        })
+    }
+    fn resume_follow(&mut self, _time_us: f64) -> Result<(), String> {
+        Err("chart has no data to follow: no data".to_string())
+    }
+    fn export_definition(&self) -> Result<Definition, String> {
+        Ok(Definition {
+            params: export_params(&self.raw_params, None)?,
+            config: self.base_config.raw.clone(),
+            state: ChartState::default(),
+        })
+    }
+    fn restore_state(&mut self, _state: &ChartState, _time_us: f64) -> Result<(), String> {
+        Ok(())
+    }
+    fn get_layout(&self) -> Result<JsValue, String> {
+        Err("chart has no layout: no data".to_string())
+    }
+    fn data_to_pixel(&self, _raw_coord: &JsValue, _value: f64) -> Result<JsValue, String> {
+        Err("chart has no scales: no data".to_string())
+    }
+    fn pixel_to_data(&self, _x: f64, _y: f64) -> Result<JsValue, String> {
+        Err("chart has no scales: no data".to_string())
+    }
+    fn set_visible_range(&mut self, _raw_range: &JsValue, _time_us: f64) -> Result<(), String> {
+        Err("chart has no scales: no data".to_string())
+    }
+    fn redraw(&mut self) {
+        self.dirty = true;
+    }
+    fn set_hover_x(
+        &mut self,
+        raw_coord: &JsValue,
+        _duration_ms: Option<f64>,
+        _time_us: f64,
+    ) -> Result<(), String> {
+        if raw_coord.is_null() || raw_coord.is_undefined() {
+            return Ok(());
+        }
+        Err("chart has no scales: no data".to_string())
+    }
+    fn get_link_group(&self) -> Option<&str> {
+        self.config.link_group.as_deref()
+    }
+    fn set_link_group(&mut self, link_group: Option<String>) {
+        self.config.link_group = link_group.clone();
+        self.base_config.link_group = link_group;
+    }
+    fn set_align_y(&mut self, align_y: bool) {
+        self.config.align_y = align_y;
+        self.base_config.align_y = align_y;
+    }
+    fn get_y_alignment(&self) -> Option<YAlignment> {
+        None
+    }
+    fn set_shared_y_domain(&mut self, _shared_y_domain: SharedYDomain, _time_us: f64) -> bool {
+        false
+    }
+    fn get_shared_hover(&self) -> Option<(String, f64)> {
+        None
+    }
+    fn get_tooltip_section(&self, _coord: f64) -> Option<TooltipSection> {
+        None
+    }
+    fn set_shared_sections(&mut self, _sections: Vec<TooltipSection>) -> bool {
+        false
+    }
+    fn get_wrapper(&self) -> &web_sys::Element {
+        &self.wrapper
+    }
+    fn get_preview_wrapper(&self) -> Option<&web_sys::Element> {
+        None
+    }
+    fn on_visibility_change(
+        &mut self,
+        _intersecting: Option<bool>,
+        _document_hidden: bool,
+        _time_us: f64,
+    ) {
+    }
+    fn get_memory_trim_time_us(&self) -> Option<f64> {
+        None
+    }
+    fn trim_memory(&mut self) {}
+    fn get_on_auto_destroy(&self) -> Option<js_sys::Function> {
+        self.config.on_auto_destroy.clone()
+    }
+    fn on_context_change(&mut self, lost: bool) {
+        if !lost {
+            self.on_resize();
+        }
+    }
+    fn set_debug(&mut self, _enabled: bool) {}
+    fn has_idle_work(&self) -> bool {
+        false
+    }
+    fn do_idle_work(&mut self) {}
+    fn take_warnings(&mut self) -> Vec<ChartWarning> {
+        self.warnings.take()
+    }
+    fn returns_warnings(&self) -> bool {
+        self.base_config.return_warnings
+    }
+    fn push_warning(&mut self, warning: ChartWarning) {
+        self.warnings.push(warning);
+    }
+}
@@ -5,39 +5,664 @@
  *
  * Copyright (C) 2023, Nikita Almakov
  */
+use crate::resources::{Resource, Tracked};
+use crate::tooltip::TooltipHover;
+use crate::warnings::{ChartWarning, Warnings};
+use js_sys::{Array, Object, Reflect};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::EventTarget;
+use web_sys::{CustomEvent, CustomEventInit, EventTarget};
+
+/// Number of registered listeners, to catch duplicate registrations.
+static LISTENER_COUNT: AtomicUsize = AtomicUsize::new(0);
 
 pub struct JsEventListener {
     event_target: EventTarget,
     event_name: String,
+    capture: bool,
     closure: Closure<dyn Fn(JsValue)>,
+    _tracked: [Tracked; 2],
 }
 impl JsEventListener {
     pub fn new(
         event_target: EventTarget,
         event_name: &str,
         listener: Box<dyn Fn(JsValue)>,
+    ) -> Self {
+        Self::with_capture(event_target, event_name, false, listener)
+    }
+    /// Listens in the capture phase when `capture` is set, which also sees
+    /// events of descendants which do not bubble.
+    pub fn with_capture(
+        event_target: EventTarget,
+        event_name: &str,
+        capture: bool,
+        listener: Box<dyn Fn(JsValue)>,
     ) -> Self {
         let closure = Closure::new(listener);
         event_target
-            .add_event_listener_with_callback(event_name, closure.as_ref().unchecked_ref())
+            .add_event_listener_with_callback_and_bool(
+                event_name,
+                closure.as_ref().unchecked_ref(),
+                capture,
+            )
             .unwrap();
+        LISTENER_COUNT.fetch_add(1, Ordering::Relaxed);
         Self {
             event_target,
             event_name: event_name.to_string(),
+            capture,
             closure,
+            _tracked: [
+                Tracked::new(Resource::Listener),
+                Tracked::new(Resource::Closure),
+            ],
         }
     }
+    pub fn count() -> usize {
+        LISTENER_COUNT.load(Ordering::Relaxed)
+    }
 }
 impl Drop for JsEventListener {
     fn drop(&mut self) {
         self.event_target
-            .remove_event_listener_with_callback(
+            .remove_event_listener_with_callback_and_bool(
                 self.event_name.as_str(),
                 self.closure.as_ref().unchecked_ref(),
+                self.capture,
+            )
+            .unwrap();
+        LISTENER_COUNT.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A closure passed to the browser, counted while alive.
+pub struct JsClosure<T: ?Sized> {
+    closure: Closure<T>,
+    _tracked: Tracked,
+}
+impl<T: ?Sized + wasm_bindgen::closure::WasmClosure> JsClosure<T> {
+    pub fn new(callback: Box<T>) -> Self {
+        Self {
+            closure: Closure::wrap(callback),
+            _tracked: Tracked::new(Resource::Closure),
+        }
+    }
+    pub fn as_function(&self) -> &js_sys::Function {
+        self.closure.as_ref().unchecked_ref()
+    }
+}
+
+/// Elements an observer watches; all of them are released on disconnect.
+#[derive(Default)]
+struct Observations(RefCell<Vec<(web_sys::Element, Tracked)>>);
+impl Observations {
+    fn add(&self, element: &web_sys::Element) {
+        let mut observations = self.0.borrow_mut();
+        if !observations.iter().any(|(observed, _)| observed == element) {
+            observations.push((element.clone(), Tracked::new(Resource::Observation)));
+        }
+    }
+    fn remove(&self, element: &web_sys::Element) {
+        self.0
+            .borrow_mut()
+            .retain(|(observed, _)| observed != element);
+    }
+}
+
+/// Reports size changes of observed elements; disconnects when dropped.
+pub struct JsResizeObserver {
+    observer: web_sys::ResizeObserver,
+    observations: Observations,
+    _closure: JsClosure<dyn Fn(JsValue)>,
+}
+impl JsResizeObserver {
+    pub fn is_supported() -> bool {
+        Reflect::has(&web_sys::window().unwrap(), &"ResizeObserver".into()).unwrap()
+    }
+    /// Returns None when the browser has no ResizeObserver.
+    pub fn new(listener: Box<dyn Fn(JsValue)>) -> Option<Self> {
+        if !Self::is_supported() {
+            return None;
+        }
+        let closure = JsClosure::new(listener);
+        let observer = web_sys::ResizeObserver::new(closure.as_function()).unwrap();
+        Some(Self {
+            observer,
+            observations: Observations::default(),
+            _closure: closure,
+        })
+    }
+    pub fn observe(&self, element: &web_sys::Element) {
+        self.observer.observe(element);
+        self.observations.add(element);
+    }
+    pub fn unobserve(&self, element: &web_sys::Element) {
+        self.observer.unobserve(element);
+        self.observations.remove(element);
+    }
+}
+impl Drop for JsResizeObserver {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
+
+/// Reports whether observed elements intersect the viewport; disconnects
+/// when dropped.
+pub struct JsIntersectionObserver {
+    observer: web_sys::IntersectionObserver,
+    observations: Observations,
+    _closure: JsClosure<dyn Fn(JsValue)>,
+}
+impl JsIntersectionObserver {
+    /// Returns None when the browser has no IntersectionObserver.
+    pub fn new(listener: Box<dyn Fn(JsValue)>) -> Option<Self> {
+        if !Reflect::has(&web_sys::window().unwrap(), &"IntersectionObserver".into()).unwrap() {
+            return None;
+        }
+        let closure = JsClosure::new(listener);
+        let observer = web_sys::IntersectionObserver::new(closure.as_function()).unwrap();
+        Some(Self {
+            observer,
+            observations: Observations::default(),
+            _closure: closure,
+        })
+    }
+    pub fn observe(&self, element: &web_sys::Element) {
+        self.observer.observe(element);
+        self.observations.add(element);
+    }
+    pub fn unobserve(&self, element: &web_sys::Element) {
+        self.observer.unobserve(element);
+        self.observations.remove(element);
+    }
+}
+impl Drop for JsIntersectionObserver {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
+
+/// Calls the callback once after the delay, unless dropped before.
+pub struct JsTimeout {
+    handle: i32,
+    _closure: JsClosure<dyn Fn()>,
+}
+impl JsTimeout {
+    pub fn new(delay_ms: f64, callback: Box<dyn Fn()>) -> Self {
+        let closure = JsClosure::new(callback);
+        let handle = web_sys::window()
+            .unwrap()
+            .set_timeout_with_callback_and_timeout_and_arguments_0(
+                closure.as_function(),
+                delay_ms as i32,
             )
             .unwrap();
+        Self {
+            handle,
+            _closure: closure,
+        }
+    }
+}
+impl Drop for JsTimeout {
+    fn drop(&mut self) {
+        web_sys::window()
+            .unwrap()
+            .clear_timeout_with_handle(self.handle);
+    }
+}
+
+/// A pending call of `request_idle`.
+pub enum IdleHandle {
+    Idle(u32),
+    Timeout(i32),
+}
+
+/// Calls the callback once the browser is idle, or after a zero timeout
+/// where requestIdleCallback is missing.
+pub fn request_idle(callback: &js_sys::Function, idle_callback: bool) -> IdleHandle {
+    let window = web_sys::window().unwrap();
+    if idle_callback {
+        IdleHandle::Idle(window.request_idle_callback(callback).unwrap())
+    } else {
+        IdleHandle::Timeout(
+            window
+                .set_timeout_with_callback_and_timeout_and_arguments_0(callback, 0)
+                .unwrap(),
+        )
+    }
+}
+
+pub fn cancel_idle(handle: IdleHandle) {
+    let window = web_sys::window().unwrap();
+    match handle {
+        IdleHandle::Idle(handle) => window.cancel_idle_callback(handle),
+        IdleHandle::Timeout(handle) => window.clear_timeout_with_handle(handle),
+    }
+}
+
+thread_local! {
+    /// Calls held back until no chart is borrowed, see `defer_call`.
+    static DEFERRED_CALLS: RefCell<VecDeque<Box<dyn FnOnce()>>> =
+        RefCell::new(VecDeque::new());
+}
+
+/// Queues a call which may reach back into the manager, e.g. one which
+/// makes the browser fire events synchronously, while charts are borrowed.
+pub fn defer_call(call: Box<dyn FnOnce()>) {
+    DEFERRED_CALLS.with(|calls| calls.borrow_mut().push_back(call));
+}
+
+/// Runs the queued calls in order, those they queue included; must be called
+/// with no chart borrowed.
+pub fn run_deferred_calls() {
+    while let Some(call) = DEFERRED_CALLS.with(|calls| calls.borrow_mut().pop_front()) {
+        call();
+    }
+}
+
+/// Event kinds with the config keys of their callbacks.
+pub const EVENT_KINDS: [(&str, &str); 6] = [
+    ("ready", "onReady"),
+    ("rangechange", "onRangeChange"),
+    ("hover", "onHover"),
+    ("pointclick", "onPointClick"),
+    ("visibilitychange", "onVisibilityChange"),
+    ("followchange", "onFollowChange"),
+];
+
+pub enum ChartEvent {
+    Ready,
+    RangeChange {
+        coord_min: f64,
+        coord_max: f64,
+    },
+    Hover(Option<TooltipHover>),
+    PointClick(TooltipHover),
+    VisibilityChange(Vec<(String, bool)>),
+    /// Whether the window follows appended data.
+    FollowChange(bool),
+}
+impl ChartEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ready => "graphima:ready",
+            Self::RangeChange { .. } => "graphima:rangechange",
+            Self::Hover(_) => "graphima:hover",
+            Self::PointClick(_) => "graphima:pointclick",
+            Self::VisibilityChange(_) => "graphima:visibilitychange",
+            Self::FollowChange(_) => "graphima:followchange",
+        }
+    }
+    /// The key of the kind in `EVENT_KINDS`.
+    pub fn kind(&self) -> &'static str {
+        &self.name()["graphima:".len()..]
+    }
+    fn hover_to_js(hover: &TooltipHover, detail: &Object) {
+        let series = Array::new();
+        for (name, value) in hover.values.iter() {
+            let item = Object::new();
+            Reflect::set(&item, &"name".into(), &JsValue::from_str(name)).unwrap();
+            Reflect::set(&item, &"value".into(), &JsValue::from_f64(*value)).unwrap();
+            series.push(&item);
+        }
+        Reflect::set(detail, &"x".into(), &JsValue::from_f64(hover.coord)).unwrap();
+        Reflect::set(detail, &"series".into(), &series).unwrap();
+        Reflect::set(
+            detail,
+            &"nearest".into(),
+            &JsValue::from_str(&hover.nearest),
+        )
+        .unwrap();
+    }
+    pub fn detail(&self, chart_id: &str) -> JsValue {
+        let detail = Object::new();
+        Reflect::set(&detail, &"chartId".into(), &JsValue::from_str(chart_id)).unwrap();
+        match self {
+            Self::Ready => {}
+            Self::RangeChange {
+                coord_min,
+                coord_max,
+            } => {
+                Reflect::set(&detail, &"from".into(), &JsValue::from_f64(*coord_min)).unwrap();
+                Reflect::set(&detail, &"to".into(), &JsValue::from_f64(*coord_max)).unwrap();
+            }
+            // the same shape as a hover, so listeners know which chart it left
+            Self::Hover(None) => {
+                Reflect::set(&detail, &"x".into(), &JsValue::NULL).unwrap();
+            }
+            Self::Hover(Some(hover)) | Self::PointClick(hover) => {
+                Self::hover_to_js(hover, &detail);
+            }
+            Self::VisibilityChange(visibility) => {
+                let visible = Object::new();
+                for (name, value) in visibility.iter() {
+                    Reflect::set(
+                        &visible,
+                        &JsValue::from_str(name),
+                        &JsValue::from_bool(*value),
+                    )
+                    .unwrap();
+                }
+                Reflect::set(&detail, &"visible".into(), &visible).unwrap();
+            }
+            Self::FollowChange(following) => {
+                Reflect::set(
+                    &detail,
+                    &"following".into(),
+                    &JsValue::from_bool(*following),
+                )
+                .unwrap();
+            }
+        }
+        detail.into()
+    }
+}
+
+/// Where the events of a chart go: DOM events on its wrapper and the
+/// callbacks of the config.
+#[derive(Clone, Default)]
+pub struct EventSinks {
+    pub dom_events: bool,
+    pub callbacks: Vec<(&'static str, js_sys::Function)>,
+    pub throttle_ms: Vec<(&'static str, f64)>,
+}
+impl EventSinks {
+    fn has_listener(&self, kind: &str) -> bool {
+        self.dom_events || self.callbacks.iter().any(|(k, _)| *k == kind)
+    }
+    fn get_throttle_us(&self, kind: &str) -> f64 {
+        self.throttle_ms
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .map_or(0.0, |(_, throttle_ms)| throttle_ms * 1000.0)
+    }
+}
+
+/// Queues the event: a range change or a hover replaces the queued one, so
+/// that only the latest is delivered, but a hover leave is always kept.
+fn queue_event(events: &mut Vec<ChartEvent>, event: ChartEvent) {
+    match event {
+        ChartEvent::RangeChange { .. } => {
+            events.retain(|e| !matches!(e, ChartEvent::RangeChange { .. }));
+        }
+        ChartEvent::Hover(_) => {
+            if let Some(index) = events
+                .iter()
+                .rposition(|e| matches!(e, ChartEvent::Hover(_)))
+                .filter(|index| matches!(events[*index], ChartEvent::Hover(Some(_))))
+            {
+                events.remove(index);
+            }
+        }
+        _ => {}
+    }
+    events.push(event);
+}
+
+pub struct PendingChartEvents {
+    pub chart_id: String,
+    pub target: EventTarget,
+    pub sinks: EventSinks,
+    pub events: Vec<ChartEvent>,
+    /// Of the chart, for the callbacks which fail.
+    pub warnings: Warnings,
+}
+impl PendingChartEvents {
+    /// Must be called with no chart borrowed: listeners may call back into
+    /// the manager synchronously.
+    pub fn dispatch(self) {
+        for event in self.events.iter() {
+            let kind = event.kind();
+            // payloads are built for listeners only
+            if !self.sinks.has_listener(kind) {
+                continue;
+            }
+            let detail = event.detail(self.chart_id.as_str());
+            if self.sinks.dom_events {
+                let init = CustomEventInit::new();
+                init.set_bubbles(true);
+                init.set_detail(&detail);
+                let custom_event =
+                    CustomEvent::new_with_event_init_dict(event.name(), &init).unwrap();
+                let _ = self.target.dispatch_event(&custom_event);
+            }
+            for (_, callback) in self.sinks.callbacks.iter().filter(|(k, _)| *k == kind) {
+                if let Err(error) = callback.call1(&JsValue::NULL, &detail) {
+                    let (_, key) = EVENT_KINDS.iter().find(|(k, _)| *k == kind).unwrap();
+                    self.warnings
+                        .push(ChartWarning::callback_failed(key, error));
+                }
+            }
+        }
+    }
+}
+
+/// Batches the events of charts between frames and holds back the kinds
+/// which are throttled, see `EventSinks::throttle_ms`.
+#[derive(Default)]
+pub struct EventDispatcher {
+    queue: Vec<PendingChartEvents>,
+    /// Last delivery by chart id and kind, of throttled kinds only.
+    last_dispatch_us: Vec<(String, &'static str, f64)>,
+}
+impl EventDispatcher {
+    pub fn push(&mut self, pending: PendingChartEvents) {
+        let queued = match self
+            .queue
+            .iter_mut()
+            .position(|queued| queued.chart_id == pending.chart_id)
+        {
+            Some(index) => &mut self.queue[index],
+            None => {
+                self.queue.push(PendingChartEvents {
+                    chart_id: pending.chart_id.clone(),
+                    target: pending.target.clone(),
+                    sinks: EventSinks::default(),
+                    events: Vec::new(),
+                    warnings: pending.warnings.clone(),
+                });
+                self.queue.last_mut().unwrap()
+            }
+        };
+        queued.sinks = pending.sinks;
+        for event in pending.events {
+            queue_event(&mut queued.events, event);
+        }
+    }
+    /// Drops the queue of a destroyed chart.
+    pub fn forget(&mut self, chart_id: &str) {
+        self.queue.retain(|queued| queued.chart_id != chart_id);
+        self.last_dispatch_us.retain(|(id, _, _)| id != chart_id);
+    }
+    /// Takes the events due at the time; also returns when the earliest of
+    /// the held back ones is due.
+    pub fn take_due(&mut self, time_us: f64) -> (Vec<PendingChartEvents>, Option<f64>) {
+        let mut due = Vec::new();
+        let mut next_due_us: Option<f64> = None;
+        for queued in self.queue.iter_mut() {
+            let mut due_events = Vec::new();
+            for event in std::mem::take(&mut queued.events) {
+                let kind = event.kind();
+                let throttle_us = queued.sinks.get_throttle_us(kind);
+                if throttle_us <= 0.0 {
+                    due_events.push(event);
+                    continue;
+                }
+                let last = self
+                    .last_dispatch_us
+                    .iter_mut()
+                    .find(|(id, k, _)| *id == queued.chart_id && *k == kind);
+                let due_us = last.as_ref().map_or(time_us, |(_, _, t)| t + throttle_us);
+                if due_us <= time_us {
+                    match last {
+                        Some(last) => last.2 = time_us,
+                        None => {
+                            self.last_dispatch_us
+                                .push((queued.chart_id.clone(), kind, time_us));
+                        }
+                    }
+                    due_events.push(event);
+                } else {
+                    next_due_us = Some(next_due_us.map_or(due_us, |v| v.min(due_us)));
+                    queued.events.push(event);
+                }
+            }
+            if !due_events.is_empty() {
+                due.push(PendingChartEvents {
+                    chart_id: queued.chart_id.clone(),
+                    target: queued.target.clone(),
+                    sinks: queued.sinks.clone(),
+                    events: due_events,
+                    warnings: queued.warnings.clone(),
+                });
+            }
+        }
+        self.queue.retain(|queued| !queued.events.is_empty());
+        (due, next_due_us)
+    }
+}
+
+/// Holds the outbound hover back until it stays the same for the settle
+/// time, see `ChartConfig::hover_settle_ms`; times are frame times.
+#[derive(Default)]
+pub struct HoverSettle {
+    /// The latest hover with the time it started.
+    pending: Option<(Option<TooltipHover>, f64)>,
+    /// The last hover sent out.
+    emitted: Option<TooltipHover>,
+}
+impl HoverSettle {
+    /// Returns the hover to send out at once: the leave of a hover sent out
+    /// before, or any hover with no settle time.
+    pub fn change(
+        &mut self,
+        hover: Option<TooltipHover>,
+        settle_us: f64,
+        time_us: f64,
+    ) -> Option<Option<TooltipHover>> {
+        if settle_us <= 0.0 || hover.is_none() {
+            self.pending = None;
+            return self.emit(hover);
+        }
+        self.pending = Some((hover, time_us));
+        None
+    }
+    /// Returns the pending hover once it has settled.
+    pub fn take_settled(&mut self, settle_us: f64, time_us: f64) -> Option<Option<TooltipHover>> {
+        match &self.pending {
+            Some((_, since_us)) if time_us - since_us >= settle_us => {
+                let (hover, _) = self.pending.take().unwrap();
+                self.emit(hover)
+            }
+            _ => None,
+        }
+    }
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+    fn emit(&mut self, hover: Option<TooltipHover>) -> Option<Option<TooltipHover>> {
+        if hover == self.emitted {
+            return None;
+        }
+        self.emitted = hover.clone();
+        Some(hover)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::events::{defer_call, queue_event, run_deferred_calls, ChartEvent, HoverSettle};
+    use crate::tooltip::TooltipHover;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn hover(coord: f64) -> ChartEvent {
+        ChartEvent::Hover(Some(TooltipHover {
+            coord,
+            values: Vec::new(),
+            nearest: String::new(),
+        }))
+    }
+
+    #[test]
+    fn test_queue_event() {
+        let mut events = Vec::new();
+        for (coord_min, coord_max) in [(0.0, 10.0), (1.0, 9.0)] {
+            queue_event(
+                &mut events,
+                ChartEvent::RangeChange {
+                    coord_min,
+                    coord_max,
+                },
+            );
+        }
+        queue_event(&mut events, hover(1.0));
+        queue_event(&mut events, hover(2.0));
+        queue_event(&mut events, ChartEvent::Hover(None));
+        queue_event(&mut events, hover(3.0));
+        queue_event(&mut events, hover(4.0));
+        let queued: Vec<String> = events
+            .iter()
+            .map(|event| match event {
+                ChartEvent::RangeChange { coord_min, .. } => format!("range {}", coord_min),
+                ChartEvent::Hover(Some(hover)) => format!("hover {}", hover.coord),
+                ChartEvent::Hover(None) => "leave".to_string(),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(queued, vec!["range 1", "leave", "hover 4"]);
+    }
+
+    #[test]
+    fn test_deferred_calls() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let log_1 = Rc::clone(&log);
+        defer_call(Box::new(move || {
+            log_1.borrow_mut().push(1);
+            let log_3 = Rc::clone(&log_1);
+            // queued by a deferred call, runs in the same pass
+            defer_call(Box::new(move || log_3.borrow_mut().push(3)));
+        }));
+        let log_2 = Rc::clone(&log);
+        defer_call(Box::new(move || log_2.borrow_mut().push(2)));
+        assert!(log.borrow().is_empty());
+        run_deferred_calls();
+        assert_eq!(*log.borrow(), vec![1, 2, 3]);
+        run_deferred_calls();
+        assert_eq!(log.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_hover_settle() {
+        let at = |coord: f64| match hover(coord) {
+            ChartEvent::Hover(hover) => hover,
+            _ => unreachable!(),
+        };
+        let coord = |hover: Option<Option<TooltipHover>>| hover.map(|h| h.map(|h| h.coord));
+        let mut settle = HoverSettle::default();
+        assert_eq!(coord(settle.change(at(1.0), 0.0, 0.0)), Some(Some(1.0)));
+        assert_eq!(coord(settle.change(None, 0.0, 0.0)), Some(None));
+
+        let mut settle = HoverSettle::default();
+        assert_eq!(coord(settle.change(at(1.0), 100.0, 0.0)), None);
+        assert_eq!(coord(settle.change(at(2.0), 100.0, 50.0)), None);
+        assert_eq!(coord(settle.take_settled(100.0, 120.0)), None);
+        assert!(settle.is_pending());
+        assert_eq!(coord(settle.take_settled(100.0, 150.0)), Some(Some(2.0)));
+        assert!(!settle.is_pending());
+        // moving away and back within the settle time sends nothing
+        assert_eq!(coord(settle.change(at(3.0), 100.0, 200.0)), None);
+        assert_eq!(coord(settle.change(at(2.0), 100.0, 250.0)), None);
+        assert_eq!(coord(settle.take_settled(100.0, 350.0)), None);
+        // the leave is sent at once, and only after a hover was sent
+        assert_eq!(coord(settle.change(None, 100.0, 400.0)), Some(None));
+        assert_eq!(coord(settle.change(at(4.0), 100.0, 500.0)), None);
+        assert_eq!(coord(settle.change(None, 100.0, 550.0)), None);
+        assert_eq!(coord(settle.take_settled(100.0, 700.0)), None);
     }
 }
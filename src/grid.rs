@@ -7,8 +7,41 @@
  */
 use crate::animate::AnimatedNumber;
 use crate::params::DataType;
+use crate::time_zone::{align_to_local, TimeZone};
 use std::cmp::Ordering;
 
+const MS_PER_HOUR: f64 = 3600000.0;
+const MS_PER_DAY: f64 = 86400000.0;
+// seconds to weeks, in ms
+const TIME_STEPS: [f64; 22] = [
+    1e3, 2e3, 5e3, 1e4, 15e3, 3e4, 6e4, 12e4, 3e5, 6e5, 9e5, 18e5, 36e5, 72e5, 108e5, 216e5, 432e5,
+    864e5, 1728e5, 3456e5, 6048e5, 12096e5,
+];
+
+fn get_nice_number(raw_step: f64) -> f64 {
+    let magnitude = 10f64.powf(raw_step.log10().floor());
+    for multiplier in [1.0, 2.0, 5.0, 10.0] {
+        if multiplier * magnitude >= raw_step * (1.0 - 1e-9) {
+            return multiplier * magnitude;
+        }
+    }
+    magnitude * 10.0
+}
+
+/// Returns the smallest nice step not less than `raw_step`: 1/2/5 × 10^k for
+/// numbers; seconds, minutes, hours, days and weeks for dates, then 1/2/5 ×
+/// 10^k days.
+pub fn get_nice_step(data_type: &DataType, raw_step: f64) -> f64 {
+    match data_type {
+        DataType::Number => get_nice_number(raw_step),
+        DataType::Date | DataType::DateTime { .. } => TIME_STEPS
+            .iter()
+            .cloned()
+            .find(|step| *step >= raw_step)
+            .unwrap_or_else(|| get_nice_number(raw_step / MS_PER_DAY) * MS_PER_DAY),
+    }
+}
+
 #[derive(Debug)]
 pub struct Tick {
     pub normalized_value: f64,
@@ -29,6 +62,12 @@ pub struct Grid {
     pub min_period: Option<f64>,
     pub current_period: f64,
     pub generations: Vec<TickGeneration>,
+    nice_steps: Option<(DataType, f64)>,
+    global_min: f64,
+    /// The zone of datetime ticks and its offset at `global_min`, which
+    /// places them; ticks an hour or more apart are moved to the same local
+    /// time under the offset at each of them, see `align_to_local`.
+    local_alignment: Option<(TimeZone, f64)>,
 }
 impl Grid {
     pub fn new(data_type: DataType, global_min: f64, global_max: f64) -> Self {
@@ -63,6 +102,32 @@ impl Grid {
                 period: grid_period,
                 alpha: AnimatedNumber::new(1.0),
             }],
+            nice_steps: None,
+            global_min,
+            local_alignment: None,
+        }
+    }
+    /// Places ticks on nice values of the data (see `get_nice_step`) instead
+    /// of halving the normalized range.
+    pub fn with_nice_steps(data_type: DataType, global_min: f64, global_max: f64) -> Self {
+        let span = global_max - global_min;
+        // ticks are aligned to zero, i.e. to midnights for dates
+        let local_alignment = match data_type {
+            DataType::DateTime { time_zone } => {
+                Some((time_zone, time_zone.get_offset_ms(global_min)))
+            }
+            _ => None,
+        };
+        let origin = local_alignment.map_or(0.0, |(_, offset_ms)| -offset_ms);
+        Self {
+            grid_base: (origin - global_min) / span,
+            grid_period: 1.0,
+            min_period: data_type.get_min_period().map(|v| v / span),
+            current_period: 0.0,
+            generations: Vec::new(),
+            nice_steps: Some((data_type, span)),
+            global_min,
+            local_alignment,
         }
     }
 
@@ -74,11 +139,16 @@ impl Grid {
         max_ticks: f64,
     ) -> Vec<Tick> {
         let range = normalized_max_value - normalized_min_value;
-        let mut period = self.grid_period
-            * f64::powi(
-                2.0,
-                (range / self.grid_period / max_ticks).log2().round() as i32,
-            );
+        let mut period = match &self.nice_steps {
+            Some((data_type, span)) => get_nice_step(data_type, range / max_ticks * span) / span,
+            None => {
+                self.grid_period
+                    * f64::powi(
+                        2.0,
+                        (range / self.grid_period / max_ticks).log2().round() as i32,
+                    )
+            }
+        };
         if let Some(min_period) = self.min_period {
             if min_period > period {
                 period = min_period;
@@ -104,7 +174,11 @@ impl Grid {
                 }
             }
             if generation_to_be_created {
-                let mut alpha = AnimatedNumber::new(0.4);
+                let mut alpha = AnimatedNumber::new(if self.generations.is_empty() {
+                    1.0
+                } else {
+                    0.4
+                });
                 alpha.set_value(1.0, Some(time_us));
                 self.generations.push(TickGeneration { period, alpha });
             }
@@ -119,17 +193,35 @@ impl Grid {
             let alpha = generation.alpha.get_value(time_us);
             let end_alpha = generation.alpha.get_end_value();
             let period = generation.period;
-            let mut normalized_value =
-                normalized_min_value - (normalized_min_value - self.grid_base) % period + period;
+            let mut normalized_value = normalized_min_value
+                - (normalized_min_value - self.grid_base).rem_euclid(period)
+                + period;
 
             let right_bound = normalized_max_value - period * 0.25;
             let left_bound = normalized_min_value + period * 0.25;
+            let local_alignment = match (self.local_alignment, &self.nice_steps) {
+                (Some(local_alignment), Some((_, span))) if period * span >= MS_PER_HOUR => {
+                    Some((local_alignment, *span))
+                }
+                _ => None,
+            };
 
             while normalized_value < normalized_max_value {
+                let tick_value = match local_alignment {
+                    Some(((time_zone, offset_ms), span)) => {
+                        let value = align_to_local(
+                            self.global_min + normalized_value * span,
+                            offset_ms,
+                            |utc_ms| time_zone.get_offset_ms(utc_ms),
+                        );
+                        (value - self.global_min) / span
+                    }
+                    None => normalized_value,
+                };
                 ticks.push(Tick {
-                    normalized_value,
+                    normalized_value: tick_value,
                     value: 0.0,
-                    alpha: if normalized_value < left_bound || normalized_value > right_bound {
+                    alpha: if tick_value < left_bound || tick_value > right_bound {
                         alpha * 0.5
                     } else {
                         alpha
@@ -160,3 +252,24 @@ impl Grid {
         ticks
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::get_nice_step;
+    use crate::params::DataType;
+
+    #[test]
+    fn test_get_nice_step() {
+        assert_eq!(get_nice_step(&DataType::Number, 0.3), 0.5);
+        assert_eq!(get_nice_step(&DataType::Number, 1.0), 1.0);
+        assert_eq!(get_nice_step(&DataType::Number, 1.1), 2.0);
+        assert_eq!(get_nice_step(&DataType::Number, 2.5), 5.0);
+        assert_eq!(get_nice_step(&DataType::Number, 700.0), 1000.0);
+        assert_eq!(get_nice_step(&DataType::Date, 40e3), 6e4);
+        assert_eq!(get_nice_step(&DataType::Date, 5e6), 72e5);
+        assert_eq!(
+            get_nice_step(&DataType::Date, 30.0 * 86400000.0),
+            50.0 * 86400000.0
+        );
+    }
+}
@@ -0,0 +1,126 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use crate::params::ChartConfig;
+use crate::tooltip::TooltipHover;
+use wasm_bindgen::JsCast;
+
+const SERIES_ATTRIBUTE: &str = "data-graphima-series";
+const HOVER_X_ATTRIBUTE: &str = "data-graphima-hover-x";
+const HOVER_Y_ATTRIBUTE: &str = "data-graphima-hover-y";
+
+/// Mirrors the hover onto the page: elements whose data-graphima-series is
+/// the hovered series get `ChartConfig::hover_link_class` and the wrapper
+/// gets the hovered values as data-graphima-hover-x/-y. Everything is
+/// undone on hover end and when dropped.
+pub struct HoverLink {
+    wrapper: web_sys::Element,
+    series: Option<String>,
+    /// Elements which got the class.
+    marked: Vec<web_sys::Element>,
+    class: String,
+    /// Whether the wrapper has the attributes.
+    attributes_set: bool,
+}
+impl HoverLink {
+    pub fn new(wrapper: &web_sys::Element) -> Self {
+        Self {
+            wrapper: wrapper.clone(),
+            series: None,
+            marked: Vec::new(),
+            class: String::new(),
+            attributes_set: false,
+        }
+    }
+    /// Called once per frame when the hover changed, so the DOM is written
+    /// at most once a frame.
+    pub fn update(&mut self, config: &ChartConfig, hover: Option<&TooltipHover>) {
+        let class = match &config.hover_link_class {
+            Some(class) => class,
+            None => {
+                self.clear();
+                return;
+            }
+        };
+        let series = hover.map(|hover| hover.nearest.clone());
+        if series != self.series || *class != self.class {
+            self.unmark();
+            self.class = class.clone();
+            if let Some(series) = &series {
+                self.mark(config.hover_link_root.as_deref(), series);
+            }
+            self.series = series;
+        }
+        match hover {
+            Some(hover) => {
+                self.attributes_set = true;
+                self.wrapper
+                    .set_attribute(HOVER_X_ATTRIBUTE, hover.coord.to_string().as_str())
+                    .unwrap();
+                match hover.values.iter().find(|(name, _)| *name == hover.nearest) {
+                    Some((_, value)) => self
+                        .wrapper
+                        .set_attribute(HOVER_Y_ATTRIBUTE, value.to_string().as_str())
+                        .unwrap(),
+                    None => self.wrapper.remove_attribute(HOVER_Y_ATTRIBUTE).unwrap(),
+                }
+            }
+            None => self.remove_attributes(),
+        }
+    }
+    /// Adds the class to the elements of the series within the root, found
+    /// anew each time as the page may have changed.
+    fn mark(&mut self, root_selector: Option<&str>, series: &str) {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let root = match root_selector {
+            Some(root_selector) => document.query_selector(root_selector).ok().flatten(),
+            None => document.document_element(),
+        };
+        let elements =
+            match root.map(|root| root.query_selector_all(&format!("[{}]", SERIES_ATTRIBUTE))) {
+                Some(Ok(elements)) => elements,
+                _ => return,
+            };
+        for index in 0..elements.length() {
+            let element = match elements
+                .item(index)
+                .and_then(|node| node.dyn_into::<web_sys::Element>().ok())
+            {
+                Some(element) => element,
+                None => continue,
+            };
+            // compared here, so names need no escaping in the selector
+            if element.get_attribute(SERIES_ATTRIBUTE).as_deref() == Some(series) {
+                element.class_list().add_1(self.class.as_str()).unwrap();
+                self.marked.push(element);
+            }
+        }
+    }
+    fn unmark(&mut self) {
+        for element in self.marked.drain(..) {
+            element.class_list().remove_1(self.class.as_str()).unwrap();
+        }
+    }
+    fn remove_attributes(&mut self) {
+        if !self.attributes_set {
+            return;
+        }
+        self.attributes_set = false;
+        self.wrapper.remove_attribute(HOVER_X_ATTRIBUTE).unwrap();
+        self.wrapper.remove_attribute(HOVER_Y_ATTRIBUTE).unwrap();
+    }
+    fn clear(&mut self) {
+        self.unmark();
+        self.series = None;
+        self.remove_attributes();
+    }
+}
+impl Drop for HoverLink {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
@@ -0,0 +1,401 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use crate::debug::console_warn;
+use crate::params::ChartConfig;
+use crate::tooltip::{TooltipHover, TooltipSection};
+use crate::utils::place_rect_inside;
+use js_sys::{Array, Object, Reflect};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+const POINTER_SHIFT: f64 = 12.0;
+
+pub struct HtmlTooltipLine {
+    pub name: String,
+    pub value: f64,
+    pub formatted_value: String,
+    pub formatted_change: Option<String>,
+    pub color: String,
+    pub nearest: bool,
+}
+
+pub struct HtmlTooltip {
+    chart_config: Rc<ChartConfig>,
+    wrapper: web_sys::Element,
+    element: web_sys::HtmlElement,
+    rendered_hover: Option<TooltipHover>,
+    rendered_sections: Vec<TooltipSection>,
+    /// offsetWidth and offsetHeight of the rendered content, measured once
+    /// per render as reading them forces a layout.
+    rendered_size: Option<(f64, f64)>,
+}
+
+impl HtmlTooltip {
+    pub fn new(chart_config: Rc<ChartConfig>, wrapper: &web_sys::Element) -> Self {
+        let element: web_sys::HtmlElement = web_sys::window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("div")
+            .unwrap()
+            .unchecked_into();
+        wrapper.append_child(&element).unwrap();
+        let tooltip = Self {
+            chart_config,
+            wrapper: wrapper.clone(),
+            element,
+            rendered_hover: None,
+            rendered_sections: Vec::new(),
+            rendered_size: None,
+        };
+        tooltip.set_style(None);
+        tooltip
+    }
+
+    fn set_style(&self, position: Option<(f64, f64)>) {
+        let v = &self.chart_config.color_tooltip;
+        let background = format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3);
+        let v = &self.chart_config.color_tooltip_font;
+        let font_color = format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3);
+        let (display, x, y) = match position {
+            Some((x, y)) => ("block", x, y),
+            None => ("none", 0.0, 0.0),
+        };
+        self.element
+            .set_attribute(
+                "style",
+                format!(
+                    "display: {}; position: absolute; left: {:.1}px; top: {:.1}px; \
+                     pointer-events: none; box-sizing: border-box; padding: 5px; \
+                     border: {}px solid {}; background: {}; color: {}; \
+                     font: {}px {}; white-space: nowrap; direction: {}",
+                    display,
+                    x,
+                    y,
+                    self.chart_config.stroke_width_coeff,
+                    font_color,
+                    background,
+                    font_color,
+                    self.chart_config.font_size_normal,
+                    self.chart_config.font_monospace,
+                    if self.chart_config.is_rtl() {
+                        "rtl"
+                    } else {
+                        "ltr"
+                    },
+                )
+                .as_str(),
+            )
+            .unwrap();
+    }
+
+    pub fn set_chart_config(&mut self, chart_config: Rc<ChartConfig>) {
+        self.chart_config = chart_config;
+        // re-rendered in the new colors on the next show
+        self.rendered_hover = None;
+        self.set_style(None);
+    }
+    pub fn hide(&mut self) {
+        if self.rendered_hover.is_some() {
+            self.rendered_hover = None;
+            self.set_style(None);
+        }
+    }
+
+    /// Re-renders and measures the content only when the hovered point or
+    /// the sections change; the position is updated on every call. The anchor is in css px
+    /// relative to the chart wrapper.
+    pub fn show(
+        &mut self,
+        hover: &TooltipHover,
+        formatted_coord: &str,
+        lines: &[HtmlTooltipLine],
+        more_line: Option<&str>,
+        sections: &[TooltipSection],
+        anchor: (f64, f64),
+    ) {
+        if self.rendered_hover.as_ref() != Some(hover) || self.rendered_sections != sections {
+            self.render(hover, formatted_coord, lines, more_line, sections);
+            self.rendered_hover = Some(hover.clone());
+            self.rendered_sections = sections.to_vec();
+            self.rendered_size = None;
+        }
+        let size = match self.rendered_size {
+            Some(size) => size,
+            None => {
+                // the element has to be displayed to be measured
+                self.set_style(Some((0.0, 0.0)));
+                let size = (
+                    self.element.offset_width() as f64,
+                    self.element.offset_height() as f64,
+                );
+                self.rendered_size = Some(size);
+                size
+            }
+        };
+        let (x, y) = place_rect_inside(
+            anchor,
+            size,
+            [
+                0.0,
+                0.0,
+                self.wrapper.client_width() as f64,
+                self.wrapper.client_height() as f64,
+            ],
+            (POINTER_SHIFT, POINTER_SHIFT),
+        );
+        self.set_style(Some((x, y)));
+    }
+
+    fn render(
+        &self,
+        hover: &TooltipHover,
+        formatted_coord: &str,
+        lines: &[HtmlTooltipLine],
+        more_line: Option<&str>,
+        sections: &[TooltipSection],
+    ) {
+        if let Some(render_tooltip) = &self.chart_config.render_tooltip {
+            let payload = Self::get_payload(hover, formatted_coord, lines, more_line, sections);
+            match render_tooltip.call1(&JsValue::NULL, &payload) {
+                Ok(result) => {
+                    if let Some(html) = result.as_string() {
+                        self.element.set_inner_html(html.as_str());
+                        return;
+                    }
+                    if let Ok(node) = result.dyn_into::<web_sys::Node>() {
+                        self.element.set_inner_html("");
+                        self.element.append_child(&node).unwrap();
+                        return;
+                    }
+                    console_warn("renderTooltip should return a string or a Node");
+                }
+                Err(_) => console_warn("renderTooltip failed, using the default tooltip"),
+            }
+        }
+        self.render_default(formatted_coord, lines, more_line, sections);
+    }
+
+    fn render_default(
+        &self,
+        formatted_coord: &str,
+        lines: &[HtmlTooltipLine],
+        more_line: Option<&str>,
+        sections: &[TooltipSection],
+    ) {
+        let document = web_sys::window().unwrap().document().unwrap();
+        self.element.set_inner_html("");
+
+        let heading = document.create_element("div").unwrap();
+        heading
+            .set_attribute(
+                "style",
+                "font-weight: bold; text-align: center; margin-bottom: 10px",
+            )
+            .unwrap();
+        heading.set_text_content(Some(formatted_coord));
+        self.element.append_child(&heading).unwrap();
+
+        for line in lines.iter() {
+            let row = document.create_element("div").unwrap();
+            row.set_attribute(
+                "style",
+                format!(
+                    "display: flex; align-items: center; margin-top: 2px{}",
+                    if line.nearest {
+                        "; font-weight: bold"
+                    } else {
+                        ""
+                    }
+                )
+                .as_str(),
+            )
+            .unwrap();
+
+            let color = document.create_element("span").unwrap();
+            color
+                .set_attribute(
+                    "style",
+                    format!(
+                        "display: inline-block; width: 1em; height: 1em; margin-right: 5px; background: {}",
+                        line.color
+                    )
+                    .as_str(),
+                )
+                .unwrap();
+            let name = document.create_element("span").unwrap();
+            name.set_attribute("style", "flex-grow: 1; margin-right: 5px")
+                .unwrap();
+            name.set_text_content(Some(line.name.as_str()));
+            let value = document.create_element("span").unwrap();
+            value.set_text_content(Some(
+                match &line.formatted_change {
+                    Some(formatted_change) => {
+                        format!("{} {}", line.formatted_value, formatted_change)
+                    }
+                    None => line.formatted_value.clone(),
+                }
+                .as_str(),
+            ));
+
+            row.append_child(&color).unwrap();
+            row.append_child(&name).unwrap();
+            row.append_child(&value).unwrap();
+            self.element.append_child(&row).unwrap();
+        }
+        if let Some(more_line) = more_line {
+            self.append_more_line(more_line);
+        }
+
+        for section in sections.iter() {
+            let title = document.create_element("div").unwrap();
+            title
+                .set_attribute("style", "font-weight: bold; margin-top: 5px")
+                .unwrap();
+            title.set_text_content(Some(section.title.as_str()));
+            self.element.append_child(&title).unwrap();
+            for line in section.lines.iter() {
+                let row = document.create_element("div").unwrap();
+                row.set_attribute(
+                    "style",
+                    "display: flex; align-items: center; margin-top: 2px",
+                )
+                .unwrap();
+                let color = document.create_element("span").unwrap();
+                color
+                    .set_attribute(
+                        "style",
+                        format!(
+                            "display: inline-block; width: 1em; height: 1em; margin-right: 5px; background: {}",
+                            line.color
+                        )
+                        .as_str(),
+                    )
+                    .unwrap();
+                let name = document.create_element("span").unwrap();
+                name.set_attribute("style", "flex-grow: 1; margin-right: 5px")
+                    .unwrap();
+                name.set_text_content(Some(line.name.as_str()));
+                let value = document.create_element("span").unwrap();
+                value.set_text_content(Some(line.formatted_value.as_str()));
+                row.append_child(&color).unwrap();
+                row.append_child(&name).unwrap();
+                row.append_child(&value).unwrap();
+                self.element.append_child(&row).unwrap();
+            }
+            if let Some(more_line) = &section.more_line {
+                self.append_more_line(more_line.as_str());
+            }
+        }
+    }
+
+    fn append_more_line(&self, more_line: &str) {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let row = document.create_element("div").unwrap();
+        row.set_attribute("style", "margin-top: 2px; opacity: 0.7")
+            .unwrap();
+        row.set_text_content(Some(more_line));
+        self.element.append_child(&row).unwrap();
+    }
+
+    fn get_payload(
+        hover: &TooltipHover,
+        formatted_coord: &str,
+        lines: &[HtmlTooltipLine],
+        more_line: Option<&str>,
+        sections: &[TooltipSection],
+    ) -> JsValue {
+        let series = Array::new();
+        for line in lines.iter() {
+            let item = Object::new();
+            Reflect::set(
+                &item,
+                &"name".into(),
+                &JsValue::from_str(line.name.as_str()),
+            )
+            .unwrap();
+            Reflect::set(&item, &"value".into(), &JsValue::from_f64(line.value)).unwrap();
+            Reflect::set(
+                &item,
+                &"formattedValue".into(),
+                &JsValue::from_str(line.formatted_value.as_str()),
+            )
+            .unwrap();
+            Reflect::set(
+                &item,
+                &"formattedChange".into(),
+                &line
+                    .formatted_change
+                    .as_deref()
+                    .map_or(JsValue::NULL, JsValue::from_str),
+            )
+            .unwrap();
+            Reflect::set(
+                &item,
+                &"color".into(),
+                &JsValue::from_str(line.color.as_str()),
+            )
+            .unwrap();
+            Reflect::set(&item, &"nearest".into(), &JsValue::from_bool(line.nearest)).unwrap();
+            series.push(&item);
+        }
+        let payload = Object::new();
+        Reflect::set(&payload, &"x".into(), &JsValue::from_f64(hover.coord)).unwrap();
+        Reflect::set(
+            &payload,
+            &"formattedX".into(),
+            &JsValue::from_str(formatted_coord),
+        )
+        .unwrap();
+        Reflect::set(&payload, &"series".into(), &series).unwrap();
+        Reflect::set(
+            &payload,
+            &"more".into(),
+            &more_line.map_or(JsValue::NULL, JsValue::from_str),
+        )
+        .unwrap();
+        let shared = Array::new();
+        for section in sections.iter() {
+            let series = Array::new();
+            for line in section.lines.iter() {
+                let item = Object::new();
+                Reflect::set(&item, &"name".into(), &line.name.as_str().into()).unwrap();
+                Reflect::set(&item, &"value".into(), &line.value.into()).unwrap();
+                Reflect::set(
+                    &item,
+                    &"formattedValue".into(),
+                    &line.formatted_value.as_str().into(),
+                )
+                .unwrap();
+                Reflect::set(&item, &"color".into(), &line.color.as_str().into()).unwrap();
+                series.push(&item);
+            }
+            let item = Object::new();
+            Reflect::set(&item, &"title".into(), &section.title.as_str().into()).unwrap();
+            Reflect::set(&item, &"series".into(), &series).unwrap();
+            Reflect::set(
+                &item,
+                &"more".into(),
+                &section
+                    .more_line
+                    .as_deref()
+                    .map_or(JsValue::NULL, JsValue::from_str),
+            )
+            .unwrap();
+            shared.push(&item);
+        }
+        Reflect::set(&payload, &"sections".into(), &shared).unwrap();
+        payload.into()
+    }
+}
+impl Drop for HtmlTooltip {
+    fn drop(&mut self) {
+        self.element.remove();
+    }
+}
@@ -5,7 +5,8 @@
  *
  * Copyright (C) 2023, Nikita Almakov
  */
-use crate::params::{ChartConfig, Content};
+use crate::params::{ChartConfig, Content, LegendOverflow};
+use crate::pattern::{get_comparison_line_dash, get_line_dash, HatchPatterns};
 use crate::screen::{Screen, ScreenRect};
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
@@ -13,12 +14,21 @@ use wasm_bindgen::prelude::*;
 const SCREEN_PADDING: f64 = 5.0;
 const MARGIN_HORIZONTAL: f64 = 15.0;
 const MARGIN_VERTICAL: f64 = 5.0;
+// an item is hit this close to it, scaled by the hit scale
+const HIT_PADDING: f64 = 2.0;
+// part of the visible row an arrow click scrolls by
+const SCROLL_PAGE_PORTION: f64 = 0.8;
 
 pub struct LegendItem {
     pub width: f64,
     pub height: f64,
     pub color: String,
     pub name: String,
+    pub pattern: usize,
+    /// Set for comparison series, which get a dashed frame.
+    pub comparison: bool,
+    /// Data sets with showInLegend unset have no item.
+    pub data_set_index: usize,
 }
 
 pub struct Legend {
@@ -34,7 +44,19 @@ pub struct Legend {
     pub offset: usize,
     pub mandatory_right_index: Option<usize>,
     pub approx_per_page: Option<usize>,
+    pub has_prev: bool,
     pub has_next: bool,
+    pub focused: Option<usize>,
+    /// The data set shown alone, its item is marked with a bar under it.
+    pub isolated: Option<usize>,
+    /// Horizontal range where items are displayed, between the arrows.
+    pub viewport_cx: (f64, f64),
+    pub scroll_cx: f64,
+    pub max_scroll_cx: f64,
+    pub drag_origin_scroll_cx: Option<f64>,
+    /// Height needed to show all rows, in the "wrap" mode.
+    pub required_cheight: Option<f64>,
+    pub hatch_patterns: HatchPatterns,
 }
 
 impl Legend {
@@ -53,7 +75,16 @@ impl Legend {
             offset: 0,
             mandatory_right_index: None,
             approx_per_page: None,
+            has_prev: false,
             has_next: false,
+            focused: None,
+            isolated: None,
+            viewport_cx: (0.0, 0.0),
+            scroll_cx: 0.0,
+            max_scroll_cx: 0.0,
+            drag_origin_scroll_cx: None,
+            required_cheight: None,
+            hatch_patterns: HatchPatterns::default(),
         }
     }
     fn content_to_items(
@@ -67,15 +98,105 @@ impl Legend {
         content
             .data_sets
             .iter()
-            .map(|data_set| LegendItem {
+            .enumerate()
+            .filter(|(_, data_set)| data_set.show_in_legend)
+            .map(|(data_set_index, data_set)| LegendItem {
                 width: c_font_width * data_set.name.len() as f64 + c_double_padding,
                 height: c_font_height + c_double_padding,
                 color: data_set.to_css_color(1.0),
                 name: data_set.name.clone(),
+                pattern: data_set.pattern,
+                comparison: data_set.comparison.is_some(),
+                data_set_index,
             })
             .collect()
     }
+    /// Rebuilds items once data sets are added, removed or renamed.
+    pub fn sync_items(&mut self, content: &Content, screen: &Screen) {
+        let items = Self::content_to_items(Rc::clone(&self.chart_config), content, screen);
+        if items.len() == self.items.len()
+            && items.iter().zip(self.items.iter()).all(|(a, b)| {
+                a.name == b.name
+                    && a.color == b.color
+                    && a.width == b.width
+                    && a.pattern == b.pattern
+                    && a.comparison == b.comparison
+                    && a.data_set_index == b.data_set_index
+            })
+        {
+            return;
+        }
+        self.items = items;
+        if self.offset >= self.items.len() {
+            self.offset = 0;
+        }
+        self.focused = self
+            .focused
+            .filter(|_| !self.items.is_empty())
+            .map(|index| index.min(self.items.len() - 1));
+        self.cx_end = 0.0; // forcing resize
+    }
+    /// Moves the page so that the item at `index` is displayed.
+    pub fn scroll_to(&mut self, index: usize) {
+        match self.chart_config.legend_overflow {
+            LegendOverflow::Page => {
+                if index < self.offset {
+                    self.prev_page();
+                } else if index >= self.offset + self.positions.len() {
+                    self.next_page();
+                }
+            }
+            LegendOverflow::Wrap => {}
+            LegendOverflow::Scroll => {
+                if let Some(position) = self.positions.get(index) {
+                    // compared in the unmirrored layout, where scrolling goes right
+                    let (cx1, cx2, viewport_cx) = if self.chart_config.is_rtl() {
+                        let mirror = |cx: f64| self.cx_start + self.cx_end - cx;
+                        (
+                            mirror(position.cx2),
+                            mirror(position.cx1),
+                            (mirror(self.viewport_cx.1), mirror(self.viewport_cx.0)),
+                        )
+                    } else {
+                        (position.cx1, position.cx2, self.viewport_cx)
+                    };
+                    if cx1 < viewport_cx.0 {
+                        self.set_scroll(self.scroll_cx - (viewport_cx.0 - cx1));
+                    } else if cx2 > viewport_cx.1 {
+                        self.set_scroll(self.scroll_cx + (cx2 - viewport_cx.1));
+                    }
+                }
+            }
+        }
+    }
+    pub fn set_scroll(&mut self, scroll_cx: f64) {
+        let scroll_cx = scroll_cx.min(self.max_scroll_cx).max(0.0);
+        if scroll_cx != self.scroll_cx {
+            self.scroll_cx = scroll_cx;
+            self.cx_end = 0.0; // forcing resize
+        }
+    }
+    /// Returns the data set of the displayed item nearest to the point,
+    /// within the hit padding of it.
+    pub fn get_item_at(&self, screen: &Screen, cx: f64, cy: f64, hit_scale: f64) -> Option<usize> {
+        let c_hit_padding = screen.apx_to_cpx(HIT_PADDING * hit_scale);
+        if cx < self.viewport_cx.0 || cx > self.viewport_cx.1 {
+            return None;
+        }
+        self.positions
+            .iter()
+            .map(|position| position.distance_to(cx, cy))
+            .enumerate()
+            .filter(|(_, distance)| *distance <= c_hit_padding)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| self.items[self.offset + index].data_set_index)
+    }
     pub fn prev_page(&mut self) {
+        if self.chart_config.legend_overflow == LegendOverflow::Scroll {
+            let page_width = (self.viewport_cx.1 - self.viewport_cx.0) * SCROLL_PAGE_PORTION;
+            self.set_scroll(self.scroll_cx - page_width);
+            return;
+        }
         if self.offset > 0 {
             self.mandatory_right_index = Some(self.offset - 1);
             let approx_page_length = match self.approx_per_page {
@@ -92,6 +213,11 @@ impl Legend {
         }
     }
     pub fn next_page(&mut self) {
+        if self.chart_config.legend_overflow == LegendOverflow::Scroll {
+            let page_width = (self.viewport_cx.1 - self.viewport_cx.0) * SCROLL_PAGE_PORTION;
+            self.set_scroll(self.scroll_cx + page_width);
+            return;
+        }
         let current_page_length = self.positions.len();
         if self.offset + current_page_length < self.items.len() {
             self.offset += self.positions.len();
@@ -117,12 +243,69 @@ impl Legend {
         self.cx_end = cx_end;
         self.cy_start = cy_start;
         self.cy_end = cy_end;
+        match self.chart_config.legend_overflow {
+            LegendOverflow::Page => self.layout_pages(screen),
+            LegendOverflow::Wrap => self.layout_rows(screen),
+            LegendOverflow::Scroll => self.layout_row(screen),
+        }
+        if self.chart_config.is_rtl() {
+            self.mirror_layout();
+        }
+    }
+    /// Flips the laid out items, the viewport and the arrows for the rtl
+    /// layout; the "previous" arrow ends up on the right.
+    fn mirror_layout(&mut self) {
+        let (cx_start, cx_end) = (self.cx_start, self.cx_end);
+        for position in self.positions.iter_mut() {
+            *position = position.mirror_x(cx_start, cx_end);
+        }
+        self.viewport_cx = (
+            cx_start + cx_end - self.viewport_cx.1,
+            cx_start + cx_end - self.viewport_cx.0,
+        );
+        self.arrow_left = self
+            .arrow_left
+            .as_ref()
+            .map(|arrow| arrow.mirror_x(cx_start, cx_end));
+        self.arrow_right = self
+            .arrow_right
+            .as_ref()
+            .map(|arrow| arrow.mirror_x(cx_start, cx_end));
+    }
+    fn get_arrow_width(&self, screen: &Screen) -> f64 {
+        screen.apx_to_cpx(self.chart_config.font_size_large)
+            * self.chart_config.font_width_coeff
+            * 3.0
+    }
+    fn set_arrows(&mut self, screen: &Screen, cy_start: f64, arrow_height: Option<f64>) {
+        let c_margin_horizontal = screen.apx_to_cpx(MARGIN_HORIZONTAL);
+        let c_arrow_width = self.get_arrow_width(screen);
+        match arrow_height {
+            Some(arrow_height) => {
+                self.arrow_left = Some(ScreenRect::from_width(
+                    self.viewport_cx.0 - c_arrow_width - c_margin_horizontal,
+                    cy_start,
+                    c_arrow_width,
+                    arrow_height,
+                ));
+                self.arrow_right = Some(ScreenRect::from_width(
+                    self.viewport_cx.1 + c_margin_horizontal,
+                    cy_start,
+                    c_arrow_width,
+                    arrow_height,
+                ));
+            }
+            None => {
+                self.arrow_left = None;
+                self.arrow_right = None;
+            }
+        }
+    }
+    fn layout_pages(&mut self, screen: &Screen) {
         let c_screen_padding = screen.apx_to_cpx(SCREEN_PADDING);
         let c_margin_horizontal = screen.apx_to_cpx(MARGIN_HORIZONTAL);
         let c_margin_vertical = screen.apx_to_cpx(MARGIN_VERTICAL);
-        let c_arrow_width = screen.apx_to_cpx(self.chart_config.font_size_large)
-            * self.chart_config.font_width_coeff
-            * 3.0;
+        let c_arrow_width = self.get_arrow_width(screen);
 
         let cy_start = self.cy_start + c_screen_padding;
         let cy_end = self.cy_end - c_screen_padding;
@@ -175,25 +358,93 @@ impl Legend {
             break;
         }
         self.mandatory_right_index = None;
+        self.has_prev = self.offset > 0;
+        self.viewport_cx = (cx_start, cx_end);
 
-        if self.offset > 0 || self.has_next {
-            let arrow_height = self.positions[self.positions.len() - 1].cy2 - self.positions[0].cy1;
-            self.arrow_left = Some(ScreenRect::from_width(
-                cx_start - c_arrow_width - c_margin_horizontal,
-                cy_start,
-                c_arrow_width,
-                arrow_height,
-            ));
-            self.arrow_right = Some(ScreenRect::from_width(
-                cx_end + c_margin_horizontal,
-                cy_start,
-                c_arrow_width,
-                arrow_height,
-            ));
+        let arrow_height = if self.has_prev || self.has_next {
+            Some(self.positions[self.positions.len() - 1].cy2 - self.positions[0].cy1)
         } else {
-            self.arrow_left = None;
-            self.arrow_right = None;
+            None
+        };
+        self.set_arrows(screen, cy_start, arrow_height);
+    }
+    /// Lays out all items in as many rows as needed; the caller grows the
+    /// screen to `required_cheight`.
+    fn layout_rows(&mut self, screen: &Screen) {
+        let c_screen_padding = screen.apx_to_cpx(SCREEN_PADDING);
+        let c_margin_horizontal = screen.apx_to_cpx(MARGIN_HORIZONTAL);
+        let c_margin_vertical = screen.apx_to_cpx(MARGIN_VERTICAL);
+
+        let cx_start = self.cx_start + c_screen_padding;
+        let cx_end = self.cx_end - c_screen_padding;
+        let mut cx = cx_start;
+        let mut cy = self.cy_start + c_screen_padding;
+
+        self.offset = 0;
+        self.positions.clear();
+        for item in self.items.iter() {
+            if cx + item.width > cx_end && cx > cx_start {
+                cx = cx_start;
+                cy += item.height + c_margin_vertical;
+            }
+            self.positions
+                .push(ScreenRect::from_width(cx, cy, item.width, item.height));
+            cx += item.width + c_margin_horizontal;
+        }
+        self.required_cheight = Some(match self.positions.last() {
+            Some(position) => position.cy2 + c_screen_padding - self.cy_start,
+            None => 0.0,
+        });
+        self.has_prev = false;
+        self.has_next = false;
+        self.viewport_cx = (cx_start, cx_end);
+        self.set_arrows(screen, 0.0, None);
+    }
+    /// Lays out items in a single row shifted by `scroll_cx`; items outside
+    /// of the viewport are clipped.
+    fn layout_row(&mut self, screen: &Screen) {
+        let c_screen_padding = screen.apx_to_cpx(SCREEN_PADDING);
+        let c_margin_horizontal = screen.apx_to_cpx(MARGIN_HORIZONTAL);
+        let c_arrow_width = self.get_arrow_width(screen);
+
+        let cy = self.cy_start + c_screen_padding;
+        let total_width = self
+            .items
+            .iter()
+            .map(|item| item.width + c_margin_horizontal)
+            .sum::<f64>()
+            - c_margin_horizontal;
+        let overflows = total_width > self.cx_end - self.cx_start - c_screen_padding * 2.0;
+        self.viewport_cx = if overflows {
+            (
+                self.cx_start + c_arrow_width + c_margin_horizontal,
+                self.cx_end - c_arrow_width - c_margin_horizontal,
+            )
+        } else {
+            (
+                self.cx_start + c_screen_padding,
+                self.cx_end - c_screen_padding,
+            )
+        };
+        self.max_scroll_cx = (total_width - (self.viewport_cx.1 - self.viewport_cx.0)).max(0.0);
+        self.scroll_cx = self.scroll_cx.min(self.max_scroll_cx).max(0.0);
+
+        self.offset = 0;
+        self.positions.clear();
+        let mut cx = self.viewport_cx.0 - self.scroll_cx;
+        for item in self.items.iter() {
+            self.positions
+                .push(ScreenRect::from_width(cx, cy, item.width, item.height));
+            cx += item.width + c_margin_horizontal;
         }
+        self.has_prev = self.scroll_cx > 0.0;
+        self.has_next = self.scroll_cx < self.max_scroll_cx;
+        let arrow_height = self
+            .items
+            .first()
+            .filter(|_| overflows)
+            .map(|item| item.height);
+        self.set_arrows(screen, cy, arrow_height);
     }
 
     pub fn draw(&mut self, content: &Content, screen: &mut Screen, _time_us: f64) {
@@ -214,22 +465,84 @@ impl Legend {
         context.set_text_align("center");
         context.set_line_width(screen.apx_to_cpx(2.0));
 
-        for ((item, position), data_set) in self
+        context.save();
+        if self.chart_config.legend_overflow == LegendOverflow::Scroll {
+            let c_gap = screen.apx_to_cpx(MARGIN_HORIZONTAL) * 0.5;
+            context.begin_path();
+            context.rect(
+                self.viewport_cx.0 - c_gap,
+                0.0,
+                self.viewport_cx.1 - self.viewport_cx.0 + c_gap * 2.0,
+                screen.canvas_height,
+            );
+            context.clip();
+        }
+        for (item, position) in self
             .items
             .iter()
             .skip(self.offset)
             .zip(self.positions.iter())
-            .zip(content.data_sets.iter().skip(self.offset))
         {
+            let data_set = &content.data_sets[item.data_set_index];
             let color = JsValue::from_str(item.color.as_str());
             context.set_fill_style(&color);
-            if data_set.alpha.get_end_value() == 0.0 {
+            let visible = data_set.alpha.get_end_value() != 0.0;
+            if item.comparison {
+                let c_inset = if visible { screen.apx_to_cpx(3.0) } else { 0.0 };
+                context.save();
+                if visible {
+                    context.fill_rect(position.cx1, position.cy1, item.width, item.height);
+                    context.set_stroke_style_str("white");
+                } else {
+                    context.set_stroke_style_str(item.color.as_str());
+                }
+                context
+                    .set_line_dash(&get_comparison_line_dash(screen.apx_to_cpx(1.0)))
+                    .unwrap();
+                context.stroke_rect(
+                    position.cx1 + c_inset,
+                    position.cy1 + c_inset,
+                    item.width - c_inset * 2.0,
+                    item.height - c_inset * 2.0,
+                );
+                context.restore();
+                if visible {
+                    context.set_fill_style_str("white");
+                }
+            } else if !visible {
                 context.set_stroke_style(&color);
                 context.stroke_rect(position.cx1, position.cy1, item.width, item.height);
             } else {
                 context.fill_rect(position.cx1, position.cy1, item.width, item.height);
+                if self.chart_config.series_patterns {
+                    if let Some(hatch) =
+                        self.hatch_patterns
+                            .get(context, item.pattern, screen.apx_to_cpx(1.0))
+                    {
+                        context.set_fill_style_canvas_pattern(hatch);
+                        context.fill_rect(position.cx1, position.cy1, item.width, item.height);
+                    }
+                }
                 context.set_fill_style(&color_white);
             }
+            if self.chart_config.series_patterns {
+                // a sample of the series line under the name
+                let c_inset = item.height * 0.25;
+                let cy = position.cy2 - c_inset * 0.5;
+                context.save();
+                if visible {
+                    context.set_stroke_style_str("white");
+                }
+                context.set_line_width(screen.apx_to_cpx(self.chart_config.line_width));
+                context
+                    .set_line_dash(&get_line_dash(item.pattern, screen.apx_to_cpx(1.0)))
+                    .unwrap();
+                context.begin_path();
+                context.move_to(position.cx1 + c_inset, cy);
+                context.line_to(position.cx2 - c_inset, cy);
+                context.stroke();
+                context.restore();
+            }
             context
                 .fill_text(
                     item.name.as_str(),
@@ -239,6 +552,48 @@ impl Legend {
                 .unwrap();
         }
 
+        if let Some((position, item)) = self
+            .isolated
+            .and_then(|isolated| {
+                self.items
+                    .iter()
+                    .position(|item| item.data_set_index == isolated)
+            })
+            .and_then(|index| index.checked_sub(self.offset))
+            .and_then(|index| {
+                self.positions
+                    .get(index)
+                    .zip(self.items.get(index + self.offset))
+            })
+        {
+            context.set_fill_style_str(item.color.as_str());
+            context.fill_rect(
+                position.cx1,
+                position.cy2 + screen.apx_to_cpx(1.5),
+                position.width(),
+                screen.apx_to_cpx(2.0),
+            );
+        }
+
+        if let Some(position) = self
+            .focused
+            .and_then(|index| index.checked_sub(self.offset))
+            .and_then(|index| self.positions.get(index))
+        {
+            let c_gap = screen.apx_to_cpx(3.0);
+            let v = &self.chart_config.color_tooltip_font;
+            context
+                .set_stroke_style_str(format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3).as_str());
+            context.stroke_rect(
+                position.cx1 - c_gap,
+                position.cy1 - c_gap,
+                position.width() + c_gap * 2.0,
+                position.height() + c_gap * 2.0,
+            );
+        }
+
+        context.restore();
+
         if let (Some(arrow_left), Some(arrow_right)) = (&self.arrow_left, &self.arrow_right) {
             let v = &self.chart_config.color_preview_overlay;
             context.set_fill_style(&JsValue::from_str(
@@ -247,7 +602,7 @@ impl Legend {
                     v.0,
                     v.1,
                     v.2,
-                    if self.offset > 0 { v.3 } else { v.3 * 0.5 }
+                    if self.has_prev { v.3 } else { v.3 * 0.5 }
                 )
                 .as_str(),
             ));
@@ -290,34 +645,28 @@ impl Legend {
             let c_size = c_width.min(c_height);
             let c_horizontal = c_size * 0.3;
             let c_vertical = c_size * 0.4;
-            let cx_center = arrow_left.cx_center();
-            let cy_center = arrow_left.cy_center();
-            context.begin_path();
-            context.move_to(
-                cx_center + c_horizontal * 0.35,
-                cy_center - c_vertical * 0.5,
-            );
-            context.line_to(
-                cx_center + c_horizontal * 0.35,
-                cy_center + c_vertical * 0.5,
-            );
-            context.line_to(cx_center - c_horizontal * 0.65, cy_center);
-            context.close_path();
-            context.fill();
-            let cx_center = arrow_right.cx_center();
-            let cy_center = arrow_right.cy_center();
-            context.begin_path();
-            context.move_to(
-                cx_center - c_horizontal * 0.35,
-                cy_center - c_vertical * 0.5,
-            );
-            context.line_to(
-                cx_center - c_horizontal * 0.35,
-                cy_center + c_vertical * 0.5,
-            );
-            context.line_to(cx_center + c_horizontal * 0.65, cy_center);
-            context.close_path();
-            context.fill();
+            // both arrows point outwards, whichever side they are on
+            for (arrow, other) in [(arrow_left, arrow_right), (arrow_right, arrow_left)] {
+                let cx_center = arrow.cx_center();
+                let cy_center = arrow.cy_center();
+                let sign = if cx_center < other.cx_center() {
+                    1.0
+                } else {
+                    -1.0
+                };
+                context.begin_path();
+                context.move_to(
+                    cx_center + sign * c_horizontal * 0.35,
+                    cy_center - c_vertical * 0.5,
+                );
+                context.line_to(
+                    cx_center + sign * c_horizontal * 0.35,
+                    cy_center + c_vertical * 0.5,
+                );
+                context.line_to(cx_center - sign * c_horizontal * 0.65, cy_center);
+                context.close_path();
+                context.fill();
+            }
         }
     }
 }
@@ -7,21 +7,41 @@
  */
 #[macro_use]
 mod debug;
+mod accessibility;
+mod align_y;
 mod animate;
 mod camera;
+mod compact;
 mod controls;
+mod csv;
 mod data_set;
+mod date_format;
+mod debug_overlay;
+mod definition;
+mod empty_chart;
 mod events;
 mod grid;
+mod hover_link;
+mod html_tooltip;
 mod legend;
+mod loading;
 mod main_chart;
 mod manager;
+mod number_format;
 mod params;
+mod pattern;
+mod preview_layout;
+mod resources;
 mod scale;
 mod screen;
+mod strings;
+mod time_zone;
 mod tooltip;
 mod utils;
-use crate::manager::{get_or_create_manager_addr, ChartManager};
+mod warnings;
+mod watermark;
+use crate::events::defer_call;
+use crate::manager::{get_or_create_manager_addr, ChartManager, CreateError};
 use std::pin::Pin;
 
 use wasm_bindgen::prelude::*;
@@ -33,18 +53,203 @@ fn destruct_pinned_manager(manager: Pin<Box<ChartManager>>) {
     Box::into_raw(unsafe { Pin::into_inner_unchecked(manager) });
 }
 
-#[wasm_bindgen(js_name = createMain)]
-pub fn create_main(raw_params: JsValue, raw_config: JsValue) -> Result<String, String> {
+/// Runs the call on the manager. One made by a chart callback (e.g.
+/// renderTooltip) while the charts are borrowed runs once they are released:
+/// it returns Ok right away and its error becomes a DEFERRED_CALL_FAILED
+/// warning of the chart, see `ChartManager::report_deferred_failure`.
+fn call_manager<F>(chart_id: Option<JsValue>, call: F) -> Result<(), String>
+where
+    F: FnOnce(Pin<&mut ChartManager>) -> Result<(), String> + 'static,
+{
     let mut pinned_manager = get_pinned_manager();
-    let result = pinned_manager.as_mut().create_main(raw_params, raw_config);
+    if pinned_manager.is_busy() {
+        destruct_pinned_manager(pinned_manager);
+        defer_call(Box::new(move || {
+            if let Err(error) = call_manager(chart_id.clone(), call) {
+                let pinned_manager = get_pinned_manager();
+                pinned_manager.report_deferred_failure(chart_id.as_ref(), error);
+                destruct_pinned_manager(pinned_manager);
+            }
+        }));
+        return Ok(());
+    }
+    let result = call(pinned_manager.as_mut());
     destruct_pinned_manager(pinned_manager);
     result
 }
-
-#[wasm_bindgen(js_name = destroyMain)]
-pub fn destroy_main(chart_id: JsValue) -> Result<(), String> {
+/// Runs a call which returns a value right away, so a chart callback cannot
+/// make it while the charts are borrowed.
+fn query_manager<T, E, F>(call: F) -> Result<T, E>
+where
+    E: From<String>,
+    F: FnOnce(Pin<&mut ChartManager>) -> Result<T, E>,
+{
     let mut pinned_manager = get_pinned_manager();
-    let result = pinned_manager.as_mut().destroy_main(chart_id);
+    let result = if pinned_manager.is_busy() {
+        Err("charts are busy: not available from chart callbacks".to_string().into())
+    } else {
+        call(pinned_manager.as_mut())
+    };
     destruct_pinned_manager(pinned_manager);
     result
 }
+
+#[wasm_bindgen(js_name = createMain)]
+pub fn create_main(raw_params: JsValue, raw_config: JsValue) -> Result<JsValue, CreateError> {
+    query_manager(|mut manager| {
+        let chart_id = manager.as_mut().create_main(raw_params, raw_config)?;
+        Ok(manager.get_creation_result(chart_id)?)
+    })
+}
+
+#[wasm_bindgen(js_name = destroyMain)]
+pub fn destroy_main(chart_id: JsValue) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), |manager| manager.destroy_main(chart_id))
+}
+
+#[wasm_bindgen(js_name = setDataSetVisible)]
+pub fn set_data_set_visible(
+    chart_id: JsValue,
+    name: JsValue,
+    visible: JsValue,
+) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), |manager| {
+        manager.set_data_set_visible(chart_id, name, visible)
+    })
+}
+
+#[wasm_bindgen(js_name = isolateDataSet)]
+pub fn isolate_data_set(chart_id: JsValue, name: JsValue) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), |manager| manager.isolate_data_set(chart_id, name))
+}
+
+#[wasm_bindgen(js_name = setPointMarkers)]
+pub fn set_point_markers(chart_id: JsValue, name: JsValue, markers: JsValue) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), |manager| {
+        manager.set_point_markers(chart_id, name, markers)
+    })
+}
+
+#[wasm_bindgen(js_name = setDataSetZIndex)]
+pub fn set_data_set_z_index(chart_id: JsValue, name: JsValue, z_index: i32) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), move |manager| {
+        manager.set_data_set_z_index(chart_id, name, z_index)
+    })
+}
+
+#[wasm_bindgen(js_name = setInteractions)]
+pub fn set_interactions(chart_id: JsValue, interactions: JsValue) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), |manager| manager.set_interactions(chart_id, interactions))
+}
+
+#[wasm_bindgen(js_name = setLoading)]
+pub fn set_loading(
+    chart_id: JsValue,
+    loading: bool,
+    message: Option<String>,
+) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), move |manager| {
+        manager.set_loading(chart_id, loading, message)
+    })
+}
+
+#[wasm_bindgen(js_name = appendData)]
+pub fn append_data(chart_id: JsValue, data_sets: JsValue) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), |manager| manager.append_data(chart_id, data_sets))
+}
+
+#[wasm_bindgen(js_name = resumeFollow)]
+pub fn resume_follow(chart_id: JsValue) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), |manager| manager.resume_follow(chart_id))
+}
+
+#[wasm_bindgen(js_name = exportDefinition)]
+pub fn export_definition(chart_id: JsValue) -> Result<String, String> {
+    query_manager(|manager| manager.export_definition(chart_id))
+}
+
+#[wasm_bindgen(js_name = createFromDefinition)]
+pub fn create_from_definition(
+    selector: JsValue,
+    definition: String,
+) -> Result<JsValue, CreateError> {
+    query_manager(|mut manager| {
+        let chart_id = manager.as_mut().create_from_definition(selector, definition)?;
+        Ok(manager.get_creation_result(chart_id)?)
+    })
+}
+
+#[wasm_bindgen(js_name = getWarnings)]
+pub fn get_warnings(chart_id: JsValue) -> Result<JsValue, String> {
+    query_manager(|manager| manager.get_warnings(chart_id))
+}
+
+#[wasm_bindgen(js_name = getResourceCounts)]
+pub fn get_resource_counts() -> Result<JsValue, String> {
+    query_manager(|manager| manager.get_resource_counts())
+}
+
+#[wasm_bindgen(js_name = setDebug)]
+pub fn set_debug(chart_id: JsValue, enabled: bool) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), move |manager| manager.set_debug(chart_id, enabled))
+}
+
+#[wasm_bindgen(js_name = setHoverX)]
+pub fn set_hover_x(chart_id: JsValue, x: JsValue, duration_ms: Option<f64>) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), move |manager| {
+        manager.set_hover_x(chart_id, x, duration_ms)
+    })
+}
+
+#[wasm_bindgen(js_name = setVisibleRange)]
+pub fn set_visible_range(chart_id: JsValue, range: JsValue) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), |manager| manager.set_visible_range(chart_id, range))
+}
+
+#[wasm_bindgen(js_name = redraw)]
+pub fn redraw(chart_id: JsValue) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), |manager| manager.redraw(chart_id))
+}
+
+#[wasm_bindgen(js_name = setLinkGroup)]
+pub fn set_link_group(chart_id: JsValue, link_group: Option<String>) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), |manager| manager.set_link_group(chart_id, link_group))
+}
+
+#[wasm_bindgen(js_name = setAlignY)]
+pub fn set_align_y(chart_id: JsValue, enabled: bool) -> Result<(), String> {
+    call_manager(Some(chart_id.clone()), move |manager| manager.set_align_y(chart_id, enabled))
+}
+
+#[wasm_bindgen(js_name = getLayout)]
+pub fn get_layout(chart_id: JsValue) -> Result<JsValue, String> {
+    query_manager(|manager| manager.get_layout(chart_id))
+}
+
+#[wasm_bindgen(js_name = dataToPixel)]
+pub fn data_to_pixel(chart_id: JsValue, x: JsValue, y: f64) -> Result<JsValue, String> {
+    query_manager(|manager| manager.data_to_pixel(chart_id, x, y))
+}
+
+#[wasm_bindgen(js_name = pixelToData)]
+pub fn pixel_to_data(chart_id: JsValue, x: f64, y: f64) -> Result<JsValue, String> {
+    query_manager(|manager| manager.pixel_to_data(chart_id, x, y))
+}
+
+#[wasm_bindgen(js_name = trimMemory)]
+pub fn trim_memory() {
+    let _ = call_manager(None, |manager| {
+        manager.trim_memory();
+        Ok(())
+    });
+}
+
+#[wasm_bindgen(js_name = defaultStrings)]
+pub fn default_strings() -> JsValue {
+    ChartManager::default_strings()
+}
+
+#[wasm_bindgen(js_name = paramsFromCsv)]
+pub fn params_from_csv(csv: String, options: JsValue) -> Result<JsValue, String> {
+    crate::csv::params_from_csv(csv.as_str(), &options)
+}
@@ -0,0 +1,80 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use crate::params::ChartConfig;
+use crate::screen::Screen;
+use std::f64::consts::PI;
+
+// in apx
+const SPINNER_SIZE: f64 = 28.0;
+const SPINNER_WIDTH: f64 = 3.0;
+const SPINNER_PERIOD_US: f64 = 1_000_000.0;
+
+/// Shown while the host loads data: dims the screen and draws a spinner with
+/// an optional message in its center.
+pub struct LoadingOverlay {
+    pub message: Option<String>,
+}
+impl LoadingOverlay {
+    /// Draws over what the screen has; returns whether the spinner turns, so
+    /// another frame is needed. With reduced motion it stands still.
+    pub fn draw(
+        &self,
+        screen: &Screen,
+        config: &ChartConfig,
+        dim: bool,
+        reduced_motion: bool,
+        time_us: f64,
+    ) -> bool {
+        let context = &screen.context;
+        if dim {
+            let v = &config.color_loading_overlay;
+            context
+                .set_fill_style_str(format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3).as_str());
+            context.fill_rect(0.0, 0.0, screen.canvas_width, screen.canvas_height);
+        }
+        let c_size = screen.apx_to_cpx(SPINNER_SIZE);
+        let c_radius = (c_size - screen.apx_to_cpx(SPINNER_WIDTH)) * 0.5;
+        let font_size = screen.apx_to_cpx(config.font_size_large);
+        let cx = screen.canvas_width * 0.5;
+        let mut cy = screen.canvas_height * 0.5;
+        if self.message.is_some() {
+            cy -= font_size;
+        }
+        let v = &config.color_tick;
+        context.set_line_width(screen.apx_to_cpx(SPINNER_WIDTH));
+        context.set_line_cap("round");
+        context.set_stroke_style_str(format!("rgba({}, {}, {}, 0.3)", v.0, v.1, v.2).as_str());
+        context.begin_path();
+        context.arc(cx, cy, c_radius, 0.0, 2.0 * PI).unwrap();
+        context.stroke();
+
+        let angle = if reduced_motion {
+            -0.5 * PI
+        } else {
+            (time_us % SPINNER_PERIOD_US) / SPINNER_PERIOD_US * 2.0 * PI
+        };
+        context.set_stroke_style_str(format!("rgb({}, {}, {})", v.0, v.1, v.2).as_str());
+        context.begin_path();
+        context
+            .arc(cx, cy, c_radius, angle, angle + 0.5 * PI)
+            .unwrap();
+        context.stroke();
+        context.set_line_cap("butt");
+
+        if let Some(message) = &self.message {
+            context.set_fill_style_str(format!("rgb({}, {}, {})", v.0, v.1, v.2).as_str());
+            context.set_font(format!("{}px {}", font_size, config.font_standard.as_str()).as_str());
+            context.set_text_align("center");
+            context.set_text_baseline("top");
+            context
+                .fill_text(message.as_str(), cx, cy + c_size * 0.5 + font_size * 0.75)
+                .unwrap();
+        }
+        !reduced_motion
+    }
+}
@@ -5,15 +5,39 @@
  *
  * Copyright (C) 2023, Nikita Almakov
  */
-use crate::animate::ANIMATED_NUMBERS_COUNT;
+use crate::accessibility::ChartDescription;
+use crate::align_y::{SharedYDomain, YAlignment};
+use crate::animate::{AnimatedNumber, ANIMATED_NUMBERS_COUNT};
 use crate::camera::Camera;
+use crate::compact::DroppedParts;
 use crate::controls::ControlEvent;
+use crate::data_set::DataSet;
+use crate::debug_overlay::DebugOverlay;
+use crate::definition::{ChartState, Definition};
+use crate::events::{
+    defer_call, ChartEvent, HoverSettle, JsResizeObserver, JsTimeout, PendingChartEvents,
+};
+use crate::hover_link::HoverLink;
 use crate::legend::Legend;
-use crate::params::{ChartConfig, ChartParams, ClientCaps, Content};
+use crate::loading::LoadingOverlay;
+use crate::params::{
+    export_params, params_with_points, parse_js_values, parse_point_markers, ChartConfig,
+    ChartParams, ClientCaps, ColorScheme, Content, Contrast, EdgeBehavior, InitialRange,
+    Interactions, LegendOverflow,
+};
+use crate::preview_layout::{
+    clamp_preview_height, get_dragged_preview_height, PreviewPosition, SPLITTER_HEIGHT,
+    SPLITTER_KEYBOARD_STEP,
+};
 use crate::scale::Scale;
-use crate::screen::Screen;
-use crate::tooltip::Tooltip;
-use crate::utils::is_click;
+use crate::screen::{Screen, ScreenArea};
+use crate::tooltip::{format_more_line, Tooltip, TooltipHover, TooltipSection, TooltipSectionLine};
+use crate::utils::{
+    get_memory_trim_time_us, is_click, is_point_hit, js_element_global_position,
+    select_tooltip_rows, snap_line_width, snap_rect, snap_to_pixel, POINT_HIT_RADIUS,
+};
+use crate::warnings::{ChartWarning, Warnings};
+use crate::watermark::Watermark;
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::atomic::Ordering;
@@ -29,22 +53,180 @@ fn get_tick_height(_chart_config: &ChartConfig, font_size: f64) -> f64 {
 const CSS_DISABLE_DEFAULT_LONG_TOUCH: &'static str =
     "-webkit-touch-callout: none !important; -webkit-user-select: none !important";
 const CSS_DISABLE_TOUCH_GESTURES: &'static str = "touch-action: none";
+/// A hairline across the middle of the splitter in the color of the text.
+const CSS_SPLITTER_LINE: &str =
+    "background: linear-gradient(currentColor, currentColor) center / 100% 1px no-repeat";
+const US_DOUBLE_CLICK: f64 = 400000.0;
+const KEYBOARD_PAN_STEP: f64 = 0.1;
+// the preview window is grabbed this far outside of it, scaled by the hit scale
+const GRIP_HIT_MARGIN: f64 = 4.0;
+const KEYBOARD_ZOOM_STEP: f64 = 1.5;
+// apx, scaled by the hit scale
+const PREVIEW_HANDLE_WIDTH: f64 = 4.0;
+const PREVIEW_HANDLE_HEIGHT: f64 = 16.0;
 pub trait DrawChart {
     fn on_control_event(&mut self, event: &ControlEvent, time_us: f64);
+    /// Whether pointer and key events may change the chart; otherwise they
+    /// are not passed and cause no frame.
+    fn accepts_control_events(&self) -> bool;
     fn on_resize(&mut self);
+    /// Called when the wrapper or the preview wrapper resized; only the
+    /// screens within it are laid out again.
+    fn on_element_resize(&mut self, element: &web_sys::Element);
+    /// Called when user preferences tracked by ClientCaps change.
+    fn on_client_caps_change(&mut self);
     fn draw(&mut self, time_us: f64) -> usize;
+    fn take_events(&mut self) -> Option<PendingChartEvents>;
+    fn set_data_set_visible(
+        &mut self,
+        name: &str,
+        visible: bool,
+        time_us: f64,
+    ) -> Result<(), String>;
+    fn isolate_data_set(&mut self, name: &str, time_us: f64) -> Result<(), String>;
+    /// Replaces the point markers of the data set, its data stays as is.
+    fn set_point_markers(&mut self, name: &str, raw_markers: &JsValue) -> Result<(), String>;
+    /// Changes the paint order of the data set, see `get_paint_order`.
+    fn set_data_set_z_index(&mut self, name: &str, z_index: i32) -> Result<(), String>;
+    /// Replaces the interactions of the config, see `Interactions`.
+    fn set_interactions(&mut self, raw_interactions: &JsValue) -> Result<(), String>;
+    /// Shows or clears the loading overlay; hover is off while it is shown.
+    fn set_loading(&mut self, loading: bool, message: Option<String>);
+    /// Appends points to the ends of data sets, see `Content::append_data`;
+    /// returns whether pending data sets got their first points, so the chart
+    /// has to be created anew from `take_rebuild`.
+    fn append_data(&mut self, raw_data_sets: &JsValue, time_us: f64) -> Result<bool, String>;
+    /// What the chart is created anew from, see `ChartManager::rebuild_chart`.
+    fn take_rebuild(&mut self) -> Result<ChartRebuild, String>;
+    /// Moves the window back to the latest data and follows it again.
+    fn resume_follow(&mut self, time_us: f64) -> Result<(), String>;
+    /// Describes the chart by params, config and state.
+    fn export_definition(&self) -> Result<Definition, String>;
+    /// Applies the state of a definition to the chart created from it; the
+    /// window comes with the params.
+    fn restore_state(&mut self, state: &ChartState, time_us: f64) -> Result<(), String>;
+    /// Rectangles of the last drawn layout in CSS pixels relative to the
+    /// wrapper.
+    fn get_layout(&self) -> Result<JsValue, String>;
+    /// Maps a data point to CSS pixels relative to the wrapper.
+    fn data_to_pixel(&self, raw_coord: &JsValue, value: f64) -> Result<JsValue, String>;
+    /// Maps CSS pixels relative to the wrapper to a data point.
+    fn pixel_to_data(&self, x: f64, y: f64) -> Result<JsValue, String>;
+    /// Hovers the x as the pointer would, tweened over the duration from the
+    /// x hovered this way before; null clears it.
+    fn set_hover_x(
+        &mut self,
+        raw_coord: &JsValue,
+        duration_ms: Option<f64>,
+        time_us: f64,
+    ) -> Result<(), String>;
+    /// Zooms to [from, to] as a selection would; a range out of the data is
+    /// fitted as initialRange is.
+    fn set_visible_range(&mut self, raw_range: &JsValue, time_us: f64) -> Result<(), String>;
+    /// Draws every part anew with the next frame.
+    fn redraw(&mut self);
+    fn get_link_group(&self) -> Option<&str>;
+    fn set_link_group(&mut self, link_group: Option<String>);
+    fn set_align_y(&mut self, align_y: bool);
+    /// What the chart offers to the y domain of its link group, when it
+    /// aligns its y axis.
+    fn get_y_alignment(&self) -> Option<YAlignment>;
+    /// Returns whether the domain changed, so the chart needs a frame.
+    fn set_shared_y_domain(&mut self, shared_y_domain: SharedYDomain, time_us: f64) -> bool;
+    /// The link group and the hovered coord, when the chart is hovered and
+    /// shares its tooltip.
+    fn get_shared_hover(&self) -> Option<(String, f64)>;
+    /// Values of the visible data sets nearest to the coord, for the shared
+    /// tooltip of another chart.
+    fn get_tooltip_section(&self, coord: f64) -> Option<TooltipSection>;
+    /// Returns whether the sections changed, so the chart needs a frame.
+    fn set_shared_sections(&mut self, sections: Vec<TooltipSection>) -> bool;
+    fn get_wrapper(&self) -> &web_sys::Element;
+    /// The wrapper injected into `ChartConfig::preview_container`.
+    fn get_preview_wrapper(&self) -> Option<&web_sys::Element>;
+    /// Called when the wrapper enters or leaves the viewport (None if that
+    /// did not change) or the document is hidden or shown.
+    fn on_visibility_change(
+        &mut self,
+        intersecting: Option<bool>,
+        document_hidden: bool,
+        time_us: f64,
+    );
+    /// When a hidden chart is due to release its memory.
+    fn get_memory_trim_time_us(&self) -> Option<f64>;
+    /// Releases canvas backing stores and caches of a hidden chart; they are
+    /// rebuilt once it is shown.
+    fn trim_memory(&mut self);
+    fn get_on_auto_destroy(&self) -> Option<js_sys::Function>;
+    /// Called when a canvas of the chart loses its context or gets it back;
+    /// the chart is redrawn from its data once restored.
+    fn on_context_change(&mut self, lost: bool);
+    /// Shows or hides the debug overlay, see `DebugOverlay`.
+    fn set_debug(&mut self, enabled: bool);
+    /// Whether value blocks of the data sets are left to compute while the
+    /// browser is idle, see `DataSet::precompute_value_blocks`.
+    fn has_idle_work(&self) -> bool;
+    /// Computes the next few of them.
+    fn do_idle_work(&mut self);
+    /// Warnings since the last call, see `Warnings`.
+    fn take_warnings(&mut self) -> Vec<ChartWarning>;
+    /// Whether createMain returns the warnings along with the chart id.
+    fn returns_warnings(&self) -> bool;
+    fn push_warning(&mut self, warning: ChartWarning);
+}
+/// Value blocks computed per `DrawChart::do_idle_work` call.
+const IDLE_BLOCKS_PER_STEP: usize = 64;
+
+/// Params with the current points, the config and the state of a chart to
+/// be created anew with the same id.
+pub struct ChartRebuild {
+    pub raw_params: JsValue,
+    pub config: ChartConfig,
+    /// See `DrawChart::restore_state`.
+    pub state: ChartState,
+    /// Not taken yet, handed to the new chart.
+    pub warnings: Vec<ChartWarning>,
+}
+
+/// Builds {x, y} or, with a size, {x, y, width, height}.
+pub fn rect_to_js(x: f64, y: f64, size: Option<(f64, f64)>) -> JsValue {
+    let rect = js_sys::Object::new();
+    js_sys::Reflect::set(&rect, &"x".into(), &x.into()).unwrap();
+    js_sys::Reflect::set(&rect, &"y".into(), &y.into()).unwrap();
+    if let Some((width, height)) = size {
+        js_sys::Reflect::set(&rect, &"width".into(), &width.into()).unwrap();
+        js_sys::Reflect::set(&rect, &"height".into(), &height.into()).unwrap();
+    }
+    rect.into()
 }
+
+/// A data set shown alone and the visibility to restore.
+#[derive(Clone)]
+pub struct Isolation {
+    pub index: usize,
+    pub restore_visibility: Vec<bool>,
+}
+
 pub struct MainChart<T>
 where
     T: Scale,
 {
     pub container_selector: String,
+    pub wrapper: web_sys::Element,
+    pub description: ChartDescription,
     pub client_caps: Rc<RefCell<ClientCaps>>,
     pub config: Rc<ChartConfig>,
+    /// The config as passed, before the theme and the contrast are applied.
+    pub base_config: ChartConfig,
+    pub color_scheme: Option<ColorScheme>,
+    pub contrast: Contrast,
     pub content: Content,
     pub pointer_position: Option<(f64, f64)>,
-    pub camera_grip_x_offset: Option<f64>,
+    /// From the grip center to the pointer, in coords rather than pixels to
+    /// survive resizes while dragging.
+    pub camera_grip_coord_offset: Option<f64>,
     pub camera_grip_pointer_down_position: Option<(f64, f64)>,
+    pub camera_grip_focused: bool,
     pub camera_grip_screen: Screen,
     pub main_camera: Camera<T>,
     pub main_screen: Screen,
@@ -55,11 +237,57 @@ where
     pub tooltip_pinch_coords: Option<(f64, f64)>,
     pub tooltip_screen: Screen,
     pub zoomed_in: bool,
+    /// The user moved the window, so it stops following appended data.
+    pub user_detached: bool,
     pub legend_screen: Screen,
     pub legend: Legend,
     pub legend_pointer_down_position: Option<(f64, f64)>,
     pub legend_pointer_down_time_us: Option<f64>,
+    pub watermark: Option<Watermark>,
+    /// The data set under the pointer in the legend, or held on touch.
+    pub legend_hovered: Option<usize>,
+    pub legend_last_click: Option<(usize, f64, Vec<bool>, Option<Isolation>)>,
+    pub isolation: Option<Isolation>,
     pub dirty: bool,
+    pub events: Vec<ChartEvent>,
+    pub ready: bool,
+    /// Nothing is drawn until the container gets a size.
+    pub waiting_for_size: bool,
+    pub size_warning: Option<JsTimeout>,
+    pub intersecting: bool,
+    pub hidden_since_us: Option<f64>,
+    pub memory_trimmed: bool,
+    /// Some canvas lost its context, drawing waits for the restore.
+    pub context_lost: bool,
+    pub loading: Option<LoadingOverlay>,
+    pub last_range: Option<(f64, f64)>,
+    pub last_hover: Option<TooltipHover>,
+    /// Coord hovered by `set_hover_x` until the pointer enters the plot.
+    pub playhead: Option<AnimatedNumber>,
+    pub hover_link: HoverLink,
+    pub hover_settle: HoverSettle,
+    pub shared_y_domain: SharedYDomain,
+    /// See `ChartConfig::preview_container`.
+    pub preview_wrapper: Option<web_sys::Element>,
+    /// See `ChartConfig::preview_splitter`.
+    pub splitter: Option<web_sys::HtmlElement>,
+    /// Pointer y and the preview height when the splitter was grabbed.
+    pub splitter_drag: Option<(f64, f64)>,
+    /// CSS px the splitter set the preview to, instead of the weight of the
+    /// config.
+    pub preview_height_px: Option<f64>,
+    /// Parts left out at the current size, see `ChartConfig::compact_layout`.
+    pub dropped: DroppedParts,
+    /// The wrapper may have been resized since the parts were picked.
+    pub compact_check_needed: bool,
+    /// Height the legend needs, in the "wrap" mode.
+    pub legend_height_px: Option<f64>,
+    pub point_click_pending: bool,
+    pub last_visibility: Option<Vec<bool>>,
+    /// See `ChartParams::raw`.
+    pub raw_params: JsValue,
+    pub debug: Option<DebugOverlay>,
+    pub warnings: Warnings,
 }
 impl<T> MainChart<T>
 where
@@ -72,7 +300,40 @@ where
         main_scale: T,
         preview_scale: T,
     ) -> Result<MainChart<T>, String> {
-        let config = Rc::new(config);
+        let color_scheme = config.resolve_color_scheme(&client_caps.borrow());
+        let contrast = config.resolve_contrast(&client_caps.borrow());
+        let base_config = config;
+        let config = Rc::new(base_config.resolve(&client_caps.borrow()));
+        let wrapper = web_sys::window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .query_selector(params.selector.as_str())
+            .unwrap()
+            .ok_or_else(|| "container not found".to_string())?;
+        // e.g. a hidden tab; measured before the canvases are added
+        let waiting_for_size = !Self::has_size(&wrapper);
+        let warnings = Warnings::default();
+        warnings.extend(params.warnings);
+        let size_warning = if waiting_for_size && config.zero_size_warning_timeout > 0.0 {
+            let selector = params.selector.clone();
+            let warnings = warnings.clone();
+            Some(JsTimeout::new(
+                config.zero_size_warning_timeout,
+                Box::new(move || {
+                    warnings.push(ChartWarning::new(
+                        "ZERO_SIZE_CONTAINER",
+                        format!(
+                            "container {} still has zero size, rendering is deferred",
+                            selector
+                        ),
+                        None,
+                    ))
+                }),
+            ))
+        } else {
+            None
+        };
         let main_screen = Screen::new(
             params.selector.as_str(),
             Rc::clone(&client_caps),
@@ -81,6 +342,7 @@ where
                 config.layout_content_height
             )
             .as_str(),
+            config.get_resolution_cap(),
         )?;
         let tooltip_screen = Screen::new(
             params.selector.as_str(),
@@ -92,33 +354,48 @@ where
                 CSS_DISABLE_TOUCH_GESTURES,
             )
             .as_str(),
+            config.get_resolution_cap(),
         )?;
+        let preview_selector = params
+            .preview_selector
+            .clone()
+            .unwrap_or_else(|| params.selector.clone());
         let preview_screen = Screen::new(
-            params.selector.as_str(),
+            preview_selector.as_str(),
             Rc::clone(&client_caps),
             format!(
                 "display: block; width: 100%; height: {:.1}%",
                 config.layout_preview_height
             )
             .as_str(),
+            config.get_resolution_cap(),
         )?;
         let camera_grip_screen = Screen::new(
-            params.selector.as_str(),
+            preview_selector.as_str(),
             Rc::clone(&client_caps),
             format!(
                 "display: block; width: 100%; height: {:.1}%; position: absolute; left: 0; top: {:.1}%; {}; {}",
                 config.layout_preview_height, config.layout_content_height, CSS_DISABLE_DEFAULT_LONG_TOUCH, CSS_DISABLE_TOUCH_GESTURES
             )
             .as_str(),
+            config.get_resolution_cap(),
         )?;
         let legend_screen = Screen::new(
             params.selector.as_str(),
             Rc::clone(&client_caps),
             format!(
-                "display: block; width: 100%; height: {:.0}%; {}",
-                config.layout_legend_height, CSS_DISABLE_DEFAULT_LONG_TOUCH
+                "display: block; width: 100%; height: {:.0}%; {}{}",
+                config.layout_legend_height,
+                CSS_DISABLE_DEFAULT_LONG_TOUCH,
+                if config.legend_overflow == LegendOverflow::Scroll {
+                    // horizontal drags scroll the legend rather than the page
+                    "; touch-action: pan-y"
+                } else {
+                    ""
+                }
             )
             .as_str(),
+            config.get_resolution_cap(),
         )?;
 
         let coord_ticks_height = get_tick_height(config.as_ref(), config.font_size_small);
@@ -127,7 +404,20 @@ where
             config.font_size_small,
             params.content.value_short_verbose_len,
         );
-        let main_camera_padding = [5.0, 0.0, coord_ticks_height, value_ticks_width];
+        let main_camera_padding = [
+            5.0,
+            if config.y_axis_position.has_right() {
+                value_ticks_width
+            } else {
+                0.0
+            },
+            coord_ticks_height,
+            if config.y_axis_position.has_left() {
+                value_ticks_width
+            } else {
+                0.0
+            },
+        ];
         let preview_camera_padding = [0.0, main_camera_padding[1], 0.0, main_camera_padding[3]];
 
         let main_camera = Camera::new(
@@ -148,12 +438,66 @@ where
             &preview_screen,
             preview_camera_padding,
         );
+        let preview_wrapper = params.preview_selector.as_ref().map(|preview_selector| {
+            web_sys::window()
+                .unwrap()
+                .document()
+                .unwrap()
+                .query_selector(preview_selector.as_str())
+                .unwrap()
+                .unwrap()
+        });
+        let splitter = (config.preview_splitter && preview_wrapper.is_none())
+            .then(|| Self::create_splitter(&config));
+        if preview_wrapper.is_none() {
+            if config.preview_position == PreviewPosition::Top {
+                wrapper
+                    .insert_before(&preview_screen.canvas, Some(&main_screen.canvas))
+                    .unwrap();
+            }
+            if let Some(splitter) = &splitter {
+                let next = match config.preview_position {
+                    PreviewPosition::Bottom => &preview_screen.canvas,
+                    PreviewPosition::Top => &main_screen.canvas,
+                };
+                wrapper.insert_before(splitter, Some(next)).unwrap();
+            }
+        }
+        // tab order: the wrapper, legend entries, then the preview window;
+        // the grip is absolutely positioned, so moving it after the legend
+        // changes only the order
+        wrapper.set_attribute("tabindex", "0").unwrap();
+        legend_screen.canvas.set_attribute("tabindex", "0").unwrap();
+        if preview_wrapper.is_none() {
+            wrapper.append_child(&camera_grip_screen.canvas).unwrap();
+        }
+        camera_grip_screen
+            .canvas
+            .set_attribute("tabindex", "0")
+            .unwrap();
+        camera_grip_screen
+            .canvas
+            .set_attribute("aria-label", config.strings.get("zoomWindowLabel"))
+            .unwrap();
         let legend = Legend::from_content(Rc::clone(&config), &params.content, &main_screen);
-        let tooltip = Tooltip::new(Rc::clone(&config));
-        let chart = MainChart {
+        let tooltip = Tooltip::new(Rc::clone(&config), &wrapper);
+        let description = ChartDescription::new(Rc::clone(&config), &wrapper, warnings.clone());
+        let hover_link = HoverLink::new(&wrapper);
+        let watermark = Watermark::new(&config);
+        let follow = config.follow;
+        let initial_range = params.initial_range.or(follow
+            .span_x
+            .filter(|_| follow.enabled)
+            .map(InitialRange::Last));
+        let mut chart = MainChart {
             container_selector: params.selector.clone(),
+            wrapper,
+            description,
             client_caps,
             config,
+            base_config,
+            color_scheme,
+            contrast,
             content: params.content,
             pointer_position: None,
             main_camera,
@@ -164,23 +508,330 @@ where
             tooltip_screen,
             preview_camera,
             preview_screen,
-            camera_grip_x_offset: None,
+            camera_grip_coord_offset: None,
             // TODO rename mouse to pointer
             camera_grip_pointer_down_position: None,
             camera_grip_screen,
+            camera_grip_focused: false,
             legend_screen,
             legend,
             legend_pointer_down_position: None,
             legend_pointer_down_time_us: None,
+            legend_hovered: None,
+            watermark,
+            legend_last_click: None,
+            isolation: None,
             zoomed_in: false,
+            user_detached: false,
             dirty: true,
+            events: Vec::new(),
+            ready: false,
+            waiting_for_size,
+            size_warning,
+            intersecting: true,
+            hidden_since_us: None,
+            memory_trimmed: false,
+            context_lost: false,
+            loading: None,
+            last_range: None,
+            last_hover: None,
+            playhead: None,
+            hover_link,
+            hover_settle: HoverSettle::default(),
+            shared_y_domain: SharedYDomain::Own,
+            preview_wrapper,
+            splitter,
+            splitter_drag: None,
+            preview_height_px: None,
+            dropped: DroppedParts::default(),
+            compact_check_needed: true,
+            legend_height_px: None,
+            point_click_pending: false,
+            last_visibility: None,
+            raw_params: params.raw,
+            debug: None,
+            warnings,
         };
+        // data sets enter with the first draw
+        let enter_time_us = chart
+            .get_series_animation_time(Self::now_ms() * 1000.0)
+            .filter(|_| chart.config.series_enter_grow);
+        for data_set in chart.content.data_sets.iter_mut() {
+            data_set.set_fade_duration(chart.config.series_animation_duration);
+            if let Some(enter_time_us) = enter_time_us {
+                data_set.set_visible(false, None);
+                data_set.set_visible(true, Some(enter_time_us));
+            }
+        }
+        if chart.preview_wrapper.is_some()
+            || chart.splitter.is_some()
+            || chart.config.preview_position != PreviewPosition::Bottom
+        {
+            chart.apply_section_heights();
+            chart.on_resize();
+        }
+        // before the first draw, so the chart never shows the whole range
+        if let Some(initial_range) = initial_range {
+            chart.apply_initial_range(initial_range);
+        }
+        if chart.config.initial_loading {
+            chart.set_loading(true, None);
+        }
         Ok(chart)
     }
 
+    fn apply_initial_range(&mut self, initial_range: InitialRange) {
+        let (coord_min, coord_max) = (self.content.global_coord_min, self.content.global_coord_max);
+        let (window, warning) = initial_range.resolve(
+            coord_min,
+            coord_max,
+            self.config.edge_behavior != EdgeBehavior::Clip,
+        );
+        if let Some(warning) = warning {
+            self.warnings.push(ChartWarning::new(
+                "INITIAL_RANGE_CLAMPED",
+                warning,
+                Some("initialRange".to_string()),
+            ));
+        }
+        if window == (coord_min, coord_max) {
+            return;
+        }
+        self.main_camera
+            .zoom_by_coords(&mut self.content, window.0, window.1, None);
+        // a window with less than 2 points is not zoomed into
+        if self.main_camera.coord_range.get_end_value() != window.1 - window.0 {
+            self.warnings.push(ChartWarning::new(
+                "INITIAL_RANGE_TOO_SMALL",
+                "the initial range has less than 2 points, showing the whole range".to_string(),
+                Some("initialRange".to_string()),
+            ));
+        }
+        // a window past the edges of the data is not zoomed in either
+        self.zoomed_in = self.main_camera.coord_range.get_end_value() < coord_max - coord_min;
+    }
+
+    fn create_splitter(config: &ChartConfig) -> web_sys::HtmlElement {
+        let splitter: web_sys::HtmlElement = web_sys::window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("div")
+            .unwrap()
+            .unchecked_into();
+        splitter
+            .set_attribute(
+                "style",
+                format!(
+                    "display: block; width: 100%; height: {:.0}px; cursor: row-resize; opacity: 0.3; {}; {}",
+                    SPLITTER_HEIGHT, CSS_SPLITTER_LINE, CSS_DISABLE_TOUCH_GESTURES
+                )
+                .as_str(),
+            )
+            .unwrap();
+        for (name, value) in [
+            ("role", "separator"),
+            ("aria-orientation", "horizontal"),
+            ("aria-label", config.strings.get("previewSplitterLabel")),
+            ("tabindex", "0"),
+        ] {
+            splitter.set_attribute(name, value).unwrap();
+        }
+        splitter
+    }
+    fn splitter_contains_pos(&self, pos: &(f64, f64)) -> bool {
+        let splitter = match &self.splitter {
+            Some(splitter) => splitter,
+            None => return false,
+        };
+        let (x, y) = js_element_global_position(splitter);
+        x <= pos.0
+            && y <= pos.1
+            && x + splitter.offset_width() as f64 >= pos.0
+            && y + splitter.offset_height() as f64 >= pos.1
+    }
+    /// Height the main plot and the preview share, in CSS px.
+    fn get_panes_height(&self) -> f64 {
+        self.main_screen.height + self.preview_screen.height
+    }
+    fn set_preview_height(&mut self, height: f64) {
+        if self.preview_height_px == Some(height) {
+            return;
+        }
+        self.preview_height_px = Some(height);
+        self.apply_section_heights();
+        self.on_resize();
+    }
+    /// Moves the splitter by shift_y, see `get_dragged_preview_height`.
+    fn move_splitter(&mut self, start_height: f64, shift_y: f64) {
+        let height = get_dragged_preview_height(
+            self.config.preview_position,
+            start_height,
+            shift_y,
+            self.get_panes_height(),
+        );
+        self.set_preview_height(height);
+    }
+    fn resize_main_pane(&mut self) {
+        self.dirty = true;
+        self.compact_check_needed = true;
+        self.main_camera.dirty = true;
+        self.main_screen.schedule_canvas_size_sync();
+        self.tooltip_screen.schedule_canvas_size_sync();
+        self.legend_screen.schedule_canvas_size_sync();
+    }
+    fn resize_preview_pane(&mut self) {
+        self.dirty = true;
+        self.preview_camera.dirty = true;
+        self.preview_screen.schedule_canvas_size_sync();
+        self.camera_grip_screen.schedule_canvas_size_sync();
+    }
+
+    fn now_ms() -> f64 {
+        web_sys::window().unwrap().performance().unwrap().now()
+    }
+
+    /// Draws the frame stats, the scale, the window with the points in it and
+    /// outlines of the layout.
+    fn draw_debug_overlay(&mut self, started_ms: f64, time_us: f64) {
+        let coord = self.main_camera.coord.get_end_value();
+        let coord_half_range = self.main_camera.coord_range.get_end_value() * 0.5;
+        let (from, to) = (coord - coord_half_range, coord + coord_half_range);
+        let formatted_window = self.content.coord_verbose_format.format_values(
+            [from, to].into_iter(),
+            |v| v,
+            self.content.global_coord_min,
+            self.content.global_coord_max,
+        );
+        let scale_name = std::any::type_name::<T>().rsplit("::").next().unwrap();
+        let debug = self.debug.as_mut().unwrap();
+        debug.on_frame(time_us, Self::now_ms() - started_ms);
+        let format_rate =
+            |rate: Option<f64>| rate.map_or("-".to_string(), |v| format!("{:.0}%", v));
+        let mut lines = vec![
+            format!(
+                "{} fps, last frame {:.1} ms",
+                debug.get_fps(),
+                debug.last_frame_ms
+            ),
+            format!("scale: {}", scale_name),
+            format!("window: {} - {}", formatted_window[0], formatted_window[1]),
+            format!(
+                "frames reused: main {}, preview {}",
+                format_rate(debug.main_frames.get_rate()),
+                format_rate(debug.preview_frames.get_rate())
+            ),
+        ];
+        for data_set in self.content.data_sets.iter() {
+            if data_set.alpha.get_end_value() == 0.0 {
+                continue;
+            }
+            let points = match (
+                data_set.bin_search_left_bound(from),
+                data_set.bin_search_right_bound(to),
+            ) {
+                (Some(first), Some(last)) if last >= first => last - first + 1,
+                _ => 0,
+            };
+            lines.push(format!("{}: {} points", data_set.name, points));
+        }
+
+        let area_rect = |area: &ScreenArea<T>| {
+            (
+                area.left_cx(),
+                area.top_cy(),
+                area.right_cx() - area.left_cx(),
+                area.bottom_cy() - area.top_cy(),
+            )
+        };
+        let screen_rect = |screen: &Screen| (0.0, 0.0, screen.canvas_width, screen.canvas_height);
+        let debug = self.debug.as_ref().unwrap();
+        debug.draw(
+            &self.tooltip_screen,
+            &self.config,
+            lines.as_slice(),
+            &[
+                screen_rect(&self.tooltip_screen),
+                area_rect(self.main_camera.get_last_screen_area()),
+            ],
+        );
+        debug.draw(
+            &self.preview_screen,
+            &self.config,
+            &[],
+            &[
+                screen_rect(&self.preview_screen),
+                area_rect(self.preview_camera.get_last_screen_area()),
+            ],
+        );
+        debug.draw(
+            &self.legend_screen,
+            &self.config,
+            &[],
+            &[screen_rect(&self.legend_screen)],
+        );
+    }
+
+    fn has_size(wrapper: &web_sys::Element) -> bool {
+        wrapper.client_width() > 0 && wrapper.client_height() > 0
+    }
+
+    /// Keeps the window where the user put it when data is appended.
+    fn detach_follow(&mut self) {
+        if self.config.follow.enabled && !self.user_detached {
+            self.user_detached = true;
+            self.events.push(ChartEvent::FollowChange(false));
+        }
+    }
+    /// The state a definition restores, see `DrawChart::restore_state`.
+    fn get_state(&self) -> ChartState {
+        let coord = self.main_camera.coord.get_end_value();
+        let coord_half_range = self.main_camera.coord_range.get_end_value() * 0.5;
+        ChartState {
+            window: Some((coord - coord_half_range, coord + coord_half_range)),
+            hidden_data_sets: self
+                .content
+                .data_sets
+                .iter()
+                .filter(|data_set| data_set.alpha.get_end_value() == 0.0)
+                .map(|data_set| data_set.name.clone())
+                .collect(),
+            following: Some(self.config.follow.enabled && !self.user_detached),
+            preview_height: self.preview_height_px,
+        }
+    }
+    /// Moves the window to the last `span_x` of the data, or the whole of it.
+    fn follow_latest(&mut self, time_us: f64) {
+        self.dirty = true;
+        let (coord_min, coord_max) = (self.content.global_coord_min, self.content.global_coord_max);
+        match self.config.follow.span_x {
+            Some(span_x) => {
+                let (window, _) = InitialRange::Last(span_x).resolve(
+                    coord_min,
+                    coord_max,
+                    self.config.edge_behavior != EdgeBehavior::Clip,
+                );
+                self.main_camera.zoom_by_coords(
+                    &mut self.content,
+                    window.0,
+                    window.1,
+                    Some(time_us),
+                );
+                self.zoomed_in =
+                    self.main_camera.coord_range.get_end_value() < coord_max - coord_min;
+            }
+            None => {
+                self.main_camera
+                    .update_by_content(&mut self.content, Some(time_us));
+                self.zoomed_in = false;
+            }
+        }
+    }
+
     fn zoom_out(&mut self, time_us: f64) {
         self.dirty = true;
         self.zoomed_in = false;
+        self.detach_follow();
         let screen_area = self
             .preview_camera
             .get_content_screen_area(self.preview_camera.scale_time_us);
@@ -191,49 +842,55 @@ where
             Some(time_us),
         );
     }
+    fn get_hit_scale(&self) -> f64 {
+        self.config.resolve_hit_scale(&self.client_caps.borrow())
+    }
     fn try_to_grab_camera_grip(&mut self, time_us: f64) {
         self.dirty = true;
         if let Some((x, _)) = self.pointer_position {
+            let hit_margin = self
+                .preview_screen
+                .apx_to_cpx(GRIP_HIT_MARGIN * self.get_hit_scale())
+                / self.preview_screen.css_px_to_cpx(1.0);
             let screen_area = self
                 .preview_camera
                 .get_content_screen_area(self.preview_camera.scale_time_us);
-            if let Some(mouse_coord) = screen_area.x_to_coord(x) {
-                let grip_coord = self.main_camera.coord.get_value(time_us);
-                let grip_coord_half_range = self.main_camera.coord_range.get_value(time_us) * 0.5;
-                if mouse_coord >= grip_coord - grip_coord_half_range
-                    && mouse_coord <= grip_coord + grip_coord_half_range
-                {
-                    self.camera_grip_x_offset = Some(x - screen_area.coord_to_x(grip_coord));
-                }
+            let grip_coord = self.main_camera.coord.get_value(time_us);
+            let grip_coord_half_range = self.main_camera.coord_range.get_value(time_us) * 0.5;
+            let x_1 = screen_area.coord_to_x(grip_coord - grip_coord_half_range);
+            let x_2 = screen_area.coord_to_x(grip_coord + grip_coord_half_range);
+            if x >= x_1.min(x_2) - hit_margin && x <= x_1.max(x_2) + hit_margin {
+                self.camera_grip_coord_offset =
+                    Some(screen_area.x_to_coord_unbounded(x) - grip_coord);
             }
         }
     }
 
     fn drag_main_camera(&mut self, time_us: f64) {
         self.dirty = true;
-        if let (Some((mouse_x, _)), Some(x_offset)) =
-            (self.pointer_position, self.camera_grip_x_offset)
+        if let (Some((mouse_x, _)), Some(coord_offset)) =
+            (self.pointer_position, self.camera_grip_coord_offset)
         {
             let screen_area = self
                 .preview_camera
                 .get_content_screen_area(self.preview_camera.scale_time_us);
-            if let Some(new_grip_coord) = screen_area.x_to_coord(mouse_x - x_offset) {
-                let half_range = self.main_camera.coord_range.get_end_value() * 0.5;
-                let coord_min = screen_area.scale.get_coord_min();
-                let coord_max = screen_area.scale.get_coord_max();
-
-                let new_camera_coord = if new_grip_coord - half_range < coord_min {
-                    coord_min + half_range
-                } else if new_grip_coord + half_range > coord_max {
-                    coord_max - half_range
-                } else {
-                    new_grip_coord
-                };
+            let new_grip_coord = screen_area.x_to_coord_unbounded(mouse_x) - coord_offset;
+            let half_range = self.main_camera.coord_range.get_end_value() * 0.5;
+            let coord_min = screen_area.scale.get_coord_min();
+            let coord_max = screen_area.scale.get_coord_max();
 
-                if new_camera_coord != self.main_camera.coord.get_end_value() {
-                    self.main_camera
-                        .move_to(&mut self.content, new_camera_coord, Some(time_us));
-                }
+            let new_camera_coord = if new_grip_coord - half_range < coord_min {
+                coord_min + half_range
+            } else if new_grip_coord + half_range > coord_max {
+                coord_max - half_range
+            } else {
+                new_grip_coord
+            };
+
+            if new_camera_coord != self.main_camera.coord.get_end_value() {
+                self.detach_follow();
+                self.main_camera
+                    .move_to(&mut self.content, new_camera_coord, Some(time_us));
             }
         }
     }
@@ -247,6 +904,13 @@ where
             ));
         }
         let is_visible = self.content.data_sets[index].alpha.get_end_value() == 1.0;
+        if let Some(isolation) = &mut self.isolation {
+            if isolation.index == index {
+                self.toggle_isolation(index, time_us);
+                return Ok(());
+            }
+            isolation.restore_visibility[index] = !is_visible;
+        }
         if is_visible
             && self
                 .content
@@ -256,18 +920,220 @@ where
                 .count()
                 == 1
         {
+            let animation_time_us = self.get_series_animation_time(time_us);
             for (index_, data_set) in self.content.data_sets.iter_mut().enumerate() {
                 if index_ != index {
-                    data_set.alpha.set_value(1.0, Some(time_us));
+                    data_set.set_visible(true, animation_time_us);
                 }
             }
         } else {
-            let alpha = &mut self.content.data_sets[index].alpha;
-            alpha.set_value(1.0 - alpha.get_end_value(), Some(time_us));
+            let animation_time_us = self.get_series_animation_time(time_us);
+            let data_set = &mut self.content.data_sets[index];
+            data_set.set_visible(data_set.alpha.get_end_value() == 0.0, animation_time_us);
         }
         self.update_cameras(time_us);
         Ok(())
     }
+    fn get_visibility(&self) -> Vec<bool> {
+        self.content
+            .data_sets
+            .iter()
+            .map(|data_set| data_set.alpha.get_end_value() != 0.0)
+            .collect()
+    }
+    fn set_visibility(&mut self, visibility: &[bool], time_us: f64) {
+        self.dirty = true;
+        let animation_time_us = self.get_series_animation_time(time_us);
+        for (data_set, visible) in self.content.data_sets.iter_mut().zip(visibility.iter()) {
+            data_set.set_visible(*visible, animation_time_us);
+        }
+        self.update_cameras(time_us);
+    }
+    /// The time data sets start fading from, None when they are shown or
+    /// hidden at once.
+    fn get_series_animation_time(&self, time_us: f64) -> Option<f64> {
+        (self.config.series_animation_duration > 0.0 && !self.client_caps.borrow().reduced_motion)
+            .then_some(time_us)
+    }
+    /// Shows the data set alone, remembering the visibility to restore;
+    /// for the isolated data set restores that visibility instead.
+    fn toggle_isolation(&mut self, index: usize, time_us: f64) {
+        let restore_visibility = match self.isolation.take() {
+            Some(isolation) if isolation.index == index => {
+                self.set_visibility(isolation.restore_visibility.as_slice(), time_us);
+                return;
+            }
+            Some(isolation) => isolation.restore_visibility,
+            None => self.get_visibility(),
+        };
+        let visibility: Vec<bool> = (0..restore_visibility.len())
+            .map(|index_| index_ == index)
+            .collect();
+        self.set_visibility(visibility.as_slice(), time_us);
+        self.isolation = Some(Isolation {
+            index,
+            restore_visibility,
+        });
+    }
+    /// The first click toggles a data set, a double click or a click with
+    /// ctrl or meta toggles its isolation.
+    fn click_data_set(&mut self, index: usize, modifier: bool, time_us: f64) {
+        if modifier {
+            self.legend_last_click = None;
+            self.toggle_isolation(index, time_us);
+            return;
+        }
+        if let Some((last_index, last_time_us, last_visibility, last_isolation)) =
+            self.legend_last_click.take()
+        {
+            if last_index == index && time_us - last_time_us < US_DOUBLE_CLICK {
+                // undo the first click
+                self.isolation = last_isolation;
+                self.set_visibility(last_visibility.as_slice(), time_us);
+                self.toggle_isolation(index, time_us);
+                return;
+            }
+        }
+        let visibility = self.get_visibility();
+        let isolation = self.isolation.clone();
+        self.toggle_data_set(index, time_us).unwrap();
+        self.legend_last_click = Some((index, time_us, visibility, isolation));
+    }
+    fn handle_key_down(&mut self, event: &web_sys::KeyboardEvent, time_us: f64) {
+        let target = match event.target() {
+            Some(target) => JsValue::from(target),
+            None => return,
+        };
+        let is_wrapper_target = target == JsValue::from(&self.wrapper);
+        let is_camera_grip_target = target == JsValue::from(&self.camera_grip_screen.canvas);
+        let is_legend_target = target == JsValue::from(&self.legend_screen.canvas);
+        let is_splitter_target = self
+            .splitter
+            .as_ref()
+            .is_some_and(|splitter| target == JsValue::from(splitter));
+        if event.key() == "Escape"
+            && (is_camera_grip_target || is_legend_target || is_splitter_target)
+        {
+            event.prevent_default();
+            // focusing fires focus events synchronously, which reach the
+            // manager while this chart is borrowed
+            let wrapper = self.wrapper.clone();
+            defer_call(Box::new(move || {
+                wrapper
+                    .unchecked_ref::<web_sys::HtmlElement>()
+                    .focus()
+                    .unwrap();
+            }));
+            return;
+        }
+        if is_legend_target {
+            self.handle_legend_key_down(event, time_us);
+            return;
+        }
+        if is_splitter_target {
+            let shift_y = match event.key().as_str() {
+                "ArrowUp" => -SPLITTER_KEYBOARD_STEP,
+                "ArrowDown" => SPLITTER_KEYBOARD_STEP,
+                _ => return,
+            };
+            event.prevent_default();
+            self.move_splitter(self.preview_screen.height, shift_y);
+            return;
+        }
+        if !is_wrapper_target && !is_camera_grip_target {
+            return;
+        }
+        // the wrapper pans and zooms the chart, the preview window moves and
+        // resizes with shift
+        let (pan, zoom) = (self.config.interactions.pan, self.config.interactions.zoom);
+        let (shift, scale) = match (event.key().as_str(), event.shift_key()) {
+            ("ArrowLeft", false) if pan => (-KEYBOARD_PAN_STEP, 1.0),
+            ("ArrowRight", false) if pan => (KEYBOARD_PAN_STEP, 1.0),
+            ("ArrowLeft", true) if zoom && is_camera_grip_target => {
+                (0.0, KEYBOARD_ZOOM_STEP.recip())
+            }
+            ("ArrowRight", true) if zoom && is_camera_grip_target => (0.0, KEYBOARD_ZOOM_STEP),
+            ("+" | "=", _) if zoom && is_wrapper_target => (0.0, KEYBOARD_ZOOM_STEP.recip()),
+            ("-", _) if zoom && is_wrapper_target => (0.0, KEYBOARD_ZOOM_STEP),
+            ("0", _) if zoom && is_wrapper_target => {
+                event.prevent_default();
+                self.zoom_out(time_us);
+                return;
+            }
+            _ => return,
+        };
+        event.prevent_default();
+        // arrows follow the screen, not the coords
+        let shift = if self.config.is_x_axis_reversed() {
+            -shift
+        } else {
+            shift
+        };
+        self.move_camera_by(shift, scale, time_us);
+    }
+    /// Shifts the main camera by a portion of its range and scales the range.
+    fn move_camera_by(&mut self, shift: f64, scale: f64, time_us: f64) {
+        let screen_area = self
+            .preview_camera
+            .get_content_screen_area(self.preview_camera.scale_time_us);
+        let coord_min = screen_area.scale.get_coord_min();
+        let coord_max = screen_area.scale.get_coord_max();
+        let coord = self.main_camera.coord.get_end_value();
+        let coord_range = self.main_camera.coord_range.get_end_value();
+
+        let new_coord_range = (coord_range * scale).min(coord_max - coord_min);
+        if new_coord_range >= coord_max - coord_min {
+            self.zoom_out(time_us);
+            return;
+        }
+        let coord_start = (coord + coord_range * shift - new_coord_range * 0.5)
+            .max(coord_min)
+            .min(coord_max - new_coord_range);
+        self.zoom_by_coords(coord_start, coord_start + new_coord_range, time_us);
+    }
+    fn handle_legend_key_down(&mut self, event: &web_sys::KeyboardEvent, time_us: f64) {
+        let is_legend_target = event.target().is_some_and(|target| {
+            JsValue::from(target) == JsValue::from(&self.legend_screen.canvas)
+        });
+        if !is_legend_target || self.legend.items.is_empty() {
+            return;
+        }
+        let focused = self.legend.focused.unwrap_or(self.legend.offset);
+        let last_index = self.legend.items.len() - 1;
+        // the legend is mirrored in the rtl layout
+        let key = match (event.key().as_str(), self.config.is_rtl()) {
+            ("ArrowLeft", true) => "ArrowRight".to_string(),
+            ("ArrowRight", true) => "ArrowLeft".to_string(),
+            (key, _) => key.to_string(),
+        };
+        let next_focused = match key.as_str() {
+            "Tab" if event.shift_key() => focused.checked_sub(1),
+            "Tab" => Some(focused + 1).filter(|index| *index <= last_index),
+            "ArrowLeft" | "ArrowUp" => Some(focused.saturating_sub(1)),
+            "ArrowRight" | "ArrowDown" => Some((focused + 1).min(last_index)),
+            "Home" => Some(0),
+            "End" => Some(last_index),
+            "Enter" | " " if self.config.interactions.legend_toggle => {
+                event.prevent_default();
+                let index = self.legend.items[focused].data_set_index;
+                if event.ctrl_key() || event.meta_key() {
+                    self.toggle_isolation(index, time_us);
+                } else {
+                    self.toggle_data_set(index, time_us).unwrap();
+                }
+                self.legend.focused = Some(focused);
+                return;
+            }
+            _ => return,
+        };
+        // tabbing past the first or the last item moves the focus out of the legend
+        if let Some(next_focused) = next_focused {
+            event.prevent_default();
+            self.legend.focused = Some(next_focused);
+            self.legend.scroll_to(next_focused);
+            self.dirty = true;
+        }
+    }
     fn update_cameras(&mut self, time_us: f64) {
         let coord = self.main_camera.coord.get_end_value();
         let coord_half_range = self.main_camera.coord_range.get_end_value() * 0.5;
@@ -277,25 +1143,19 @@ where
             coord + coord_half_range,
             Some(time_us),
         );
-        self.preview_camera.update_by_content(
-            &mut self.content,
-            Some(time_us),
-        );
+        self.preview_camera
+            .update_by_content(&mut self.content, Some(time_us));
     }
-    fn handle_legend_click(&mut self, x: f64, y: f64, time_us: f64) {
+    fn handle_legend_click(&mut self, x: f64, y: f64, modifier: bool, time_us: f64) {
         self.dirty = true;
-        let mut clicked_index: Option<usize> = None;
         let cx = self.legend_screen.x_to_cx(x);
         let cy = self.legend_screen.y_to_cy(y);
-        for (index, position) in self.legend.positions.iter().enumerate() {
-            if position.contains(cx, cy) {
-                clicked_index = Some(index);
-                break;
-            }
-        }
-        if let Some(index) = clicked_index {
-            self.toggle_data_set(self.legend.offset + index, time_us)
-                .unwrap();
+        if let Some(index) = self
+            .legend
+            .get_item_at(&self.legend_screen, cx, cy, self.get_hit_scale())
+            .filter(|_| self.config.interactions.legend_toggle)
+        {
+            self.click_data_set(index, modifier, time_us);
         }
         if let Some(arrow_left) = &self.legend.arrow_left {
             if arrow_left.contains(cx, cy) {
@@ -310,6 +1170,267 @@ where
             }
         }
     }
+    fn set_legend_hovered(&mut self, pos: Option<&(f64, f64)>) {
+        let legend_hovered = pos
+            .filter(|pos| self.legend_screen.contains_pos(pos))
+            .and_then(|(x, y)| {
+                self.legend.get_item_at(
+                    &self.legend_screen,
+                    self.legend_screen.x_to_cx(*x),
+                    self.legend_screen.y_to_cy(*y),
+                    self.get_hit_scale(),
+                )
+            });
+        if legend_hovered != self.legend_hovered {
+            self.legend_hovered = legend_hovered;
+            self.dirty = true;
+            let canvas = &self.legend_screen.canvas;
+            match legend_hovered.and_then(|index| self.content.data_sets[index].get_legend_title())
+            {
+                Some(title) => canvas.set_attribute("title", title.as_str()).unwrap(),
+                None => canvas.remove_attribute("title").unwrap(),
+            }
+        }
+    }
+    /// Dims the preview outside of the window, outlines the window and, when
+    /// zoomed in, adds handles on its edges sized for the pointer.
+    fn draw_preview_mask(&self, window: (f64, f64), time_us: f64) {
+        let screen_area = self.preview_camera.get_content_screen_area(time_us);
+        let screen = &self.camera_grip_screen;
+        let context = &screen.context;
+        let top_cy = screen_area.top_cy();
+        let height = screen_area.bottom_cy() - top_cy;
+
+        let v = &self.config.color_preview_mask;
+        context.set_fill_style_str(format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3).as_str());
+        context.fill_rect(
+            screen_area.left_cx(),
+            top_cy,
+            window.0 - screen_area.left_cx(),
+            height,
+        );
+        context.fill_rect(window.1, top_cy, screen_area.right_cx() - window.1, height);
+
+        let v = &self.config.color_preview_window;
+        let color = format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3);
+        let c_line_width = snap_line_width(screen.apx_to_cpx(self.config.stroke_width_coeff));
+        context.set_stroke_style_str(color.as_str());
+        context.set_line_width(c_line_width);
+        let (x, y, width, height) = snap_rect(
+            (
+                window.0 + c_line_width * 0.5,
+                top_cy + c_line_width * 0.5,
+                window.1 - window.0 - c_line_width,
+                height - c_line_width,
+            ),
+            c_line_width,
+        );
+        context.stroke_rect(x, y, width, height);
+        if !self.zoomed_in {
+            return;
+        }
+        let hit_scale = self.get_hit_scale();
+        let c_handle_width = screen.apx_to_cpx(PREVIEW_HANDLE_WIDTH * hit_scale);
+        let c_handle_height = screen
+            .apx_to_cpx(PREVIEW_HANDLE_HEIGHT * hit_scale)
+            .min(height);
+        context.set_fill_style_str(color.as_str());
+        for cx in [window.0, window.1] {
+            context.fill_rect(
+                cx - c_handle_width * 0.5,
+                top_cy + (height - c_handle_height) * 0.5,
+                c_handle_width,
+                c_handle_height,
+            );
+        }
+    }
+    /// Marks the hovered coord in the preview, so it works as an overview.
+    fn draw_preview_hover_marker(&self, time_us: f64) {
+        let hover = match &self.tooltip.hover {
+            Some(hover) => hover,
+            None => return,
+        };
+        let screen_area = self.preview_camera.get_content_screen_area(time_us);
+        let screen = &self.camera_grip_screen;
+        let context = &screen.context;
+        let c_line_width = snap_line_width(screen.apx_to_cpx(self.config.stroke_width_coeff));
+        let cx = snap_to_pixel(screen_area.get_cx(hover.coord), c_line_width);
+        let v = &self.config.color_preview_window;
+        context.set_stroke_style_str(format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3).as_str());
+        context.set_line_width(c_line_width);
+        context.begin_path();
+        context.move_to(cx, screen_area.top_cy());
+        context.line_to(cx, screen_area.bottom_cy());
+        context.stroke();
+    }
+    /// The data set hovered in the legend, otherwise the one nearest to the
+    /// pointer, while there are others to dim; data sets which are not
+    /// hoverable are never dimmed.
+    fn get_highlighted(&self) -> Option<usize> {
+        if !self.config.hover_highlight
+            || self
+                .content
+                .data_sets
+                .iter()
+                .filter(|data_set| data_set.alpha.get_end_value() > 0.0 && data_set.hoverable)
+                .count()
+                < 2
+        {
+            return None;
+        }
+        if let Some(index) = self.legend_hovered {
+            return self
+                .content
+                .data_sets
+                .get(index)
+                .filter(|data_set| data_set.alpha.get_end_value() > 0.0)
+                .map(|_| index);
+        }
+        let hover = self.tooltip.hover.as_ref()?;
+        self.content
+            .data_sets
+            .iter()
+            .position(|data_set| data_set.name == hover.nearest)
+    }
+    /// Swaps the config of every part without touching their state, so
+    /// running animations go on.
+    fn set_config(&mut self, config: Rc<ChartConfig>) {
+        self.main_camera.chart_config = Rc::clone(&config);
+        self.preview_camera.chart_config = Rc::clone(&config);
+        self.legend.chart_config = Rc::clone(&config);
+        self.tooltip.set_chart_config(Rc::clone(&config));
+        self.description.set_chart_config(Rc::clone(&config));
+        self.config = config;
+    }
+    fn resize_legend(&mut self, time_us: f64) {
+        let content_screen_area = self.main_camera.get_content_screen_area(time_us);
+        self.legend.sync_items(&self.content, &self.legend_screen);
+        self.legend.resize(
+            &self.legend_screen,
+            content_screen_area.left_cx(),
+            content_screen_area.right_cx(),
+            0.0,
+            self.legend_screen.canvas_height,
+        );
+    }
+    /// In the "wrap" mode the legend takes as much height as its rows need,
+    /// the plot gives the difference up, the preview keeps its height.
+    fn layout_legend(&mut self, time_us: f64) {
+        if self.dropped.legend {
+            return;
+        }
+        self.resize_legend(time_us);
+        let required_cheight = match self.legend.required_cheight {
+            Some(required_cheight) => required_cheight,
+            None => return,
+        };
+        let height = (required_cheight / self.legend_screen.css_px_to_cpx(1.0)).ceil();
+        if (height - self.legend_screen.height).abs() < 1.0 {
+            return;
+        }
+        self.legend_height_px = Some(height);
+        self.apply_section_heights();
+        self.on_resize();
+        self.legend_screen.sync_canvas_size();
+        self.resize_legend(time_us);
+    }
+    /// Picks the parts to drop for the size of the wrapper; the dropped ones
+    /// are hidden rather than removed, so growing back restores them as
+    /// they were.
+    fn update_compact_layout(&mut self) {
+        let mut dropped = DroppedParts::from_steps(
+            self.config.compact_layout.as_slice(),
+            self.wrapper.client_width() as f64,
+            self.wrapper.client_height() as f64,
+        );
+        // the size of the wrapper says nothing of the preview elsewhere
+        dropped.preview &= self.preview_wrapper.is_none();
+        if dropped == self.dropped {
+            return;
+        }
+        self.dropped = dropped;
+        self.main_camera
+            .set_compact(dropped.axis_labels, dropped.sparkline);
+        if dropped.legend {
+            self.legend_hovered = None;
+        }
+        self.apply_section_heights();
+        self.on_resize();
+    }
+    /// Sizes the screens by the weights of the config, the height the legend
+    /// needs, the splitter and the dropped parts; the main screen takes the
+    /// rest.
+    fn apply_section_heights(&self) {
+        let legend_height = match (self.dropped.legend, self.legend_height_px) {
+            (true, _) => "0px".to_string(),
+            (false, Some(height)) => format!("{:.0}px", height),
+            (false, None) => format!("{:.1}%", self.config.layout_legend_height),
+        };
+        let separate = self.preview_wrapper.is_some();
+        let preview_height = match (self.dropped.preview, self.preview_height_px) {
+            // the preview fills its own wrapper
+            _ if separate => "100%".to_string(),
+            (true, _) => "0px".to_string(),
+            (false, Some(height)) => format!("{:.0}px", height),
+            (false, None) => format!("{:.1}%", self.config.layout_preview_height),
+        };
+        let splitter_height = if self.splitter.is_some() && !self.dropped.preview {
+            format!("{:.0}px", SPLITTER_HEIGHT)
+        } else {
+            "0px".to_string()
+        };
+        let content_height = if separate {
+            format!("calc(100% - {})", legend_height)
+        } else {
+            format!(
+                "calc(100% - {} - {} - {})",
+                preview_height, splitter_height, legend_height
+            )
+        };
+        let (content_top, preview_top) = match self.config.preview_position {
+            _ if separate => ("0px".to_string(), "0px".to_string()),
+            PreviewPosition::Bottom => (
+                "0px".to_string(),
+                format!("calc({} + {})", content_height, splitter_height),
+            ),
+            PreviewPosition::Top => (
+                format!("calc({} + {})", preview_height, splitter_height),
+                "0px".to_string(),
+            ),
+        };
+        let display = |dropped: bool| if dropped { "none" } else { "block" };
+        for (screen, height, dropped) in [
+            (&self.main_screen, &content_height, false),
+            (&self.tooltip_screen, &content_height, false),
+            (&self.preview_screen, &preview_height, self.dropped.preview),
+            (
+                &self.camera_grip_screen,
+                &preview_height,
+                self.dropped.preview,
+            ),
+            (&self.legend_screen, &legend_height, self.dropped.legend),
+        ] {
+            let style = screen.canvas.style();
+            style.set_property("height", height.as_str()).unwrap();
+            style.set_property("display", display(dropped)).unwrap();
+        }
+        self.tooltip_screen
+            .canvas
+            .style()
+            .set_property("top", content_top.as_str())
+            .unwrap();
+        self.camera_grip_screen
+            .canvas
+            .style()
+            .set_property("top", preview_top.as_str())
+            .unwrap();
+        if let Some(splitter) = &self.splitter {
+            splitter
+                .style()
+                .set_property("display", display(self.dropped.preview))
+                .unwrap();
+        }
+    }
     fn check_legend_long_press(&mut self, time_us: f64) -> usize {
         if let Some(legend_pointer_down_time_us) = &self.legend_pointer_down_time_us {
             if time_us - *legend_pointer_down_time_us > self.config.us_long_press
@@ -317,25 +1438,15 @@ where
             {
                 let (x, y) = self.legend_pointer_down_position.as_ref().unwrap();
 
-                let mut clicked_index: Option<usize> = None;
                 let cx = self.legend_screen.x_to_cx(*x);
                 let cy = self.legend_screen.y_to_cy(*y);
-                for (index, position) in self.legend.positions.iter().enumerate() {
-                    if position.contains(cx, cy) {
-                        clicked_index = Some(index);
-                        break;
-                    }
-                }
-                if let Some(index) = clicked_index {
-                    let index_to_show = index + self.legend.offset;
-                    for (index, data_set) in self.content.data_sets.iter_mut().enumerate() {
-                        data_set.alpha.set_value(
-                            if index == index_to_show { 1.0 } else { 0.0 },
-                            Some(time_us),
-                        );
-                    }
-                    self.update_cameras(time_us);
-                    self.dirty = true;
+                if let Some(index_to_show) = self
+                    .legend
+                    .get_item_at(&self.legend_screen, cx, cy, self.get_hit_scale())
+                    .filter(|_| self.config.interactions.legend_toggle)
+                {
+                    self.legend_last_click = None;
+                    self.toggle_isolation(index_to_show, time_us);
                     self.legend_pointer_down_position = None;
                     self.legend_pointer_down_time_us = None;
                 }
@@ -349,6 +1460,7 @@ where
     fn zoom_by_coords(&mut self, left_coord: f64, right_coord: f64, time_us: f64) {
         self.dirty = true;
         self.zoomed_in = true;
+        self.detach_follow();
         self.main_camera
             .zoom_by_coords(&mut self.content, left_coord, right_coord, Some(time_us));
     }
@@ -366,20 +1478,104 @@ where
                 .get_content_screen_area(self.preview_camera.scale_time_us)
         };
 
-        let left_x = mouse_x1
-            .min(mouse_x2)
-            .max(screen_area.coord_to_x(screen_area.scale.get_coord_min()));
-        let right_x = mouse_x1
-            .max(mouse_x2)
-            .min(screen_area.coord_to_x(screen_area.scale.get_coord_max()));
+        let x_min = screen_area.coord_to_x(screen_area.scale.get_coord_min());
+        let x_max = screen_area.coord_to_x(screen_area.scale.get_coord_max());
+        let (x_min, x_max) = (x_min.min(x_max), x_min.max(x_max));
         match (
-            screen_area.x_to_coord(left_x),
-            screen_area.x_to_coord(right_x),
+            screen_area.x_to_coord(mouse_x1.max(x_min).min(x_max)),
+            screen_area.x_to_coord(mouse_x2.max(x_min).min(x_max)),
         ) {
-            (Some(left_coord), Some(right_coord)) => Some((left_coord, right_coord)),
+            (Some(coord_1), Some(coord_2)) => Some((coord_1.min(coord_2), coord_1.max(coord_2))),
             _ => None,
         }
     }
+    /// Where the pointer would be to hover the playhead: at its x, halfway
+    /// up the plot.
+    fn get_playhead_position(&mut self, time_us: f64) -> Option<(f64, f64)> {
+        let coord = self.playhead.as_mut()?.get_value(time_us);
+        let area = self.main_camera.get_content_screen_area(time_us);
+        let to_x = |cx: f64| cx / area.screen_to_canvas_scale + area.screen_x;
+        // kept off the edges, where the way back to a coord may round outside
+        let (left_x, right_x) = (to_x(area.left_cx()) + 0.001, to_x(area.right_cx()) - 0.001);
+        Some((
+            area.coord_to_x(coord).clamp(left_x, right_x),
+            (area.top_cy() + area.bottom_cy()) * 0.5 / area.screen_to_canvas_scale + area.screen_y,
+        ))
+    }
+    /// Sends out the hover once it settled; keeps frames coming until then.
+    fn flush_settled_hover(&mut self, time_us: f64) -> usize {
+        let settle_us = self.config.hover_settle_ms * 1000.0;
+        if let Some(hover) = self.hover_settle.take_settled(settle_us, time_us) {
+            self.events.push(ChartEvent::Hover(hover));
+        }
+        self.hover_settle.is_pending() as usize
+    }
+    fn collect_events(&mut self, time_us: f64) {
+        if !self.ready {
+            self.ready = true;
+            self.events.push(ChartEvent::Ready);
+        }
+        let coord = self.main_camera.coord.get_end_value();
+        let coord_half_range = self.main_camera.coord_range.get_end_value() * 0.5;
+        let range = (coord - coord_half_range, coord + coord_half_range);
+        let visibility = self.get_visibility();
+        if self.last_range != Some(range) || self.last_visibility.as_ref() != Some(&visibility) {
+            self.description
+                .update(&self.content, range, visibility.as_slice());
+        }
+        if self.last_range != Some(range) {
+            if self.last_range.is_some() {
+                self.events.push(ChartEvent::RangeChange {
+                    coord_min: range.0,
+                    coord_max: range.1,
+                });
+            }
+            self.last_range = Some(range);
+        }
+        if self.tooltip.hover != self.last_hover {
+            self.last_hover = self.tooltip.hover.clone();
+            self.hover_link
+                .update(&self.config, self.last_hover.as_ref());
+            let settle_us = self.config.hover_settle_ms * 1000.0;
+            if let Some(hover) =
+                self.hover_settle
+                    .change(self.last_hover.clone(), settle_us, time_us)
+            {
+                self.events.push(ChartEvent::Hover(hover));
+            }
+        }
+        if self.last_visibility.as_ref() != Some(&visibility) {
+            if self.last_visibility.is_some() {
+                self.events.push(ChartEvent::VisibilityChange(
+                    self.content
+                        .data_sets
+                        .iter()
+                        .zip(visibility.iter())
+                        .map(|(data_set, visible)| (data_set.name.clone(), *visible))
+                        .collect(),
+                ));
+            }
+            self.last_visibility = Some(visibility);
+        }
+        if self.point_click_pending {
+            self.point_click_pending = false;
+            if let (Some(hover), Some((x, y))) = (&self.tooltip.hover, self.pointer_position) {
+                let screen_area = self
+                    .main_camera
+                    .get_content_screen_area(self.main_camera.scale_time_us);
+                let radius = self
+                    .main_screen
+                    .apx_to_cpx(POINT_HIT_RADIUS * self.get_hit_scale());
+                let pointer = (screen_area.x_to_cx(x), screen_area.y_to_cy(y));
+                if hover.values.iter().any(|(_, value)| {
+                    let point = (screen_area.get_cx(hover.coord), screen_area.get_cy(*value));
+                    is_point_hit(point, pointer, radius)
+                }) {
+                    self.events.push(ChartEvent::PointClick(hover.clone()));
+                }
+            }
+        }
+    }
     fn draw_selected_area(&mut self, time_us: f64) {
         if let (Some(down_pos), Some(pos)) =
             (self.tooltip_pointer_down_position, self.pointer_position)
@@ -400,8 +1596,9 @@ where
                 context.set_fill_style(&color);
                 context.fill_rect(left_x, top_y, right_x - left_x, bottom_y - top_y);
 
-                let left_x = preview_screen_area.get_cx(left_coord);
-                let right_x = preview_screen_area.get_cx(right_coord);
+                let cx_1 = preview_screen_area.get_cx(left_coord);
+                let cx_2 = preview_screen_area.get_cx(right_coord);
+                let (left_x, right_x) = (cx_1.min(cx_2), cx_1.max(cx_2));
                 let top_y = preview_screen_area.top_cy();
                 let bottom_y = preview_screen_area.bottom_cy();
                 let context = &self.camera_grip_screen.context;
@@ -434,12 +1631,10 @@ where
                 if right_coord >= content_screen_area.scale.get_coord_min()
                     && left_coord <= content_screen_area.scale.get_coord_max()
                 {
-                    let left_x = content_screen_area
-                        .get_cx(left_coord)
-                        .max(content_screen_area.left_cx());
-                    let right_x = content_screen_area
-                        .get_cx(right_coord)
-                        .min(content_screen_area.right_cx());
+                    let cx_1 = content_screen_area.get_cx(left_coord);
+                    let cx_2 = content_screen_area.get_cx(right_coord);
+                    let left_x = cx_1.min(cx_2).max(content_screen_area.left_cx());
+                    let right_x = cx_1.max(cx_2).min(content_screen_area.right_cx());
                     let top_y = content_screen_area.top_cy();
                     let bottom_y = content_screen_area.bottom_cy();
                     let context = &self.tooltip_screen.context;
@@ -454,50 +1649,111 @@ impl<T> DrawChart for MainChart<T>
 where
     T: Scale,
 {
+    fn accepts_control_events(&self) -> bool {
+        self.config.interactions.any()
+    }
     fn on_control_event(&mut self, event: &ControlEvent, time_us: f64) {
+        let mut interactions = self.config.interactions;
+        interactions.hover &= self.loading.is_none();
+        // a presentational chart keeps only the focus ring
+        if !interactions.any() && !matches!(event, ControlEvent::FocusChanged) {
+            return;
+        }
+        // real input takes over from `set_hover_x`
+        if let ControlEvent::PointerDown { pos }
+        | ControlEvent::PointerMoved { pos }
+        | ControlEvent::PinchStarted { pos1: pos, .. } = event
+        {
+            if interactions.hover
+                && self.playhead.is_some()
+                && self.tooltip_screen.contains_pos(pos)
+            {
+                self.playhead = None;
+                self.dirty = true;
+            }
+        }
         match event {
             ControlEvent::PointerDown { pos } => {
                 self.pointer_position = Some(pos.clone());
-                if self.tooltip_screen.contains_pos(&pos) {
+                if self.splitter_contains_pos(pos) {
+                    self.splitter_drag = Some((pos.1, self.preview_screen.height));
+                }
+                if (interactions.hover || interactions.zoom)
+                    && self.tooltip_screen.contains_pos(&pos)
+                {
                     self.tooltip_pointer_down_position = Some(pos.clone());
                     self.dirty = true;
                 }
-                if self.camera_grip_screen.contains_pos(&pos) {
+                if (interactions.zoom || interactions.pan)
+                    && self.camera_grip_screen.contains_pos(&pos)
+                {
                     self.camera_grip_pointer_down_position = Some(pos.clone());
                     self.dirty = true;
-                    if self.zoomed_in {
+                    if self.zoomed_in && interactions.pan {
                         self.try_to_grab_camera_grip(time_us);
                     }
                 }
                 if self.legend_screen.contains_pos(&pos) {
                     self.legend_pointer_down_position = Some(pos.clone());
                     self.legend_pointer_down_time_us = Some(time_us);
+                    self.legend.drag_origin_scroll_cx = Some(self.legend.scroll_cx);
+                    if interactions.hover && self.client_caps.borrow().touch_device {
+                        self.set_legend_hovered(Some(pos));
+                    }
                 }
             }
             ControlEvent::PointerMoved { pos } => {
                 self.pointer_position = Some(pos.clone());
+                if let Some((start_y, start_height)) = self.splitter_drag {
+                    self.move_splitter(start_height, pos.1 - start_y);
+                }
+                if interactions.hover && !self.client_caps.borrow().touch_device {
+                    self.set_legend_hovered(Some(pos));
+                }
                 if self.tooltip_pointer_down_position.is_some()
-                    || (self.tooltip_screen.contains_pos(&pos) || self.tooltip.visible)
+                    || interactions.hover
+                        && (self.tooltip_screen.contains_pos(&pos) || self.tooltip.visible)
                         && self.tooltip.mouse_click_at.is_none()
                 {
                     self.dirty = true;
                 }
                 if self.camera_grip_pointer_down_position.is_some() {
                     self.dirty = true;
-                    if self.camera_grip_x_offset.is_some() {
+                    if self.camera_grip_coord_offset.is_some() {
                         self.drag_main_camera(time_us);
                     }
                 }
+                if let (Some(down_pos), Some(origin_scroll_cx)) = (
+                    self.legend_pointer_down_position,
+                    self.legend.drag_origin_scroll_cx,
+                ) {
+                    if !is_click(&self.legend_pointer_down_position, &self.pointer_position) {
+                        if self.client_caps.borrow().touch_device {
+                            self.set_legend_hovered(None);
+                        }
+                        let shift_cx = self.legend_screen.css_px_to_cpx(pos.0 - down_pos.0);
+                        self.legend.set_scroll(if self.config.is_rtl() {
+                            origin_scroll_cx + shift_cx
+                        } else {
+                            origin_scroll_cx - shift_cx
+                        });
+                        self.dirty = true;
+                    }
+                }
             }
-            ControlEvent::PointerUp => {
+            ControlEvent::PointerUp { modifier } => {
+                self.splitter_drag = None;
                 if self.tooltip_pointer_down_position.is_some() {
                     if is_click(&self.tooltip_pointer_down_position, &self.pointer_position) {
-                        self.tooltip.mouse_click_at = if self.tooltip.mouse_click_at.is_none() {
-                            self.pointer_position.clone()
-                        } else {
-                            None
-                        };
-                    } else {
+                        if interactions.hover {
+                            self.point_click_pending = true;
+                            self.tooltip.mouse_click_at = if self.tooltip.mouse_click_at.is_none() {
+                                self.pointer_position.clone()
+                            } else {
+                                None
+                            };
+                        }
+                    } else if interactions.zoom {
                         // MOUSE UP AFTER DRAGGING
                         match (self.tooltip_pointer_down_position, self.pointer_position) {
                             (Some(down_pos), Some(pos)) => {
@@ -519,8 +1775,10 @@ where
                         &self.camera_grip_pointer_down_position,
                         &self.pointer_position,
                     ) {
-                        self.zoom_out(time_us);
-                    } else if self.camera_grip_x_offset.is_none() {
+                        if interactions.zoom {
+                            self.zoom_out(time_us);
+                        }
+                    } else if interactions.zoom && self.camera_grip_coord_offset.is_none() {
                         if let (Some(down_pos), Some(pos)) = (
                             self.camera_grip_pointer_down_position,
                             self.pointer_position,
@@ -533,21 +1791,26 @@ where
                         }
                     }
                     self.camera_grip_pointer_down_position = None;
-                    self.camera_grip_x_offset = None;
+                    self.camera_grip_coord_offset = None;
                     self.dirty = true;
                 }
 
                 if self.legend_pointer_down_position.is_some() {
                     if is_click(&self.legend_pointer_down_position, &self.pointer_position) {
                         let pos = self.legend_pointer_down_position.as_ref().unwrap();
-                        self.handle_legend_click(pos.0, pos.1, time_us);
+                        self.handle_legend_click(pos.0, pos.1, *modifier, time_us);
                     }
                     self.legend_pointer_down_position = None;
                     self.legend_pointer_down_time_us = None;
+                    self.legend.drag_origin_scroll_cx = None;
+                    if self.client_caps.borrow().touch_device {
+                        self.set_legend_hovered(None);
+                    }
                 }
             }
             ControlEvent::PinchStarted { pos1, pos2 } => {
-                if self.tooltip_screen.contains_pos(pos1)
+                if interactions.zoom
+                    && self.tooltip_screen.contains_pos(pos1)
                     && self.tooltip_screen.contains_pos(pos2)
                     && pos1.0 != pos2.0
                 {
@@ -580,14 +1843,10 @@ where
                         let coord_min = main_screen_area.global_scale.get_coord_min();
                         let coord_max = main_screen_area.global_scale.get_coord_max();
 
-                        let left_cx = main_screen_area.left_cx();
-
-                        let pos1_x_portion = (main_screen_area.x_to_cx(pos1.0.min(pos2.0))
-                            - left_cx)
-                            / main_screen_area.canvas_content_width;
-                        let pos2_x_portion = (main_screen_area.x_to_cx(pos1.0.max(pos2.0))
-                            - left_cx)
-                            / main_screen_area.canvas_content_width;
+                        let portion_1 = main_screen_area.x_to_portion(pos1.0);
+                        let portion_2 = main_screen_area.x_to_portion(pos2.0);
+                        let pos1_x_portion = portion_1.min(portion_2);
+                        let pos2_x_portion = portion_1.max(portion_2);
 
                         let coord_to_portion =
                             (coord_2 - coord_1) / (pos2_x_portion - pos1_x_portion);
@@ -611,78 +1870,794 @@ where
                     self.tooltip_pinch_coords = None;
                 }
             }
+            ControlEvent::KeyDown(event) => {
+                self.handle_key_down(event, time_us);
+            }
+            ControlEvent::FocusChanged => {
+                let active_element = web_sys::window()
+                    .unwrap()
+                    .document()
+                    .unwrap()
+                    .active_element()
+                    .map(JsValue::from);
+                let is_legend_focused =
+                    active_element.as_ref() == Some(&JsValue::from(&self.legend_screen.canvas));
+                let is_camera_grip_focused = active_element.as_ref()
+                    == Some(&JsValue::from(&self.camera_grip_screen.canvas));
+                if is_camera_grip_focused != self.camera_grip_focused {
+                    self.camera_grip_focused = is_camera_grip_focused;
+                    self.dirty = true;
+                }
+                let focused = if is_legend_focused {
+                    self.legend
+                        .focused
+                        .or(Some(self.legend.offset))
+                        .filter(|_| !self.legend.items.is_empty())
+                } else {
+                    None
+                };
+                if focused != self.legend.focused {
+                    self.legend.focused = focused;
+                    self.dirty = true;
+                }
+            }
         }
     }
     fn on_resize(&mut self) {
+        self.resize_main_pane();
+        self.resize_preview_pane();
+    }
+    fn on_element_resize(&mut self, element: &web_sys::Element) {
+        match &self.preview_wrapper {
+            Some(preview_wrapper) if preview_wrapper == element => self.resize_preview_pane(),
+            Some(_) if self.wrapper == *element => self.resize_main_pane(),
+            None if self.wrapper == *element => self.on_resize(),
+            _ => {}
+        }
+    }
+    fn on_client_caps_change(&mut self) {
         self.dirty = true;
         self.main_camera.dirty = true;
         self.preview_camera.dirty = true;
-        self.main_screen.schedule_canvas_size_sync();
-        self.tooltip_screen.schedule_canvas_size_sync();
-        self.preview_screen.schedule_canvas_size_sync();
-        self.camera_grip_screen.schedule_canvas_size_sync();
-        self.legend_screen.schedule_canvas_size_sync();
+
+        let client_caps = self.client_caps.borrow();
+        let color_scheme = self.base_config.resolve_color_scheme(&client_caps);
+        let contrast = self.base_config.resolve_contrast(&client_caps);
+        if color_scheme != self.color_scheme || contrast != self.contrast {
+            let config = Rc::new(self.base_config.resolve(&client_caps));
+            drop(client_caps);
+            self.set_config(config);
+            self.color_scheme = color_scheme;
+            self.contrast = contrast;
+        }
     }
     fn draw(&mut self, time_us: f64) -> usize {
+        if self.memory_trimmed || self.context_lost {
+            return 0;
+        }
+        if self.waiting_for_size {
+            if !Self::has_size(&self.wrapper) {
+                // without a resize observer, keep polling every frame
+                return if JsResizeObserver::is_supported() {
+                    0
+                } else {
+                    1
+                };
+            }
+            self.waiting_for_size = false;
+            self.size_warning = None;
+            self.on_resize();
+        }
         let mut actions: usize = 0;
         actions += self.check_legend_long_press(time_us);
+        if let Some(watermark) = &mut self.watermark {
+            if watermark.poll() {
+                self.dirty = true;
+                self.main_camera.dirty = true;
+            } else if watermark.is_loading() {
+                // there is no callback into the frame loop, so keep polling
+                actions += 1;
+            }
+        }
+        actions += self.flush_settled_hover(time_us);
         if !self.dirty {
             return actions;
         }
         actions += 1;
         // console_debug!("DRAWING");
         ANIMATED_NUMBERS_COUNT.store(0, Ordering::Relaxed);
+        let debug_started_ms = self.debug.as_ref().map(|_| Self::now_ms());
+
+        if self.compact_check_needed {
+            self.update_compact_layout();
+            self.compact_check_needed = false;
+        }
+        // the legend may change the height of the other screens, so it goes
+        // before they are synced
+        self.legend_screen.sync_canvas_size();
+        self.layout_legend(time_us);
 
         // cameras sync their own screens themselves
         self.tooltip_screen.sync_canvas_size();
         self.camera_grip_screen.sync_canvas_size();
         self.legend_screen.sync_canvas_size();
 
-        self.main_camera
-            .shoot(&mut self.content, &mut self.main_screen, time_us);
+        if let Some(debug) = &mut self.debug {
+            debug.main_frames.count(!self.main_camera.dirty);
+            debug.preview_frames.count(!self.preview_camera.dirty);
+        }
+        self.main_camera.shoot(
+            &mut self.content,
+            &mut self.main_screen,
+            self.watermark.as_ref(),
+            time_us,
+        );
 
+        let main_padding = self.main_camera.get_padding();
+        self.preview_camera.set_horizontal_padding(
+            &self.preview_screen,
+            main_padding[3],
+            main_padding[1],
+        );
         self.preview_camera
-            .shoot(&mut self.content, &mut self.preview_screen, time_us);
-        self.main_camera.draw_grip(
+            .shoot(&mut self.content, &mut self.preview_screen, None, time_us);
+        let window = self.main_camera.draw_grip(
             &mut self.camera_grip_screen,
             self.preview_camera.get_content_screen_area(time_us),
             self.zoomed_in,
-            self.camera_grip_x_offset.is_some(),
+            self.camera_grip_coord_offset.is_some(),
+            self.camera_grip_focused,
             time_us,
         );
+        if self.config.preview_mask {
+            self.draw_preview_mask(window, time_us);
+        }
 
         self.tooltip_screen.clear();
-        if self.camera_grip_x_offset.is_none() {
+        if self.camera_grip_coord_offset.is_none() && self.config.interactions.zoom {
             self.draw_selected_area(time_us);
         }
+        let playhead_position = self.get_playhead_position(time_us);
         self.tooltip.draw(
             &mut self.content,
             &mut self.tooltip_screen,
             self.main_camera.get_content_screen_area(time_us),
-            if self.client_caps.borrow().touch_device {
+            if self.loading.is_some() {
+                &None
+            } else if playhead_position.is_some() {
+                &playhead_position
+            } else if self.client_caps.borrow().touch_device || !self.config.interactions.hover {
                 &None
             } else {
                 &self.pointer_position
             },
             time_us,
         );
+        self.main_camera.set_highlighted(self.get_highlighted());
+        if self.config.preview_hover_marker {
+            self.draw_preview_hover_marker(time_us);
+        }
 
-        let content_screen_area = self.main_camera.get_content_screen_area(time_us);
-        self.legend.resize(
-            &self.legend_screen,
-            content_screen_area.left_cx(),
-            content_screen_area.right_cx(),
-            0.0,
-            self.legend_screen.canvas_height,
-        );
-        self.legend
-            .draw(&mut self.content, &mut self.legend_screen, time_us);
+        if !self.dropped.legend {
+            self.resize_legend(time_us);
+            self.legend.isolated = self.isolation.as_ref().map(|isolation| isolation.index);
+            self.legend
+                .draw(&mut self.content, &mut self.legend_screen, time_us);
+        }
 
-        if ANIMATED_NUMBERS_COUNT.load(Ordering::Relaxed) == 0 {
+        let mut spinning = false;
+        if let Some(loading) = &self.loading {
+            spinning = loading.draw(
+                &self.tooltip_screen,
+                &self.config,
+                true,
+                self.client_caps.borrow().reduced_motion,
+                time_us,
+            );
+        }
+
+        if let Some(debug_started_ms) = debug_started_ms {
+            self.draw_debug_overlay(debug_started_ms, time_us);
+        }
+
+        self.collect_events(time_us);
+
+        if ANIMATED_NUMBERS_COUNT.load(Ordering::Relaxed) == 0
+            && !self.main_camera.dirty
+            && !spinning
+        {
             self.dirty = false;
         }
         actions
     }
+    fn take_events(&mut self) -> Option<PendingChartEvents> {
+        if self.events.is_empty() {
+            return None;
+        }
+        Some(PendingChartEvents {
+            chart_id: self.container_selector.clone(),
+            target: self.wrapper.clone().into(),
+            sinks: self.config.get_event_sinks(),
+            events: std::mem::take(&mut self.events),
+            warnings: self.warnings.clone(),
+        })
+    }
+    fn set_data_set_visible(
+        &mut self,
+        name: &str,
+        visible: bool,
+        time_us: f64,
+    ) -> Result<(), String> {
+        let index = self
+            .content
+            .data_sets
+            .iter()
+            .position(|data_set| data_set.name == name)
+            .ok_or_else(|| format!("data set not found: {}", name))?;
+        let mut visibility = self.get_visibility();
+        visibility[index] = visible;
+        if !visibility.iter().any(|v| *v) {
+            return Err("at least one data set should stay visible".to_string());
+        }
+        if let Some(isolation) = &mut self.isolation {
+            if isolation.index == index && !visible {
+                self.isolation = None;
+            } else {
+                isolation.restore_visibility[index] = visible;
+            }
+        }
+        self.set_visibility(visibility.as_slice(), time_us);
+        Ok(())
+    }
+    fn isolate_data_set(&mut self, name: &str, time_us: f64) -> Result<(), String> {
+        let index = self
+            .content
+            .data_sets
+            .iter()
+            .position(|data_set| data_set.name == name)
+            .ok_or_else(|| format!("data set not found: {}", name))?;
+        self.toggle_isolation(index, time_us);
+        Ok(())
+    }
+    fn set_point_markers(&mut self, name: &str, raw_markers: &JsValue) -> Result<(), String> {
+        let coord_type = self.content.coord_type;
+        let data_set = self
+            .content
+            .data_sets
+            .iter_mut()
+            .find(|data_set| data_set.name == name)
+            .ok_or_else(|| format!("data set not found: {}", name))?;
+        let markers = parse_point_markers(raw_markers, coord_type, &|| "markers".to_string())?;
+        data_set.set_markers(markers)?;
+        self.dirty = true;
+        self.main_camera.dirty = true;
+        Ok(())
+    }
+    fn set_data_set_z_index(&mut self, name: &str, z_index: i32) -> Result<(), String> {
+        let data_set = self
+            .content
+            .data_sets
+            .iter_mut()
+            .find(|data_set| data_set.name == name)
+            .ok_or_else(|| format!("data set not found: {}", name))?;
+        if data_set.z_index != z_index {
+            // a repaint, the layout stays
+            data_set.z_index = z_index;
+            self.dirty = true;
+            self.main_camera.dirty = true;
+            self.preview_camera.dirty = true;
+        }
+        Ok(())
+    }
+    fn set_interactions(&mut self, raw_interactions: &JsValue) -> Result<(), String> {
+        let interactions = Interactions::from_raw(raw_interactions)?;
+        if interactions == self.config.interactions {
+            return Ok(());
+        }
+        self.base_config.interactions = interactions;
+        let mut config = (*self.config).clone();
+        config.interactions = interactions;
+        self.set_config(Rc::new(config));
+        // gestures in progress are dropped rather than finished
+        self.tooltip_pointer_down_position = None;
+        self.camera_grip_pointer_down_position = None;
+        self.camera_grip_coord_offset = None;
+        self.tooltip_pinch_coords = None;
+        if !interactions.hover {
+            self.tooltip.mouse_click_at = None;
+            self.legend_hovered = None;
+        }
+        self.dirty = true;
+        self.main_camera.dirty = true;
+        Ok(())
+    }
+    fn set_loading(&mut self, loading: bool, message: Option<String>) {
+        self.loading = loading.then_some(LoadingOverlay { message });
+        if loading {
+            self.wrapper.set_attribute("aria-busy", "true").unwrap();
+            self.tooltip.mouse_click_at = None;
+            self.legend_hovered = None;
+        } else {
+            self.wrapper.remove_attribute("aria-busy").unwrap();
+        }
+        self.dirty = true;
+        self.main_camera.dirty = true;
+    }
+    fn append_data(&mut self, raw_data_sets: &JsValue, time_us: f64) -> Result<bool, String> {
+        let (warnings, first_points) = self
+            .content
+            .append_data(raw_data_sets, self.config.non_finite_values)?;
+        self.warnings.extend(warnings);
+        if first_points {
+            return Ok(true);
+        }
+        self.dirty = true;
+        self.preview_camera
+            .update_by_content(&mut self.content, Some(time_us));
+        if self.config.follow.enabled && !self.user_detached {
+            self.follow_latest(time_us);
+        } else if self.zoomed_in {
+            // the window stays, its values may change
+            self.update_cameras(time_us);
+        } else {
+            self.main_camera
+                .update_by_content(&mut self.content, Some(time_us));
+        }
+        Ok(false)
+    }
+    fn take_rebuild(&mut self) -> Result<ChartRebuild, String> {
+        let raw_params = params_with_points(&self.raw_params, Some(&self.content))?;
+        let state = self.get_state();
+        // the window is restored as it is restored from a definition
+        let (from, to) = state.window.unwrap();
+        let window = js_sys::Array::of2(&from.into(), &to.into());
+        js_sys::Reflect::set(&raw_params, &"initialRange".into(), &window).unwrap();
+        js_sys::Reflect::delete_property(
+            raw_params.unchecked_ref::<js_sys::Object>(),
+            &"initialWindow".into(),
+        )
+        .unwrap();
+        Ok(ChartRebuild {
+            raw_params,
+            config: self.base_config.clone(),
+            state,
+            warnings: self.warnings.take(),
+        })
+    }
+    fn resume_follow(&mut self, time_us: f64) -> Result<(), String> {
+        if !self.config.follow.enabled {
+            return Err("follow is not enabled".to_string());
+        }
+        if self.user_detached {
+            self.user_detached = false;
+            self.events.push(ChartEvent::FollowChange(true));
+        }
+        self.follow_latest(time_us);
+        Ok(())
+    }
+    fn export_definition(&self) -> Result<Definition, String> {
+        Ok(Definition {
+            params: export_params(&self.raw_params, Some(&self.content))?,
+            config: self.base_config.raw.clone(),
+            state: self.get_state(),
+        })
+    }
+    fn restore_state(&mut self, state: &ChartState, time_us: f64) -> Result<(), String> {
+        if !state.hidden_data_sets.is_empty() {
+            let mut visibility = self.get_visibility();
+            for (hidden_index, name) in state.hidden_data_sets.iter().enumerate() {
+                // e.g. the data set was renamed since the export
+                match self
+                    .content
+                    .data_sets
+                    .iter()
+                    .position(|data_set| data_set.name == *name)
+                {
+                    Some(index) => visibility[index] = false,
+                    None => self.warnings.push(ChartWarning::new(
+                        "UNKNOWN_DATA_SET",
+                        format!("data set not found: {}", name),
+                        Some(format!("state.hiddenDataSets[{}]", hidden_index)),
+                    )),
+                }
+            }
+            if !visibility.iter().any(|v| *v) {
+                return Err("at least one data set should stay visible".to_string());
+            }
+            self.set_visibility(visibility.as_slice(), time_us);
+        }
+        if state.following == Some(false) {
+            self.detach_follow();
+        }
+        if let Some(preview_height) = state.preview_height.filter(|_| self.splitter.is_some()) {
+            // not laid out yet in a container without size
+            let panes_height = self.get_panes_height();
+            self.set_preview_height(if panes_height > 0.0 {
+                clamp_preview_height(preview_height, panes_height)
+            } else {
+                preview_height
+            });
+        }
+        Ok(())
+    }
+    fn set_debug(&mut self, enabled: bool) {
+        if enabled == self.debug.is_some() {
+            return;
+        }
+        self.debug = enabled.then(DebugOverlay::default);
+        // the outlines go away with the next frame of each screen
+        self.dirty = true;
+        self.main_camera.dirty = true;
+        self.preview_camera.dirty = true;
+    }
+    fn take_warnings(&mut self) -> Vec<ChartWarning> {
+        self.warnings.take()
+    }
+    fn returns_warnings(&self) -> bool {
+        self.base_config.return_warnings
+    }
+    fn push_warning(&mut self, warning: ChartWarning) {
+        self.warnings.push(warning);
+    }
+    fn has_idle_work(&self) -> bool {
+        self.ready
+            && self.config.idle_precompute
+            && self.hidden_since_us.is_none()
+            && self
+                .content
+                .data_sets
+                .iter()
+                .any(|data_set| data_set.has_missing_value_blocks())
+    }
+    fn do_idle_work(&mut self) {
+        if let Some(data_set) = self
+            .content
+            .data_sets
+            .iter_mut()
+            .find(|data_set| data_set.has_missing_value_blocks())
+        {
+            data_set.precompute_value_blocks(IDLE_BLOCKS_PER_STEP);
+        }
+    }
+    fn get_layout(&self) -> Result<JsValue, String> {
+        let (wrapper_x, wrapper_y) = js_element_global_position(&self.wrapper);
+        let screen_rect = |screen: &Screen| {
+            rect_to_js(
+                screen.x - wrapper_x,
+                screen.y - wrapper_y,
+                Some((screen.width, screen.height)),
+            )
+        };
+        let area = self.main_camera.get_last_screen_area();
+        let scale = area.screen_to_canvas_scale;
+        let layout = js_sys::Object::new();
+        let set = |key: &str, value: &JsValue| {
+            js_sys::Reflect::set(&layout, &key.into(), value).unwrap();
+        };
+        set(
+            "plot",
+            &rect_to_js(
+                area.screen_x + area.left_cx() / scale - wrapper_x,
+                area.screen_y + area.top_cy() / scale - wrapper_y,
+                Some((
+                    area.get_content_cwidth() / scale,
+                    area.get_content_cheight() / scale,
+                )),
+            ),
+        );
+        set("main", &screen_rect(&self.main_screen));
+        match &self.preview_wrapper {
+            Some(preview_wrapper) => {
+                let (x, y) = js_element_global_position(preview_wrapper);
+                let screen = &self.preview_screen;
+                set(
+                    "preview",
+                    &rect_to_js(
+                        screen.x - x,
+                        screen.y - y,
+                        Some((screen.width, screen.height)),
+                    ),
+                );
+            }
+            None => set("preview", &screen_rect(&self.preview_screen)),
+        }
+        set("legend", &screen_rect(&self.legend_screen));
+        // between the edges of the main screen and the plot: axis labels
+        let gutters = js_sys::Object::new();
+        for (key, padding) in ["top", "right", "bottom", "left"]
+            .iter()
+            .zip(area.canvas_padding.iter())
+        {
+            js_sys::Reflect::set(&gutters, &(*key).into(), &(padding / scale).into()).unwrap();
+        }
+        set("gutters", &gutters);
+        Ok(layout.into())
+    }
+    fn data_to_pixel(&self, raw_coord: &JsValue, value: f64) -> Result<JsValue, String> {
+        let coord = parse_js_values(
+            js_sys::Array::of1(raw_coord),
+            self.content.coord_type,
+            &|| "x".to_string(),
+        )?[0];
+        let (wrapper_x, wrapper_y) = js_element_global_position(&self.wrapper);
+        let area = self.main_camera.get_last_screen_area();
+        let x = area.coord_to_x(coord) - wrapper_x;
+        let y = area.value_to_y(value) - wrapper_y;
+        if !x.is_finite() || !y.is_finite() {
+            return Err(format!("not on the scale: {}", value));
+        }
+        Ok(rect_to_js(x, y, None))
+    }
+    fn pixel_to_data(&self, x: f64, y: f64) -> Result<JsValue, String> {
+        let (wrapper_x, wrapper_y) = js_element_global_position(&self.wrapper);
+        let area = self.main_camera.get_last_screen_area();
+        Ok(rect_to_js(
+            area.x_to_coord_unbounded(x + wrapper_x),
+            area.y_to_value_unbounded(y + wrapper_y),
+            None,
+        ))
+    }
+    fn set_visible_range(&mut self, raw_range: &JsValue, time_us: f64) -> Result<(), String> {
+        let range = parse_js_values(
+            raw_range
+                .clone()
+                .dyn_into::<js_sys::Array>()
+                .map_err(|_| "range is not an array".to_string())?,
+            self.content.coord_type,
+            &|| "range".to_string(),
+        )?;
+        let (from, to) = match range.as_slice() {
+            [from, to] if from < to => (*from, *to),
+            [_, _] => return Err("range: from should be less than to".to_string()),
+            _ => return Err("range is an array of length 2".to_string()),
+        };
+        let (coord_min, coord_max) = (self.content.global_coord_min, self.content.global_coord_max);
+        let (window, _) = InitialRange::Coords(from, to).resolve(
+            coord_min,
+            coord_max,
+            self.config.edge_behavior != EdgeBehavior::Clip,
+        );
+        self.zoom_by_coords(window.0, window.1, time_us);
+        self.zoomed_in = window.1 - window.0 < coord_max - coord_min;
+        Ok(())
+    }
+    fn redraw(&mut self) {
+        self.dirty = true;
+        self.main_camera.dirty = true;
+        self.preview_camera.dirty = true;
+    }
+    fn set_hover_x(
+        &mut self,
+        raw_coord: &JsValue,
+        duration_ms: Option<f64>,
+        time_us: f64,
+    ) -> Result<(), String> {
+        self.dirty = true;
+        if raw_coord.is_null() || raw_coord.is_undefined() {
+            self.playhead = None;
+            return Ok(());
+        }
+        let coord = parse_js_values(
+            js_sys::Array::of1(raw_coord),
+            self.content.coord_type,
+            &|| "x".to_string(),
+        )?[0]
+            .clamp(self.content.global_coord_min, self.content.global_coord_max);
+        let duration_us = duration_ms.unwrap_or(0.0).max(0.0) * 1000.0;
+        match &mut self.playhead {
+            Some(playhead) if duration_us > 0.0 => {
+                let mut tween = AnimatedNumber::custom(
+                    playhead.get_value(time_us),
+                    duration_us * 0.5,
+                    duration_us * 0.5,
+                );
+                tween.set_value(coord, Some(time_us));
+                *playhead = tween;
+            }
+            _ => self.playhead = Some(AnimatedNumber::new(coord)),
+        }
+        Ok(())
+    }
+    fn get_link_group(&self) -> Option<&str> {
+        self.config.link_group.as_deref()
+    }
+    fn set_link_group(&mut self, link_group: Option<String>) {
+        if link_group == self.config.link_group {
+            return;
+        }
+        self.base_config.link_group = link_group.clone();
+        let mut config = (*self.config).clone();
+        config.link_group = link_group;
+        self.set_config(Rc::new(config));
+        self.dirty = true;
+    }
+    fn set_align_y(&mut self, align_y: bool) {
+        if align_y == self.config.align_y {
+            return;
+        }
+        self.base_config.align_y = align_y;
+        let mut config = (*self.config).clone();
+        config.align_y = align_y;
+        self.set_config(Rc::new(config));
+        self.dirty = true;
+    }
+    fn get_y_alignment(&self) -> Option<YAlignment> {
+        if !self.config.align_y {
+            return None;
+        }
+        Some(YAlignment {
+            link_group: self.config.link_group.clone()?,
+            domain: (self.content.global_value_min, self.content.global_value_max),
+            log_scale: self.main_camera.is_log_scale(),
+        })
+    }
+    fn set_shared_y_domain(&mut self, shared_y_domain: SharedYDomain, time_us: f64) -> bool {
+        if shared_y_domain == self.shared_y_domain {
+            return false;
+        }
+        if shared_y_domain == SharedYDomain::ScaleMismatch {
+            self.warnings.push(ChartWarning::new(
+                "Y_SCALE_MISMATCH",
+                format!(
+                    "the {} scale of the chart differs from the one of link group '{}', \
+                     its y axis is not aligned",
+                    if self.main_camera.is_log_scale() {
+                        "log"
+                    } else {
+                        "linear"
+                    },
+                    self.config.link_group.as_deref().unwrap_or_default()
+                ),
+                Some("alignY".to_string()),
+            ));
+        }
+        self.shared_y_domain = shared_y_domain;
+        let value_domain = match shared_y_domain {
+            SharedYDomain::Shared(min, max) if min < max => Some((min, max)),
+            _ => None,
+        };
+        let value_origin = value_domain.map_or(self.content.global_value_min, |(min, _)| min);
+        self.main_camera
+            .set_value_domain(value_domain, value_origin);
+        // animates from the current domain
+        self.update_cameras(time_us);
+        self.dirty = true;
+        true
+    }
+    fn get_shared_hover(&self) -> Option<(String, f64)> {
+        if !self.config.shared_tooltip {
+            return None;
+        }
+        Some((
+            self.config.link_group.clone()?,
+            self.tooltip.hover.as_ref()?.coord,
+        ))
+    }
+    fn get_tooltip_section(&self, coord: f64) -> Option<TooltipSection> {
+        let points: Vec<(&DataSet, f64)> = self
+            .content
+            .data_sets
+            .iter()
+            .filter(|data_set| {
+                data_set.alpha.get_end_value() != 0.0
+                    && data_set.hoverable
+                    && data_set.show_in_tooltip
+            })
+            .filter_map(|data_set| {
+                let index = data_set.get_nearest_index(coord)?;
+                Some((data_set, data_set.data_points[index].value))
+            })
+            .collect();
+        if points.is_empty() {
+            return None;
+        }
+        // there is no nearest series in a section to keep
+        let (rows, collapsed) = select_tooltip_rows(
+            &points.iter().map(|point| point.1).collect::<Vec<f64>>(),
+            self.config.tooltip_sort,
+            self.config.tooltip_hide_zero,
+            self.config.tooltip_max_entries,
+            None,
+        );
+        if rows.is_empty() {
+            return None;
+        }
+        let formatted_values = self.content.value_verbose_format.format_values(
+            rows.iter().map(|index| points[*index]),
+            |point| point.1,
+            self.content.global_value_min,
+            self.content.global_value_max,
+        );
+        Some(TooltipSection {
+            title: self
+                .content
+                .name
+                .clone()
+                .unwrap_or_else(|| self.container_selector.clone()),
+            lines: rows
+                .iter()
+                .map(|index| points[*index])
+                .zip(formatted_values)
+                .map(|((data_set, value), formatted_value)| TooltipSectionLine {
+                    name: data_set.name.clone(),
+                    value,
+                    formatted_value: data_set.with_unit(formatted_value),
+                    color: data_set.to_css_color(1.0),
+                })
+                .collect(),
+            more_line: format_more_line(
+                &self.config,
+                &self.content.value_verbose_format,
+                collapsed.iter().map(|index| points[*index].1),
+                self.content.global_value_min,
+                self.content.global_value_max,
+            ),
+        })
+    }
+    fn set_shared_sections(&mut self, sections: Vec<TooltipSection>) -> bool {
+        if self.tooltip.shared_sections == sections {
+            return false;
+        }
+        self.tooltip.shared_sections = sections;
+        self.dirty = true;
+        true
+    }
+    fn get_wrapper(&self) -> &web_sys::Element {
+        &self.wrapper
+    }
+    fn get_preview_wrapper(&self) -> Option<&web_sys::Element> {
+        self.preview_wrapper.as_ref()
+    }
+    fn get_on_auto_destroy(&self) -> Option<js_sys::Function> {
+        self.config.on_auto_destroy.clone()
+    }
+    fn on_visibility_change(
+        &mut self,
+        intersecting: Option<bool>,
+        document_hidden: bool,
+        time_us: f64,
+    ) {
+        if let Some(intersecting) = intersecting {
+            self.intersecting = intersecting;
+        }
+        if self.intersecting && !document_hidden {
+            self.hidden_since_us = None;
+            if self.memory_trimmed {
+                self.memory_trimmed = false;
+                self.on_resize();
+            }
+        } else if self.hidden_since_us.is_none() {
+            self.hidden_since_us = Some(time_us);
+        }
+    }
+    fn get_memory_trim_time_us(&self) -> Option<f64> {
+        if self.memory_trimmed {
+            return None;
+        }
+        get_memory_trim_time_us(self.hidden_since_us, self.config.memory_trim_delay)
+    }
+    fn trim_memory(&mut self) {
+        if self.hidden_since_us.is_none() || self.memory_trimmed {
+            return;
+        }
+        self.memory_trimmed = true;
+        self.main_screen.release_canvas();
+        self.tooltip_screen.release_canvas();
+        self.preview_screen.release_canvas();
+        self.camera_grip_screen.release_canvas();
+        self.legend_screen.release_canvas();
+        self.legend.hatch_patterns.clear();
+        self.tooltip.hatch_patterns.clear();
+    }
+    fn on_context_change(&mut self, lost: bool) {
+        self.context_lost = lost;
+        if !lost {
+            // a restored context is blank with its state reset
+            self.legend.hatch_patterns.clear();
+            self.tooltip.hatch_patterns.clear();
+            self.on_resize();
+        }
+    }
 }
 
 // https://chartio.com/learn/charts/line-chart-complete-guide/
@@ -5,11 +5,23 @@
  *
  * Copyright (C) 2023, Nikita Almakov
  */
-use crate::controls::{MouseControls, TouchControls, WatchControls};
-use crate::events::JsEventListener;
+use crate::align_y::{get_shared_y_domains, YAlignment};
+use crate::controls::{ControlEvent, MouseControls, TouchControls, WatchControls};
+use crate::debug::console_warn;
+use crate::definition::Definition;
+use crate::empty_chart::EmptyChart;
+use crate::events::{
+    cancel_idle, request_idle, run_deferred_calls, EventDispatcher, IdleHandle, JsClosure,
+    JsEventListener, JsIntersectionObserver, JsResizeObserver, JsTimeout, PendingChartEvents,
+};
 use crate::main_chart::{DrawChart, MainChart};
-use crate::params::{ChartConfig, ChartParams, ClientCaps};
+use crate::params::{
+    ChartConfig, ChartParams, ClientCaps, ContainerReuse, Direction, MEDIA_FEATURE_QUERIES,
+};
+use crate::resources::resource_counts_to_js;
 use crate::scale::{LinearScale, LogScale, Scale};
+use crate::strings::Strings;
+use crate::warnings::{warnings_to_js, ChartWarning};
 use js_sys::Reflect;
 use std::cell::RefCell;
 use std::marker::PhantomPinned;
@@ -18,6 +30,43 @@ use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
+const RESIZE_SETTLE_MS: i32 = 150;
+/// ms of idle work done per idle callback.
+const IDLE_CHUNK_MS: f64 = 3.0;
+const CONTENT_WRAPPER_STYLE: &str = "width: 100%; height: 100%; position: relative";
+
+/// Why createMain or createFromDefinition rejected: a message, or an object
+/// callers can tell apart.
+#[derive(Debug, PartialEq)]
+pub enum CreateError {
+    Invalid(String),
+    /// The container holds the chart of this id, see `ContainerReuse`.
+    ContainerAlreadyUsed(String),
+}
+impl From<String> for CreateError {
+    fn from(error: String) -> Self {
+        Self::Invalid(error)
+    }
+}
+impl From<CreateError> for JsValue {
+    /// The message as is, {code, message, path, chartId} otherwise.
+    fn from(error: CreateError) -> Self {
+        match error {
+            CreateError::Invalid(error) => error.into(),
+            CreateError::ContainerAlreadyUsed(chart_id) => {
+                let error = ChartWarning::new(
+                    "CONTAINER_ALREADY_USED",
+                    format!("the container holds chart {}", chart_id),
+                    None,
+                )
+                .to_js();
+                Reflect::set(&error, &"chartId".into(), &chart_id.into()).unwrap();
+                error
+            }
+        }
+    }
+}
+
 pub struct ChartManager {
     global_pointer_move: Option<JsEventListener>,
     global_pointer_out: Option<JsEventListener>,
@@ -25,10 +74,39 @@ pub struct ChartManager {
     global_pointer_up: Option<JsEventListener>,
     global_window_resize: Option<JsEventListener>,
     global_orintation_change: Option<JsEventListener>,
-    global_request_animation_frame_closure: Option<Closure<dyn Fn(JsValue)>>,
-    animation_frame_requested: bool,
+    global_key_down: Option<JsEventListener>,
+    global_focus_in: Option<JsEventListener>,
+    global_focus_out: Option<JsEventListener>,
+    global_context_lost: Option<JsEventListener>,
+    global_context_restored: Option<JsEventListener>,
+    global_media_changes: Vec<JsEventListener>,
+    global_resize_observer: Option<JsResizeObserver>,
+    global_intersection_observer: Option<JsIntersectionObserver>,
+    global_visibility_change: Option<JsEventListener>,
+    global_memory_trim_closure: Option<JsClosure<dyn Fn()>>,
+    memory_trim_timeout: Option<i32>,
+    global_idle_closure: Option<JsClosure<dyn Fn()>>,
+    idle_handle: Option<IdleHandle>,
+    global_resize_settle_closure: Option<JsClosure<dyn Fn()>>,
+    resize_settle_timeout: Option<i32>,
+    /// Whether resizes came after the leading one of the current storm.
+    resize_pending: bool,
+    /// Wrappers resized since the charts were last resized, None when all
+    /// of them are, e.g. with the window.
+    resized_elements: Option<Vec<web_sys::Element>>,
+    global_request_animation_frame_closure: Option<JsClosure<dyn Fn(JsValue)>>,
+    /// Handle of the requested frame.
+    animation_frame: Option<i32>,
+    event_dispatcher: EventDispatcher,
+    /// Requests a frame when held back events are due.
+    event_flush_timeout: Option<JsTimeout>,
+    listeners_installed: bool,
     charts: Rc<RefCell<Vec<Box<dyn DrawChart>>>>,
     chart_ids: Vec<String>,
+    /// The containers passed to `create_main`, by chart.
+    containers: Vec<web_sys::Element>,
+    /// Numbers wrapper ids of deterministic charts.
+    wrapper_counter: usize,
     control_watcher: Rc<RefCell<Box<dyn WatchControls>>>,
     touch_device: bool,
     client_caps: Rc<RefCell<ClientCaps>>,
@@ -45,10 +123,32 @@ impl ChartManager {
             global_pointer_down: None,
             global_window_resize: None,
             global_orintation_change: None,
+            global_key_down: None,
+            global_focus_in: None,
+            global_focus_out: None,
+            global_context_lost: None,
+            global_context_restored: None,
+            global_media_changes: Vec::new(),
+            global_resize_observer: None,
+            global_intersection_observer: None,
+            global_visibility_change: None,
+            global_memory_trim_closure: None,
+            memory_trim_timeout: None,
+            global_idle_closure: None,
+            idle_handle: None,
+            global_resize_settle_closure: None,
+            resize_settle_timeout: None,
+            resize_pending: false,
+            resized_elements: Some(Vec::new()),
             global_request_animation_frame_closure: None,
-            animation_frame_requested: false,
+            animation_frame: None,
+            event_dispatcher: EventDispatcher::default(),
+            event_flush_timeout: None,
+            listeners_installed: false,
             charts: Rc::new(RefCell::new(Vec::new())),
             chart_ids: Vec::new(),
+            containers: Vec::new(),
+            wrapper_counter: 0,
             control_watcher: Rc::new(RefCell::new(if touch_device {
                 Box::new(TouchControls::new())
             } else {
@@ -63,8 +163,8 @@ impl ChartManager {
         mut self: Pin<&mut Self>,
         raw_params: JsValue,
         raw_config: JsValue,
-    ) -> Result<String, String> {
-        let chart_config =
+    ) -> Result<String, CreateError> {
+        let mut chart_config =
             ChartConfig::from_raw(&raw_config).map_err(|e| format!("config: {}", e.as_str()))?;
         let mut chart_params = ChartParams::from(&raw_params, &chart_config)
             .map_err(|e| format!("params: {}", e.as_str()))?;
@@ -73,13 +173,127 @@ impl ChartManager {
             .content
             .sort_data_sets(&chart_config.sort_data_sets_by);
 
-        let content_wrapper_selector =
-            Self::inject_content_wrapper(chart_params.selector.as_str())?;
-        unsafe { self.as_mut().get_unchecked_mut() }
+        // e.g. a framework effect which runs again before the cleanup
+        let container = web_sys::window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .query_selector(chart_params.selector.as_str())
+            .unwrap()
+            .ok_or_else(|| "container not found".to_string())?;
+        if let Some(index) = self
+            .containers
+            .iter()
+            .position(|c| c.is_same_node(Some(container.as_ref())))
+        {
+            match chart_config.container_reuse {
+                ContainerReuse::Error => {
+                    let chart_id = self.chart_ids[index].clone();
+                    return Err(CreateError::ContainerAlreadyUsed(chart_id));
+                }
+                ContainerReuse::Replace => {
+                    unsafe { self.as_mut().get_unchecked_mut() }.remove_chart(index);
+                }
+            }
+        }
+
+        let wrapper_number = if chart_config.deterministic {
+            let chart_manager = unsafe { self.as_mut().get_unchecked_mut() };
+            chart_manager.wrapper_counter += 1;
+            Some(chart_manager.wrapper_counter)
+        } else {
+            None
+        };
+        let (content_wrapper_selector, direction) =
+            Self::inject_content_wrapper(chart_params.selector.as_str(), wrapper_number)?;
+        chart_config.direction.get_or_insert(direction);
+        let chart_manager = unsafe { self.as_mut().get_unchecked_mut() };
+        chart_manager
             .chart_ids
             .push(content_wrapper_selector.clone());
+        chart_manager.containers.push(container);
         chart_params.selector = content_wrapper_selector.clone();
+        // an empty chart has no preview
+        if let Some(preview_container) = chart_config
+            .preview_container
+            .as_ref()
+            .filter(|_| !chart_params.content.data_sets.is_empty())
+        {
+            let preview_wrapper_number = wrapper_number.map(|_| {
+                let chart_manager = unsafe { self.as_mut().get_unchecked_mut() };
+                chart_manager.wrapper_counter += 1;
+                chart_manager.wrapper_counter
+            });
+            match Self::inject_content_wrapper(preview_container.as_str(), preview_wrapper_number) {
+                Ok((preview_wrapper_selector, _)) => {
+                    chart_params.preview_selector = Some(preview_wrapper_selector)
+                }
+                Err(_) => {
+                    let chart_manager = unsafe { self.as_mut().get_unchecked_mut() };
+                    chart_manager.chart_ids.pop();
+                    chart_manager.containers.pop();
+                    Self::remove_element(content_wrapper_selector.as_str());
+                    return Err("preview container not found".to_string().into());
+                }
+            }
+        }
+
+        let preview_selector = chart_params.preview_selector.clone();
+        let chart =
+            match Self::create_chart(chart_params, chart_config, Rc::clone(&self.client_caps)) {
+                Ok(chart) => chart,
+                Err(error) => {
+                    // the wrapper is left without a chart otherwise
+                    let chart_manager = unsafe { self.as_mut().get_unchecked_mut() };
+                    chart_manager.chart_ids.pop();
+                    chart_manager.containers.pop();
+                    Self::remove_element(content_wrapper_selector.as_str());
+                    if let Some(preview_selector) = &preview_selector {
+                        Self::remove_element(preview_selector.as_str());
+                    }
+                    return Err(error.into());
+                }
+            };
+        self.charts.borrow_mut().push(chart);
+
+        let chart_manager = unsafe { self.as_mut().get_unchecked_mut() };
+        chart_manager.install_listeners();
+        chart_manager.request_animation_frame();
+        let content_wrapper = web_sys::window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .query_selector(content_wrapper_selector.as_str())
+            .unwrap()
+            .unwrap();
+        if let Some(resize_observer) = &self.global_resize_observer {
+            resize_observer.observe(&content_wrapper);
+            if let Some(preview_wrapper) =
+                self.charts.borrow().last().unwrap().get_preview_wrapper()
+            {
+                resize_observer.observe(preview_wrapper);
+            }
+        }
+        if let Some(intersection_observer) = &self.global_intersection_observer {
+            intersection_observer.observe(&content_wrapper);
+        }
+        Ok(content_wrapper_selector)
+    }
 
+    /// Picks the log scale when it spreads the data sets out better than the
+    /// linear one; content without data sets gets a placeholder.
+    fn create_chart(
+        chart_params: ChartParams,
+        chart_config: ChartConfig,
+        client_caps: Rc<RefCell<ClientCaps>>,
+    ) -> Result<Box<dyn DrawChart>, String> {
+        if chart_params.content.data_sets.is_empty() {
+            return Ok(Box::new(EmptyChart::new(
+                chart_params,
+                chart_config,
+                client_caps,
+            )?));
+        }
         let log_main_scale = LogScale::new(&chart_params.content);
         let linear_main_scale = LinearScale::new(&chart_params.content);
         let mut min_log_covered_square: f64 = f64::MAX;
@@ -99,67 +313,708 @@ impl ChartManager {
             > min_linear_covered_square * chart_config.auto_log_scale_threshold
         {
             let preview_scale = LogScale::new(&chart_params.content);
-            self.charts.borrow_mut().push(Box::new(MainChart::new(
+            Ok(Box::new(MainChart::new(
                 chart_params,
                 chart_config,
-                Rc::clone(&self.client_caps),
+                client_caps,
                 log_main_scale,
                 preview_scale,
-            )?));
+            )?))
         } else {
             let preview_scale = LinearScale::new(&chart_params.content);
-            self.charts.borrow_mut().push(Box::new(MainChart::new(
+            Ok(Box::new(MainChart::new(
                 chart_params,
                 chart_config,
-                Rc::clone(&self.client_caps),
+                client_caps,
                 linear_main_scale,
                 preview_scale,
-            )?));
-        };
+            )?))
+        }
+    }
 
-        unsafe { self.as_mut().get_unchecked_mut() }.ensure_global_listeners_are_set_up();
-        Ok(content_wrapper_selector)
+    /// Returns the built-in strings, which `strings` of the config overrides
+    /// by key.
+    pub fn default_strings() -> JsValue {
+        Strings::defaults_to_js()
     }
 
     pub fn destroy_main(mut self: Pin<&mut Self>, chart_id: JsValue) -> Result<(), String> {
-        let chart_id = chart_id
-            .as_string()
-            .ok_or_else(|| "not a string".to_string())?;
-        let index = self
-            .chart_ids
-            .iter()
-            .position(|id| id == chart_id.as_str())
-            .ok_or_else(|| "chart not found by id".to_string())?;
+        let index = self.get_chart_index(&chart_id)?;
+        let chart_id = self.chart_ids[index].clone();
         let document = web_sys::window().unwrap().document().unwrap();
         let chart_wrapper = document
             .query_selector(chart_id.as_str())
             .unwrap()
             .ok_or_else(|| "chart wrapper not found in dom".to_string())?;
-        chart_wrapper.remove();
+        let preview_wrapper = self.charts.borrow()[index].get_preview_wrapper().cloned();
+        // do not leave focus on a detached element
+        if let Some(active_element) = document.active_element() {
+            if chart_wrapper.contains(Some(&active_element))
+                || preview_wrapper
+                    .as_ref()
+                    .is_some_and(|wrapper| wrapper.contains(Some(&active_element)))
+            {
+                if let Some(active_element) = active_element.dyn_ref::<web_sys::HtmlElement>() {
+                    active_element.blur().unwrap();
+                }
+            }
+        }
+        unsafe { self.as_mut().get_unchecked_mut() }.remove_chart(index);
+        Ok(())
+    }
+
+    pub fn set_data_set_visible(
+        mut self: Pin<&mut Self>,
+        chart_id: JsValue,
+        name: JsValue,
+        visible: JsValue,
+    ) -> Result<(), String> {
+        let index = self.get_chart_index(&chart_id)?;
+        let name = name
+            .as_string()
+            .ok_or_else(|| "name is not a string".to_string())?;
+        let visible = visible
+            .as_bool()
+            .ok_or_else(|| "visible is not a bool".to_string())?;
+        self.charts.borrow_mut()[index].set_data_set_visible(
+            name.as_str(),
+            visible,
+            Self::get_time_us(),
+        )?;
+        unsafe { self.as_mut().get_unchecked_mut() }.request_animation_frame();
+        Ok(())
+    }
+
+    /// Shows the data set alone; for the isolated one restores the
+    /// visibility from before.
+    pub fn isolate_data_set(
+        mut self: Pin<&mut Self>,
+        chart_id: JsValue,
+        name: JsValue,
+    ) -> Result<(), String> {
+        let index = self.get_chart_index(&chart_id)?;
+        let name = name
+            .as_string()
+            .ok_or_else(|| "name is not a string".to_string())?;
+        self.charts.borrow_mut()[index].isolate_data_set(name.as_str(), Self::get_time_us())?;
+        unsafe { self.as_mut().get_unchecked_mut() }.request_animation_frame();
+        Ok(())
+    }
+
+    /// Replaces the point markers of a data set without touching its data.
+    pub fn set_point_markers(
+        mut self: Pin<&mut Self>,
+        chart_id: JsValue,
+        name: JsValue,
+        markers: JsValue,
+    ) -> Result<(), String> {
+        let index = self.get_chart_index(&chart_id)?;
+        let name = name
+            .as_string()
+            .ok_or_else(|| "name is not a string".to_string())?;
+        self.charts.borrow_mut()[index].set_point_markers(name.as_str(), &markers)?;
+        unsafe { self.as_mut().get_unchecked_mut() }.request_animation_frame();
+        Ok(())
+    }
+
+    /// Changes which interactions the chart reacts to.
+    pub fn set_interactions(
+        mut self: Pin<&mut Self>,
+        chart_id: JsValue,
+        interactions: JsValue,
+    ) -> Result<(), String> {
+        let index = self.get_chart_index(&chart_id)?;
+        self.charts.borrow_mut()[index].set_interactions(&interactions)?;
+        unsafe { self.as_mut().get_unchecked_mut() }.request_animation_frame();
+        Ok(())
+    }
+
+    pub fn set_loading(
+        mut self: Pin<&mut Self>,
+        chart_id: JsValue,
+        loading: bool,
+        message: Option<String>,
+    ) -> Result<(), String> {
+        let index = self.get_chart_index(&chart_id)?;
+        self.charts.borrow_mut()[index].set_loading(loading, message);
+        unsafe { self.as_mut().get_unchecked_mut() }.request_animation_frame();
+        Ok(())
+    }
 
+    pub fn set_data_set_z_index(
+        mut self: Pin<&mut Self>,
+        chart_id: JsValue,
+        name: JsValue,
+        z_index: i32,
+    ) -> Result<(), String> {
+        let index = self.get_chart_index(&chart_id)?;
+        let name = name
+            .as_string()
+            .ok_or_else(|| "name is not a string".to_string())?;
+        self.charts.borrow_mut()[index].set_data_set_z_index(name.as_str(), z_index)?;
+        unsafe { self.as_mut().get_unchecked_mut() }.request_animation_frame();
+        Ok(())
+    }
+    pub fn append_data(
+        mut self: Pin<&mut Self>,
+        chart_id: JsValue,
+        data_sets: JsValue,
+    ) -> Result<(), String> {
+        let index = self.get_chart_index(&chart_id)?;
+        let time_us = Self::get_time_us();
+        let first_points = self.charts.borrow_mut()[index].append_data(&data_sets, time_us)?;
         let chart_manager = unsafe { self.as_mut().get_unchecked_mut() };
-        chart_manager.chart_ids.remove(index);
-        let charts = &mut chart_manager.charts;
-        charts.borrow_mut().remove(index);
-        if charts.borrow().len() == 0 {
-            unsafe { self.as_mut().get_unchecked_mut() }.uninstall_listeners();
+        if first_points {
+            chart_manager.rebuild_chart(index, time_us)?;
         }
+        chart_manager.request_animation_frame();
         Ok(())
     }
+    /// Creates the chart anew in place, keeping its id, wrappers and state:
+    /// data sets which got their first points change its scales and layout,
+    /// an empty chart becomes a real one. The chart is destroyed when the
+    /// new one fails to be created.
+    fn rebuild_chart(&mut self, index: usize, time_us: f64) -> Result<(), String> {
+        let rebuild = self.charts.borrow_mut()[index].take_rebuild()?;
+        let config = rebuild.config;
+        let mut chart_params = ChartParams::from(&rebuild.raw_params, &config)
+            .map_err(|e| format!("params: {}", e.as_str()))?;
+        chart_params
+            .content
+            .sort_data_sets(&config.sort_data_sets_by);
+        chart_params.selector = self.chart_ids[index].clone();
+        let mut warnings = rebuild.warnings;
+        warnings.append(&mut chart_params.warnings);
+        chart_params.warnings = warnings;
+
+        // the old chart leaves its canvases and listeners on the wrappers
+        let old_wrapper = self.charts.borrow()[index].get_wrapper().clone();
+        let old_preview_wrapper = self.charts.borrow()[index].get_preview_wrapper().cloned();
+        let mut new_preview_wrapper = None;
+        if let Some(old_preview_wrapper) = &old_preview_wrapper {
+            let preview_wrapper = Self::clone_wrapper(old_preview_wrapper);
+            chart_params.preview_selector = Some(format!("#{}", preview_wrapper.id()));
+            new_preview_wrapper = Some(preview_wrapper);
+        } else if let Some(preview_container) = &config.preview_container {
+            let wrapper_number = config.deterministic.then(|| {
+                self.wrapper_counter += 1;
+                self.wrapper_counter
+            });
+            let (preview_selector, _) =
+                Self::inject_content_wrapper(preview_container.as_str(), wrapper_number)
+                    .map_err(|_| "preview container not found".to_string())?;
+            chart_params.preview_selector = Some(preview_selector);
+        }
+        let wrapper = Self::clone_wrapper(&old_wrapper);
+        if let Some(resize_observer) = &self.global_resize_observer {
+            resize_observer.unobserve(&old_wrapper);
+            resize_observer.observe(&wrapper);
+            if let Some(old_preview_wrapper) = &old_preview_wrapper {
+                resize_observer.unobserve(old_preview_wrapper);
+            }
+        }
+        if let Some(intersection_observer) = &self.global_intersection_observer {
+            intersection_observer.unobserve(&old_wrapper);
+            intersection_observer.observe(&wrapper);
+        }
+        old_wrapper.replace_with_with_node_1(&wrapper).unwrap();
+        if let (Some(old_preview_wrapper), Some(preview_wrapper)) =
+            (&old_preview_wrapper, &new_preview_wrapper)
+        {
+            old_preview_wrapper
+                .replace_with_with_node_1(preview_wrapper)
+                .unwrap();
+        }
+
+        let preview_selector = chart_params.preview_selector.clone();
+        let chart = match Self::create_chart(chart_params, config, Rc::clone(&self.client_caps)) {
+            Ok(chart) => chart,
+            Err(error) => {
+                // the new wrappers have no chart to remove them
+                if let Some(preview_selector) = &preview_selector {
+                    Self::remove_element(preview_selector.as_str());
+                }
+                if let Some(resize_observer) = &self.global_resize_observer {
+                    resize_observer.unobserve(&wrapper);
+                }
+                if let Some(intersection_observer) = &self.global_intersection_observer {
+                    intersection_observer.unobserve(&wrapper);
+                }
+                wrapper.remove();
+                self.remove_chart(index);
+                return Err(error);
+            }
+        };
+        if let Some(resize_observer) = &self.global_resize_observer {
+            if let Some(preview_wrapper) = chart.get_preview_wrapper() {
+                resize_observer.observe(preview_wrapper);
+            }
+        }
+        drop(std::mem::replace(
+            &mut self.charts.borrow_mut()[index],
+            chart,
+        ));
+        self.charts.borrow_mut()[index].restore_state(&rebuild.state, time_us)
+    }
+    /// A fresh wrapper with the id of the given one, see
+    /// `inject_content_wrapper`.
+    fn clone_wrapper(wrapper: &web_sys::Element) -> web_sys::Element {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let clone = document.create_element("div").unwrap();
+        clone.set_attribute("id", wrapper.id().as_str()).unwrap();
+        clone.set_attribute("style", CONTENT_WRAPPER_STYLE).unwrap();
+        clone
+    }
+    pub fn resume_follow(mut self: Pin<&mut Self>, chart_id: JsValue) -> Result<(), String> {
+        let index = self.get_chart_index(&chart_id)?;
+        self.charts.borrow_mut()[index].resume_follow(Self::get_time_us())?;
+        unsafe { self.as_mut().get_unchecked_mut() }.request_animation_frame();
+        Ok(())
+    }
+    /// Serializes the chart to a JSON definition: params with the current
+    /// data, the config as passed (without functions) and the state, i.e. the
+    /// window, hidden data sets and whether it follows appended data.
+    pub fn export_definition(&self, chart_id: JsValue) -> Result<String, String> {
+        let index = self.get_chart_index(&chart_id)?;
+        let definition = self.charts.borrow()[index].export_definition()?;
+        Ok(definition.to_json())
+    }
+    /// Creates a chart in the container from a definition made by
+    /// `export_definition`, validated as `create_main` does.
+    pub fn create_from_definition(
+        mut self: Pin<&mut Self>,
+        selector: JsValue,
+        definition: String,
+    ) -> Result<String, CreateError> {
+        let selector = selector
+            .as_string()
+            .ok_or_else(|| "selector is not a string".to_string())?;
+        let definition = Definition::parse(definition.as_str())?;
+        let raw_params = definition.params;
+        let raw_config = definition.config;
+        Reflect::set(&raw_params, &"selector".into(), &selector.into()).unwrap();
+        if let Some((from, to)) = definition.state.window {
+            let window = js_sys::Array::of2(&from.into(), &to.into());
+            Reflect::set(&raw_params, &"initialRange".into(), &window).unwrap();
+        }
+        let chart_id = self.as_mut().create_main(raw_params, raw_config)?;
+        let index = self.get_chart_index(&JsValue::from_str(chart_id.as_str()))?;
+        let restored =
+            self.charts.borrow_mut()[index].restore_state(&definition.state, Self::get_time_us());
+        if let Err(error) = restored {
+            self.destroy_main(chart_id.into())?;
+            return Err(error.into());
+        }
+        Ok(chart_id)
+    }
+    /// The chart id, or {chartId, warnings} when the chart has
+    /// returnWarnings; called right after the chart is created.
+    pub fn get_creation_result(&self, chart_id: String) -> Result<JsValue, String> {
+        let index = self.get_chart_index(&JsValue::from_str(chart_id.as_str()))?;
+        let mut charts = self.charts.borrow_mut();
+        if !charts[index].returns_warnings() {
+            return Ok(chart_id.into());
+        }
+        let warnings = charts[index].take_warnings();
+        let result = js_sys::Object::new();
+        Reflect::set(&result, &"chartId".into(), &chart_id.into()).unwrap();
+        Reflect::set(&result, &"warnings".into(), &warnings_to_js(&warnings)).unwrap();
+        Ok(result.into())
+    }
+    /// Warnings of the chart since it was created (or returned by it, with
+    /// returnWarnings) or the last call.
+    pub fn get_warnings(&self, chart_id: JsValue) -> Result<JsValue, String> {
+        let index = self.get_chart_index(&chart_id)?;
+        let warnings = self.charts.borrow_mut()[index].take_warnings();
+        Ok(warnings_to_js(warnings.as_slice()))
+    }
+    /// Counts the browser resources held by the charts and the manager, see
+    /// `resource_counts_to_js`.
+    pub fn get_resource_counts(&self) -> Result<JsValue, String> {
+        resource_counts_to_js(self.charts.borrow().len())
+    }
+    pub fn set_debug(
+        mut self: Pin<&mut Self>,
+        chart_id: JsValue,
+        enabled: bool,
+    ) -> Result<(), String> {
+        let index = self.get_chart_index(&chart_id)?;
+        self.charts.borrow_mut()[index].set_debug(enabled);
+        unsafe { self.as_mut().get_unchecked_mut() }.request_animation_frame();
+        Ok(())
+    }
+    pub fn set_link_group(
+        mut self: Pin<&mut Self>,
+        chart_id: JsValue,
+        link_group: Option<String>,
+    ) -> Result<(), String> {
+        let index = self.get_chart_index(&chart_id)?;
+        self.charts.borrow_mut()[index].set_link_group(link_group);
+        unsafe { self.as_mut().get_unchecked_mut() }.request_animation_frame();
+        Ok(())
+    }
+    pub fn set_hover_x(
+        mut self: Pin<&mut Self>,
+        chart_id: JsValue,
+        raw_coord: JsValue,
+        duration_ms: Option<f64>,
+    ) -> Result<(), String> {
+        let index = self.get_chart_index(&chart_id)?;
+        self.charts.borrow_mut()[index].set_hover_x(
+            &raw_coord,
+            duration_ms,
+            Self::get_time_us(),
+        )?;
+        unsafe { self.as_mut().get_unchecked_mut() }.request_animation_frame();
+        Ok(())
+    }
+    pub fn set_visible_range(
+        mut self: Pin<&mut Self>,
+        chart_id: JsValue,
+        range: JsValue,
+    ) -> Result<(), String> {
+        let index = self.get_chart_index(&chart_id)?;
+        self.charts.borrow_mut()[index].set_visible_range(&range, Self::get_time_us())?;
+        unsafe { self.as_mut().get_unchecked_mut() }.request_animation_frame();
+        Ok(())
+    }
+    pub fn redraw(mut self: Pin<&mut Self>, chart_id: JsValue) -> Result<(), String> {
+        let index = self.get_chart_index(&chart_id)?;
+        self.charts.borrow_mut()[index].redraw();
+        unsafe { self.as_mut().get_unchecked_mut() }.request_animation_frame();
+        Ok(())
+    }
+    /// Hands the failure of a deferred call to the warnings of its chart, see
+    /// `call_manager`.
+    pub fn report_deferred_failure(&self, chart_id: Option<&JsValue>, error: String) {
+        self.report_warning(
+            chart_id,
+            ChartWarning::new("DEFERRED_CALL_FAILED", error, None),
+        );
+    }
+    /// Adds the warning to the chart; logs it when there is no such chart
+    /// (any more), e.g. onAutoDestroy of a destroyed one.
+    fn report_warning(&self, chart_id: Option<&JsValue>, warning: ChartWarning) {
+        match chart_id.and_then(|chart_id| self.get_chart_index(chart_id).ok()) {
+            Some(index) => self.charts.borrow_mut()[index].push_warning(warning),
+            None => console_warn(format!("graphima: {}", warning.message).as_str()),
+        }
+    }
+    pub fn set_align_y(
+        mut self: Pin<&mut Self>,
+        chart_id: JsValue,
+        enabled: bool,
+    ) -> Result<(), String> {
+        let index = self.get_chart_index(&chart_id)?;
+        self.charts.borrow_mut()[index].set_align_y(enabled);
+        unsafe { self.as_mut().get_unchecked_mut() }.request_animation_frame();
+        Ok(())
+    }
+    /// Whether the charts are borrowed, so the call comes from a callback
+    /// run by a chart, e.g. renderTooltip, and has to wait, see `defer_call`.
+    pub fn is_busy(&self) -> bool {
+        self.charts.try_borrow_mut().is_err()
+    }
+    fn get_chart_index(&self, chart_id: &JsValue) -> Result<usize, String> {
+        let chart_id = chart_id
+            .as_string()
+            .ok_or_else(|| "not a string".to_string())?;
+        self.chart_ids
+            .iter()
+            .position(|id| id == chart_id.as_str())
+            .ok_or_else(|| "chart not found by id".to_string())
+    }
+    pub fn get_layout(&self, chart_id: JsValue) -> Result<JsValue, String> {
+        let index = self.get_chart_index(&chart_id)?;
+        self.charts.borrow()[index].get_layout()
+    }
+    pub fn data_to_pixel(&self, chart_id: JsValue, x: JsValue, y: f64) -> Result<JsValue, String> {
+        let index = self.get_chart_index(&chart_id)?;
+        self.charts.borrow()[index].data_to_pixel(&x, y)
+    }
+    pub fn pixel_to_data(&self, chart_id: JsValue, x: f64, y: f64) -> Result<JsValue, String> {
+        let index = self.get_chart_index(&chart_id)?;
+        self.charts.borrow()[index].pixel_to_data(x, y)
+    }
+
+    fn remove_chart(&mut self, index: usize) {
+        let chart_wrapper = self.charts.borrow()[index].get_wrapper().clone();
+        let preview_wrapper = self.charts.borrow()[index].get_preview_wrapper().cloned();
+        if let Some(resize_observer) = &self.global_resize_observer {
+            resize_observer.unobserve(&chart_wrapper);
+            if let Some(preview_wrapper) = &preview_wrapper {
+                resize_observer.unobserve(preview_wrapper);
+            }
+        }
+        if let Some(intersection_observer) = &self.global_intersection_observer {
+            intersection_observer.unobserve(&chart_wrapper);
+        }
+        chart_wrapper.remove();
+        if let Some(preview_wrapper) = preview_wrapper {
+            preview_wrapper.remove();
+        }
+
+        let chart_id = self.chart_ids.remove(index);
+        self.containers.remove(index);
+        self.event_dispatcher.forget(chart_id.as_str());
+        self.charts.borrow_mut().remove(index);
+        if self.charts.borrow().len() == 0 {
+            self.uninstall_listeners();
+        } else {
+            // the link group of the chart may share its y domain
+            self.request_animation_frame();
+        }
+    }
+
+    /// Destroys charts whose wrapper is no longer in the document, e.g. an
+    /// ancestor was removed without calling destroyMain; a chart which was
+    /// moved stays connected. Returns the onAutoDestroy callbacks to call
+    /// with the chart ids.
+    fn destroy_disconnected_charts(&mut self) -> Vec<(js_sys::Function, String)> {
+        let disconnected: Vec<usize> = self
+            .charts
+            .borrow()
+            .iter()
+            .enumerate()
+            .filter(|(_, chart)| !chart.get_wrapper().is_connected())
+            .map(|(index, _)| index)
+            .collect();
+        let mut callbacks = Vec::new();
+        for index in disconnected.into_iter().rev() {
+            let on_auto_destroy = self.charts.borrow()[index].get_on_auto_destroy();
+            let chart_id = self.chart_ids[index].clone();
+            self.remove_chart(index);
+            if let Some(on_auto_destroy) = on_auto_destroy {
+                callbacks.push((on_auto_destroy, chart_id));
+            }
+        }
+        callbacks
+    }
 
     fn uninstall_listeners(&mut self) {
+        if !self.listeners_installed {
+            return;
+        }
+        self.listeners_installed = false;
         self.global_pointer_move = None;
         self.global_pointer_out = None;
         self.global_pointer_down = None;
         self.global_pointer_up = None;
         self.global_window_resize = None;
         self.global_orintation_change = None;
+        self.global_key_down = None;
+        self.global_focus_in = None;
+        self.global_focus_out = None;
+        self.global_context_lost = None;
+        self.global_context_restored = None;
+        self.global_media_changes.clear();
+        self.global_resize_observer = None;
+        self.global_intersection_observer = None;
+        self.global_visibility_change = None;
+        debug_assert_eq!(JsEventListener::count(), 0, "listeners are left installed");
+        self.cancel_memory_trim();
+        if let Some(handle) = self.idle_handle.take() {
+            cancel_idle(handle);
+        }
+        if let Some(handle) = self.resize_settle_timeout.take() {
+            web_sys::window().unwrap().clear_timeout_with_handle(handle);
+        }
+        if let Some(handle) = self.animation_frame.take() {
+            web_sys::window()
+                .unwrap()
+                .cancel_animation_frame(handle)
+                .unwrap();
+        }
+        self.event_flush_timeout = None;
+        self.retire_closures();
+    }
+
+    /// Drops the frame and timeout closures once the current task is over,
+    /// as this may run inside one of them, e.g. destroyMain called by an
+    /// event listener; they are created again with the next chart.
+    fn retire_closures(&mut self) {
+        let closures = (
+            self.global_request_animation_frame_closure.take(),
+            self.global_memory_trim_closure.take(),
+            self.global_idle_closure.take(),
+            self.global_resize_settle_closure.take(),
+        );
+        let retire = Closure::once_into_js(move || drop(closures));
+        web_sys::window()
+            .unwrap()
+            .set_timeout_with_callback_and_timeout_and_arguments_0(retire.unchecked_ref(), 0)
+            .unwrap();
+    }
+
+    /// Coalesces resizes of the window, its orientation and the containers:
+    /// the first one of a storm resizes the charts right away, the rest
+    /// once there were none for RESIZE_SETTLE_MS; meanwhile canvases stretch
+    /// the last frame, being sized in percents. Resized wrappers are given
+    /// by the resize observer, None stands for all of them.
+    fn on_resize_event(&mut self, elements: Option<Vec<web_sys::Element>>) {
+        match (&mut self.resized_elements, elements) {
+            (Some(resized_elements), Some(elements)) => resized_elements.extend(elements),
+            (resized_elements, _) => *resized_elements = None,
+        }
+        match self.resize_settle_timeout.take() {
+            Some(handle) => {
+                web_sys::window().unwrap().clear_timeout_with_handle(handle);
+                self.resize_pending = true;
+            }
+            None => self.resize_charts(),
+        }
+        if self.global_resize_settle_closure.is_none() {
+            let ptr = self as *mut Self;
+            self.global_resize_settle_closure = Some(JsClosure::new(Box::new(move || {
+                let chart_manager = unsafe { ptr.as_mut().unwrap() };
+                chart_manager.resize_settle_timeout = None;
+                if chart_manager.resize_pending {
+                    chart_manager.resize_pending = false;
+                    chart_manager.resize_charts();
+                }
+            })));
+        }
+        self.resize_settle_timeout = Some(
+            web_sys::window()
+                .unwrap()
+                .set_timeout_with_callback_and_timeout_and_arguments_0(
+                    self.global_resize_settle_closure
+                        .as_ref()
+                        .unwrap()
+                        .as_function(),
+                    RESIZE_SETTLE_MS,
+                )
+                .unwrap(),
+        );
+    }
+
+    fn resize_charts(&mut self) {
+        let resized_elements = self.resized_elements.replace(Vec::new());
+        for chart in self.charts.borrow_mut().iter_mut() {
+            match &resized_elements {
+                Some(elements) => {
+                    for element in elements.iter() {
+                        chart.on_element_resize(element);
+                    }
+                }
+                None => chart.on_resize(),
+            }
+        }
+        self.request_animation_frame();
     }
 
-    fn ensure_global_listeners_are_set_up(&mut self) {
-        if self.global_pointer_move.is_some() {
+    /// Releases the memory of the charts which are hidden now, without
+    /// waiting for memoryTrimDelay.
+    pub fn trim_memory(mut self: Pin<&mut Self>) {
+        for chart in self.charts.borrow_mut().iter_mut() {
+            chart.trim_memory();
+        }
+        unsafe { self.as_mut().get_unchecked_mut() }.schedule_memory_trim();
+    }
+
+    fn cancel_memory_trim(&mut self) {
+        if let Some(handle) = self.memory_trim_timeout.take() {
+            web_sys::window().unwrap().clear_timeout_with_handle(handle);
+        }
+    }
+
+    /// Sets a timeout for the chart which is due to release its memory
+    /// first.
+    fn schedule_memory_trim(&mut self) {
+        self.cancel_memory_trim();
+        let trim_time_us = self
+            .charts
+            .borrow()
+            .iter()
+            .filter_map(|chart| chart.get_memory_trim_time_us())
+            .fold(f64::INFINITY, f64::min);
+        if !trim_time_us.is_finite() {
             return;
         }
+        if self.global_memory_trim_closure.is_none() {
+            let charts = Rc::clone(&self.charts);
+            let ptr = self as *mut Self;
+            self.global_memory_trim_closure = Some(JsClosure::new(Box::new(move || {
+                // timeouts may fire a bit early
+                let time_us = Self::get_time_us() + 1000.0;
+                for chart in charts.borrow_mut().iter_mut() {
+                    if chart
+                        .get_memory_trim_time_us()
+                        .is_some_and(|trim_time_us| trim_time_us <= time_us)
+                    {
+                        chart.trim_memory();
+                    }
+                }
+                let chart_manager = unsafe { ptr.as_mut().unwrap() };
+                chart_manager.memory_trim_timeout = None;
+                chart_manager.schedule_memory_trim();
+            })));
+        }
+        let delay_ms = ((trim_time_us - Self::get_time_us()) / 1000.0)
+            .ceil()
+            .max(0.0);
+        self.memory_trim_timeout = Some(
+            web_sys::window()
+                .unwrap()
+                .set_timeout_with_callback_and_timeout_and_arguments_0(
+                    self.global_memory_trim_closure
+                        .as_ref()
+                        .unwrap()
+                        .as_function(),
+                    delay_ms as i32,
+                )
+                .unwrap(),
+        );
+    }
+
+    /// Asks the browser for an idle callback while any chart has idle work,
+    /// see `DrawChart::has_idle_work`; each callback works for up to
+    /// IDLE_CHUNK_MS, so that input is never held up for long.
+    fn request_idle_work(&mut self) {
+        if self.idle_handle.is_some()
+            || !self
+                .charts
+                .borrow()
+                .iter()
+                .any(|chart| chart.has_idle_work())
+        {
+            return;
+        }
+        if self.global_idle_closure.is_none() {
+            let charts = Rc::clone(&self.charts);
+            let ptr = self as *mut Self;
+            self.global_idle_closure = Some(JsClosure::new(Box::new(move || {
+                let chart_manager = unsafe { ptr.as_mut().unwrap() };
+                chart_manager.idle_handle = None;
+                // a chart may be borrowed if the callback came in a nested
+                // event loop, e.g. of alert
+                if let Ok(mut charts) = charts.try_borrow_mut() {
+                    let deadline_us = Self::get_time_us() + IDLE_CHUNK_MS * 1000.0;
+                    while let Some(chart) = charts.iter_mut().find(|chart| chart.has_idle_work()) {
+                        chart.do_idle_work();
+                        if Self::get_time_us() >= deadline_us {
+                            break;
+                        }
+                    }
+                }
+                chart_manager.request_idle_work();
+            })));
+        }
+        self.idle_handle = Some(request_idle(
+            self.global_idle_closure.as_ref().unwrap().as_function(),
+            self.client_caps.borrow().idle_callback,
+        ));
+    }
+
+    /// Installs the global listeners once for all the charts;
+    /// `uninstall_listeners` removes exactly what is installed here, along
+    /// with the frame and timeout closures.
+    fn install_listeners(&mut self) {
+        if self.listeners_installed {
+            return;
+        }
+        self.listeners_installed = true;
+        debug_assert_eq!(JsEventListener::count(), 0, "listeners are installed twice");
         let charts = Rc::clone(&self.charts);
         let control_watcher = Rc::clone(&self.control_watcher);
         let ptr = self as *mut Self;
@@ -172,11 +1027,9 @@ impl ChartManager {
             },
             Box::new(move |event: JsValue| {
                 if let Some(control_event) = control_watcher.borrow_mut().down(&event) {
-                    let time_us = Self::get_time_us();
-                    for chart in charts.borrow_mut().iter_mut() {
-                        chart.on_control_event(&control_event, time_us);
+                    if Self::dispatch_control_event(&charts, &control_event) {
+                        unsafe { ptr.as_mut().unwrap().request_animation_frame() }
                     }
-                    unsafe { ptr.as_mut().unwrap().request_animation_frame() }
                 }
             }),
         ));
@@ -191,11 +1044,9 @@ impl ChartManager {
             },
             Box::new(move |event: JsValue| {
                 if let Some(control_event) = control_watcher.borrow_mut().up(&event) {
-                    let time_us = Self::get_time_us();
-                    for chart in charts.borrow_mut().iter_mut() {
-                        chart.on_control_event(&control_event, time_us);
+                    if Self::dispatch_control_event(&charts, &control_event) {
+                        unsafe { ptr.as_mut().unwrap().request_animation_frame() }
                     }
-                    unsafe { ptr.as_mut().unwrap().request_animation_frame() }
                 }
             }),
         ));
@@ -210,11 +1061,9 @@ impl ChartManager {
             },
             Box::new(move |event: JsValue| {
                 if let Some(control_event) = control_watcher.borrow_mut().moved(&event) {
-                    let time_us = Self::get_time_us();
-                    for chart in charts.borrow_mut().iter_mut() {
-                        chart.on_control_event(&control_event, time_us);
+                    if Self::dispatch_control_event(&charts, &control_event) {
+                        unsafe { ptr.as_mut().unwrap().request_animation_frame() }
                     }
-                    unsafe { ptr.as_mut().unwrap().request_animation_frame() }
                 }
             }),
         ));
@@ -226,42 +1075,37 @@ impl ChartManager {
                 "touchcancel",
                 Box::new(move |event: JsValue| {
                     if let Some(control_event) = control_watcher.borrow_mut().left(&event) {
-                        let time_us = Self::get_time_us();
-                        for chart in charts.borrow_mut().iter_mut() {
-                            chart.on_control_event(&control_event, time_us);
+                        if Self::dispatch_control_event(&charts, &control_event) {
+                            unsafe { ptr.as_mut().unwrap().request_animation_frame() }
                         }
-                        unsafe { ptr.as_mut().unwrap().request_animation_frame() }
                     }
                 }),
             ));
         }
-        let charts = Rc::clone(&self.charts);
         self.global_window_resize = Some(JsEventListener::new(
             web_sys::window().unwrap().into(),
             "resize",
-            Box::new(move |_: JsValue| {
-                for chart in charts.borrow_mut().iter_mut() {
-                    chart.on_resize();
-                }
-                unsafe { ptr.as_mut().unwrap().request_animation_frame() }
-            }),
+            Box::new(move |_: JsValue| unsafe { ptr.as_mut().unwrap().on_resize_event(None) }),
         ));
+        // containers resize without the window too, e.g. when revealed
+        self.global_resize_observer = JsResizeObserver::new(Box::new(move |entries: JsValue| {
+            let elements = entries
+                .unchecked_into::<js_sys::Array>()
+                .iter()
+                .filter_map(|entry| Reflect::get(&entry, &"target".into()).ok())
+                .map(|target| target.unchecked_into::<web_sys::Element>())
+                .collect();
+            unsafe { ptr.as_mut().unwrap().on_resize_event(Some(elements)) }
+        }));
         let client_caps = Rc::clone(&self.client_caps);
-        let charts = Rc::clone(&self.charts);
         let ptr = self as *mut Self;
-        if self.client_caps.borrow().screen_orientation {
+        if let Some(screen_orientation) = ClientCaps::get_screen_orientation() {
             self.global_orintation_change = Some(JsEventListener::new(
-                Reflect::get(&web_sys::window().unwrap(), &JsValue::from_str("screen"))
-                    .and_then(|screen| Reflect::get(&screen, &JsValue::from_str("orientation")))
-                    .unwrap()
-                    .into(),
+                screen_orientation.into(),
                 "change",
                 Box::new(move |_: JsValue| {
                     *client_caps.borrow_mut() = ClientCaps::detect();
-                    for chart in charts.borrow_mut().iter_mut() {
-                        chart.on_resize();
-                    }
-                    unsafe { ptr.as_mut().unwrap().request_animation_frame() }
+                    unsafe { ptr.as_mut().unwrap().on_resize_event(None) }
                 }),
             ));
         } else {
@@ -270,68 +1114,333 @@ impl ChartManager {
                 "orientationchange",
                 Box::new(move |_: JsValue| {
                     *client_caps.borrow_mut() = ClientCaps::detect();
+                    unsafe { ptr.as_mut().unwrap().on_resize_event(None) }
+                }),
+            ));
+        }
+
+        let charts = Rc::clone(&self.charts);
+        self.global_key_down = Some(JsEventListener::new(
+            web_sys::window().unwrap().into(),
+            "keydown",
+            Box::new(move |event: JsValue| {
+                if let Ok(event) = event.dyn_into::<web_sys::KeyboardEvent>() {
+                    let control_event = ControlEvent::KeyDown(event);
+                    if Self::dispatch_control_event(&charts, &control_event) {
+                        unsafe { ptr.as_mut().unwrap().request_animation_frame() }
+                    }
+                }
+            }),
+        ));
+        for (listener, event_name) in [
+            (&mut self.global_focus_in, "focusin"),
+            (&mut self.global_focus_out, "focusout"),
+        ] {
+            let charts = Rc::clone(&self.charts);
+            *listener = Some(JsEventListener::new(
+                web_sys::window().unwrap().into(),
+                event_name,
+                Box::new(move |_: JsValue| {
+                    let time_us = Self::get_time_us();
                     for chart in charts.borrow_mut().iter_mut() {
-                        chart.on_resize();
+                        chart.on_control_event(&ControlEvent::FocusChanged, time_us);
                     }
+                    run_deferred_calls();
                     unsafe { ptr.as_mut().unwrap().request_animation_frame() }
                 }),
             ));
         }
 
-        if self.global_request_animation_frame_closure.is_none() {
-            let charts = Rc::clone(&self.charts);
-            let ptr = self as *mut Self;
-            let closure = Closure::new(Box::new(move |time_ms: JsValue| {
-                unsafe { ptr.as_mut().unwrap().animation_frame_requested = false }
+        // canvases do not bubble context loss, so it is caught on the way down
+        if self.client_caps.borrow().context_loss_events {
+            for (listener, event_name, lost) in [
+                (&mut self.global_context_lost, "contextlost", true),
+                (&mut self.global_context_restored, "contextrestored", false),
+            ] {
+                let charts = Rc::clone(&self.charts);
+                *listener = Some(JsEventListener::with_capture(
+                    web_sys::window().unwrap().into(),
+                    event_name,
+                    true,
+                    Box::new(move |event: JsValue| {
+                        let target = event
+                            .dyn_into::<web_sys::Event>()
+                            .ok()
+                            .and_then(|event| event.target())
+                            .and_then(|target| target.dyn_into::<web_sys::Node>().ok());
+                        for chart in charts.borrow_mut().iter_mut() {
+                            if chart.get_wrapper().contains(target.as_ref())
+                                || chart
+                                    .get_preview_wrapper()
+                                    .is_some_and(|wrapper| wrapper.contains(target.as_ref()))
+                            {
+                                chart.on_context_change(lost);
+                            }
+                        }
+                        if !lost {
+                            unsafe { ptr.as_mut().unwrap().request_animation_frame() }
+                        }
+                    }),
+                ));
+            }
+        }
 
-                let mut actions: usize = 0;
-                let time_us = time_ms.as_f64().unwrap() * 1000.0;
+        // hidden charts release their memory after memoryTrimDelay
+        let charts = Rc::clone(&self.charts);
+        self.global_visibility_change = Some(JsEventListener::new(
+            web_sys::window().unwrap().document().unwrap().into(),
+            "visibilitychange",
+            Box::new(move |_: JsValue| {
+                let document_hidden = Self::is_document_hidden();
+                let time_us = Self::get_time_us();
                 for chart in charts.borrow_mut().iter_mut() {
-                    actions += chart.draw(time_us);
+                    chart.on_visibility_change(None, document_hidden, time_us);
                 }
-                if actions > 0 {
-                    unsafe { ptr.as_mut().unwrap().request_animation_frame() };
+                let chart_manager = unsafe { ptr.as_mut().unwrap() };
+                chart_manager.schedule_memory_trim();
+                chart_manager.request_animation_frame();
+            }),
+        ));
+        let charts = Rc::clone(&self.charts);
+        self.global_intersection_observer =
+            JsIntersectionObserver::new(Box::new(move |entries: JsValue| {
+                let document_hidden = Self::is_document_hidden();
+                let time_us = Self::get_time_us();
+                for entry in entries.unchecked_into::<js_sys::Array>().iter() {
+                    let entry = entry.unchecked_into::<web_sys::IntersectionObserverEntry>();
+                    let target = entry.target();
+                    for chart in charts.borrow_mut().iter_mut() {
+                        if *chart.get_wrapper() == target {
+                            chart.on_visibility_change(
+                                Some(entry.is_intersecting()),
+                                document_hidden,
+                                time_us,
+                            );
+                        }
+                    }
                 }
+                let chart_manager = unsafe { ptr.as_mut().unwrap() };
+                chart_manager.schedule_memory_trim();
+                chart_manager.request_animation_frame();
             }));
-            self.global_request_animation_frame_closure = Some(closure);
+
+        for query in MEDIA_FEATURE_QUERIES {
+            if let Some(media_query_list) = ClientCaps::match_media(query) {
+                let client_caps = Rc::clone(&self.client_caps);
+                let charts = Rc::clone(&self.charts);
+                self.global_media_changes.push(JsEventListener::new(
+                    media_query_list.into(),
+                    "change",
+                    Box::new(move |_: JsValue| {
+                        client_caps.borrow_mut().update_media_features();
+                        for chart in charts.borrow_mut().iter_mut() {
+                            chart.on_client_caps_change();
+                        }
+                        unsafe { ptr.as_mut().unwrap().request_animation_frame() }
+                    }),
+                ));
+            }
         }
-        self.request_animation_frame();
     }
+    /// Draws the charts and dispatches their events once per frame.
+    fn create_animation_frame_closure(&mut self) -> JsClosure<dyn Fn(JsValue)> {
+        let charts = Rc::clone(&self.charts);
+        let ptr = self as *mut Self;
+        JsClosure::new(Box::new(move |time_ms: JsValue| {
+            unsafe { ptr.as_mut().unwrap().animation_frame = None }
+            let auto_destroy_callbacks =
+                unsafe { ptr.as_mut().unwrap().destroy_disconnected_charts() };
+
+            let mut actions: usize = 0;
+            let mut pending_events: Vec<PendingChartEvents> = Vec::new();
+            let time_us = time_ms.as_f64().unwrap() * 1000.0;
+            actions += Self::update_aligned_y_domains(&mut charts.borrow_mut(), time_us);
+            for chart in charts.borrow_mut().iter_mut() {
+                actions += chart.draw(time_us);
+                if let Some(events) = chart.take_events() {
+                    pending_events.push(events);
+                }
+            }
+            actions += Self::update_shared_tooltips(&mut charts.borrow_mut());
+            if actions > 0 {
+                unsafe { ptr.as_mut().unwrap().request_animation_frame() };
+            }
+            // e.g. points were appended or the chart got ready
+            unsafe { ptr.as_mut().unwrap().request_idle_work() };
+            let due_events = unsafe { ptr.as_mut().unwrap().take_due_events(pending_events) };
+            // charts are no longer borrowed, so listeners are free to call
+            // back into the manager
+            for events in due_events {
+                events.dispatch();
+            }
+            // e.g. calls of renderTooltip and describeChart into the manager
+            run_deferred_calls();
+            for (on_auto_destroy, chart_id) in auto_destroy_callbacks {
+                let chart_id = JsValue::from_str(chart_id.as_str());
+                if let Err(error) = on_auto_destroy.call1(&JsValue::NULL, &chart_id) {
+                    let warning = ChartWarning::callback_failed("onAutoDestroy", error);
+                    unsafe { ptr.as_mut().unwrap() }.report_warning(Some(&chart_id), warning);
+                }
+            }
+        }))
+    }
+    /// Hands the charts of each link group aligning their y axes the union
+    /// of their domains, which follows their data; all the other charts get
+    /// their own, so a chart leaving the group or turning alignment off goes
+    /// back to it. Returns the number of charts which need a frame.
+    fn update_aligned_y_domains(charts: &mut [Box<dyn DrawChart>], time_us: f64) -> usize {
+        let alignments: Vec<Option<YAlignment>> =
+            charts.iter().map(|chart| chart.get_y_alignment()).collect();
+        get_shared_y_domains(&alignments)
+            .into_iter()
+            .zip(charts.iter_mut())
+            .map(|(shared_y_domain, chart)| chart.set_shared_y_domain(shared_y_domain, time_us))
+            .filter(|changed| *changed)
+            .count()
+    }
+    /// Hands the hovered chart of each link group the tooltip sections of
+    /// the other charts of the group; all the other charts get none, so
+    /// nothing stale stays after the hover moves or a chart leaves the
+    /// group. Returns the number of charts which need a frame.
+    fn update_shared_tooltips(charts: &mut [Box<dyn DrawChart>]) -> usize {
+        // hovered chart by link group
+        let shared_hovers: Vec<(usize, String, f64)> = charts
+            .iter()
+            .enumerate()
+            .filter_map(|(index, chart)| {
+                chart
+                    .get_shared_hover()
+                    .map(|(link_group, coord)| (index, link_group, coord))
+            })
+            .collect();
+        let mut actions = 0;
+        for index in 0..charts.len() {
+            let sections = match shared_hovers.iter().find(|(i, _, _)| *i == index) {
+                Some((_, link_group, coord)) => charts
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, chart)| {
+                        *i != index && chart.get_link_group() == Some(link_group.as_str())
+                    })
+                    .filter_map(|(_, chart)| chart.get_tooltip_section(*coord))
+                    .collect(),
+                None => Vec::new(),
+            };
+            if charts[index].set_shared_sections(sections) {
+                actions += 1;
+            }
+        }
+        actions
+    }
+    /// Queues the events of the frame and takes those which are due; a
+    /// timeout requests a frame once the held back ones are.
+    fn take_due_events(
+        &mut self,
+        pending_events: Vec<PendingChartEvents>,
+    ) -> Vec<PendingChartEvents> {
+        for events in pending_events {
+            self.event_dispatcher.push(events);
+        }
+        let time_us = Self::get_time_us();
+        let (due_events, next_due_us) = self.event_dispatcher.take_due(time_us);
+        self.event_flush_timeout = next_due_us.map(|next_due_us| {
+            let ptr = self as *mut Self;
+            JsTimeout::new(
+                ((next_due_us - time_us) / 1000.0).ceil(),
+                Box::new(move || unsafe { ptr.as_mut().unwrap().request_animation_frame() }),
+            )
+        });
+        due_events
+    }
+    /// Passes the event to the charts which react to it; returns whether any
+    /// did, so that pointer traffic over presentational charts needs no frame.
+    fn dispatch_control_event(
+        charts: &RefCell<Vec<Box<dyn DrawChart>>>,
+        control_event: &ControlEvent,
+    ) -> bool {
+        let time_us = Self::get_time_us();
+        let mut dispatched = false;
+        for chart in charts.borrow_mut().iter_mut() {
+            if chart.accepts_control_events() {
+                chart.on_control_event(control_event, time_us);
+                dispatched = true;
+            }
+        }
+        run_deferred_calls();
+        dispatched
+    }
+
     fn request_animation_frame(&mut self) {
-        if !self.animation_frame_requested {
-            web_sys::window()
-                .unwrap()
-                .request_animation_frame(
-                    self.global_request_animation_frame_closure
-                        .as_ref()
-                        .unwrap()
-                        .as_ref()
-                        .unchecked_ref(),
-                )
-                .unwrap();
-            self.animation_frame_requested = true;
+        if self.animation_frame.is_none() {
+            if self.global_request_animation_frame_closure.is_none() {
+                self.global_request_animation_frame_closure =
+                    Some(self.create_animation_frame_closure());
+            }
+            self.animation_frame = Some(
+                web_sys::window()
+                    .unwrap()
+                    .request_animation_frame(
+                        self.global_request_animation_frame_closure
+                            .as_ref()
+                            .unwrap()
+                            .as_function(),
+                    )
+                    .unwrap(),
+            );
         }
     }
-    fn inject_content_wrapper(selector: &str) -> Result<String, String> {
+    /// Returns the selector of the injected wrapper and the direction of the
+    /// container; the wrapper id is random unless numbered.
+    fn inject_content_wrapper(
+        selector: &str,
+        wrapper_number: Option<usize>,
+    ) -> Result<(String, Direction), String> {
         let document = web_sys::window().unwrap().document().unwrap();
         let container = document
             .query_selector(selector)
             .unwrap()
             .ok_or_else(|| "container not found".to_string())?;
 
+        let direction = web_sys::window()
+            .unwrap()
+            .get_computed_style(&container)
+            .unwrap()
+            .and_then(|style| style.get_property_value("direction").ok())
+            .map_or(Direction::Ltr, |direction| {
+                if direction == "rtl" {
+                    Direction::Rtl
+                } else {
+                    Direction::Ltr
+                }
+            });
+
         let wrapper = document.create_element("div").unwrap();
-        let content_wrapper_selector = format!(
-            "ac-{}",
-            (js_sys::Math::random() * 1000000.0).floor() as usize
-        );
+        let content_wrapper_selector = match wrapper_number {
+            Some(wrapper_number) => format!("ac-n{}", wrapper_number),
+            None => format!(
+                "ac-{}",
+                (js_sys::Math::random() * 1000000.0).floor() as usize
+            ),
+        };
         container.append_child(&wrapper).unwrap();
         wrapper
             .set_attribute("id", content_wrapper_selector.as_str())
             .unwrap();
         wrapper
-            .set_attribute("style", "width: 100%; height: 100%; position: relative")
+            .set_attribute("style", CONTENT_WRAPPER_STYLE)
             .unwrap();
-        Ok(format!("#{}", content_wrapper_selector.as_str()))
+        Ok((format!("#{}", content_wrapper_selector.as_str()), direction))
+    }
+    fn remove_element(selector: &str) {
+        if let Some(element) = web_sys::window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .query_selector(selector)
+            .unwrap()
+        {
+            element.remove();
+        }
     }
     fn is_touch_device() -> bool {
         let window = web_sys::window().unwrap();
@@ -340,6 +1449,9 @@ impl ChartManager {
             .is_undefined()
             && window.navigator().max_touch_points() > 0
     }
+    fn is_document_hidden() -> bool {
+        web_sys::window().unwrap().document().unwrap().hidden()
+    }
     fn get_time_us() -> f64 {
         web_sys::window().unwrap().performance().unwrap().now() * 1000.0
     }
@@ -0,0 +1,400 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use js_sys::{Array, Function, Reflect};
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+const MAX_DECIMALS: usize = 6;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ValueLabelFormat {
+    Plain,
+    Compact,
+    Si,
+    Bytes,
+}
+impl FromStr for ValueLabelFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Self::Plain),
+            "compact" => Ok(Self::Compact),
+            "si" => Ok(Self::Si),
+            "bytes" => Ok(Self::Bytes),
+            v => Err(format!(
+                "unsupported label format: '{}'; use 'plain', 'compact', 'si' or 'bytes'",
+                v
+            )),
+        }
+    }
+}
+impl ValueLabelFormat {
+    fn get_units(&self) -> (f64, &'static [&'static str]) {
+        match self {
+            Self::Plain => (1.0, &[""]),
+            Self::Compact => (1000.0, &["", "K", "M", "B", "T"]),
+            Self::Si => (1000.0, &["", "k", "M", "G", "T", "P"]),
+            Self::Bytes => (1024.0, &["B", "KiB", "MiB", "GiB", "TiB", "PiB"]),
+        }
+    }
+}
+
+/// Display units of values stored in a base unit, as factors to the base
+/// unit in ascending order with their suffixes.
+#[derive(Clone, PartialEq, Debug)]
+pub struct UnitLadder {
+    units: Vec<(f64, String)>,
+}
+impl UnitLadder {
+    pub fn new(mut units: Vec<(f64, String)>) -> Result<Self, String> {
+        if units.is_empty() {
+            return Err("unit ladder is empty".to_string());
+        }
+        if units
+            .iter()
+            .any(|(factor, _)| !factor.is_finite() || *factor <= 0.0)
+        {
+            return Err("unit factors should be positive".to_string());
+        }
+        units.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Self { units })
+    }
+    pub fn duration_seconds() -> Self {
+        Self::new(
+            [
+                (1e-3, "ms"),
+                (1.0, "s"),
+                (60.0, "min"),
+                (3600.0, "h"),
+                (86400.0, "d"),
+            ]
+            .iter()
+            .map(|(factor, suffix)| (*factor, suffix.to_string()))
+            .collect(),
+        )
+        .unwrap()
+    }
+    pub fn bytes() -> Self {
+        Self::new(
+            ["B", "KiB", "MiB", "GiB", "TiB", "PiB"]
+                .iter()
+                .enumerate()
+                .map(|(index, suffix)| (1024f64.powi(index as i32), suffix.to_string()))
+                .collect(),
+        )
+        .unwrap()
+    }
+    /// The largest unit not exceeding the value, the smallest one for values
+    /// below all of them.
+    pub fn pick(&self, max_abs: f64) -> &(f64, String) {
+        self.units
+            .iter()
+            .rev()
+            .find(|(factor, _)| max_abs >= *factor)
+            .unwrap_or(&self.units[0])
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct NumberLocale {
+    pub decimal_separator: char,
+    pub group_separator: Option<char>,
+}
+impl Default for NumberLocale {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            group_separator: Some(','),
+        }
+    }
+}
+impl NumberLocale {
+    /// Reads separators from `Intl.NumberFormat`; `None` stands for the
+    /// browser locale.
+    pub fn from_locale(locale: Option<&str>) -> Result<Self, String> {
+        let locales = Array::new();
+        if let Some(locale) = locale {
+            locales.push(&JsValue::from_str(locale));
+        }
+        let constructor = Reflect::get(&js_sys::global(), &"Intl".into())
+            .and_then(|intl| Reflect::get(&intl, &"NumberFormat".into()))
+            .ok()
+            .and_then(|v| v.dyn_into::<Function>().ok());
+        let constructor = match constructor {
+            Some(constructor) => constructor,
+            None => return Ok(Self::default()),
+        };
+        let number_format: js_sys::Intl::NumberFormat =
+            Reflect::construct(&constructor, &Array::of1(&locales))
+                .map_err(|_| format!("invalid locale: {}", locale.unwrap_or_default()))?
+                .unchecked_into();
+
+        let mut number_locale = Self {
+            decimal_separator: '.',
+            group_separator: None,
+        };
+        for part in number_format.format_to_parts(1000.5).iter() {
+            let part_type = Reflect::get(&part, &"type".into())
+                .ok()
+                .and_then(|v| v.as_string());
+            let value = Reflect::get(&part, &"value".into())
+                .ok()
+                .and_then(|v| v.as_string())
+                .and_then(|v| v.chars().next());
+            match (part_type.as_deref(), value) {
+                (Some("decimal"), Some(c)) => number_locale.decimal_separator = c,
+                (Some("group"), Some(c)) => number_locale.group_separator = Some(c),
+                _ => {}
+            }
+        }
+        Ok(number_locale)
+    }
+}
+
+fn format_fixed(value: f64, decimals: usize, locale: &NumberLocale) -> String {
+    let formatted = format!("{:.decimals$}", value.abs(), decimals = decimals);
+    let is_zero = formatted.chars().all(|c| c == '0' || c == '.');
+    let (integral, fraction) = match formatted.split_once('.') {
+        Some((integral, fraction)) => (integral, Some(fraction)),
+        None => (formatted.as_str(), None),
+    };
+    let mut result = String::with_capacity(formatted.len() + integral.len() / 3 + 1);
+    if value < 0.0 && !is_zero {
+        result.push('-');
+    }
+    for (index, c) in integral.chars().enumerate() {
+        if index > 0 && (integral.len() - index) % 3 == 0 {
+            if let Some(group_separator) = locale.group_separator {
+                result.push(group_separator);
+            }
+        }
+        result.push(c);
+    }
+    if let Some(fraction) = fraction {
+        result.push(locale.decimal_separator);
+        result.push_str(fraction);
+    }
+    result
+}
+
+fn format_exp(value: f64, locale: &NumberLocale) -> String {
+    format!("{:.2e}", value).replace('.', locale.decimal_separator.to_string().as_str())
+}
+
+fn is_exact(value: f64, decimals: usize) -> bool {
+    let scaled = value * 10f64.powi(decimals as i32);
+    (scaled - scaled.round()).abs() < 1e-6 * scaled.abs().max(1.0)
+}
+
+/// Picks the number of decimals so that neighbouring values do not collapse
+/// into the same label (1.2K and 1.4K rather than 1K twice). With `precise`
+/// at least three significant digits are kept, which suits single values.
+fn get_decimals(values: &[f64], precise: bool) -> usize {
+    let mut sorted: Vec<f64> = values.iter().cloned().filter(|v| v.is_finite()).collect();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    sorted.dedup();
+
+    let mut min_decimals = 0;
+    if precise || sorted.len() < 2 {
+        let max_abs = sorted.iter().map(|v| v.abs()).fold(0.0, f64::max);
+        if max_abs > 0.0 {
+            min_decimals = (2.0 - max_abs.log10().floor()).max(0.0) as usize;
+        }
+    }
+    while min_decimals < MAX_DECIMALS {
+        let scale = 10f64.powi(min_decimals as i32);
+        if sorted
+            .windows(2)
+            .all(|pair| (pair[0] * scale).round() != (pair[1] * scale).round())
+        {
+            break;
+        }
+        min_decimals += 1;
+    }
+    if precise {
+        return min_decimals.min(MAX_DECIMALS);
+    }
+    // nice tick values such as 1.25K deserve their full precision
+    (min_decimals..=(min_decimals + 2).min(MAX_DECIMALS))
+        .find(|decimals| sorted.iter().all(|v| is_exact(*v, *decimals)))
+        .unwrap_or(min_decimals)
+}
+
+/// Formats values sharing the same unit and number of decimals, so labels of
+/// one axis read consistently.
+pub fn format_labels(
+    values: &[f64],
+    format: ValueLabelFormat,
+    locale: &NumberLocale,
+    precise: bool,
+) -> Vec<String> {
+    let (base, suffixes) = format.get_units();
+    let max_abs = values
+        .iter()
+        .filter(|v| v.is_finite())
+        .map(|v| v.abs())
+        .fold(0.0, f64::max);
+    if max_abs != 0.0 && max_abs < 1e-3 {
+        return values.iter().map(|v| format_exp(*v, locale)).collect();
+    }
+    let mut index = 0;
+    if base > 1.0 {
+        while index + 1 < suffixes.len() && max_abs >= base.powi(index as i32 + 1) {
+            index += 1;
+        }
+    }
+    let unit = base.powi(index as i32);
+    if max_abs / unit >= 1e15 {
+        return values.iter().map(|v| format_exp(*v, locale)).collect();
+    }
+    let scaled: Vec<f64> = values.iter().map(|v| v / unit).collect();
+    let decimals = get_decimals(scaled.as_slice(), precise);
+    scaled
+        .iter()
+        .map(|v| format!("{}{}", format_fixed(*v, decimals, locale), suffixes[index]))
+        .collect()
+}
+
+/// Formats values in the unit picked by the largest of them, so labels of one
+/// axis share it; with `precise` every value gets its own unit instead.
+pub fn format_unit_labels(
+    values: &[f64],
+    ladder: &UnitLadder,
+    locale: &NumberLocale,
+    precise: bool,
+    with_suffix: bool,
+) -> Vec<String> {
+    let format = |values: &[f64]| -> Vec<String> {
+        let max_abs = values
+            .iter()
+            .filter(|v| v.is_finite())
+            .map(|v| v.abs())
+            .fold(0.0, f64::max);
+        let (factor, suffix) = ladder.pick(max_abs);
+        let scaled: Vec<f64> = values.iter().map(|v| v / factor).collect();
+        let decimals = get_decimals(scaled.as_slice(), precise);
+        scaled
+            .iter()
+            .map(|v| {
+                let formatted = format_fixed(*v, decimals, locale);
+                if with_suffix {
+                    format!("{} {}", formatted, suffix)
+                } else {
+                    formatted
+                }
+            })
+            .collect()
+    };
+    if precise {
+        values
+            .iter()
+            .flat_map(|value| format(std::slice::from_ref(value)))
+            .collect()
+    } else {
+        format(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::number_format::{
+        format_labels, format_unit_labels, NumberLocale, UnitLadder, ValueLabelFormat,
+    };
+
+    #[test]
+    fn test_format_labels() {
+        let locale = NumberLocale::default();
+        assert_eq!(
+            format_labels(
+                &[1000.0, 1200.0, 1400.0],
+                ValueLabelFormat::Compact,
+                &locale,
+                false
+            ),
+            vec!["1.0K", "1.2K", "1.4K"]
+        );
+        assert_eq!(
+            format_labels(&[0.0, 2e6, 4e6], ValueLabelFormat::Compact, &locale, false),
+            vec!["0M", "2M", "4M"]
+        );
+        assert_eq!(
+            format_labels(&[1250.0, 1500.0], ValueLabelFormat::Si, &locale, false),
+            vec!["1.25k", "1.50k"]
+        );
+        assert_eq!(
+            format_labels(&[512.0, 1536.0], ValueLabelFormat::Bytes, &locale, false),
+            vec!["0.5KiB", "1.5KiB"]
+        );
+        assert_eq!(
+            format_labels(&[-1500.0, 12500.5], ValueLabelFormat::Plain, &locale, false),
+            vec!["-1,500.0", "12,500.5"]
+        );
+        assert_eq!(
+            format_labels(&[1234.5], ValueLabelFormat::Compact, &locale, true),
+            vec!["1.23K"]
+        );
+    }
+
+    #[test]
+    fn test_format_unit_labels() {
+        let locale = NumberLocale::default();
+        let seconds = UnitLadder::duration_seconds();
+        // the axis maximum picks the unit of every tick
+        assert_eq!(
+            format_unit_labels(&[0.0, 30.0, 60.0, 90.0], &seconds, &locale, false, true),
+            vec!["0.0 min", "0.5 min", "1.0 min", "1.5 min"]
+        );
+        assert_eq!(
+            format_unit_labels(&[0.0, 0.25, 0.5], &seconds, &locale, false, false),
+            vec!["0", "250", "500"]
+        );
+        // single values get their own units
+        assert_eq!(
+            format_unit_labels(&[0.0125, 7200.0], &seconds, &locale, true, true),
+            vec!["12.5 ms", "2.00 h"]
+        );
+        assert_eq!(
+            format_unit_labels(
+                &[512.0, 3.0 * 1048576.0],
+                &UnitLadder::bytes(),
+                &locale,
+                true,
+                true
+            ),
+            vec!["512 B", "3.00 MiB"]
+        );
+        let custom = UnitLadder::new(vec![(1000.0, "km".to_string()), (1.0, "m".to_string())]);
+        assert_eq!(
+            format_unit_labels(&[500.0, 2500.0], &custom.unwrap(), &locale, false, true),
+            vec!["0.5 km", "2.5 km"]
+        );
+        assert!(UnitLadder::new(vec![]).is_err());
+    }
+
+    #[test]
+    fn test_format_labels_locale() {
+        let locale = NumberLocale {
+            decimal_separator: ',',
+            group_separator: Some('.'),
+        };
+        assert_eq!(
+            format_labels(&[1500.0, 2500.0], ValueLabelFormat::Compact, &locale, false),
+            vec!["1,5K", "2,5K"]
+        );
+        assert_eq!(
+            format_labels(
+                &[-0.0001, 1234567.0],
+                ValueLabelFormat::Plain,
+                &locale,
+                false
+            ),
+            vec!["0", "1.234.567"]
+        );
+    }
+}
@@ -5,7 +5,23 @@
  *
  * Copyright (C) 2023, Nikita Almakov
  */
-use crate::data_set::{DataPoint, DataSet};
+use crate::compact::{CompactPart, CompactStep, DEFAULT_COMPACT_STEPS};
+use crate::data_set::{
+    append_by_name, check_lengths, sanitize_non_finite, split_gaps, AreaFill, Comparison,
+    DataPoint, DataSet, MarkerShape, PointMarker,
+};
+use crate::date_format::DateGranularity;
+use crate::events::{EventSinks, EVENT_KINDS};
+use crate::number_format::{
+    format_labels, format_unit_labels, NumberLocale, UnitLadder, ValueLabelFormat,
+};
+use crate::pattern::COLORBLIND_SAFE_PALETTE;
+use crate::preview_layout::PreviewPosition;
+use crate::screen::ResolutionCap;
+use crate::strings::Strings;
+use crate::time_zone::TimeZone;
+use crate::utils::fit_window;
+use crate::warnings::ChartWarning;
 use chrono::prelude::*;
 use js_sys::Reflect;
 use std::str::{from_utf8_unchecked, FromStr};
@@ -15,7 +31,7 @@ use wasm_bindgen::JsCast;
 #[derive(Copy, Clone)]
 pub enum DataType {
     Number,
-    DateTime { tz_offset: FixedOffset },
+    DateTime { time_zone: TimeZone },
     Date,
 }
 impl FromStr for DataType {
@@ -24,13 +40,10 @@ impl FromStr for DataType {
         match s.to_lowercase().as_str() {
             "number" => Ok(DataType::Number),
             "date" => Ok(DataType::Date),
-            "datetime" => {
-                let tz_offset_ms = js_sys::Date::new_0().get_timezone_offset() as i32 * 60;
-                Ok(DataType::DateTime {
-                    tz_offset: FixedOffset::west_opt(tz_offset_ms)
-                        .ok_or_else(|| "invalid timezone offset".to_string())?,
-                })
-            }
+            // see `ChartConfig::time_zone`
+            "datetime" => Ok(DataType::DateTime {
+                time_zone: TimeZone::Local,
+            }),
             v => Err(format!(
                 "invalid data type: '{}'; use 'number' or 'date'",
                 v
@@ -57,12 +70,23 @@ pub enum VerboseFormat {
         scale: usize,
     },
     NumberConcise,
+    NumberLabel {
+        format: ValueLabelFormat,
+        locale: NumberLocale,
+        precise: bool,
+    },
+    NumberUnit {
+        ladder: UnitLadder,
+        locale: NumberLocale,
+        precise: bool,
+        with_suffix: bool,
+    },
     Date {
         fmt_str: String,
     },
     DateTime {
         fmt_str: String,
-        tz_offset: FixedOffset,
+        time_zone: TimeZone,
     },
 }
 impl VerboseFormat {
@@ -75,9 +99,9 @@ impl VerboseFormat {
             DataType::Date => VerboseFormat::Date {
                 fmt_str: "%b %d, %Y".to_string(),
             },
-            DataType::DateTime { tz_offset } => VerboseFormat::DateTime {
+            DataType::DateTime { time_zone } => VerboseFormat::DateTime {
                 fmt_str: "%b %d, %Y %H:%M:%S".to_string(),
-                tz_offset: *tz_offset,
+                time_zone: *time_zone,
             },
             DataType::Number => {
                 if concise {
@@ -91,6 +115,32 @@ impl VerboseFormat {
             }
         }
     }
+    pub fn from_value_type(
+        data_type: &DataType,
+        chart_config: &ChartConfig,
+        concise: bool,
+    ) -> VerboseFormat {
+        if let (DataType::Number, Some(ladder)) = (data_type, &chart_config.y_unit) {
+            return Self::NumberUnit {
+                ladder: ladder.clone(),
+                locale: chart_config.number_locale,
+                precise: !concise,
+                with_suffix: !concise || !chart_config.y_unit_in_title,
+            };
+        }
+        match (data_type, chart_config.y_label_format) {
+            (DataType::Number, Some(format))
+                if concise || chart_config.y_label_format_in_tooltip =>
+            {
+                Self::NumberLabel {
+                    format,
+                    locale: chart_config.number_locale,
+                    precise: !concise,
+                }
+            }
+            _ => Self::from_data_type(data_type, chart_config, concise),
+        }
+    }
     pub fn format_values<T, U, F>(
         &self,
         values: T,
@@ -112,10 +162,11 @@ impl VerboseFormat {
                         .to_string()
                 })
                 .collect(),
-            Self::DateTime { fmt_str, tz_offset } => values
+            Self::DateTime { fmt_str, time_zone } => values
                 .map(getter)
                 .map(|value| {
-                    DateTime::<FixedOffset>::from_utc(NaiveDateTime::from_timestamp_millis(value as i64).unwrap(), *tz_offset)
+                    let offset = time_zone.get_fixed_offset(value);
+                    DateTime::<FixedOffset>::from_utc(NaiveDateTime::from_timestamp_millis(value as i64).unwrap(), offset)
                         .format(fmt_str)
                         .to_string()
                 })
@@ -148,59 +199,82 @@ impl VerboseFormat {
 
                 })
                 .collect(),
+            Self::NumberLabel {
+                format,
+                locale,
+                precise,
+            } => {
+                let values: Vec<f64> = values.map(getter).collect();
+                format_labels(values.as_slice(), *format, locale, *precise)
+            }
+            Self::NumberUnit {
+                ladder,
+                locale,
+                precise,
+                with_suffix,
+            } => {
+                let values: Vec<f64> = values.map(getter).collect();
+                format_unit_labels(values.as_slice(), ladder, locale, *precise, *with_suffix)
+            }
             Self::NumberConcise => {
-                if min_value < -1e12 || max_value > 1e12 {
-                    values
-                        .map(getter)
-                        .map(|value| format!("{:3.2e}", value))
-                        .collect()
-                } else {
-                    values
-                        .map(getter)
-                        .map(|value| {
-                            let mut index = 0;
-                            let mut value_abs = value.abs();
-                            if value_abs < 1e-12 {
-                                format!("{:3.2e}", value)
-                            } else {
-                                while value_abs >= 1000.0 {
-                                    index += 1;
-                                    value_abs *= 0.001
-                                }
-                                if value_abs < 10.0 {
-                                    unsafe {
-                                        format!(
-                                            "{:.2}{}",
-                                            value_abs * value.signum(),
-                                            SUFFIXES.get_unchecked(index)
-                                        )
-                                    }
-                                } else if value_abs < 100.0 {
-                                    unsafe {
-                                        format!(
-                                            "{:.1}{}",
-                                            value_abs * value.signum(),
-                                            SUFFIXES.get_unchecked(index)
-                                        )
-                                    }
-                                } else {
-                                    unsafe {
-                                        format!(
-                                            "{:.0}{}",
-                                            value_abs * value.signum(),
-                                            SUFFIXES.get_unchecked(index)
-                                        )
-                                    }
-                                }
-                            }
-                        })
-                        .collect()
-                }
+                let exponential = min_value < -1e12 || max_value > 1e12;
+                values.map(getter).map(|value| format_concise(value, 0, exponential)).collect()
+            }
+        }
+    }
+    /// Formats a value of `format_values` with `extra_decimals` more; None for
+    /// formats which pick their decimals themselves or have none.
+    pub fn format_with_extra_decimals(
+        &self,
+        value: f64,
+        extra_decimals: usize,
+        min_value: f64,
+        max_value: f64,
+    ) -> Option<String> {
+        match self {
+            Self::Number { precision, scale } => Self::Number {
+                precision: precision + extra_decimals,
+                scale: scale + extra_decimals,
             }
+            .format_values([value].into_iter(), |value| value, min_value, max_value)
+            .pop(),
+            Self::NumberConcise => Some(format_concise(
+                value,
+                extra_decimals,
+                min_value < -1e12 || max_value > 1e12,
+            )),
+            _ => None,
         }
     }
 }
 
+/// Formats like 1.25K, with `extra_decimals` more than the magnitude calls for.
+fn format_concise(value: f64, extra_decimals: usize, exponential: bool) -> String {
+    let value_abs = value.abs();
+    if exponential || value_abs < 1e-12 {
+        return format!("{:3.*e}", 2 + extra_decimals, value);
+    }
+    let mut index = 0;
+    let mut value_abs = value_abs;
+    while value_abs >= 1000.0 && index + 1 < SUFFIXES.len() {
+        index += 1;
+        value_abs *= 0.001
+    }
+    let decimals = if value_abs < 10.0 {
+        2
+    } else if value_abs < 100.0 {
+        1
+    } else {
+        0
+    };
+    format!(
+        "{:.*}{}",
+        decimals + extra_decimals,
+        value_abs * value.signum(),
+        SUFFIXES[index]
+    )
+}
+
 pub fn js_value_to_f64<O: Fn() -> String>(value: &JsValue, path: &O) -> Result<f64, String> {
     if let Some(v) = value.as_f64() {
         return Ok(v);
@@ -266,7 +340,7 @@ fn js_value_to_rgb<O: Fn() -> String>(value: &JsValue, path: &O) -> Result<(u8,
         js_value_to_u8(&items[2], &|| format!("{}.{}", path(), 2))?,
     ))
 }
-fn get_by_str_key<O: Fn() -> String>(
+pub fn get_by_str_key<O: Fn() -> String>(
     obj: &JsValue,
     key: &str,
     path: &O,
@@ -275,6 +349,55 @@ fn get_by_str_key<O: Fn() -> String>(
         .map_err(|_| format!("not an object to fetch: '{}'", path()))
 }
 
+pub fn get_optional_by_str_key<O: Fn() -> String>(
+    obj: &JsValue,
+    key: &str,
+    path: &O,
+) -> Result<Option<JsValue>, String> {
+    if obj.is_undefined() || obj.is_null() {
+        return Ok(None);
+    }
+    let value = get_by_str_key(obj, key, path)?;
+    if value.is_undefined() || value.is_null() {
+        Ok(None)
+    } else {
+        Ok(Some(value))
+    }
+}
+
+pub fn get_optional_string_by_str_key<O: Fn() -> String>(
+    obj: &JsValue,
+    key: &str,
+    path: &O,
+) -> Result<Option<String>, String> {
+    get_optional_by_str_key(obj, key, path)?
+        .map(|v| {
+            v.as_string()
+                .ok_or_else(|| format!("not a string: {}", path()))
+        })
+        .transpose()
+}
+
+pub fn get_optional_f64_by_str_key<O: Fn() -> String>(
+    obj: &JsValue,
+    key: &str,
+    path: &O,
+) -> Result<Option<f64>, String> {
+    get_optional_by_str_key(obj, key, path)?
+        .map(|v| js_value_to_f64(&v, path))
+        .transpose()
+}
+
+pub fn get_optional_bool_by_str_key<O: Fn() -> String>(
+    obj: &JsValue,
+    key: &str,
+    path: &O,
+) -> Result<Option<bool>, String> {
+    get_optional_by_str_key(obj, key, path)?
+        .map(|v| v.as_bool().ok_or_else(|| format!("not a bool: {}", path())))
+        .transpose()
+}
+
 fn get_string_by_str_key<O: Fn() -> String>(
     obj: &JsValue,
     key: &str,
@@ -342,6 +465,35 @@ fn get_rgba_by_str_key<O: Fn() -> String>(
     ))
 }
 
+/// Reads `[r, g, b]` or `[r, g, b, alpha]`.
+fn js_value_to_rgba<O: Fn() -> String>(
+    value: &JsValue,
+    path: &O,
+) -> Result<(u8, u8, u8, f64), String> {
+    let items: Vec<JsValue> = value
+        .clone()
+        .dyn_into::<js_sys::Array>()
+        .map_err(|_| format!("not an array: {}", path()))?
+        .iter()
+        .collect();
+    if items.len() != 3 && items.len() != 4 {
+        return Err(format!("color is an array of length 3 or 4: {}", path()));
+    }
+    let alpha = match items.get(3) {
+        Some(alpha) => js_value_to_f64(alpha, &|| format!("{}.3", path()))?,
+        None => 1.0,
+    };
+    if !(0.0..=1.0).contains(&alpha) {
+        return Err(format!("should be within [0, 1]: {}.3", path()));
+    }
+    Ok((
+        js_value_to_u8(&items[0], &|| format!("{}.0", path()))?,
+        js_value_to_u8(&items[1], &|| format!("{}.1", path()))?,
+        js_value_to_u8(&items[2], &|| format!("{}.2", path()))?,
+        alpha,
+    ))
+}
+
 pub fn parse_js_values<O: Fn() -> String>(
     value: js_sys::Array,
     data_type: DataType,
@@ -372,17 +524,170 @@ pub fn parse_js_values<O: Fn() -> String>(
     Ok(result)
 }
 
+/// Parses values like `parse_js_values`, null ones as gaps (None).
+pub fn parse_js_values_with_gaps<O: Fn() -> String>(
+    value: js_sys::Array,
+    data_type: DataType,
+    path: &O,
+) -> Result<Vec<Option<f64>>, String> {
+    let mut result = Vec::with_capacity(value.length() as usize);
+    for (index, item) in value.iter().enumerate() {
+        let item_path = || format!("{}.{}", path(), index);
+        result.push(match data_type {
+            _ if item.is_null() => None,
+            DataType::Number => Some(js_value_to_f64(&item, &item_path)?),
+            DataType::Date | DataType::DateTime { .. } => {
+                Some(js_value_to_date_as_f64(&item, &item_path)?)
+            }
+        });
+    }
+    Ok(result)
+}
+
+/// Parses markers like `[{coord, shape?, color?, size?}]`, the coords of
+/// `coord_type`.
+/// Parses [{part, minWidth, minHeight}] of the compactLayout config.
+fn parse_compact_steps(raw_steps: &JsValue) -> Result<Vec<CompactStep>, String> {
+    let raw_steps = raw_steps
+        .clone()
+        .dyn_into::<js_sys::Array>()
+        .map_err(|_| "not an array: compactLayout".to_string())?;
+    let mut steps = Vec::with_capacity(raw_steps.length() as usize);
+    for (index, raw_step) in raw_steps.iter().enumerate() {
+        let step_path = || format!("compactLayout.{}", index);
+        steps.push(CompactStep {
+            part: CompactPart::from_str(&get_string_by_str_key(&raw_step, "part", &|| {
+                format!("{}.part", step_path())
+            })?)?,
+            min_width: get_optional_f64_by_str_key(&raw_step, "minWidth", &|| {
+                format!("{}.minWidth", step_path())
+            })?
+            .unwrap_or(0.0),
+            min_height: get_optional_f64_by_str_key(&raw_step, "minHeight", &|| {
+                format!("{}.minHeight", step_path())
+            })?
+            .unwrap_or(0.0),
+        });
+    }
+    Ok(steps)
+}
+
+pub fn parse_point_markers<O: Fn() -> String>(
+    raw_markers: &JsValue,
+    coord_type: DataType,
+    path: &O,
+) -> Result<Vec<PointMarker>, String> {
+    let raw_markers = raw_markers
+        .clone()
+        .dyn_into::<js_sys::Array>()
+        .map_err(|_| format!("not an array: {}", path()))?;
+    let mut markers = Vec::with_capacity(raw_markers.length() as usize);
+    for (index, raw_marker) in raw_markers.iter().enumerate() {
+        let marker_path = || format!("{}.{}", path(), index);
+        let raw_coord =
+            get_by_str_key(&raw_marker, "coord", &|| format!("{}.coord", marker_path()))?;
+        let coord = parse_js_values(js_sys::Array::of1(&raw_coord), coord_type, &|| {
+            format!("{}.coord", marker_path())
+        })?[0];
+        let shape = MarkerShape::from_str(
+            get_optional_string_by_str_key(&raw_marker, "shape", &|| {
+                format!("{}.shape", marker_path())
+            })?
+            .as_deref()
+            .unwrap_or("circle"),
+        )?;
+        let rgb = match get_optional_by_str_key(&raw_marker, "color", &|| {
+            format!("{}.color", marker_path())
+        })? {
+            Some(raw_color) => Some(js_value_to_rgb(&raw_color, &|| {
+                format!("{}.color", marker_path())
+            })?),
+            None => None,
+        };
+        let size = get_optional_f64_by_str_key(&raw_marker, "size", &|| {
+            format!("{}.size", marker_path())
+        })?;
+        if size.is_some_and(|size| size <= 0.0) {
+            return Err(format!("should be positive: {}.size", marker_path()));
+        }
+        markers.push(PointMarker {
+            coord,
+            shape,
+            rgb,
+            size,
+        });
+    }
+    Ok(markers)
+}
+
+/// Reads `{type: "solid", color}` or `{type: "gradient", stops: [{offset,
+/// color}]}`; without a color or stops the series color fades out.
+fn parse_area_fill<O: Fn() -> String>(
+    raw_fill: &JsValue,
+    rgb: (u8, u8, u8),
+    path: &O,
+) -> Result<AreaFill, String> {
+    let fill_type = get_optional_string_by_str_key(raw_fill, "type", &|| {
+        format!("{}.type", path())
+    })?;
+    match fill_type.as_deref().unwrap_or("solid") {
+        "solid" => Ok(AreaFill::Solid(
+            match get_optional_by_str_key(raw_fill, "color", &|| format!("{}.color", path()))? {
+                Some(raw_color) => {
+                    js_value_to_rgba(&raw_color, &|| format!("{}.color", path()))?
+                }
+                None => (rgb.0, rgb.1, rgb.2, 0.25),
+            },
+        )),
+        "gradient" => {
+            let raw_stops =
+                get_optional_by_str_key(raw_fill, "stops", &|| format!("{}.stops", path()))?;
+            let stops = match raw_stops {
+                Some(raw_stops) => {
+                    let mut stops = Vec::new();
+                    for (index, raw_stop) in raw_stops
+                        .dyn_into::<js_sys::Array>()
+                        .map_err(|_| format!("not an array: {}.stops", path()))?
+                        .iter()
+                        .enumerate()
+                    {
+                        let stop_path = || format!("{}.stops.{}", path(), index);
+                        let offset = get_f64_by_str_key(&raw_stop, "offset", &|| {
+                            format!("{}.offset", stop_path())
+                        })?;
+                        let color = js_value_to_rgba(
+                            &get_by_str_key(&raw_stop, "color", &|| {
+                                format!("{}.color", stop_path())
+                            })?,
+                            &|| format!("{}.color", stop_path()),
+                        )?;
+                        stops.push((offset, color));
+                    }
+                    stops
+                }
+                None => vec![(0.0, (rgb.0, rgb.1, rgb.2, 0.5)), (1.0, (rgb.0, rgb.1, rgb.2, 0.0))],
+            };
+            AreaFill::gradient(stops).map_err(|e| format!("{}: {}", e, path()))
+        }
+        fill_type => Err(format!("unsupported fill type: {}", fill_type)),
+    }
+}
+
 pub struct Content {
     pub name: Option<String>,
     pub coord_type: DataType,
     pub coord_verbose_format: VerboseFormat,
     pub coord_verbose_format_short: VerboseFormat,
     pub coord_short_verbose_len: usize,
+    pub coord_granularity: Option<DateGranularity>,
     pub value_type: DataType,
     pub value_verbose_format: VerboseFormat,
     pub value_verbose_format_short: VerboseFormat,
     pub value_short_verbose_len: usize,
     pub data_sets: Vec<DataSet>,
+    /// Data sets declared without points, left out of everything until
+    /// `append_data` brings their first ones.
+    pub pending_data_sets: Vec<DataSet>,
     pub global_coord_min: f64,
     pub global_coord_max: f64,
     pub global_value_min: f64,
@@ -406,21 +711,61 @@ impl Content {
                 true,
             ),
             coord_short_verbose_len: 0,
+            coord_granularity: None,
             value_type,
-            value_verbose_format: VerboseFormat::from_data_type(&value_type, chart_config, false),
-            value_verbose_format_short: VerboseFormat::from_data_type(
+            value_verbose_format: VerboseFormat::from_value_type(&value_type, chart_config, false),
+            value_verbose_format_short: VerboseFormat::from_value_type(
                 &value_type,
                 chart_config,
                 true,
             ),
             value_short_verbose_len: 0,
             data_sets: Vec::new(),
+            pending_data_sets: Vec::new(),
             global_coord_min: f64::MAX,
             global_coord_max: f64::MIN,
             global_value_min: f64::MAX,
             global_value_max: f64::MIN,
         }
     }
+    /// Picks the date granularity of x labels by the tick step; the tooltip
+    /// of a datetime axis follows it.
+    pub fn update_coord_granularity(&mut self, step_ms: f64) {
+        let time_zone = match self.coord_type {
+            DataType::Number => return,
+            DataType::Date => None,
+            DataType::DateTime { time_zone } => Some(time_zone),
+        };
+        let granularity = DateGranularity::from_step(step_ms, self.coord_granularity);
+        if self.coord_granularity == Some(granularity) {
+            return;
+        }
+        self.coord_granularity = Some(granularity);
+        if let Some(time_zone) = time_zone {
+            self.coord_verbose_format = VerboseFormat::DateTime {
+                fmt_str: granularity.get_verbose_fmt().to_string(),
+                time_zone,
+            };
+        }
+    }
+    /// Adds a data set without points to `pending_data_sets`.
+    pub fn add_pending_data_set(
+        &mut self,
+        name: &str,
+        rgb: (u8, u8, u8),
+    ) -> Result<&mut DataSet, String> {
+        if self
+            .data_sets
+            .iter()
+            .chain(self.pending_data_sets.iter())
+            .any(|item| item.name == name)
+        {
+            return Err(format!("duplicate data set name: {}", name));
+        }
+        self.pending_data_sets
+            .push(DataSet::new(name, rgb, Vec::new()));
+        Ok(self.pending_data_sets.last_mut().unwrap())
+    }
     pub fn parse_and_add_data_set(
         &mut self,
         name: &str,
@@ -437,7 +782,12 @@ impl Content {
         if coords.is_empty() {
             return Err(format!("data set is empty: {}", name));
         }
-        if self.data_sets.iter().any(|item| item.name == name) {
+        if self
+            .data_sets
+            .iter()
+            .chain(self.pending_data_sets.iter())
+            .any(|item| item.name == name)
+        {
             return Err(format!("duplicate data set name: {}", name));
         }
         let mut data_points: Vec<DataPoint> = coords
@@ -501,6 +851,127 @@ impl Content {
         Ok(())
     }
 
+    /// Adds a copy of the last data set shifted by `offset` along x.
+    pub fn add_comparison(
+        &mut self,
+        name: &str,
+        offset: f64,
+        include_in_domain: bool,
+    ) -> Result<(), String> {
+        if self
+            .data_sets
+            .iter()
+            .chain(self.pending_data_sets.iter())
+            .any(|item| item.name == name)
+        {
+            return Err(format!("duplicate data set name: {}", name));
+        }
+        let original = self.data_sets.last().unwrap();
+        let data_points = original
+            .data_points
+            .iter()
+            .map(|p| DataPoint {
+                coord: p.coord + offset,
+                value: p.value,
+            })
+            .collect();
+        // halfway to the gray of the same luminance
+        let (r, g, b) = original.rgb;
+        let gray = 0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64;
+        let mute = |channel: u8| ((channel as f64 + gray) * 0.5).round() as u8;
+        let mut data_set = DataSet::new(name, (mute(r), mute(g), mute(b)), data_points);
+        data_set.add_gaps(original.gaps.iter().map(|gap| gap + offset).collect());
+        data_set.pattern = original.pattern;
+        data_set.unit = original.unit.clone();
+        data_set.comparison = Some(Comparison {
+            of: original.name.clone(),
+            include_in_domain,
+        });
+        if include_in_domain {
+            self.global_value_min = self.global_value_min.min(data_set.meta.min);
+            self.global_value_max = self.global_value_max.max(data_set.meta.max);
+        }
+        self.data_sets.push(data_set);
+        Ok(())
+    }
+    /// Appends points from an array like `[{name, coords, values}]`; either
+    /// all data sets get their points or none. Returns warnings of dropped or
+    /// clamped points and whether pending data sets got their first points,
+    /// after which the chart is created anew, see `DrawChart::take_rebuild`.
+    pub fn append_data(
+        &mut self,
+        raw_data_sets: &JsValue,
+        non_finite_values: NonFiniteValues,
+    ) -> Result<(Vec<ChartWarning>, bool), String> {
+        let raw_data_sets = raw_data_sets
+            .dyn_ref::<js_sys::Array>()
+            .ok_or_else(|| "dataSets is not an array".to_string())?;
+        let mut appends = Vec::with_capacity(raw_data_sets.length() as usize);
+        let mut all_gaps = Vec::with_capacity(raw_data_sets.length() as usize);
+        let mut warnings = Vec::new();
+        for (index, raw_data_set) in raw_data_sets.iter().enumerate() {
+            let name = get_string_by_str_key(&raw_data_set, "name", &|| {
+                format!("dataSets[{}].name", index)
+            })?;
+            let coords = get_array_by_str_key(&raw_data_set, "coords", &|| {
+                format!("dataSets[{}].coords", index)
+            })?;
+            let coords = parse_js_values(coords, self.coord_type, &|| {
+                format!("dataSets[{}].coords", index)
+            })?;
+            let values = get_array_by_str_key(&raw_data_set, "values", &|| {
+                format!("dataSets[{}].values", index)
+            })?;
+            let values = parse_js_values_with_gaps(values, self.value_type, &|| {
+                format!("dataSets[{}].values", index)
+            })?;
+            check_lengths(&coords, &values, format!("dataSets[{}]", index))?;
+            warnings.extend(ChartWarning::non_finite(
+                name.as_str(),
+                coords.as_slice(),
+                values.as_slice(),
+                format!("dataSets[{}]", index),
+            ));
+            let (coords, values) =
+                sanitize_non_finite(name.as_str(), coords, values, non_finite_values)?;
+            let (coords, values, gaps) = split_gaps(coords, values);
+            appends.push((name, coords, values));
+            all_gaps.push(gaps);
+        }
+        let names: Vec<String> = appends.iter().map(|(name, _, _)| name.clone()).collect();
+        let first_points =
+            append_by_name(&mut self.data_sets, &mut self.pending_data_sets, appends)?;
+        for (name, gaps) in names.iter().zip(all_gaps) {
+            if let Some(data_set) = self
+                .data_sets
+                .iter_mut()
+                .chain(self.pending_data_sets.iter_mut())
+                .find(|data_set| data_set.comparison.is_none() && data_set.name == *name)
+            {
+                data_set.add_gaps(gaps);
+            }
+        }
+        for data_set in self
+            .data_sets
+            .iter()
+            .filter(|data_set| data_set.comparison.is_none() && names.contains(&data_set.name))
+        {
+            let points = data_set.data_points.as_slice();
+            self.global_coord_min = self.global_coord_min.min(points[0].coord);
+            self.global_coord_max = self.global_coord_max.max(points[points.len() - 1].coord);
+            self.global_value_min = self.global_value_min.min(data_set.meta.min);
+            self.global_value_max = self.global_value_max.max(data_set.meta.max);
+        }
+        Ok((warnings, first_points))
+    }
+    /// Whether no data set but comparisons is visible.
+    pub fn only_comparisons_visible(&self) -> bool {
+        !self
+            .data_sets
+            .iter()
+            .any(|data_set| data_set.comparison.is_none() && data_set.alpha.get_end_value() > 0.0)
+    }
+
     pub fn sort_data_sets(&mut self, strategy: &DataSetSorting) {
         match strategy {
             DataSetSorting::MaxAsc => {
@@ -529,9 +1000,28 @@ impl Content {
             }
             DataSetSorting::None => {}
         }
+        // comparisons follow the compared data sets
+        let (comparisons, data_sets): (Vec<DataSet>, Vec<DataSet>) =
+            std::mem::take(&mut self.data_sets)
+                .into_iter()
+                .partition(|data_set| data_set.comparison.is_some());
+        self.data_sets = data_sets;
+        for comparison in comparisons {
+            let of = &comparison.comparison.as_ref().unwrap().of;
+            let index = self
+                .data_sets
+                .iter()
+                .rposition(|data_set| {
+                    &data_set.name == of
+                        || data_set.comparison.as_ref().map(|c| &c.of) == Some(of)
+                })
+                .map_or(self.data_sets.len(), |index| index + 1);
+            self.data_sets.insert(index, comparison);
+        }
     }
 }
 
+#[derive(Clone)]
 pub enum DataSetSorting {
     MaxAsc,
     MaxDesc,
@@ -557,93 +1047,891 @@ impl FromStr for DataSetSorting {
     }
 }
 
-pub struct ChartConfig {
-    pub font_standard: String,
-    pub font_monospace: String,
-    pub font_size_small: f64,
-    pub font_size_normal: f64,
-    pub font_size_large: f64,
-    pub font_width_coeff: f64,
-    pub line_width: f64,
-    pub circle_diameter: f64,
-    pub color_grid: (u8, u8, u8),
-    pub color_tick: (u8, u8, u8),
-    pub color_camera_grip: (u8, u8, u8, f64),
-    pub color_preview_overlay: (u8, u8, u8, f64),
-    pub color_preview_hint: (u8, u8, u8, f64),
-    pub color_tooltip: (u8, u8, u8, f64),
-    pub color_tooltip_font: (u8, u8, u8, f64),
-    pub sort_data_sets_by: DataSetSorting,
-    pub layout_content_height: f64,
-    pub layout_preview_height: f64,
-    pub layout_legend_height: f64,
-    pub color_palette: Vec<(u8, u8, u8)>,
-    pub us_long_press: f64,
-    pub auto_log_scale_threshold: f64,
-    pub exp_fmt_significant_digits: usize,
+/// What to do with NaN and infinite coordinates and values of the input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NonFiniteValues {
+    /// Reject the data set, reporting its name and the index.
+    Error,
+    /// Leave the point out.
+    Drop,
+    /// Replace infinite values with the finite min or max of the data set;
+    /// points with NaN values or non-finite coordinates are left out.
+    Clamp,
+}
+impl FromStr for NonFiniteValues {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Self::Error),
+            "drop" => Ok(Self::Drop),
+            "clamp" => Ok(Self::Clamp),
+            v => Err(format!("unsupported non-finite values policy: {}", v)),
+        }
+    }
 }
-impl ChartConfig {
-    pub fn from_raw(raw_config: &JsValue) -> Result<Self, String> {
-        let layout_content_height = get_f64_by_str_key(raw_config, "layoutContentHeight", &|| {
-            "layoutContentHeight".to_string()
-        })?;
-        let layout_preview_height = get_f64_by_str_key(raw_config, "layoutPreviewHeight", &|| {
-            "layoutPreviewHeight".to_string()
-        })?;
-        let layout_legend_height = get_f64_by_str_key(raw_config, "layoutLegendHeight", &|| {
-            "layoutLegendHeight".to_string()
-        })?;
-        let total_height_norm =
-            (layout_content_height + layout_preview_height + layout_legend_height).recip() * 100.0;
 
-        let color_palette: Result<Vec<(u8, u8, u8)>, String> =
-            get_array_by_str_key(raw_config, "colorPalette", &|| "colorPalette".to_string())?
-                .iter()
-                .enumerate()
-                .map(|(index, item)| js_value_to_rgb(&item, &|| format!("colorPalette.{}", index)))
-                .collect();
+#[derive(Clone)]
+pub enum LabelOverlap {
+    Skip,
+    Rotate,
+}
+impl FromStr for LabelOverlap {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "rotate" => Ok(Self::Rotate),
+            v => Err(format!("unsupported label overlap strategy: {}", v)),
+        }
+    }
+}
 
-        Ok(Self {
-            font_standard: get_string_by_str_key(raw_config, "fontStandard", &|| {
-                "fontStandard".to_string()
-            })?,
-            font_monospace: get_string_by_str_key(raw_config, "fontMonospace", &|| {
-                "fontMonospace".to_string()
-            })?,
-            font_size_small: get_f64_by_str_key(raw_config, "fontSizeSmall", &|| {
-                "fontSizeSmall".to_string()
-            })?,
-            font_size_normal: get_f64_by_str_key(raw_config, "fontSizeNormal", &|| {
-                "fontSizeNormal".to_string()
-            })?,
-            font_size_large: get_f64_by_str_key(raw_config, "fontSizeLarge", &|| {
-                "fontSizeLarge".to_string()
-            })?,
-            font_width_coeff: get_f64_by_str_key(raw_config, "fontWidthCoeff", &|| {
-                "fontWidthCoeff".to_string()
-            })?,
-            line_width: get_f64_by_str_key(raw_config, "lineWidth", &|| "lineWidth".to_string())?,
-            circle_diameter: get_f64_by_str_key(raw_config, "circleRadius", &|| {
-                "circleRadius".to_string()
-            })? * 2.0,
-            color_grid: get_rgb_by_str_key(raw_config, "colorGrid", &|| "colorGrid".to_string())?,
-            color_tick: get_rgb_by_str_key(raw_config, "colorTick", &|| "colorTick".to_string())?,
-            color_camera_grip: get_rgba_by_str_key(raw_config, "colorCameraGrip", &|| {
-                "colorCameraGrip".to_string()
-            })?,
-            color_preview_overlay: get_rgba_by_str_key(raw_config, "colorPreviewOverlay", &|| {
-                "colorPreviewOverlay".to_string()
-            })?,
-            color_preview_hint: get_rgba_by_str_key(raw_config, "colorPreviewHint", &|| {
-                "colorPreviewHint".to_string()
-            })?,
-            color_tooltip: get_rgba_by_str_key(raw_config, "colorTooltip", &|| {
-                "colorTooltip".to_string()
-            })?,
-            color_tooltip_font: get_rgba_by_str_key(raw_config, "colorTooltipFont", &|| {
-                "colorTooltipFont".to_string()
-            })?,
-            sort_data_sets_by: DataSetSorting::from_str(&get_string_by_str_key(
+#[derive(Clone, Copy, PartialEq)]
+pub enum LegendOverflow {
+    Page,
+    Wrap,
+    Scroll,
+}
+impl FromStr for LegendOverflow {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "page" => Ok(Self::Page),
+            "wrap" => Ok(Self::Wrap),
+            "scroll" => Ok(Self::Scroll),
+            v => Err(format!(
+                "unsupported legend overflow: '{}'; use 'page', 'wrap' or 'scroll'",
+                v
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PointLabelsShow {
+    Auto,
+    Always,
+    Never,
+}
+impl FromStr for PointLabelsShow {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            v => Err(format!(
+                "unsupported point labels mode: '{}'; use 'auto', 'always' or 'never'",
+                v
+            )),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum YAxisPosition {
+    Left,
+    Right,
+    Both,
+}
+impl FromStr for YAxisPosition {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            "both" => Ok(Self::Both),
+            v => Err(format!("unsupported y axis position: {}", v)),
+        }
+    }
+}
+impl YAxisPosition {
+    pub fn mirrored(&self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+            Self::Both => Self::Both,
+        }
+    }
+    pub fn has_left(&self) -> bool {
+        matches!(self, Self::Left | Self::Both)
+    }
+    pub fn has_right(&self) -> bool {
+        matches!(self, Self::Right | Self::Both)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+impl FromStr for Corner {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "top-left" => Ok(Self::TopLeft),
+            "top-right" => Ok(Self::TopRight),
+            "bottom-left" => Ok(Self::BottomLeft),
+            "bottom-right" => Ok(Self::BottomRight),
+            v => Err(format!(
+                "unsupported corner: '{}'; use 'top-left', 'top-right', 'bottom-left' or 'bottom-right'",
+                v
+            )),
+        }
+    }
+}
+impl Corner {
+    pub fn is_left(&self) -> bool {
+        matches!(self, Self::TopLeft | Self::BottomLeft)
+    }
+    pub fn is_top(&self) -> bool {
+        matches!(self, Self::TopLeft | Self::TopRight)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum WatermarkLayer {
+    BelowSeries,
+    AboveSeries,
+}
+impl FromStr for WatermarkLayer {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "below" => Ok(Self::BelowSeries),
+            "above" => Ok(Self::AboveSeries),
+            v => Err(format!(
+                "unsupported watermark layer: '{}'; use 'below' or 'above'",
+                v
+            )),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum TooltipMode {
+    Canvas,
+    Html,
+}
+impl FromStr for TooltipMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "canvas" => Ok(Self::Canvas),
+            "html" => Ok(Self::Html),
+            v => Err(format!("unsupported tooltip mode: {}", v)),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum TooltipPosition {
+    Pointer,
+    Point,
+}
+impl FromStr for TooltipPosition {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pointer" => Ok(Self::Pointer),
+            "point" => Ok(Self::Point),
+            v => Err(format!("unsupported tooltip position: {}", v)),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum ChangeBase {
+    Previous,
+    First,
+}
+impl FromStr for ChangeBase {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "previous" => Ok(Self::Previous),
+            "first" => Ok(Self::First),
+            v => Err(format!("unsupported change base: {}", v)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TooltipSort {
+    SeriesOrder,
+    ValueDesc,
+    ValueAsc,
+}
+impl FromStr for TooltipSort {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "seriesOrder" => Ok(Self::SeriesOrder),
+            "valueDesc" => Ok(Self::ValueDesc),
+            "valueAsc" => Ok(Self::ValueAsc),
+            v => Err(format!("unsupported tooltipSort: {}", v)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Theme {
+    Light,
+    Dark,
+    Auto,
+}
+impl FromStr for Theme {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "light" => Ok(Self::Light),
+            "dark" => Ok(Self::Dark),
+            "auto" => Ok(Self::Auto),
+            v => Err(format!(
+                "unsupported theme: '{}'; use 'light', 'dark' or 'auto'",
+                v
+            )),
+        }
+    }
+}
+
+/// What the plot shows where the window extends past the data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EdgeBehavior {
+    /// Lines stop at their first and last points.
+    Clip,
+    /// Lines ending before the window continue their last value dashed.
+    ExtendLast,
+    /// The parts of the plot without data of any visible series are hatched.
+    Fade,
+}
+impl FromStr for EdgeBehavior {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clip" => Ok(Self::Clip),
+            "extendLast" => Ok(Self::ExtendLast),
+            "fade" => Ok(Self::Fade),
+            v => Err(format!("unsupported edgeBehavior: {}", v)),
+        }
+    }
+}
+
+/// What creating a chart in a container which already holds one does.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ContainerReuse {
+    /// Fails with `CONTAINER_ALREADY_USED` and the id of the existing chart.
+    Error,
+    /// Destroys the existing chart first.
+    Replace,
+}
+impl FromStr for ContainerReuse {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Self::Error),
+            "replace" => Ok(Self::Replace),
+            v => Err(format!(
+                "unsupported containerReuse: '{}'; use 'error' or 'replace'",
+                v
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+impl FromStr for Direction {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ltr" => Ok(Self::Ltr),
+            "rtl" => Ok(Self::Rtl),
+            v => Err(format!(
+                "unsupported direction: '{}'; use 'ltr' or 'rtl'",
+                v
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Contrast {
+    Normal,
+    High,
+}
+impl FromStr for Contrast {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "high" => Ok(Self::High),
+            v => Err(format!(
+                "unsupported contrast: '{}'; use 'normal' or 'high'",
+                v
+            )),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ChartConfig {
+    pub font_standard: String,
+    pub font_monospace: String,
+    pub font_size_small: f64,
+    pub font_size_normal: f64,
+    pub font_size_large: f64,
+    pub font_width_coeff: f64,
+    pub line_width: f64,
+    pub circle_diameter: f64,
+    pub color_grid: (u8, u8, u8),
+    pub color_tick: (u8, u8, u8),
+    pub color_camera_grip: (u8, u8, u8, f64),
+    pub color_preview_overlay: (u8, u8, u8, f64),
+    pub color_preview_hint: (u8, u8, u8, f64),
+    pub color_preview_mask: (u8, u8, u8, f64),
+    pub color_preview_window: (u8, u8, u8, f64),
+    pub color_zero_line: (u8, u8, u8, f64),
+    pub color_negative_region: (u8, u8, u8, f64),
+    /// Dims the main screen of a loading chart.
+    pub color_loading_overlay: (u8, u8, u8, f64),
+    pub color_tooltip: (u8, u8, u8, f64),
+    pub color_tooltip_font: (u8, u8, u8, f64),
+    pub sort_data_sets_by: DataSetSorting,
+    pub layout_content_height: f64,
+    pub layout_preview_height: f64,
+    pub layout_legend_height: f64,
+    pub preview_position: PreviewPosition,
+    /// A bar between the main plot and the preview, dragged to trade their
+    /// heights.
+    pub preview_splitter: bool,
+    /// Selector of an element the preview goes into instead of the wrapper;
+    /// the position and the splitter do not apply then.
+    pub preview_container: Option<String>,
+    /// Parts dropped from small containers, see `DroppedParts`.
+    pub compact_layout: Vec<CompactStep>,
+    pub color_palette: Vec<(u8, u8, u8)>,
+    pub us_long_press: f64,
+    pub auto_log_scale_threshold: f64,
+    pub exp_fmt_significant_digits: usize,
+    pub x_label_overlap: LabelOverlap,
+    pub non_finite_values: NonFiniteValues,
+    pub x_label_min_gap: f64,
+    pub x_label_rotation: f64,
+    pub y_axis_position: YAxisPosition,
+    pub y_label_format: Option<ValueLabelFormat>,
+    pub y_label_format_in_tooltip: bool,
+    /// Values are in the base unit of the ladder; takes precedence over
+    /// `y_label_format`.
+    pub y_unit: Option<UnitLadder>,
+    /// The unit of the axis goes to the y axis title instead of the labels.
+    pub y_unit_in_title: bool,
+    pub number_locale: NumberLocale,
+    pub tooltip_mode: TooltipMode,
+    pub tooltip_position: TooltipPosition,
+    pub render_tooltip: Option<js_sys::Function>,
+    pub tooltip_show_change: bool,
+    pub tooltip_change_base: ChangeBase,
+    pub tooltip_sort: TooltipSort,
+    /// Rows past it are collapsed into one, see `select_tooltip_rows`.
+    pub tooltip_max_entries: Option<usize>,
+    pub tooltip_hide_zero: bool,
+    pub x_axis_title: Option<String>,
+    pub y_axis_title: Option<String>,
+    pub x_tick_spacing: Option<f64>,
+    pub grid_minor_show: bool,
+    pub grid_minor_count: usize,
+    pub grid_minor_line_width: f64,
+    pub color_grid_minor: (u8, u8, u8, f64),
+    pub legend_overflow: LegendOverflow,
+    pub point_labels_show: PointLabelsShow,
+    pub point_labels_min_spacing: f64,
+    pub accessible_table: bool,
+    pub accessible_table_max_rows: usize,
+    pub describe_chart: Option<js_sys::Function>,
+    /// Called with the chart id when the chart is destroyed because its
+    /// container left the document.
+    pub on_auto_destroy: Option<js_sys::Function>,
+    /// Callbacks by event kind, see `EVENT_KINDS`.
+    pub event_callbacks: Vec<(&'static str, js_sys::Function)>,
+    /// Events are also dispatched on the wrapper as DOM events.
+    pub dom_events: bool,
+    /// ms between two deliveries of an event kind, by kind.
+    pub event_throttle_ms: Vec<(&'static str, f64)>,
+    /// ms the hovered point has to stay the same before the hover event is
+    /// sent, 0 to send it at once; the tooltip itself is not delayed.
+    pub hover_settle_ms: f64,
+    pub theme: Option<Theme>,
+    pub contrast: Option<Contrast>,
+    pub high_contrast_line_width_coeff: f64,
+    /// Scales strokes which are not configurable, e.g. the grid.
+    pub stroke_width_coeff: f64,
+    /// None until detected from the container.
+    pub direction: Option<Direction>,
+    pub rtl_reverse_x_axis: bool,
+    pub strings: Strings,
+    pub series_patterns: bool,
+    pub hit_scale: Option<f64>,
+    pub hover_highlight: bool,
+    /// Dims the preview outside of the window instead of the hint overlay.
+    pub preview_mask: bool,
+    pub preview_hover_marker: bool,
+    /// A line at y = 0 above the grid, when 0 is in the y domain.
+    pub zero_line_show: bool,
+    pub zero_line_width: f64,
+    /// Tints the plot below y = 0, when 0 is in the y domain.
+    pub negative_region_fill: bool,
+    pub watermark_text: Option<String>,
+    /// URL of an image, exclusive with the text.
+    pub watermark_image: Option<String>,
+    pub watermark_position: Corner,
+    pub watermark_opacity: f64,
+    pub watermark_scale: f64,
+    pub watermark_layer: WatermarkLayer,
+    /// Size of point markers without one, in apx.
+    pub point_marker_size: f64,
+    /// Opacity of the data sets other than the highlighted one.
+    pub hover_dim_opacity: f64,
+    /// ms data sets take to fade in or out, 0 for no animation.
+    pub series_animation_duration: f64,
+    /// Data sets fading in also grow from the baseline, on creation too.
+    pub series_enter_grow: bool,
+    /// ms until a container without size is reported, 0 to never report.
+    pub zero_size_warning_timeout: f64,
+    /// ms a chart stays hidden before its canvases and caches are released,
+    /// 0 to keep them.
+    pub memory_trim_delay: f64,
+    /// Value ranges used by zooms are computed while the browser is idle.
+    pub idle_precompute: bool,
+    /// createMain returns {chartId, warnings} and warnings are kept for
+    /// getWarnings instead of being logged, see `Warnings`.
+    pub return_warnings: bool,
+    /// Wrapper ids come from a counter instead of being random.
+    pub deterministic: bool,
+    /// The chart starts with the loading overlay, see `set_loading`.
+    pub initial_loading: bool,
+    pub container_reuse: ContainerReuse,
+    pub edge_behavior: EdgeBehavior,
+    /// The zone datetime coords are shown in.
+    pub time_zone: TimeZone,
+    /// Charts of the same group share their tooltip, see `shared_tooltip`.
+    pub link_group: Option<String>,
+    /// The tooltip of the hovered chart lists the other charts of its link
+    /// group too.
+    pub shared_tooltip: bool,
+    /// Charts of the link group with it share the union of their y domains,
+    /// see `get_shared_y_domains`.
+    pub align_y: bool,
+    /// Class given to elements with data-graphima-series of the hovered
+    /// series, see `HoverLink`; off when None.
+    pub hover_link_class: Option<String>,
+    /// Selector of the element those are looked up in, the document if
+    /// None.
+    pub hover_link_root: Option<String>,
+    pub interactions: Interactions,
+    pub follow: Follow,
+    /// Caps the device pixel ratio canvases are rendered at.
+    pub max_render_pixel_ratio: Option<f64>,
+    /// Caps the longer side of a canvas, in physical pixels.
+    pub max_canvas_dimension_px: Option<f64>,
+    /// The config as passed, copied through JSON for `export_definition`.
+    pub raw: JsValue,
+}
+impl ChartConfig {
+    /// Returns the color scheme the theme stands for; None when colors come
+    /// from the config as they are.
+    pub fn resolve_color_scheme(&self, client_caps: &ClientCaps) -> Option<ColorScheme> {
+        match self.theme? {
+            Theme::Light => Some(ColorScheme::Light),
+            Theme::Dark => Some(ColorScheme::Dark),
+            Theme::Auto => Some(client_caps.color_scheme),
+        }
+    }
+    /// Follows the user preference unless the config forces the contrast.
+    pub fn resolve_contrast(&self, client_caps: &ClientCaps) -> Contrast {
+        self.contrast.unwrap_or(if client_caps.more_contrast {
+            Contrast::High
+        } else {
+            Contrast::Normal
+        })
+    }
+    /// The config overrides the factor derived from the pointer.
+    pub fn resolve_hit_scale(&self, client_caps: &ClientCaps) -> f64 {
+        self.hit_scale
+            .unwrap_or_else(|| client_caps.get_hit_scale())
+    }
+    /// Whether space is reserved for the y axis title.
+    pub fn has_y_axis_title(&self) -> bool {
+        self.y_axis_title.is_some() || self.y_unit.is_some() && self.y_unit_in_title
+    }
+    pub fn is_rtl(&self) -> bool {
+        self.direction == Some(Direction::Rtl)
+    }
+    /// Whether newer values are on the left.
+    pub fn is_x_axis_reversed(&self) -> bool {
+        self.is_rtl() && self.rtl_reverse_x_axis
+    }
+    /// Returns a copy with the theme, the contrast and the direction applied
+    /// for the client.
+    pub fn resolve(&self, client_caps: &ClientCaps) -> Self {
+        let mut chart_config = self.clone();
+        if self.is_rtl() {
+            chart_config.y_axis_position = self.y_axis_position.mirrored();
+        }
+        if let Some(color_scheme) = self.resolve_color_scheme(client_caps) {
+            chart_config.apply_color_scheme(color_scheme);
+        }
+        if self.resolve_contrast(client_caps) == Contrast::High {
+            chart_config.apply_high_contrast();
+        }
+        chart_config
+    }
+    /// Thickens strokes and markers, makes colors opaque and pulls the grid
+    /// and ticks towards the font color.
+    pub fn apply_high_contrast(&mut self) {
+        let coeff = self.high_contrast_line_width_coeff;
+        self.line_width *= coeff;
+        self.grid_minor_line_width *= coeff;
+        self.zero_line_width *= coeff;
+        self.circle_diameter *= coeff;
+        self.stroke_width_coeff = coeff;
+
+        let v = self.color_tooltip_font;
+        let mix = |color: (u8, u8, u8), ratio: f64| -> (u8, u8, u8) {
+            let mix_channel =
+                |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * ratio).round() as u8;
+            (
+                mix_channel(color.0, v.0),
+                mix_channel(color.1, v.1),
+                mix_channel(color.2, v.2),
+            )
+        };
+        self.color_grid = mix(self.color_grid, 0.4);
+        self.color_tick = (v.0, v.1, v.2);
+        let grid_minor = mix(
+            (
+                self.color_grid_minor.0,
+                self.color_grid_minor.1,
+                self.color_grid_minor.2,
+            ),
+            0.2,
+        );
+        self.color_grid_minor = (grid_minor.0, grid_minor.1, grid_minor.2, 1.0);
+        self.color_camera_grip.3 = (self.color_camera_grip.3 * 2.0).min(1.0);
+        self.color_preview_overlay.3 = (self.color_preview_overlay.3 * 1.5).min(1.0);
+        self.color_preview_hint.3 = 1.0;
+        self.color_preview_mask.3 = (self.color_preview_mask.3 * 1.5).min(1.0);
+        self.color_preview_window.3 = 1.0;
+        self.color_zero_line = (v.0, v.1, v.2, 1.0);
+        self.color_negative_region.3 = (self.color_negative_region.3 * 1.5).min(1.0);
+        self.color_tooltip.3 = 1.0;
+        self.color_tooltip_font.3 = 1.0;
+        self.color_loading_overlay.3 = self.color_loading_overlay.3.max(0.85);
+    }
+    pub fn get_event_sinks(&self) -> EventSinks {
+        EventSinks {
+            dom_events: self.dom_events,
+            callbacks: self.event_callbacks.clone(),
+            throttle_ms: self.event_throttle_ms.clone(),
+        }
+    }
+    pub fn get_resolution_cap(&self) -> ResolutionCap {
+        ResolutionCap {
+            max_pixel_ratio: self.max_render_pixel_ratio,
+            max_canvas_dimension: self.max_canvas_dimension_px,
+        }
+    }
+    /// Overrides the colors with the preset of the scheme; the palette is
+    /// kept.
+    pub fn apply_color_scheme(&mut self, color_scheme: ColorScheme) {
+        match color_scheme {
+            ColorScheme::Light => {
+                self.color_grid = (237, 237, 237);
+                self.color_tick = (142, 142, 142);
+                self.color_camera_grip = (0, 0, 255, 0.15);
+                self.color_preview_overlay = (0, 0, 0, 0.4);
+                self.color_preview_hint = (255, 255, 255, 1.0);
+                self.color_preview_mask = (0, 0, 0, 0.15);
+                self.color_preview_window = (0, 0, 255, 0.5);
+                self.color_zero_line = (142, 142, 142, 1.0);
+                self.color_negative_region = (255, 0, 0, 0.05);
+                self.color_loading_overlay = (255, 255, 255, 0.7);
+                self.color_tooltip = (255, 255, 255, 1.0);
+                self.color_tooltip_font = (0, 0, 0, 1.0);
+            }
+            ColorScheme::Dark => {
+                self.color_grid = (55, 57, 62);
+                self.color_tick = (150, 152, 158);
+                self.color_camera_grip = (110, 150, 255, 0.25);
+                self.color_preview_overlay = (0, 0, 0, 0.55);
+                self.color_preview_hint = (255, 255, 255, 1.0);
+                self.color_preview_mask = (0, 0, 0, 0.35);
+                self.color_preview_window = (110, 150, 255, 0.7);
+                self.color_zero_line = (150, 152, 158, 1.0);
+                self.color_negative_region = (255, 90, 90, 0.08);
+                self.color_loading_overlay = (32, 33, 36, 0.7);
+                self.color_tooltip = (32, 33, 36, 1.0);
+                self.color_tooltip_font = (232, 234, 237, 1.0);
+            }
+        }
+        self.color_grid_minor = (
+            self.color_grid.0,
+            self.color_grid.1,
+            self.color_grid.2,
+            0.4,
+        );
+    }
+    pub fn from_raw(raw_config: &JsValue) -> Result<Self, String> {
+        let color_grid = get_rgb_by_str_key(raw_config, "colorGrid", &|| "colorGrid".to_string())?;
+        let render_tooltip =
+            get_optional_by_str_key(raw_config, "renderTooltip", &|| "renderTooltip".to_string())?
+                .map(|v| {
+                    v.dyn_into::<js_sys::Function>()
+                        .map_err(|_| "not a function: renderTooltip".to_string())
+                })
+                .transpose()?;
+        let describe_chart =
+            get_optional_by_str_key(raw_config, "describeChart", &|| "describeChart".to_string())?
+                .map(|v| {
+                    v.dyn_into::<js_sys::Function>()
+                        .map_err(|_| "not a function: describeChart".to_string())
+                })
+                .transpose()?;
+        let on_auto_destroy =
+            get_optional_by_str_key(raw_config, "onAutoDestroy", &|| "onAutoDestroy".to_string())?
+                .map(|v| {
+                    v.dyn_into::<js_sys::Function>()
+                        .map_err(|_| "not a function: onAutoDestroy".to_string())
+                })
+                .transpose()?;
+        let mut event_callbacks = Vec::new();
+        for (kind, key) in EVENT_KINDS.iter() {
+            if let Some(v) = get_optional_by_str_key(raw_config, key, &|| key.to_string())? {
+                let callback = v
+                    .dyn_into::<js_sys::Function>()
+                    .map_err(|_| format!("not a function: {}", key))?;
+                event_callbacks.push((*kind, callback));
+            }
+        }
+        let mut event_throttle_ms = Vec::new();
+        if let Some(raw_throttle) =
+            get_optional_by_str_key(raw_config, "eventThrottleMs", &|| {
+                "eventThrottleMs".to_string()
+            })?
+        {
+            let raw_throttle = raw_throttle
+                .dyn_into::<js_sys::Object>()
+                .map_err(|_| "not an object: eventThrottleMs".to_string())?;
+            for key in js_sys::Object::keys(&raw_throttle).iter() {
+                let key = key.as_string().unwrap();
+                let kind = EVENT_KINDS
+                    .iter()
+                    .map(|(kind, _)| *kind)
+                    .find(|kind| *kind == key.as_str())
+                    .ok_or_else(|| format!("unknown key: eventThrottleMs.{}", key))?;
+                let throttle_ms = get_f64_by_str_key(&raw_throttle, kind, &|| {
+                    format!("eventThrottleMs.{}", kind)
+                })?;
+                event_throttle_ms.push((kind, throttle_ms.max(0.0)));
+            }
+        }
+        let layout_content_height = get_f64_by_str_key(raw_config, "layoutContentHeight", &|| {
+            "layoutContentHeight".to_string()
+        })?;
+        let layout_preview_height = get_f64_by_str_key(raw_config, "layoutPreviewHeight", &|| {
+            "layoutPreviewHeight".to_string()
+        })?;
+        let layout_legend_height = get_f64_by_str_key(raw_config, "layoutLegendHeight", &|| {
+            "layoutLegendHeight".to_string()
+        })?;
+        let total_height_norm =
+            (layout_content_height + layout_preview_height + layout_legend_height).recip() * 100.0;
+
+        let parse_palette = |key: &str| -> Result<Vec<(u8, u8, u8)>, String> {
+            get_array_by_str_key(raw_config, key, &|| key.to_string())?
+                .iter()
+                .enumerate()
+                .map(|(index, item)| js_value_to_rgb(&item, &|| format!("{}.{}", key, index)))
+                .collect()
+        };
+        // "palette" picks a preset or lists colors, colorPalette is the default
+        let color_palette = match get_optional_by_str_key(raw_config, "palette", &|| {
+            "palette".to_string()
+        })? {
+            Some(palette) => match palette.as_string() {
+                Some(palette) => match palette.as_str() {
+                    "default" => parse_palette("colorPalette"),
+                    "colorblind_safe" => Ok(COLORBLIND_SAFE_PALETTE.to_vec()),
+                    v => Err(format!(
+                        "unsupported palette: '{}'; use 'default', 'colorblind_safe' or colors",
+                        v
+                    )),
+                },
+                None => parse_palette("palette"),
+            },
+            None => parse_palette("colorPalette"),
+        }
+        .and_then(|color_palette| {
+            if color_palette.is_empty() {
+                Err("palette is empty".to_string())
+            } else {
+                Ok(color_palette)
+            }
+        });
+        let watermark_text =
+            get_optional_string_by_str_key(raw_config, "watermarkText", &|| {
+                "watermarkText".to_string()
+            })?;
+        let watermark_image =
+            get_optional_string_by_str_key(raw_config, "watermarkImage", &|| {
+                "watermarkImage".to_string()
+            })?;
+        if watermark_text.is_some() && watermark_image.is_some() {
+            return Err("watermarkText and watermarkImage are mutually exclusive".to_string());
+        }
+        let preview_position = get_optional_string_by_str_key(
+            raw_config,
+            "previewPosition",
+            &|| "previewPosition".to_string(),
+        )?
+        .map_or(Ok(PreviewPosition::Bottom), |v| PreviewPosition::from_str(v.as_str()))?;
+        let preview_splitter =
+            get_optional_bool_by_str_key(raw_config, "previewSplitter", &|| {
+                "previewSplitter".to_string()
+            })?
+            .unwrap_or(false);
+        let preview_container = get_optional_string_by_str_key(
+            raw_config,
+            "previewContainer",
+            &|| "previewContainer".to_string(),
+        )?;
+        let compact_layout = match get_optional_by_str_key(raw_config, "compactLayout", &|| {
+            "compactLayout".to_string()
+        })? {
+            Some(raw_steps) => {
+                let steps = parse_compact_steps(&raw_steps)?;
+                if preview_container.is_some()
+                    && steps.iter().any(|step| step.part == CompactPart::Preview)
+                {
+                    return Err(
+                        "the preview of previewContainer is not compacted: compactLayout"
+                            .to_string(),
+                    );
+                }
+                steps
+            }
+            // the preview of previewContainer keeps its size
+            None => DEFAULT_COMPACT_STEPS
+                .into_iter()
+                .filter(|step| preview_container.is_none() || step.part != CompactPart::Preview)
+                .collect(),
+        };
+        if preview_container.is_some() {
+            if preview_splitter {
+                return Err(
+                    "previewSplitter and previewContainer are mutually exclusive".to_string(),
+                );
+            }
+            if preview_position != PreviewPosition::Bottom {
+                return Err(
+                    "previewPosition and previewContainer are mutually exclusive".to_string(),
+                );
+            }
+        }
+
+        Ok(Self {
+            font_standard: get_string_by_str_key(raw_config, "fontStandard", &|| {
+                "fontStandard".to_string()
+            })?,
+            font_monospace: get_string_by_str_key(raw_config, "fontMonospace", &|| {
+                "fontMonospace".to_string()
+            })?,
+            font_size_small: get_f64_by_str_key(raw_config, "fontSizeSmall", &|| {
+                "fontSizeSmall".to_string()
+            })?,
+            font_size_normal: get_f64_by_str_key(raw_config, "fontSizeNormal", &|| {
+                "fontSizeNormal".to_string()
+            })?,
+            font_size_large: get_f64_by_str_key(raw_config, "fontSizeLarge", &|| {
+                "fontSizeLarge".to_string()
+            })?,
+            font_width_coeff: get_f64_by_str_key(raw_config, "fontWidthCoeff", &|| {
+                "fontWidthCoeff".to_string()
+            })?,
+            line_width: get_f64_by_str_key(raw_config, "lineWidth", &|| "lineWidth".to_string())?,
+            circle_diameter: get_f64_by_str_key(raw_config, "circleRadius", &|| {
+                "circleRadius".to_string()
+            })? * 2.0,
+            color_grid,
+            color_tick: get_rgb_by_str_key(raw_config, "colorTick", &|| "colorTick".to_string())?,
+            color_camera_grip: get_rgba_by_str_key(raw_config, "colorCameraGrip", &|| {
+                "colorCameraGrip".to_string()
+            })?,
+            color_preview_overlay: get_rgba_by_str_key(raw_config, "colorPreviewOverlay", &|| {
+                "colorPreviewOverlay".to_string()
+            })?,
+            color_preview_hint: get_rgba_by_str_key(raw_config, "colorPreviewHint", &|| {
+                "colorPreviewHint".to_string()
+            })?,
+            color_preview_mask: match get_optional_by_str_key(raw_config, "colorPreviewMask", &|| {
+                "colorPreviewMask".to_string()
+            })? {
+                Some(_) => get_rgba_by_str_key(raw_config, "colorPreviewMask", &|| {
+                    "colorPreviewMask".to_string()
+                })?,
+                None => (0, 0, 0, 0.15),
+            },
+            color_preview_window: match get_optional_by_str_key(
+                raw_config,
+                "colorPreviewWindow",
+                &|| "colorPreviewWindow".to_string(),
+            )? {
+                Some(_) => get_rgba_by_str_key(raw_config, "colorPreviewWindow", &|| {
+                    "colorPreviewWindow".to_string()
+                })?,
+                None => (0, 0, 255, 0.5),
+            },
+            color_zero_line: match get_optional_by_str_key(raw_config, "colorZeroLine", &|| {
+                "colorZeroLine".to_string()
+            })? {
+                Some(_) => get_rgba_by_str_key(raw_config, "colorZeroLine", &|| {
+                    "colorZeroLine".to_string()
+                })?,
+                None => (142, 142, 142, 1.0),
+            },
+            color_negative_region: match get_optional_by_str_key(
+                raw_config,
+                "colorNegativeRegion",
+                &|| "colorNegativeRegion".to_string(),
+            )? {
+                Some(_) => get_rgba_by_str_key(raw_config, "colorNegativeRegion", &|| {
+                    "colorNegativeRegion".to_string()
+                })?,
+                None => (255, 0, 0, 0.05),
+            },
+            color_loading_overlay: match get_optional_by_str_key(
+                raw_config,
+                "colorLoadingOverlay",
+                &|| "colorLoadingOverlay".to_string(),
+            )? {
+                Some(_) => get_rgba_by_str_key(raw_config, "colorLoadingOverlay", &|| {
+                    "colorLoadingOverlay".to_string()
+                })?,
+                None => (255, 255, 255, 0.7),
+            },
+            color_tooltip: get_rgba_by_str_key(raw_config, "colorTooltip", &|| {
+                "colorTooltip".to_string()
+            })?,
+            color_tooltip_font: get_rgba_by_str_key(raw_config, "colorTooltipFont", &|| {
+                "colorTooltipFont".to_string()
+            })?,
+            sort_data_sets_by: DataSetSorting::from_str(&get_string_by_str_key(
                 raw_config,
                 "sortDataSetsBy",
                 &|| "sortDataSetsBy".to_string(),
@@ -651,6 +1939,10 @@ impl ChartConfig {
             layout_content_height: layout_content_height * total_height_norm,
             layout_preview_height: layout_preview_height * total_height_norm,
             layout_legend_height: layout_legend_height * total_height_norm,
+            preview_position,
+            preview_splitter,
+            preview_container,
+            compact_layout,
             color_palette: color_palette?,
             us_long_press: get_f64_by_str_key(raw_config, "msLongPress", &|| {
                 "msLongPress".to_string()
@@ -665,13 +1957,599 @@ impl ChartConfig {
                 "expFmtSignificantDigits",
                 &|| "expFmtSignificantDigits".to_string(),
             )? as usize,
+            x_label_overlap: LabelOverlap::from_str(
+                get_optional_string_by_str_key(raw_config, "xLabelOverlap", &|| {
+                    "xLabelOverlap".to_string()
+                })?
+                .as_deref()
+                .unwrap_or("skip"),
+            )?,
+            non_finite_values: NonFiniteValues::from_str(
+                get_optional_string_by_str_key(raw_config, "nonFiniteValues", &|| {
+                    "nonFiniteValues".to_string()
+                })?
+                .as_deref()
+                .unwrap_or("error"),
+            )?,
+            x_label_min_gap: get_optional_f64_by_str_key(raw_config, "xLabelMinGap", &|| {
+                "xLabelMinGap".to_string()
+            })?
+            .unwrap_or(8.0),
+            x_label_rotation: match get_optional_f64_by_str_key(
+                raw_config,
+                "xLabelRotation",
+                &|| "xLabelRotation".to_string(),
+            )? {
+                // labels are spaced by font size / sin, so 0 and beyond 90 break it
+                Some(degrees) if !(degrees > 0.0 && degrees <= 90.0) => {
+                    return Err("should be within (0, 90]: xLabelRotation".to_string());
+                }
+                degrees => degrees.unwrap_or(45.0).to_radians(),
+            },
+            y_axis_position: YAxisPosition::from_str(
+                get_optional_string_by_str_key(raw_config, "yAxisPosition", &|| {
+                    "yAxisPosition".to_string()
+                })?
+                .as_deref()
+                .unwrap_or("left"),
+            )?,
+            y_label_format: get_optional_string_by_str_key(raw_config, "yLabelFormat", &|| {
+                "yLabelFormat".to_string()
+            })?
+            .map(|v| ValueLabelFormat::from_str(v.as_str()))
+            .transpose()?,
+            y_label_format_in_tooltip: get_optional_bool_by_str_key(
+                raw_config,
+                "yLabelFormatInTooltip",
+                &|| "yLabelFormatInTooltip".to_string(),
+            )?
+            .unwrap_or(false),
+            y_unit: match get_optional_by_str_key(raw_config, "yUnit", &|| "yUnit".to_string())? {
+                None => None,
+                Some(y_unit) => match y_unit.as_string().as_deref() {
+                    Some("none") => None,
+                    Some("duration_seconds") => Some(UnitLadder::duration_seconds()),
+                    Some("bytes") => Some(UnitLadder::bytes()),
+                    Some(v) => {
+                        return Err(format!(
+                            "unsupported y unit: '{}'; use 'none', 'duration_seconds', 'bytes' or [[factor, suffix], ...]",
+                            v
+                        ))
+                    }
+                    None => Some(
+                        UnitLadder::new(
+                            get_array_by_str_key(raw_config, "yUnit", &|| "yUnit".to_string())?
+                                .iter()
+                                .enumerate()
+                                .map(|(index, unit)| {
+                                    let path = || format!("yUnit.{}", index);
+                                    let unit = unit
+                                        .dyn_into::<js_sys::Array>()
+                                        .map_err(|_| format!("not an array: {}", path()))?;
+                                    Ok((
+                                        js_value_to_f64(&unit.get(0), &|| format!("{}.0", path()))?,
+                                        unit.get(1)
+                                            .as_string()
+                                            .ok_or_else(|| format!("not a string: {}.1", path()))?,
+                                    ))
+                                })
+                                .collect::<Result<Vec<(f64, String)>, String>>()?,
+                        )
+                        .map_err(|e| format!("yUnit: {}", e))?,
+                    ),
+                },
+            },
+            y_unit_in_title: match get_optional_string_by_str_key(
+                raw_config,
+                "yUnitPlacement",
+                &|| "yUnitPlacement".to_string(),
+            )?
+            .as_deref()
+            {
+                None | Some("labels") => false,
+                Some("title") => true,
+                Some(v) => {
+                    return Err(format!(
+                        "unsupported y unit placement: '{}'; use 'labels' or 'title'",
+                        v
+                    ))
+                }
+            },
+            number_locale: NumberLocale::from_locale(
+                get_optional_string_by_str_key(raw_config, "locale", &|| "locale".to_string())?
+                    .as_deref(),
+            )?,
+            tooltip_mode: TooltipMode::from_str(
+                get_optional_string_by_str_key(raw_config, "tooltipMode", &|| {
+                    "tooltipMode".to_string()
+                })?
+                .as_deref()
+                .unwrap_or(if render_tooltip.is_some() {
+                    "html"
+                } else {
+                    "canvas"
+                }),
+            )?,
+            tooltip_position: TooltipPosition::from_str(
+                get_optional_string_by_str_key(raw_config, "tooltipPosition", &|| {
+                    "tooltipPosition".to_string()
+                })?
+                .as_deref()
+                .unwrap_or("pointer"),
+            )?,
+            render_tooltip,
+            tooltip_show_change: get_optional_bool_by_str_key(
+                raw_config,
+                "tooltipShowChange",
+                &|| "tooltipShowChange".to_string(),
+            )?
+            .unwrap_or(false),
+            tooltip_change_base: ChangeBase::from_str(
+                get_optional_string_by_str_key(raw_config, "tooltipChangeBase", &|| {
+                    "tooltipChangeBase".to_string()
+                })?
+                .as_deref()
+                .unwrap_or("previous"),
+            )?,
+            tooltip_sort: TooltipSort::from_str(
+                get_optional_string_by_str_key(raw_config, "tooltipSort", &|| {
+                    "tooltipSort".to_string()
+                })?
+                .as_deref()
+                .unwrap_or("seriesOrder"),
+            )?,
+            tooltip_max_entries: match get_optional_f64_by_str_key(
+                raw_config,
+                "tooltipMaxEntries",
+                &|| "tooltipMaxEntries".to_string(),
+            )? {
+                Some(v) if v < 1.0 || v.fract() != 0.0 => {
+                    return Err("should be a positive integer: tooltipMaxEntries".to_string());
+                }
+                v => v.map(|v| v as usize),
+            },
+            tooltip_hide_zero: get_optional_bool_by_str_key(
+                raw_config,
+                "tooltipHideZero",
+                &|| "tooltipHideZero".to_string(),
+            )?
+            .unwrap_or(false),
+            x_axis_title: get_optional_string_by_str_key(raw_config, "xAxisTitle", &|| {
+                "xAxisTitle".to_string()
+            })?
+            .filter(|title| !title.is_empty()),
+            y_axis_title: get_optional_string_by_str_key(raw_config, "yAxisTitle", &|| {
+                "yAxisTitle".to_string()
+            })?
+            .filter(|title| !title.is_empty()),
+            x_tick_spacing: get_optional_f64_by_str_key(raw_config, "xTickSpacing", &|| {
+                "xTickSpacing".to_string()
+            })?
+            .filter(|spacing| *spacing > 0.0),
+            grid_minor_show: get_optional_bool_by_str_key(raw_config, "gridMinorShow", &|| {
+                "gridMinorShow".to_string()
+            })?
+            .unwrap_or(false),
+            grid_minor_count: get_optional_f64_by_str_key(raw_config, "gridMinorCount", &|| {
+                "gridMinorCount".to_string()
+            })?
+            .map_or(4, |count| count.max(0.0) as usize),
+            grid_minor_line_width: get_optional_f64_by_str_key(
+                raw_config,
+                "gridMinorLineWidth",
+                &|| "gridMinorLineWidth".to_string(),
+            )?
+            .unwrap_or(0.5),
+            color_grid_minor: match get_optional_by_str_key(raw_config, "colorGridMinor", &|| {
+                "colorGridMinor".to_string()
+            })? {
+                Some(_) => get_rgba_by_str_key(raw_config, "colorGridMinor", &|| {
+                    "colorGridMinor".to_string()
+                })?,
+                None => (color_grid.0, color_grid.1, color_grid.2, 0.4),
+            },
+            legend_overflow: LegendOverflow::from_str(
+                get_optional_string_by_str_key(raw_config, "legendOverflow", &|| {
+                    "legendOverflow".to_string()
+                })?
+                .as_deref()
+                .unwrap_or("page"),
+            )?,
+            point_labels_show: PointLabelsShow::from_str(
+                get_optional_string_by_str_key(raw_config, "pointLabelsShow", &|| {
+                    "pointLabelsShow".to_string()
+                })?
+                .as_deref()
+                .unwrap_or("never"),
+            )?,
+            point_labels_min_spacing: get_optional_f64_by_str_key(
+                raw_config,
+                "pointLabelsMinSpacing",
+                &|| "pointLabelsMinSpacing".to_string(),
+            )?
+            .unwrap_or(40.0),
+            accessible_table: get_optional_bool_by_str_key(raw_config, "accessibleTable", &|| {
+                "accessibleTable".to_string()
+            })?
+            .unwrap_or(true),
+            accessible_table_max_rows: get_optional_f64_by_str_key(
+                raw_config,
+                "accessibleTableMaxRows",
+                &|| "accessibleTableMaxRows".to_string(),
+            )?
+            .map_or(500, |v| v.max(1.0) as usize),
+            describe_chart,
+            on_auto_destroy,
+            event_callbacks,
+            dom_events: get_optional_bool_by_str_key(raw_config, "domEvents", &|| {
+                "domEvents".to_string()
+            })?
+            .unwrap_or(true),
+            event_throttle_ms,
+            hover_settle_ms: get_optional_f64_by_str_key(raw_config, "hoverSettleMs", &|| {
+                "hoverSettleMs".to_string()
+            })?
+            .map_or(0.0, |v| v.max(0.0)),
+            theme: get_optional_string_by_str_key(raw_config, "theme", &|| "theme".to_string())?
+                .map(|v| Theme::from_str(v.as_str()))
+                .transpose()?,
+            contrast: get_optional_string_by_str_key(raw_config, "contrast", &|| {
+                "contrast".to_string()
+            })?
+            .map(|v| Contrast::from_str(v.as_str()))
+            .transpose()?,
+            high_contrast_line_width_coeff: get_optional_f64_by_str_key(
+                raw_config,
+                "highContrastLineWidthCoeff",
+                &|| "highContrastLineWidthCoeff".to_string(),
+            )?
+            .unwrap_or(2.0),
+            stroke_width_coeff: 1.0,
+            direction: get_optional_string_by_str_key(raw_config, "direction", &|| {
+                "direction".to_string()
+            })?
+            .map(|v| Direction::from_str(v.as_str()))
+            .transpose()?,
+            rtl_reverse_x_axis: get_optional_bool_by_str_key(raw_config, "rtlReverseXAxis", &|| {
+                "rtlReverseXAxis".to_string()
+            })?
+            .unwrap_or(false),
+            series_patterns: get_optional_bool_by_str_key(raw_config, "seriesPatterns", &|| {
+                "seriesPatterns".to_string()
+            })?
+            .unwrap_or(false),
+            hit_scale: get_optional_f64_by_str_key(raw_config, "hitScale", &|| {
+                "hitScale".to_string()
+            })?,
+            hover_highlight: get_optional_bool_by_str_key(raw_config, "hoverHighlight", &|| {
+                "hoverHighlight".to_string()
+            })?
+            .unwrap_or(true),
+            preview_mask: get_optional_bool_by_str_key(raw_config, "previewMask", &|| {
+                "previewMask".to_string()
+            })?
+            .unwrap_or(false),
+            preview_hover_marker: get_optional_bool_by_str_key(
+                raw_config,
+                "previewHoverMarker",
+                &|| "previewHoverMarker".to_string(),
+            )?
+            .unwrap_or(false),
+            zero_line_show: get_optional_bool_by_str_key(raw_config, "zeroLineShow", &|| {
+                "zeroLineShow".to_string()
+            })?
+            .unwrap_or(false),
+            zero_line_width: get_optional_f64_by_str_key(raw_config, "zeroLineWidth", &|| {
+                "zeroLineWidth".to_string()
+            })?
+            .unwrap_or(1.5),
+            negative_region_fill: get_optional_bool_by_str_key(
+                raw_config,
+                "negativeRegionFill",
+                &|| "negativeRegionFill".to_string(),
+            )?
+            .unwrap_or(false),
+            watermark_text,
+            watermark_image,
+            watermark_position: Corner::from_str(
+                get_optional_string_by_str_key(raw_config, "watermarkPosition", &|| {
+                    "watermarkPosition".to_string()
+                })?
+                .as_deref()
+                .unwrap_or("bottom-right"),
+            )?,
+            watermark_opacity: get_optional_f64_by_str_key(raw_config, "watermarkOpacity", &|| {
+                "watermarkOpacity".to_string()
+            })?
+            .unwrap_or(0.3)
+            .clamp(0.0, 1.0),
+            watermark_scale: get_optional_f64_by_str_key(raw_config, "watermarkScale", &|| {
+                "watermarkScale".to_string()
+            })?
+            .unwrap_or(1.0),
+            watermark_layer: WatermarkLayer::from_str(
+                get_optional_string_by_str_key(raw_config, "watermarkLayer", &|| {
+                    "watermarkLayer".to_string()
+                })?
+                .as_deref()
+                .unwrap_or("below"),
+            )?,
+            point_marker_size: get_optional_f64_by_str_key(raw_config, "pointMarkerSize", &|| {
+                "pointMarkerSize".to_string()
+            })?
+            .unwrap_or(8.0),
+            hover_dim_opacity: get_optional_f64_by_str_key(raw_config, "hoverDimOpacity", &|| {
+                "hoverDimOpacity".to_string()
+            })?
+            .unwrap_or(0.3)
+            .clamp(0.0, 1.0),
+            series_animation_duration: match get_optional_f64_by_str_key(
+                raw_config,
+                "seriesAnimationDuration",
+                &|| "seriesAnimationDuration".to_string(),
+            )? {
+                Some(duration) if duration < 0.0 => {
+                    return Err("should not be negative: seriesAnimationDuration".to_string())
+                }
+                duration => duration.unwrap_or(300.0),
+            },
+            series_enter_grow: get_optional_bool_by_str_key(raw_config, "seriesEnterGrow", &|| {
+                "seriesEnterGrow".to_string()
+            })?
+            .unwrap_or(false),
+            zero_size_warning_timeout: get_optional_f64_by_str_key(
+                raw_config,
+                "zeroSizeWarningTimeout",
+                &|| "zeroSizeWarningTimeout".to_string(),
+            )?
+            .unwrap_or(10000.0),
+            memory_trim_delay: get_optional_f64_by_str_key(raw_config, "memoryTrimDelay", &|| {
+                "memoryTrimDelay".to_string()
+            })?
+            .unwrap_or(60000.0),
+            idle_precompute: get_optional_bool_by_str_key(raw_config, "idlePrecompute", &|| {
+                "idlePrecompute".to_string()
+            })?
+            .unwrap_or(true),
+            return_warnings: get_optional_bool_by_str_key(raw_config, "returnWarnings", &|| {
+                "returnWarnings".to_string()
+            })?
+            .unwrap_or(false),
+            deterministic: get_optional_bool_by_str_key(raw_config, "deterministic", &|| {
+                "deterministic".to_string()
+            })?
+            .unwrap_or(false),
+            initial_loading: get_optional_bool_by_str_key(raw_config, "initialLoading", &|| {
+                "initialLoading".to_string()
+            })?
+            .unwrap_or(false),
+            edge_behavior: EdgeBehavior::from_str(
+                get_optional_string_by_str_key(raw_config, "edgeBehavior", &|| {
+                    "edgeBehavior".to_string()
+                })?
+                .as_deref()
+                .unwrap_or("clip"),
+            )?,
+            time_zone: TimeZone::from_str(
+                get_optional_string_by_str_key(raw_config, "timeZone", &|| {
+                    "timeZone".to_string()
+                })?
+                .as_deref()
+                .unwrap_or("local"),
+            )?,
+            container_reuse: get_optional_string_by_str_key(raw_config, "containerReuse", &|| {
+                "containerReuse".to_string()
+            })?
+            .map_or(Ok(ContainerReuse::Error), |v| ContainerReuse::from_str(v.as_str()))?,
+            link_group: get_optional_string_by_str_key(raw_config, "linkGroup", &|| {
+                "linkGroup".to_string()
+            })?,
+            shared_tooltip: get_optional_bool_by_str_key(raw_config, "sharedTooltip", &|| {
+                "sharedTooltip".to_string()
+            })?
+            .unwrap_or(false),
+            align_y: get_optional_bool_by_str_key(raw_config, "alignY", &|| {
+                "alignY".to_string()
+            })?
+            .unwrap_or(false),
+            hover_link_class: match get_optional_string_by_str_key(
+                raw_config,
+                "hoverLinkClass",
+                &|| "hoverLinkClass".to_string(),
+            )? {
+                Some(class) if class.contains(char::is_whitespace) => {
+                    return Err(format!("hoverLinkClass - not a single class: {}", class));
+                }
+                class => class.filter(|class| !class.is_empty()),
+            },
+            hover_link_root: get_optional_string_by_str_key(raw_config, "hoverLinkRoot", &|| {
+                "hoverLinkRoot".to_string()
+            })?,
+            interactions: Interactions::from_raw(
+                &get_optional_by_str_key(raw_config, "interactions", &|| {
+                    "interactions".to_string()
+                })?
+                .unwrap_or(JsValue::UNDEFINED),
+            )?,
+            follow: Follow::from_raw(
+                &get_optional_by_str_key(raw_config, "follow", &|| "follow".to_string())?
+                    .unwrap_or(JsValue::UNDEFINED),
+            )?,
+            max_render_pixel_ratio: get_optional_f64_by_str_key(
+                raw_config,
+                "maxRenderPixelRatio",
+                &|| "maxRenderPixelRatio".to_string(),
+            )?
+            .filter(|v| *v > 0.0),
+            max_canvas_dimension_px: get_optional_f64_by_str_key(
+                raw_config,
+                "maxCanvasDimensionPx",
+                &|| "maxCanvasDimensionPx".to_string(),
+            )?
+            .filter(|v| *v > 0.0),
+            strings: Strings::from_raw(
+                &get_optional_by_str_key(raw_config, "strings", &|| "strings".to_string())?
+                    .unwrap_or(JsValue::UNDEFINED),
+            )?,
+            raw: json_copy(raw_config).map_err(|e| format!("{}: config", e))?,
+        })
+    }
+}
+
+/// Which interactions a chart reacts to; all are on by default.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Interactions {
+    /// The tooltip, clicks on points and the hover highlight.
+    pub hover: bool,
+    /// Selecting a range, pinching, zooming out and zoom keys.
+    pub zoom: bool,
+    /// Dragging the preview window and arrow keys.
+    pub pan: bool,
+    /// Toggling and isolating data sets from the legend.
+    pub legend_toggle: bool,
+}
+impl Default for Interactions {
+    fn default() -> Self {
+        Self {
+            hover: true,
+            zoom: true,
+            pan: true,
+            legend_toggle: true,
+        }
+    }
+}
+impl Interactions {
+    /// Reads an object like `{hover, zoom, pan, legendToggle}`, missing
+    /// flags stay on.
+    pub fn from_raw(raw_interactions: &JsValue) -> Result<Self, String> {
+        let default = Self::default();
+        let get = |key: &str, default: bool| -> Result<bool, String> {
+            Ok(
+                get_optional_bool_by_str_key(raw_interactions, key, &|| {
+                    format!("interactions.{}", key)
+                })?
+                .unwrap_or(default),
+            )
+        };
+        Ok(Self {
+            hover: get("hover", default.hover)?,
+            zoom: get("zoom", default.zoom)?,
+            pan: get("pan", default.pan)?,
+            legend_toggle: get("legendToggle", default.legend_toggle)?,
         })
     }
+    pub fn any(&self) -> bool {
+        self.hover || self.zoom || self.pan || self.legend_toggle
+    }
+}
+
+/// Keeps the window on the latest data as points are appended, until the
+/// user zooms or pans.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Follow {
+    pub enabled: bool,
+    /// The width of the window in x units (ms for dates), None for the whole
+    /// x domain.
+    pub span_x: Option<f64>,
+}
+impl Follow {
+    /// Reads an object like `{enabled, spanX}`.
+    pub fn from_raw(raw_follow: &JsValue) -> Result<Self, String> {
+        let span_x = get_optional_f64_by_str_key(raw_follow, "spanX", &|| {
+            "follow.spanX".to_string()
+        })?;
+        if span_x.is_some_and(|v| !v.is_finite() || v <= 0.0) {
+            return Err("follow.spanX should be positive".to_string());
+        }
+        Ok(Self {
+            enabled: get_optional_bool_by_str_key(raw_follow, "enabled", &|| {
+                "follow.enabled".to_string()
+            })?
+            .unwrap_or(false),
+            span_x,
+        })
+    }
+}
+
+/// The window the chart opens with instead of the whole x domain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InitialRange {
+    Coords(f64, f64),
+    /// The last n x units (ms for dates).
+    Last(f64),
+}
+impl InitialRange {
+    fn from_raw(raw_params: &JsValue, coord_type: DataType) -> Result<Option<Self>, String> {
+        let range = get_optional_by_str_key(raw_params, "initialRange", &|| {
+            "initialRange".to_string()
+        })?;
+        let window = get_optional_f64_by_str_key(raw_params, "initialWindow", &|| {
+            "initialWindow".to_string()
+        })?;
+        match (range, window) {
+            (Some(_), Some(_)) => {
+                Err("initialRange and initialWindow are mutually exclusive".to_string())
+            }
+            (Some(range), None) => {
+                let range = parse_js_values(
+                    range
+                        .dyn_into::<js_sys::Array>()
+                        .map_err(|_| "initialRange is not an array".to_string())?,
+                    coord_type,
+                    &|| "initialRange".to_string(),
+                )?;
+                match range.as_slice() {
+                    [from, to] if from < to => Ok(Some(Self::Coords(*from, *to))),
+                    [_, _] => Err("initialRange: from should be less than to".to_string()),
+                    _ => Err("initialRange is an array of length 2".to_string()),
+                }
+            }
+            (None, Some(window)) if window > 0.0 => Ok(Some(Self::Last(window))),
+            (None, Some(_)) => Err("initialWindow should be positive".to_string()),
+            (None, None) => Ok(None),
+        }
+    }
+    /// Returns the window within the x domain and a warning when it had to
+    /// be moved or clamped; with `past_edges` a window overlapping the domain
+    /// may extend past it, see `EdgeBehavior`.
+    pub fn resolve(
+        &self,
+        coord_min: f64,
+        coord_max: f64,
+        past_edges: bool,
+    ) -> ((f64, f64), Option<String>) {
+        let (from, to) = match *self {
+            Self::Coords(from, to) => (from, to),
+            Self::Last(window) => (coord_max - window, coord_max),
+        };
+        let window = if past_edges && from < coord_max && to > coord_min {
+            (from, to)
+        } else {
+            fit_window(from, to, coord_min, coord_max)
+        };
+        let warning = match *self {
+            Self::Coords(..) if window != (from, to) => Some(format!(
+                "initialRange [{}, {}] is out of the data, showing [{}, {}]",
+                from, to, window.0, window.1
+            )),
+            _ => None,
+        };
+        (window, warning)
+    }
 }
 
 pub struct ChartParams {
     pub selector: String,
+    /// Selector of the wrapper of the preview, when it has its own, see
+    /// `ChartConfig::preview_container`; set by the manager.
+    pub preview_selector: Option<String>,
     pub content: Content,
+    pub initial_range: Option<InitialRange>,
+    /// The params as passed without the points of the data sets, copied
+    /// through JSON for `export_params`.
+    pub raw: JsValue,
+    /// E.g. of dropped non-finite points, handed to the chart.
+    pub warnings: Vec<ChartWarning>,
 }
 
 impl ChartParams {
@@ -680,14 +2558,21 @@ impl ChartParams {
             get_string_by_str_key(raw_params, "contentName", &|| "contentName".to_string()).ok();
         let selector = get_string_by_str_key(raw_params, "selector", &|| "selector".to_string())?;
 
-        let coord_type = DataType::from_str(
+        let with_time_zone = |data_type: DataType| match data_type {
+            DataType::DateTime { .. } => DataType::DateTime {
+                time_zone: chart_config.time_zone,
+            },
+            data_type => data_type,
+        };
+        let coord_type = with_time_zone(DataType::from_str(
             get_string_by_str_key(raw_params, "coordType", &|| "coordType".to_string())?.as_str(),
-        )?;
-        let value_type = DataType::from_str(
+        )?);
+        let value_type = with_time_zone(DataType::from_str(
             get_string_by_str_key(raw_params, "valueType", &|| "valueType".to_string())?.as_str(),
-        )?;
+        )?);
 
         let mut content = Content::new(content_name, coord_type, value_type, chart_config);
+        let mut warnings = Vec::new();
 
         let color_palette = &chart_config.color_palette;
         let colors_number = color_palette.len();
@@ -712,23 +2597,278 @@ impl ChartParams {
             let values = get_array_by_str_key(&raw_data_set, "values", &|| {
                 format!("dataSets[{}].values", index)
             })?;
-            let values = parse_js_values(values, value_type, &|| {
+            let values = parse_js_values_with_gaps(values, value_type, &|| {
                 format!("dataSets[{}].values", index)
             })?;
 
+            check_lengths(&coords, &values, format!("dataSets[{}]", index))?;
+            warnings.extend(ChartWarning::non_finite(
+                data_set_name.as_str(),
+                coords.as_slice(),
+                values.as_slice(),
+                format!("dataSets[{}]", index),
+            ));
+            let (coords, values) = sanitize_non_finite(
+                data_set_name.as_str(),
+                coords,
+                values,
+                chart_config.non_finite_values,
+            )?;
+            let (coords, values, gaps) = split_gaps(coords, values);
             let color = color_palette[index % colors_number];
+            // a data set without points waits for appendData, no data at all
+            // is rendered as a placeholder until then
+            let pending = coords.is_empty();
+            let data_set = if pending {
+                content.add_pending_data_set(data_set_name.as_str(), color)?
+            } else {
+                content.parse_and_add_data_set(data_set_name.as_str(), coords, values, color)?;
+                content.data_sets.last_mut().unwrap()
+            };
+            data_set.pattern = index;
+            data_set.add_gaps(gaps);
 
-            content.parse_and_add_data_set(data_set_name.as_str(), coords, values, color)?;
+            if let Some(z_index) = get_optional_f64_by_str_key(&raw_data_set, "zIndex", &|| {
+                format!("dataSets[{}].zIndex", index)
+            })? {
+                data_set.z_index = z_index as i32;
+            }
+
+            if let Some(opacity) = get_optional_f64_by_str_key(&raw_data_set, "opacity", &|| {
+                format!("dataSets[{}].opacity", index)
+            })? {
+                if !(0.0..=1.0).contains(&opacity) {
+                    return Err(format!("should be within [0, 1]: dataSets[{}].opacity", index));
+                }
+                data_set.opacity = opacity;
+            }
+
+            if let Some(raw_fill) = get_optional_by_str_key(&raw_data_set, "fill", &|| {
+                format!("dataSets[{}].fill", index)
+            })? {
+                data_set.fill = Some(parse_area_fill(
+                    &raw_fill,
+                    color,
+                    &|| format!("dataSets[{}].fill", index),
+                )?);
+            }
+
+            for (key, flag) in [
+                ("hoverable", &mut data_set.hoverable),
+                ("showInTooltip", &mut data_set.show_in_tooltip),
+                ("showInLegend", &mut data_set.show_in_legend),
+            ] {
+                if let Some(value) = get_optional_bool_by_str_key(&raw_data_set, key, &|| {
+                    format!("dataSets[{}].{}", index, key)
+                })? {
+                    *flag = value;
+                }
+            }
+            for (key, text) in [
+                ("unit", &mut data_set.unit),
+                ("description", &mut data_set.description),
+            ] {
+                *text = get_optional_string_by_str_key(&raw_data_set, key, &|| {
+                    format!("dataSets[{}].{}", index, key)
+                })?;
+                if text.as_deref().is_some_and(|text| text.trim().is_empty()) {
+                    return Err(format!("should not be blank: dataSets[{}].{}", index, key));
+                }
+            }
+            // markers and comparisons need points, the chart is created anew
+            // with the first ones
+            if pending {
+                continue;
+            }
+
+            if let Some(raw_markers) = get_optional_by_str_key(&raw_data_set, "markers", &|| {
+                format!("dataSets[{}].markers", index)
+            })? {
+                let markers = parse_point_markers(&raw_markers, coord_type, &|| {
+                    format!("dataSets[{}].markers", index)
+                })?;
+                content.data_sets.last_mut().unwrap().set_markers(markers)?;
+            }
+
+            if let Some(compare) = get_optional_by_str_key(&raw_data_set, "compare", &|| {
+                format!("dataSets[{}].compare", index)
+            })? {
+                let offset = get_f64_by_str_key(&compare, "offset", &|| {
+                    format!("dataSets[{}].compare.offset", index)
+                })?;
+                let name = match get_optional_string_by_str_key(&compare, "name", &|| {
+                    format!("dataSets[{}].compare.name", index)
+                })? {
+                    Some(name) => name,
+                    None => chart_config
+                        .strings
+                        .format("comparisonName", &[("name", data_set_name.as_str())]),
+                };
+                let include_in_domain =
+                    get_optional_bool_by_str_key(&compare, "includeInDomain", &|| {
+                        format!("dataSets[{}].compare.includeInDomain", index)
+                    })?
+                    .unwrap_or(false);
+                content.add_comparison(name.as_str(), offset, include_in_domain)?;
+            }
+        }
+        let initial_range = InitialRange::from_raw(raw_params, coord_type)?;
+        Ok(ChartParams {
+            selector,
+            preview_selector: None,
+            content,
+            initial_range,
+            raw: Self::copy_without_points(raw_params)?,
+            warnings,
+        })
+    }
+    /// Leaves out coords and values of the data sets, the content keeps them
+    /// with the appended ones.
+    fn copy_without_points(raw_params: &JsValue) -> Result<JsValue, String> {
+        let params = js_sys::Object::assign(&js_sys::Object::new(), raw_params.unchecked_ref());
+        let data_sets: js_sys::Array = Reflect::get(&params, &"dataSets".into())
+            .unwrap()
+            .unchecked_into::<js_sys::Array>()
+            .iter()
+            .map(|raw_data_set| {
+                let data_set =
+                    js_sys::Object::assign(&js_sys::Object::new(), raw_data_set.unchecked_ref());
+                Reflect::delete_property(&data_set, &"coords".into()).unwrap();
+                Reflect::delete_property(&data_set, &"values".into()).unwrap();
+                JsValue::from(data_set)
+            })
+            .collect();
+        Reflect::set(&params, &"dataSets".into(), &data_sets).unwrap();
+        json_copy(&params).map_err(|e| format!("{}: params", e))
+    }
+}
+
+/// Copies a value through JSON, which leaves out functions and turns dates
+/// into strings.
+pub fn json_copy(value: &JsValue) -> Result<JsValue, String> {
+    if value.is_undefined() {
+        return Ok(JsValue::UNDEFINED);
+    }
+    js_sys::JSON::stringify(value)
+        .and_then(|json| js_sys::JSON::parse(&String::from(json)))
+        .map_err(|_| "not serializable to JSON".to_string())
+}
+
+/// Builds params for a definition from the copy kept by `ChartParams::from`,
+/// see `params_with_points`; the selector and the window are left to the
+/// importer.
+pub fn export_params(raw_params: &JsValue, content: Option<&Content>) -> Result<JsValue, String> {
+    let params = params_with_points(raw_params, content)?;
+    for key in ["selector", "initialRange", "initialWindow"] {
+        Reflect::delete_property(params.unchecked_ref::<js_sys::Object>(), &key.into()).unwrap();
+    }
+    Ok(params)
+}
+
+/// Copies the params kept by `ChartParams::from` with the current points,
+/// markers and z-indices of the data sets at full precision (dates as ms).
+/// Data sets without points get empty coords and values.
+pub fn params_with_points(
+    raw_params: &JsValue,
+    content: Option<&Content>,
+) -> Result<JsValue, String> {
+    let params = json_copy(raw_params)?;
+    let to_array = |values: &mut dyn Iterator<Item = f64>| -> js_sys::Array {
+        values.map(JsValue::from_f64).collect()
+    };
+    for raw_data_set in get_array_by_str_key(&params, "dataSets", &|| "dataSets".to_string())?
+        .iter()
+    {
+        let name = get_string_by_str_key(&raw_data_set, "name", &|| "name".to_string())?;
+        let data_set = content.and_then(|content| {
+            content
+                .data_sets
+                .iter()
+                .find(|data_set| data_set.comparison.is_none() && data_set.name == name)
+        });
+        let set = |key: &str, value: &JsValue| {
+            Reflect::set(&raw_data_set, &key.into(), value).unwrap();
+        };
+        let data_set = match data_set {
+            Some(data_set) => data_set,
+            None => {
+                set("coords", &js_sys::Array::new());
+                set("values", &js_sys::Array::new());
+                continue;
+            }
+        };
+        let points = data_set.get_values_with_gaps();
+        set("coords", &to_array(&mut points.iter().map(|p| p.0)));
+        let values: js_sys::Array = points
+            .iter()
+            .map(|p| p.1.map_or(JsValue::NULL, JsValue::from_f64))
+            .collect();
+        set("values", &values);
+        set("zIndex", &JsValue::from_f64(data_set.z_index as f64));
+        let markers: js_sys::Array = data_set
+            .markers
+            .iter()
+            .map(|(_, marker)| {
+                let raw_marker = js_sys::Object::new();
+                let set = |key: &str, value: &JsValue| {
+                    Reflect::set(&raw_marker, &key.into(), value).unwrap();
+                };
+                set("coord", &JsValue::from_f64(marker.coord));
+                set("shape", &marker.shape.as_str().into());
+                if let Some(rgb) = marker.rgb {
+                    let rgb = [rgb.0, rgb.1, rgb.2].map(f64::from);
+                    set("color", &to_array(&mut rgb.into_iter()));
+                }
+                if let Some(size) = marker.size {
+                    set("size", &JsValue::from_f64(size));
+                }
+                JsValue::from(raw_marker)
+            })
+            .collect();
+        if markers.length() > 0 {
+            set("markers", &markers);
+        } else {
+            let raw_data_set = raw_data_set.unchecked_ref::<js_sys::Object>();
+            Reflect::delete_property(raw_data_set, &"markers".into()).unwrap();
         }
-        Ok(ChartParams { selector, content })
     }
+    Ok(params)
 }
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+const MEDIA_QUERY_REDUCED_MOTION: &str = "(prefers-reduced-motion: reduce)";
+const MEDIA_QUERY_DARK: &str = "(prefers-color-scheme: dark)";
+const MEDIA_QUERY_MORE_CONTRAST: &str = "(prefers-contrast: more)";
+const MEDIA_QUERY_FORCED_COLORS: &str = "(forced-colors: active)";
+const MEDIA_QUERY_COARSE_POINTER: &str = "(pointer: coarse)";
+/// Queries which changes are to be followed by `update_media_features`.
+pub const MEDIA_FEATURE_QUERIES: [&str; 5] = [
+    MEDIA_QUERY_REDUCED_MOTION,
+    MEDIA_QUERY_DARK,
+    MEDIA_QUERY_MORE_CONTRAST,
+    MEDIA_QUERY_FORCED_COLORS,
+    MEDIA_QUERY_COARSE_POINTER,
+];
+
+const COARSE_POINTER_HIT_SCALE: f64 = 2.0;
+
 #[derive(Debug, Clone)]
 pub struct ClientCaps {
     pub touch_device: bool,
     pub device_pixel_ratio: f64,
     pub css_to_physical_scale: f64,
-    pub screen_orientation: bool,
+    pub reduced_motion: bool,
+    pub color_scheme: ColorScheme,
+    pub more_contrast: bool,
+    pub coarse_pointer: bool,
+    /// Canvases fire contextlost and contextrestored.
+    pub context_loss_events: bool,
+    /// The window has requestIdleCallback.
+    pub idle_callback: bool,
 }
 impl ClientCaps {
     pub fn detect() -> Self {
@@ -744,16 +2884,63 @@ impl ClientCaps {
             .as_f64()
             .unwrap();
         let css_to_physical_scale = viewport_scale * device_pixel_ratio;
+        let context_loss_events = Reflect::has(
+            &Reflect::get(&window, &JsValue::from_str("HTMLElement"))
+                .and_then(|class| Reflect::get(&class, &JsValue::from_str("prototype")))
+                .unwrap(),
+            &JsValue::from_str("oncontextlost"),
+        )
+        .unwrap_or(false);
+        let idle_callback = Reflect::has(&window, &JsValue::from_str("requestIdleCallback"))
+            .unwrap_or(false);
 
-        let screen_orientation = !Reflect::get(&window, &JsValue::from_str("screen"))
-            .and_then(|screen| Reflect::get(&screen, &JsValue::from_str("orientation")))
-            .unwrap()
-            .is_undefined();
-        Self {
+        let mut client_caps = Self {
             touch_device,
             device_pixel_ratio,
             css_to_physical_scale,
-            screen_orientation,
+            reduced_motion: false,
+            color_scheme: ColorScheme::Light,
+            more_contrast: false,
+            coarse_pointer: false,
+            context_loss_events,
+            idle_callback,
+        };
+        client_caps.update_media_features();
+        client_caps
+    }
+    /// Returns `screen.orientation`, which older browsers lack.
+    pub fn get_screen_orientation() -> Option<JsValue> {
+        Reflect::get(&web_sys::window().unwrap(), &JsValue::from_str("screen"))
+            .and_then(|screen| Reflect::get(&screen, &JsValue::from_str("orientation")))
+            .ok()
+            .filter(|orientation| orientation.is_object())
+    }
+    /// Scales invisible hit areas up for imprecise pointers; the rendering
+    /// stays the same.
+    pub fn get_hit_scale(&self) -> f64 {
+        if self.coarse_pointer {
+            COARSE_POINTER_HIT_SCALE
+        } else {
+            1.0
         }
     }
+    pub fn match_media(query: &str) -> Option<web_sys::MediaQueryList> {
+        web_sys::window().unwrap().match_media(query).ok().flatten()
+    }
+    fn matches_media(query: &str) -> bool {
+        Self::match_media(query).is_some_and(|media_query_list| media_query_list.matches())
+    }
+    /// Re-reads user preferences, which change without a resize.
+    pub fn update_media_features(&mut self) {
+        self.reduced_motion = Self::matches_media(MEDIA_QUERY_REDUCED_MOTION);
+        self.color_scheme = if Self::matches_media(MEDIA_QUERY_DARK) {
+            ColorScheme::Dark
+        } else {
+            ColorScheme::Light
+        };
+        // forced colors stand for Windows High Contrast
+        self.more_contrast = Self::matches_media(MEDIA_QUERY_MORE_CONTRAST)
+            || Self::matches_media(MEDIA_QUERY_FORCED_COLORS);
+        self.coarse_pointer = Self::matches_media(MEDIA_QUERY_COARSE_POINTER);
+    }
 }
@@ -0,0 +1,208 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use crate::data_set::MarkerShape;
+use js_sys::Array;
+use std::f64::consts::PI;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Okabe-Ito colors, distinguishable with the common color vision
+/// deficiencies.
+pub const COLORBLIND_SAFE_PALETTE: [(u8, u8, u8); 8] = [
+    (230, 159, 0),
+    (86, 180, 233),
+    (0, 158, 115),
+    (240, 228, 66),
+    (0, 114, 178),
+    (213, 94, 0),
+    (204, 121, 167),
+    (0, 0, 0),
+];
+
+// line dashes in apx, the first pattern is solid
+const LINE_DASHES: [&[f64]; 6] = [
+    &[],
+    &[6.0, 3.0],
+    &[1.5, 3.0],
+    &[8.0, 3.0, 1.5, 3.0],
+    &[12.0, 4.0],
+    &[4.0, 2.0, 1.5, 2.0, 1.5, 2.0],
+];
+const HATCH_SIZE: f64 = 6.0;
+const COMPARISON_LINE_DASH: [f64; 2] = [4.0, 4.0];
+
+/// Returns the line dash of the pattern in canvas pixels, for
+/// `set_line_dash`.
+pub fn get_line_dash(pattern: usize, c_apx: f64) -> JsValue {
+    LINE_DASHES[pattern % LINE_DASHES.len()]
+        .iter()
+        .map(|v| JsValue::from_f64(v * c_apx))
+        .collect::<Array>()
+        .into()
+}
+
+/// Returns the line dash of comparison series in canvas pixels.
+pub fn get_comparison_line_dash(c_apx: f64) -> JsValue {
+    COMPARISON_LINE_DASH
+        .iter()
+        .map(|v| JsValue::from_f64(v * c_apx))
+        .collect::<Array>()
+        .into()
+}
+
+/// Begins a path of the marker shape centered at the point, `c_size` across.
+pub fn trace_marker(
+    context: &web_sys::CanvasRenderingContext2d,
+    shape: MarkerShape,
+    cx: f64,
+    cy: f64,
+    c_size: f64,
+) {
+    let r = c_size * 0.5;
+    context.begin_path();
+    match shape {
+        MarkerShape::Circle => {
+            context.arc(cx, cy, r, 0.0, 2.0 * PI).unwrap();
+        }
+        MarkerShape::Square => context.rect(cx - r, cy - r, c_size, c_size),
+        MarkerShape::Triangle => {
+            context.move_to(cx, cy - r);
+            context.line_to(cx + r, cy + r);
+            context.line_to(cx - r, cy + r);
+            context.close_path();
+        }
+        MarkerShape::Diamond => {
+            context.move_to(cx, cy - r);
+            context.line_to(cx + r, cy);
+            context.line_to(cx, cy + r);
+            context.line_to(cx - r, cy);
+            context.close_path();
+        }
+    }
+}
+
+/// Creates a square canvas to draw a pattern tile on.
+fn create_tile(
+    c_size: f64,
+) -> (
+    web_sys::HtmlCanvasElement,
+    web_sys::CanvasRenderingContext2d,
+) {
+    let canvas = web_sys::window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .create_element("canvas")
+        .unwrap()
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .unwrap();
+    canvas.set_width(c_size as u32);
+    canvas.set_height(c_size as u32);
+    let tile_context = canvas
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()
+        .unwrap();
+    (canvas, tile_context)
+}
+
+/// Creates faint diagonal stripes of the color, marking parts of the plot
+/// without data.
+pub fn create_no_data_pattern(
+    context: &web_sys::CanvasRenderingContext2d,
+    rgb: (u8, u8, u8),
+    c_apx: f64,
+) -> Option<web_sys::CanvasPattern> {
+    let c_size = (HATCH_SIZE * 1.5 * c_apx).round().max(2.0);
+    let (canvas, tile_context) = create_tile(c_size);
+    tile_context
+        .set_stroke_style_str(format!("rgba({}, {}, {}, 0.2)", rgb.0, rgb.1, rgb.2).as_str());
+    tile_context.set_line_width(c_apx);
+    tile_context.begin_path();
+    for (x1, y1, x2, y2) in [
+        (0.0, 1.0, 1.0, 0.0),
+        (-0.5, 0.5, 0.5, -0.5),
+        (0.5, 1.5, 1.5, 0.5),
+    ] {
+        tile_context.move_to(x1 * c_size, y1 * c_size);
+        tile_context.line_to(x2 * c_size, y2 * c_size);
+    }
+    tile_context.stroke();
+    context
+        .create_pattern_with_html_canvas_element(&canvas, "repeat")
+        .unwrap()
+}
+
+/// Creates translucent stripes to be laid over a fill of the series color;
+/// the first pattern has none, like its solid line.
+pub fn create_hatch_pattern(
+    context: &web_sys::CanvasRenderingContext2d,
+    pattern: usize,
+    c_apx: f64,
+) -> Option<web_sys::CanvasPattern> {
+    let pattern = pattern % LINE_DASHES.len();
+    if pattern == 0 {
+        return None;
+    }
+    let c_size = (HATCH_SIZE * c_apx).round().max(2.0);
+    let (canvas, hatch_context) = create_tile(c_size);
+    hatch_context.set_stroke_style_str("rgba(255, 255, 255, 0.45)");
+    hatch_context.set_line_width(c_size * 0.25);
+    hatch_context.begin_path();
+    // diagonals run corner to corner so that tiles join seamlessly
+    let lines: &[(f64, f64, f64, f64)] = match pattern {
+        1 => &[
+            (0.0, 1.0, 1.0, 0.0),
+            (-0.5, 0.5, 0.5, -0.5),
+            (0.5, 1.5, 1.5, 0.5),
+        ],
+        2 => &[
+            (0.0, 0.0, 1.0, 1.0),
+            (-0.5, 0.5, 0.5, 1.5),
+            (0.5, -0.5, 1.5, 0.5),
+        ],
+        3 => &[(0.0, 0.5, 1.0, 0.5)],
+        4 => &[(0.5, 0.0, 0.5, 1.0)],
+        _ => &[(0.0, 0.5, 1.0, 0.5), (0.5, 0.0, 0.5, 1.0)],
+    };
+    for (x1, y1, x2, y2) in lines.iter() {
+        hatch_context.move_to(x1 * c_size, y1 * c_size);
+        hatch_context.line_to(x2 * c_size, y2 * c_size);
+    }
+    hatch_context.stroke();
+    context
+        .create_pattern_with_html_canvas_element(&canvas, "repeat")
+        .unwrap()
+}
+
+/// Hatches by pattern, recreated when the canvas scale changes.
+#[derive(Default)]
+pub struct HatchPatterns {
+    c_apx: f64,
+    patterns: Vec<Option<web_sys::CanvasPattern>>,
+}
+impl HatchPatterns {
+    pub fn get(
+        &mut self,
+        context: &web_sys::CanvasRenderingContext2d,
+        pattern: usize,
+        c_apx: f64,
+    ) -> Option<&web_sys::CanvasPattern> {
+        if self.c_apx != c_apx {
+            self.c_apx = c_apx;
+            self.patterns = (0..LINE_DASHES.len())
+                .map(|pattern| create_hatch_pattern(context, pattern, c_apx))
+                .collect();
+        }
+        self.patterns[pattern % LINE_DASHES.len()].as_ref()
+    }
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
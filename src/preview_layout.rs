@@ -0,0 +1,83 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use std::str::FromStr;
+
+/// CSS px of the bar between the main plot and the preview, see
+/// `ChartConfig::preview_splitter`.
+pub const SPLITTER_HEIGHT: f64 = 8.0;
+/// CSS px the splitter moves per arrow key.
+pub const SPLITTER_KEYBOARD_STEP: f64 = 10.0;
+/// Neither pane is dragged lower than this, in CSS px.
+const MIN_PANE_HEIGHT: f64 = 32.0;
+
+/// Where the preview goes relative to the main plot.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PreviewPosition {
+    Bottom,
+    Top,
+}
+impl FromStr for PreviewPosition {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bottom" => Ok(Self::Bottom),
+            "top" => Ok(Self::Top),
+            v => Err(format!(
+                "unsupported preview position: '{}'; use 'bottom' or 'top'",
+                v
+            )),
+        }
+    }
+}
+
+/// Keeps both panes at least MIN_PANE_HEIGHT high within panes_height, the
+/// height the main plot and the preview share; too low panes split it in
+/// half.
+pub fn clamp_preview_height(height: f64, panes_height: f64) -> f64 {
+    let panes_height = panes_height.max(0.0);
+    let min = MIN_PANE_HEIGHT.min(panes_height * 0.5);
+    let max = (panes_height - MIN_PANE_HEIGHT).max(panes_height * 0.5);
+    height.clamp(min, max).round()
+}
+
+/// Height of the preview once the splitter is moved by shift_y from where
+/// the preview had start_height; moving it towards the preview shrinks it.
+pub fn get_dragged_preview_height(
+    position: PreviewPosition,
+    start_height: f64,
+    shift_y: f64,
+    panes_height: f64,
+) -> f64 {
+    let height = match position {
+        PreviewPosition::Bottom => start_height - shift_y,
+        PreviewPosition::Top => start_height + shift_y,
+    };
+    clamp_preview_height(height, panes_height)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::preview_layout::{
+        clamp_preview_height, get_dragged_preview_height, PreviewPosition,
+    };
+
+    #[test]
+    fn test_dragged_preview_height() {
+        let drag = |position, shift_y| get_dragged_preview_height(position, 60.0, shift_y, 400.0);
+        assert_eq!(drag(PreviewPosition::Bottom, -20.0), 80.0);
+        assert_eq!(drag(PreviewPosition::Bottom, 20.4), 40.0);
+        assert_eq!(drag(PreviewPosition::Top, -20.0), 40.0);
+        // both panes keep their min height
+        assert_eq!(drag(PreviewPosition::Bottom, 100.0), 32.0);
+        assert_eq!(drag(PreviewPosition::Top, 1000.0), 368.0);
+
+        assert_eq!(clamp_preview_height(10.0, 40.0), 20.0);
+        assert_eq!(clamp_preview_height(30.0, 40.0), 20.0);
+        assert_eq!(clamp_preview_height(30.0, 0.0), 0.0);
+    }
+}
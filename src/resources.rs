@@ -0,0 +1,108 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use js_sys::{Object, Reflect};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use wasm_bindgen::prelude::*;
+
+/// Browser resources which have to be released with the charts.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Resource {
+    Listener,
+    Closure,
+    Canvas,
+    /// An element observed by a ResizeObserver or IntersectionObserver.
+    Observation,
+}
+impl Resource {
+    const ALL: [Resource; 4] = [
+        Resource::Listener,
+        Resource::Closure,
+        Resource::Canvas,
+        Resource::Observation,
+    ];
+    fn get_name(&self) -> &'static str {
+        match self {
+            Self::Listener => "listeners",
+            Self::Closure => "closures",
+            Self::Canvas => "canvases",
+            Self::Observation => "observations",
+        }
+    }
+}
+
+/// Live resources by kind; only counted with the debug-leak-check feature.
+static COUNTS: [AtomicUsize; 4] = [
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+];
+
+/// Counts a resource while alive.
+#[derive(Debug)]
+pub struct Tracked(Resource);
+impl Tracked {
+    pub fn new(resource: Resource) -> Self {
+        if cfg!(feature = "debug-leak-check") {
+            COUNTS[resource as usize].fetch_add(1, Ordering::Relaxed);
+        }
+        Self(resource)
+    }
+}
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        if cfg!(feature = "debug-leak-check") {
+            COUNTS[self.0 as usize].fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+pub fn get_count(resource: Resource) -> usize {
+    COUNTS[resource as usize].load(Ordering::Relaxed)
+}
+
+/// Builds {charts, listeners, closures, canvases, observations}; every
+/// count but charts is expected to drop to 0 once the last chart is
+/// destroyed.
+pub fn resource_counts_to_js(charts: usize) -> Result<JsValue, String> {
+    if !cfg!(feature = "debug-leak-check") {
+        return Err("built without the debug-leak-check feature".to_string());
+    }
+    let counts = Object::new();
+    Reflect::set(&counts, &"charts".into(), &(charts as f64).into()).unwrap();
+    for resource in Resource::ALL {
+        Reflect::set(
+            &counts,
+            &resource.get_name().into(),
+            &(get_count(resource) as f64).into(),
+        )
+        .unwrap();
+    }
+    Ok(counts.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resources::{get_count, Resource, Tracked};
+
+    #[test]
+    fn test_tracked() {
+        let tracked = cfg!(feature = "debug-leak-check") as usize;
+        let before = get_count(Resource::Canvas);
+        let canvas = Tracked::new(Resource::Canvas);
+        let observations = vec![
+            Tracked::new(Resource::Observation),
+            Tracked::new(Resource::Observation),
+        ];
+        assert_eq!(get_count(Resource::Canvas), before + tracked);
+        drop(canvas);
+        assert_eq!(get_count(Resource::Canvas), before);
+        drop(observations);
+        assert_eq!(get_count(Resource::Observation), 0);
+    }
+}
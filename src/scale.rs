@@ -17,6 +17,27 @@ pub trait Scale: Clone {
     fn normalize_value(&self, value: f64) -> f64;
     fn denormalize_coord(&self, normalized_coord: f64) -> f64;
     fn denormalize_value(&self, normalized_value: f64) -> f64;
+    /// Values of minor gridlines for the sorted `major` ones; `count` is the
+    /// number of lines between two majors where the scale is uniform.
+    fn minor_ticks(&self, major: &[f64], count: usize) -> Vec<f64>;
+    /// Whether values are spread by magnitude, see `LogScale`.
+    fn is_log(&self) -> bool {
+        false
+    }
+    /// Sets the value magnitudes are taken from, so log scales sharing a y
+    /// domain match; linear scales have none.
+    fn set_value_origin(&mut self, _origin: f64) {}
+}
+
+/// Replaces non-finite bounds of a domain, so that normalized values stay
+/// finite even if bad input gets through.
+pub fn get_finite_domain(min: f64, max: f64) -> (f64, f64) {
+    match (min.is_finite(), max.is_finite()) {
+        (true, true) => (min, max),
+        (true, false) => (min, min + 1.0),
+        (false, true) => (max - 1.0, max),
+        (false, false) => (0.0, 1.0),
+    }
 }
 
 #[derive(Clone)]
@@ -32,10 +53,10 @@ pub struct LinearScale {
 }
 impl LinearScale {
     pub fn new(content: &Content) -> Self {
-        let global_coord_min = content.global_coord_min;
-        let global_coord_max = content.global_coord_max;
-        let global_value_min = content.global_value_min;
-        let global_value_max = content.global_value_max;
+        let (global_coord_min, global_coord_max) =
+            get_finite_domain(content.global_coord_min, content.global_coord_max);
+        let (global_value_min, global_value_max) =
+            get_finite_domain(content.global_value_min, content.global_value_max);
         let coord_range = global_coord_max - global_coord_min;
         let value_range = global_value_max - global_value_min;
         Self {
@@ -102,6 +123,29 @@ impl Scale for LinearScale {
     fn get_value_max(&self) -> f64 {
         self.value_max
     }
+    fn minor_ticks(&self, major: &[f64], count: usize) -> Vec<f64> {
+        if major.len() < 2 || count == 0 {
+            return Vec::new();
+        }
+        let first_step = major[1] - major[0];
+        let last_step = major[major.len() - 1] - major[major.len() - 2];
+        let mut extended = Vec::with_capacity(major.len() + 2);
+        extended.push(major[0] - first_step);
+        extended.extend_from_slice(major);
+        extended.push(major[major.len() - 1] + last_step);
+
+        let mut ticks = Vec::with_capacity(extended.len() * count);
+        for pair in extended.windows(2) {
+            let step = (pair[1] - pair[0]) / (count + 1) as f64;
+            for index in 1..=count {
+                let value = pair[0] + step * index as f64;
+                if value > self.value_min && value < self.value_max {
+                    ticks.push(value);
+                }
+            }
+        }
+        ticks
+    }
 }
 
 const MIN_VALUE_TO_LOG: f64 = 1000.0;
@@ -122,10 +166,10 @@ pub struct LogScale {
 }
 impl LogScale {
     pub fn new(content: &Content) -> Self {
-        let global_coord_min = content.global_coord_min;
-        let global_coord_max = content.global_coord_max;
-        let global_value_min = content.global_value_min;
-        let global_value_max = content.global_value_max;
+        let (global_coord_min, global_coord_max) =
+            get_finite_domain(content.global_coord_min, content.global_coord_max);
+        let (global_value_min, global_value_max) =
+            get_finite_domain(content.global_value_min, content.global_value_max);
         let coord_range = global_coord_max - global_coord_min;
 
         let value_min_log = MIN_LOG_VALUE;
@@ -202,4 +246,61 @@ impl Scale for LogScale {
     fn get_value_max(&self) -> f64 {
         self.value_max
     }
+    fn minor_ticks(&self, _major: &[f64], _count: usize) -> Vec<f64> {
+        // 2x, 3x, ... 9x of every decade of the shifted values
+        let offset = MIN_VALUE_TO_LOG - self.value_global_min;
+        let low = self.value_min + offset;
+        let high = self.value_max + offset;
+        let mut ticks = Vec::new();
+        let mut magnitude = 10.0_f64.powf(low.log10().floor());
+        while magnitude <= high {
+            for digit in 2..=9 {
+                let shifted = magnitude * digit as f64;
+                if shifted > low && shifted < high {
+                    ticks.push(shifted - offset);
+                }
+            }
+            magnitude *= 10.0;
+        }
+        ticks
+    }
+    fn is_log(&self) -> bool {
+        true
+    }
+    fn set_value_origin(&mut self, origin: f64) {
+        self.value_global_min = origin;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scale::{get_finite_domain, LinearScale, Scale};
+
+    #[test]
+    fn test_get_finite_domain() {
+        assert_eq!(get_finite_domain(1.0, 5.0), (1.0, 5.0));
+        assert_eq!(get_finite_domain(1.0, f64::INFINITY), (1.0, 2.0));
+        assert_eq!(get_finite_domain(f64::NAN, 5.0), (4.0, 5.0));
+        assert_eq!(get_finite_domain(f64::NEG_INFINITY, f64::NAN), (0.0, 1.0));
+    }
+
+    #[test]
+    fn test_linear_minor_ticks() {
+        let scale = LinearScale {
+            coord_min: 0.0,
+            coord_max: 1.0,
+            coord_range: 1.0,
+            coord_range_recip: 1.0,
+            value_min: 5.0,
+            value_max: 35.0,
+            value_range: 30.0,
+            value_range_recip: 30.0_f64.recip(),
+        };
+        assert_eq!(scale.minor_ticks(&[10.0, 20.0, 30.0], 1), vec![15.0, 25.0]);
+        assert_eq!(
+            scale.minor_ticks(&[10.0, 20.0], 4),
+            vec![6.0, 8.0, 12.0, 14.0, 16.0, 18.0, 22.0, 24.0, 26.0, 28.0]
+        );
+        assert!(scale.minor_ticks(&[10.0], 4).is_empty());
+    }
 }
@@ -6,8 +6,9 @@
  * Copyright (C) 2023, Nikita Almakov
  */
 use crate::params::ClientCaps;
+use crate::resources::{Resource, Tracked};
 use crate::scale::Scale;
-use crate::utils::js_coords_to_global;
+use crate::utils::{get_resolution_coeff, js_coords_to_global};
 use js_sys::Reflect;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -30,6 +31,8 @@ where
     pub canvas_width: f64,
     pub canvas_height: f64,
     pub canvas_padding: [f64; 4],
+    /// Maps coords right to left.
+    pub reversed_x: bool,
 }
 impl<T> ScreenArea<T>
 where
@@ -47,24 +50,39 @@ where
             canvas_width: 0.0,
             canvas_height: 0.0,
             canvas_padding: padding.map(|v| screen.apx_to_cpx(v)),
+            reversed_x: false,
         };
         screen_area.update(screen);
         screen_area
     }
+    pub fn set_padding(&mut self, screen: &Screen, padding: [f64; 4]) {
+        self.canvas_padding = padding.map(|v| screen.apx_to_cpx(v));
+        self.update(screen);
+    }
     pub fn update(&mut self, screen: &Screen) {
         self.screen_x = screen.x;
         self.screen_y = screen.y;
         self.screen_to_canvas_scale = screen.css_px_to_cpx(1.0);
         self.canvas_width = screen.css_px_to_cpx(screen.width);
         self.canvas_height = screen.css_px_to_cpx(screen.height);
+        // paddings may not fit tiny or hidden screens
         self.canvas_content_width =
-            self.canvas_width - self.canvas_padding[1] - self.canvas_padding[3];
+            (self.canvas_width - self.canvas_padding[1] - self.canvas_padding[3]).max(0.0);
         self.canvas_content_height =
-            self.canvas_height - self.canvas_padding[0] - self.canvas_padding[2];
+            (self.canvas_height - self.canvas_padding[0] - self.canvas_padding[2]).max(0.0);
+    }
+    #[inline]
+    fn flip_x(&self, normalized_coord: f64) -> f64 {
+        if self.reversed_x {
+            1.0 - normalized_coord
+        } else {
+            normalized_coord
+        }
     }
     #[inline]
     pub fn get_cx(&self, coord: f64) -> f64 {
-        self.scale.normalize_coord(coord) * self.canvas_content_width + self.canvas_padding[3]
+        self.flip_x(self.scale.normalize_coord(coord)) * self.canvas_content_width
+            + self.canvas_padding[3]
     }
     #[inline]
     pub fn get_cy(&self, value: f64) -> f64 {
@@ -95,16 +113,26 @@ where
     pub fn get_content_cheight(&self) -> f64 {
         self.canvas_content_height
     }
+    /// Returns the part of the content width from the start of the x axis,
+    /// which is the right edge when the axis is reversed.
+    pub fn x_to_portion(&self, x: f64) -> f64 {
+        self.flip_x(
+            ((x - self.screen_x) * self.screen_to_canvas_scale - self.canvas_padding[3])
+                / self.canvas_content_width,
+        )
+    }
     pub fn x_to_coord(&self, x: f64) -> Option<f64> {
-        let normalized_coord = ((x - self.screen_x) * self.screen_to_canvas_scale
-            - self.canvas_padding[3])
-            / self.canvas_content_width;
+        let normalized_coord = self.x_to_portion(x);
         if normalized_coord >= 0.0 && normalized_coord <= 1.0 {
             Some(self.scale.denormalize_coord(normalized_coord))
         } else {
             None
         }
     }
+    /// Like `x_to_coord`, but extrapolates outside of the content.
+    pub fn x_to_coord_unbounded(&self, x: f64) -> f64 {
+        self.scale.denormalize_coord(self.x_to_portion(x))
+    }
     pub fn y_to_value(&self, y: f64) -> Option<f64> {
         let normalized_value = 1.0
             - ((y - self.screen_y) * self.screen_to_canvas_scale - self.canvas_padding[0])
@@ -115,12 +143,19 @@ where
             None
         }
     }
+    /// Like `y_to_value`, but extrapolates outside of the content.
+    pub fn y_to_value_unbounded(&self, y: f64) -> f64 {
+        self.scale.denormalize_value(
+            1.0 - ((y - self.screen_y) * self.screen_to_canvas_scale - self.canvas_padding[0])
+                / self.canvas_content_height,
+        )
+    }
     pub fn coord_to_x(&self, coord: f64) -> f64 {
         self.get_cx(coord) / self.screen_to_canvas_scale + self.screen_x
     }
-    // pub fn value_to_y(&self, value: f64) -> f64 {
-    //     self.get_cy(value) / self.screen_to_canvas_scale + self.screen_y
-    // }
+    pub fn value_to_y(&self, value: f64) -> f64 {
+        self.get_cy(value) / self.screen_to_canvas_scale + self.screen_y
+    }
     pub fn x_to_cx(&self, x: f64) -> f64 {
         (x - self.screen_x) * self.screen_to_canvas_scale
     }
@@ -129,12 +164,21 @@ where
     }
 }
 
+/// Upper bounds of the canvas resolution, None for no bound.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct ResolutionCap {
+    pub max_pixel_ratio: Option<f64>,
+    /// Of the longer canvas side, in physical pixels.
+    pub max_canvas_dimension: Option<f64>,
+}
+
 pub struct Screen {
     pub canvas: web_sys::HtmlCanvasElement,
     pub context: web_sys::CanvasRenderingContext2d,
     client_caps: Rc<RefCell<ClientCaps>>,
     pub css_to_physical_scale: f64,
     device_pixel_ratio: f64,
+    resolution_cap: ResolutionCap,
     pub canvas_size_sync_needed: bool,
     pub x: f64,
     pub y: f64,
@@ -142,12 +186,14 @@ pub struct Screen {
     pub height: f64,
     pub canvas_width: f64,
     pub canvas_height: f64,
+    _tracked: Tracked,
 }
 impl Screen {
     pub fn new(
         container_selector: &str,
         client_caps: Rc<RefCell<ClientCaps>>,
         style: &str,
+        resolution_cap: ResolutionCap,
     ) -> Result<Screen, String> {
         let document = web_sys::window().unwrap().document().unwrap();
         let container = document
@@ -164,18 +210,26 @@ impl Screen {
         canvas.set_attribute("style", style).unwrap();
 
         let context_options = js_sys::Object::new();
-        let context = canvas
+        // locked down browsers may have no 2d context
+        let context = match canvas
             .get_context_with_context_options("2d", &context_options)
-            .unwrap()
-            .ok_or_else(|| "failed to get canvas 2d context".to_string())?
-            .dyn_into::<web_sys::CanvasRenderingContext2d>()
-            .unwrap();
+            .ok()
+            .flatten()
+            .and_then(|context| context.dyn_into::<web_sys::CanvasRenderingContext2d>().ok())
+        {
+            Some(context) => context,
+            None => {
+                canvas.remove();
+                return Err("failed to get canvas 2d context".to_string());
+            }
+        };
         let mut screen = Screen {
             canvas,
             context,
             client_caps,
             css_to_physical_scale: 0.0,
             device_pixel_ratio: 0.0,
+            resolution_cap,
             canvas_size_sync_needed: true,
             x: 0.0,
             y: 0.0,
@@ -183,20 +237,13 @@ impl Screen {
             height: 0.0,
             canvas_width: 0.0,
             canvas_height: 0.0,
+            _tracked: Tracked::new(Resource::Canvas),
         };
         screen.sync_canvas_size();
         Ok(screen)
     }
     pub fn sync_canvas_size(&mut self) -> bool {
         if self.canvas_size_sync_needed {
-            let client_caps = self.client_caps.borrow();
-            let force_size_update_needed =
-                self.css_to_physical_scale != client_caps.css_to_physical_scale;
-            if force_size_update_needed {
-                self.css_to_physical_scale = client_caps.css_to_physical_scale;
-                self.device_pixel_ratio = client_caps.device_pixel_ratio;
-            }
-
             let rect = Reflect::get(&self.canvas, &JsValue::from_str("getBoundingClientRect"))
                 .unwrap()
                 .dyn_into::<js_sys::Function>()
@@ -222,6 +269,22 @@ impl Screen {
                 .as_f64()
                 .unwrap();
 
+            // the capped resolution scales drawing and pointer mapping alike
+            let client_caps = self.client_caps.borrow();
+            let coeff = get_resolution_coeff(
+                client_caps.device_pixel_ratio,
+                self.resolution_cap.max_pixel_ratio,
+                width.max(height) * client_caps.css_to_physical_scale,
+                self.resolution_cap.max_canvas_dimension,
+            );
+            let css_to_physical_scale = client_caps.css_to_physical_scale * coeff;
+            let force_size_update_needed = self.css_to_physical_scale != css_to_physical_scale;
+            if force_size_update_needed {
+                self.css_to_physical_scale = css_to_physical_scale;
+                self.device_pixel_ratio = client_caps.device_pixel_ratio * coeff;
+            }
+            drop(client_caps);
+
             if self.width != width || force_size_update_needed {
                 self.width = width;
                 self.canvas_width = self.css_px_to_cpx(self.width);
@@ -240,6 +303,14 @@ impl Screen {
             false
         }
     }
+    /// Shrinks the backing store to a pixel; the next sync restores it.
+    pub fn release_canvas(&mut self) {
+        self.canvas.set_width(1);
+        self.canvas.set_height(1);
+        self.width = 0.0;
+        self.height = 0.0;
+        self.canvas_size_sync_needed = true;
+    }
     pub fn schedule_canvas_size_sync(&mut self) {
         self.canvas_size_sync_needed = true;
     }
@@ -283,6 +354,21 @@ impl ScreenRect {
             cy2: cy + height,
         }
     }
+    /// Reflects the rect horizontally within [cx_start, cx_end].
+    pub fn mirror_x(&self, cx_start: f64, cx_end: f64) -> Self {
+        Self {
+            cx1: cx_start + cx_end - self.cx2,
+            cy1: self.cy1,
+            cx2: cx_start + cx_end - self.cx1,
+            cy2: self.cy2,
+        }
+    }
+    /// Distance from the point to the rect, zero inside.
+    pub fn distance_to(&self, cx: f64, cy: f64) -> f64 {
+        let dx = (self.cx1 - cx).max(cx - self.cx2).max(0.0);
+        let dy = (self.cy1 - cy).max(cy - self.cy2).max(0.0);
+        dx.hypot(dy)
+    }
     pub fn contains(&self, cx: f64, cy: f64) -> bool {
         self.cx1 <= cx && self.cx2 >= cx && self.cy1 <= cy && self.cy2 >= cy
     }
@@ -0,0 +1,142 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use js_sys::{Object, Reflect};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Built-in user-visible strings by key; placeholders in braces are
+/// substituted by `Strings::format`.
+pub const DEFAULT_STRINGS: [(&str, &str); 12] = [
+    ("previewZoomIn", "Drag here or above to zoom in"),
+    ("previewZoomOut", "Click to zoom out"),
+    ("tooltipHiddenLines", "{count} hidden"),
+    ("tooltipMoreLines", "+{count} more: {sum}"),
+    ("zoomWindowLabel", "zoom window"),
+    ("previewSplitterLabel", "preview height"),
+    (
+        "summary",
+        "line chart, {count} series, from {from} to {to}.",
+    ),
+    (
+        "summaryWithName",
+        "{name}: line chart, {count} series, from {from} to {to}.",
+    ),
+    (
+        "summarySeries",
+        "{name}: min {min}, max {max}, latest {latest}.",
+    ),
+    ("tableCoordHeader", "x"),
+    ("noData", "No data"),
+    ("comparisonName", "{name} (compared)"),
+];
+
+#[derive(Clone, Default)]
+pub struct Strings {
+    overrides: HashMap<String, String>,
+}
+
+impl Strings {
+    /// Reads overrides from an object of key-string pairs; unknown keys are
+    /// rejected to catch typos.
+    pub fn from_raw(raw_strings: &JsValue) -> Result<Self, String> {
+        let mut overrides = HashMap::new();
+        if raw_strings.is_undefined() || raw_strings.is_null() {
+            return Ok(Self { overrides });
+        }
+        let raw_strings = raw_strings
+            .dyn_ref::<Object>()
+            .ok_or_else(|| "not an object: strings".to_string())?;
+        for key in Object::keys(raw_strings).iter() {
+            let key = key.as_string().unwrap();
+            if !DEFAULT_STRINGS.iter().any(|(k, _)| *k == key.as_str()) {
+                return Err(format!("unknown key: strings.{}", key));
+            }
+            let value = Reflect::get(raw_strings, &key.as_str().into())
+                .unwrap()
+                .as_string()
+                .ok_or_else(|| format!("not a string: strings.{}", key))?;
+            overrides.insert(key, value);
+        }
+        Ok(Self { overrides })
+    }
+    pub fn get(&self, key: &str) -> &str {
+        match self.overrides.get(key) {
+            Some(value) => value.as_str(),
+            None => DEFAULT_STRINGS
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, value)| *value)
+                .unwrap_or_else(|| panic!("unknown string key: {}", key)),
+        }
+    }
+    /// Substitutes `{name}` placeholders of the string with the values in a
+    /// single pass, so values are never substituted themselves.
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        let mut result = String::new();
+        let mut rest = self.get(key);
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let arg = rest.find('}').and_then(|end| {
+                args.iter()
+                    .find(|(name, _)| *name == &rest[1..end])
+                    .map(|(_, value)| (end, value))
+            });
+            match arg {
+                Some((end, value)) => {
+                    result.push_str(value);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    result.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+    /// Returns the built-in strings as an object, for translation tools.
+    pub fn defaults_to_js() -> JsValue {
+        let object = Object::new();
+        for (key, value) in DEFAULT_STRINGS.iter() {
+            Reflect::set(&object, &(*key).into(), &(*value).into()).unwrap();
+        }
+        object.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strings::Strings;
+
+    #[test]
+    fn test_strings() {
+        let mut strings = Strings::default();
+        assert_eq!(strings.get("previewZoomOut"), "Click to zoom out");
+        assert_eq!(
+            strings.format("tooltipHiddenLines", &[("count", "3")]),
+            "3 hidden"
+        );
+        strings
+            .overrides
+            .insert("tooltipHiddenLines".to_string(), "+{count}".to_string());
+        assert_eq!(
+            strings.format("tooltipHiddenLines", &[("count", "3")]),
+            "+3"
+        );
+        assert_eq!(strings.get("previewZoomOut"), "Click to zoom out");
+        assert_eq!(
+            strings.format(
+                "summarySeries",
+                &[("name", "{min}"), ("min", "1"), ("max", "2")]
+            ),
+            "{min}: min 1, max 2, latest {latest}."
+        );
+    }
+}
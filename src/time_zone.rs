@@ -0,0 +1,158 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use chrono::prelude::*;
+use js_sys::{Array, Object, Reflect};
+use std::cell::RefCell;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+thread_local! {
+    /// Names and Intl.DateTimeFormat instances of the IANA zones in use, see
+    /// `TimeZone::Named`.
+    static NAMED_ZONES: RefCell<Vec<(String, Object)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The zone datetime coords are shown in; offsets are looked up per instant,
+/// so days across DST transitions have 23 or 25 hours.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TimeZone {
+    /// The zone of the browser.
+    Local,
+    Utc,
+    /// Index of an IANA zone in `NAMED_ZONES`.
+    Named(usize),
+}
+impl FromStr for TimeZone {
+    type Err = String;
+    /// Parses "local", "utc" or an IANA name, which the browser should know.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "local" => return Ok(Self::Local),
+            "utc" | "UTC" => return Ok(Self::Utc),
+            _ => {}
+        }
+        NAMED_ZONES.with(|zones| {
+            let mut zones = zones.borrow_mut();
+            if let Some(index) = zones.iter().position(|(name, _)| name == s) {
+                return Ok(Self::Named(index));
+            }
+            let options = Object::new();
+            Reflect::set(&options, &"timeZone".into(), &s.into()).unwrap();
+            Reflect::set(&options, &"hourCycle".into(), &"h23".into()).unwrap();
+            for key in ["year", "month", "day", "hour", "minute", "second"] {
+                Reflect::set(&options, &key.into(), &"numeric".into()).unwrap();
+            }
+            let constructor = Reflect::get(&js_sys::global(), &"Intl".into())
+                .and_then(|intl| Reflect::get(&intl, &"DateTimeFormat".into()))
+                .unwrap();
+            // a RangeError for names the browser does not know
+            let format = Reflect::construct(
+                constructor.unchecked_ref::<js_sys::Function>(),
+                &Array::of2(&"en-US".into(), &options),
+            )
+            .map_err(|_| format!("unsupported timeZone: {}", s))?;
+            zones.push((s.to_string(), format.unchecked_into()));
+            Ok(Self::Named(zones.len() - 1))
+        })
+    }
+}
+impl TimeZone {
+    /// Local time minus UTC at the instant, in ms.
+    pub fn get_offset_ms(&self, utc_ms: f64) -> f64 {
+        match self {
+            Self::Utc => 0.0,
+            Self::Local => {
+                -js_sys::Date::new(&JsValue::from_f64(utc_ms)).get_timezone_offset() * 60000.0
+            }
+            Self::Named(index) => NAMED_ZONES.with(|zones| {
+                let format = &zones.borrow()[*index].1;
+                let format_to_parts: js_sys::Function =
+                    Reflect::get(format, &"formatToParts".into())
+                        .unwrap()
+                        .unchecked_into();
+                let parts: Array = format_to_parts
+                    .call1(format, &js_sys::Date::new(&JsValue::from_f64(utc_ms)))
+                    .unwrap()
+                    .unchecked_into();
+                let mut fields = [0i64; 6];
+                for part in parts.iter() {
+                    let kind = Reflect::get(&part, &"type".into()).unwrap().as_string();
+                    let value = Reflect::get(&part, &"value".into()).unwrap().as_string();
+                    let field = match kind.as_deref() {
+                        Some("year") => 0,
+                        Some("month") => 1,
+                        Some("day") => 2,
+                        Some("hour") => 3,
+                        Some("minute") => 4,
+                        Some("second") => 5,
+                        _ => continue,
+                    };
+                    fields[field] = value.and_then(|v| v.parse().ok()).unwrap_or(0);
+                }
+                let local_ms =
+                    NaiveDate::from_ymd_opt(fields[0] as i32, fields[1] as u32, fields[2] as u32)
+                        .and_then(|date| {
+                            date.and_hms_opt(fields[3] as u32, fields[4] as u32, fields[5] as u32)
+                        })
+                        .map_or(utc_ms, |date_time| {
+                            date_time.and_utc().timestamp_millis() as f64
+                        });
+                // the parts have no ms
+                local_ms - (utc_ms / 1000.0).floor() * 1000.0
+            }),
+        }
+    }
+    pub fn get_fixed_offset(&self, utc_ms: f64) -> FixedOffset {
+        FixedOffset::east_opt((self.get_offset_ms(utc_ms) / 1000.0) as i32)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap())
+    }
+}
+
+/// Moves a tick placed by a fixed offset (e.g. on a local midnight) to the
+/// same local time under the offset at the tick.
+pub fn align_to_local<F: Fn(f64) -> f64>(
+    value: f64,
+    fixed_offset_ms: f64,
+    get_offset_ms: F,
+) -> f64 {
+    let aligned = value + fixed_offset_ms - get_offset_ms(value);
+    // right after a transition the offset of the aligned instant differs
+    value + fixed_offset_ms - get_offset_ms(aligned)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::time_zone::align_to_local;
+
+    #[test]
+    fn test_align_to_local() {
+        const HOUR: f64 = 36e5;
+        // +1h, +2h from 2024-03-31 01:00 UTC (e.g. Europe/Berlin)
+        let transition = 1711846800000.0;
+        let get_offset_ms = |utc_ms: f64| {
+            if utc_ms < transition {
+                HOUR
+            } else {
+                2.0 * HOUR
+            }
+        };
+        // 2024-03-30 00:00 local, placed with +1h
+        let midnight = 1711753200000.0;
+        assert_eq!(align_to_local(midnight, HOUR, get_offset_ms), midnight);
+        assert_eq!(
+            align_to_local(midnight + 24.0 * HOUR, HOUR, get_offset_ms),
+            midnight + 24.0 * HOUR
+        );
+        // March 31 has 23 hours
+        assert_eq!(
+            align_to_local(midnight + 48.0 * HOUR, HOUR, get_offset_ms),
+            midnight + 47.0 * HOUR
+        );
+    }
+}
@@ -7,29 +7,183 @@
  */
 use crate::animate::AnimatedNumber;
 use crate::data_set::{DataPoint, DataSet};
-use crate::params::{ChartConfig, Content};
+use crate::html_tooltip::{HtmlTooltip, HtmlTooltipLine};
+use crate::params::{
+    ChangeBase, ChartConfig, Content, EdgeBehavior, TooltipMode, TooltipPosition, VerboseFormat,
+};
+use crate::pattern::HatchPatterns;
 use crate::scale::Scale;
 use crate::screen::{Screen, ScreenArea};
-use crate::utils::place_rect_inside;
+use crate::utils::{
+    format_change, place_rect_inside, select_tooltip_rows, snap_line_width, snap_rect,
+};
 use std::f64::consts::PI;
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 
+// apx around a marker which still counts as hovering it
+const MARKER_HIT_PADDING: f64 = 4.0;
+
+#[derive(Clone, PartialEq)]
+pub struct TooltipHover {
+    pub coord: f64,
+    pub values: Vec<(String, f64)>,
+    pub nearest: String,
+}
+
+/// A series of another chart of the link group, see `TooltipSection`.
+#[derive(Clone, PartialEq)]
+pub struct TooltipSectionLine {
+    pub name: String,
+    pub value: f64,
+    pub formatted_value: String,
+    pub color: String,
+}
+
+/// Values of another chart of the link group at the hovered coord, listed
+/// under its title in the shared tooltip.
+#[derive(Clone, PartialEq)]
+pub struct TooltipSection {
+    pub title: String,
+    pub lines: Vec<TooltipSectionLine>,
+    /// The lines collapsed by `tooltipMaxEntries` of the other chart.
+    pub more_line: Option<String>,
+}
+
 pub struct Tooltip {
     pub chart_config: Rc<ChartConfig>,
     min_width: AnimatedNumber,
     pub visible: bool,
     pub mouse_click_at: Option<(f64, f64)>,
+    pub hover: Option<TooltipHover>,
+    html: Option<HtmlTooltip>,
+    pub hatch_patterns: HatchPatterns,
+    /// Sections of the other charts of the link group, see `sharedTooltip`.
+    pub shared_sections: Vec<TooltipSection>,
 }
 
 impl Tooltip {
-    pub fn new(chart_config: Rc<ChartConfig>) -> Self {
+    pub fn set_chart_config(&mut self, chart_config: Rc<ChartConfig>) {
+        if let Some(html) = &mut self.html {
+            html.set_chart_config(Rc::clone(&chart_config));
+        }
+        self.chart_config = chart_config;
+    }
+    pub fn new(chart_config: Rc<ChartConfig>, wrapper: &web_sys::Element) -> Self {
+        let html = if chart_config.tooltip_mode == TooltipMode::Html {
+            Some(HtmlTooltip::new(Rc::clone(&chart_config), wrapper))
+        } else {
+            None
+        };
         Self {
             chart_config,
             min_width: AnimatedNumber::custom(0.0, 500000.0, 500000.0),
             visible: false,
             mouse_click_at: None,
+            hover: None,
+            html,
+            hatch_patterns: HatchPatterns::default(),
+            shared_sections: Vec::new(),
+        }
+    }
+
+    /// Returns the index of the data set and the coord of the marked point
+    /// nearest to the pointer, if the pointer is over its marker.
+    fn find_marked_point<T: Scale>(
+        &self,
+        content: &Content,
+        screen: &Screen,
+        screen_area: &ScreenArea<T>,
+        pointer_cx: f64,
+        pointer_cy: f64,
+    ) -> Option<(usize, f64)> {
+        let c_hit_padding = screen.apx_to_cpx(MARKER_HIT_PADDING);
+        let mut nearest: Option<(usize, f64, f64)> = None;
+        for (index, data_set) in content.data_sets.iter().enumerate() {
+            if data_set.alpha.get_end_value() == 0.0 || !data_set.hoverable {
+                continue;
+            }
+            for (point_index, marker) in data_set.markers.iter() {
+                let distance = (screen_area.get_cx(marker.coord) - pointer_cx).hypot(
+                    screen_area.get_cy(data_set.data_points[*point_index].value) - pointer_cy,
+                );
+                let c_radius = screen
+                    .apx_to_cpx(marker.size.unwrap_or(self.chart_config.point_marker_size))
+                    * 0.5;
+                if distance <= c_radius + c_hit_padding
+                    && nearest.is_none_or(|nearest| distance < nearest.2)
+                {
+                    nearest = Some((index, marker.coord, distance));
+                }
+            }
         }
+        nearest.map(|(index, coord, _)| (index, coord))
+    }
+
+    /// Whether the coord is before the first or after the last point of all
+    /// visible data sets.
+    fn is_past_data_edges(content: &Content, coord: f64) -> bool {
+        content
+            .data_sets
+            .iter()
+            .filter(|data_set| data_set.alpha.get_end_value() > 0.0)
+            .all(|data_set| {
+                coord < data_set.data_points[0].coord
+                    || coord > data_set.data_points[data_set.data_points.len() - 1].coord
+            })
+    }
+
+    /// Draws a small box with the "no data" message next to the pointer.
+    fn draw_no_data<T>(
+        &self,
+        screen: &Screen,
+        screen_area: &ScreenArea<T>,
+        pointer_cx: f64,
+        pointer_cy: f64,
+    ) where
+        T: Scale,
+    {
+        let context = &screen.context;
+        let message = self.chart_config.strings.get("noData");
+        let c_line_width = snap_line_width(screen.apx_to_cpx(self.chart_config.stroke_width_coeff));
+        let c_padding = screen.apx_to_cpx(5.0);
+        let c_font_size = screen.apx_to_cpx(self.chart_config.font_size_normal);
+        context.set_font(
+            format!(
+                "{:.0}px {}",
+                c_font_size,
+                self.chart_config.font_standard.as_str()
+            )
+            .as_str(),
+        );
+        let width = context.measure_text(message).unwrap().width() + c_padding * 2.0;
+        let height = c_font_size + c_padding * 2.0;
+        let (x, y) = place_rect_inside(
+            (pointer_cx, pointer_cy),
+            (width, height),
+            [
+                c_line_width,
+                screen_area.top_cy() + c_line_width,
+                screen_area.right_cx() - c_line_width,
+                screen_area.bottom_cy() - c_line_width,
+            ],
+            (screen.apx_to_cpx(25.0), screen.apx_to_cpx(10.0)),
+        );
+        let (x, y, width, height) = snap_rect((x, y, width, height), c_line_width);
+        let v = &self.chart_config.color_tooltip;
+        context.set_fill_style_str(format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3).as_str());
+        context.fill_rect(x, y, width, height);
+        let v = &self.chart_config.color_tooltip_font;
+        let font_color = format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3);
+        context.set_line_width(c_line_width);
+        context.set_stroke_style_str(font_color.as_str());
+        context.stroke_rect(x, y, width, height);
+        context.set_fill_style_str(font_color.as_str());
+        context.set_text_baseline("top");
+        context.set_text_align("left");
+        context
+            .fill_text(message, x + c_padding, y + c_padding)
+            .unwrap();
     }
 
     pub fn draw<'a, T>(
@@ -60,69 +214,103 @@ impl Tooltip {
 
         if data.is_none() {
             self.visible = false;
+            self.hover = None;
+            if let Some(html) = &mut self.html {
+                html.hide();
+            }
             return;
         }
-        let (coord, value, pointer_cx, pointer_cy) = data.unwrap();
+        let (mut coord, value, pointer_cx, pointer_cy) = data.unwrap();
         self.visible = true;
 
-        let mut max_coord: f64 = f64::MIN;
-        let mut left_matches: Vec<(&DataSet, &DataPoint)> =
-            Vec::with_capacity(content.data_sets.len());
-        for data_set in content.data_sets.iter() {
-            if data_set.alpha.get_end_value() == 0.0 {
-                continue;
-            }
-            if let Some(index) = data_set.bin_search_right_bound(coord) {
-                let data_point = &data_set.data_points[index];
-                if max_coord < data_point.coord {
-                    max_coord = data_point.coord;
-                }
-                left_matches.push((data_set, data_point));
-            }
+        // a marked point under the pointer wins over the nearest coord
+        let marked = self.find_marked_point(content, screen, screen_area, pointer_cx, pointer_cy);
+        if let Some((_, marked_coord)) = marked {
+            coord = marked_coord;
         }
-        left_matches.retain(|m| m.1.coord == max_coord);
-
-        if left_matches.len() == 0 {
+        if self.chart_config.edge_behavior != EdgeBehavior::Clip
+            && Self::is_past_data_edges(content, coord)
+        {
+            self.hover = None;
+            if let Some(html) = &mut self.html {
+                html.hide();
+            }
+            self.draw_no_data(screen, screen_area, pointer_cx, pointer_cy);
             return;
         }
 
-        let mut min_coord: f64 = f64::MAX;
-        let mut right_matches: Vec<(&DataSet, &DataPoint)> =
-            Vec::with_capacity(content.data_sets.len());
-        for data_set in content.data_sets.iter() {
-            if data_set.alpha.get_end_value() == 0.0 {
-                continue;
-            }
-            if let Some(index) = data_set.bin_search_left_bound(coord) {
-                let data_point = &data_set.data_points[index];
-                if min_coord > data_point.coord {
-                    min_coord = data_point.coord;
+        // the closest coords of any series on both sides of the pointer
+        let hovered_data_sets = content
+            .data_sets
+            .iter()
+            .filter(|data_set| data_set.alpha.get_end_value() != 0.0 && data_set.hoverable);
+        let left_coords = hovered_data_sets.clone().filter_map(|data_set| {
+            let index = data_set.bin_search_right_bound(coord)?;
+            Some(data_set.data_points[index].coord)
+        });
+        let right_coords = hovered_data_sets.clone().filter_map(|data_set| {
+            let index = data_set.bin_search_left_bound(coord)?;
+            Some(data_set.data_points[index].coord)
+        });
+        let max_coord = match left_coords.reduce(f64::max) {
+            Some(max_coord) => max_coord,
+            None => {
+                self.hover = None;
+                if let Some(html) = &mut self.html {
+                    html.hide();
                 }
-                right_matches.push((data_set, data_point));
+                return;
             }
-        }
-        right_matches.retain(|m| m.1.coord == min_coord);
+        };
+        let min_coord = right_coords.fold(f64::MAX, f64::min);
 
         let cx_step_size = screen_area.get_cx(min_coord) - screen_area.get_cx(max_coord);
-        let matched_coord: f64;
-        let mut matches = if (coord - min_coord).abs() < (coord - max_coord).abs() {
-            matched_coord = min_coord;
-            right_matches
+        let matched_coord = if (coord - min_coord).abs() < (coord - max_coord).abs() {
+            min_coord
         } else {
-            matched_coord = max_coord;
-            left_matches
+            max_coord
         };
-
-        let mut min_diff: f64 = f64::MAX;
-        let mut index_with_min_diff_by_value: usize = 0;
-        for (index, (_, data_point)) in matches.iter().cloned().enumerate() {
-            let diff = (data_point.value - value).abs();
-            if min_diff > diff {
-                min_diff = diff;
-                index_with_min_diff_by_value = index;
+        // series with coords of their own show their nearest point, as long
+        // as the matched coord is within their data
+        let matches: Vec<(&DataSet, &DataPoint, usize)> = hovered_data_sets
+            .filter(|data_set| data_set.covers(matched_coord))
+            .filter_map(|data_set| {
+                let index = data_set.get_nearest_index(matched_coord)?;
+                Some((data_set, &data_set.data_points[index], index))
+            })
+            .collect();
+
+        let mut index_with_min_diff_by_value = get_nearest_by_value(&matches, value);
+        if let Some(index) = marked.and_then(|(data_set_index, _)| {
+            matches
+                .iter()
+                .position(|m| std::ptr::eq(m.0, &content.data_sets[data_set_index]))
+        }) {
+            index_with_min_diff_by_value = index;
+        }
+        self.hover = Some(TooltipHover {
+            coord: matched_coord,
+            values: matches
+                .iter()
+                .map(|(data_set, data_point, _)| (data_set.name.clone(), data_point.value))
+                .collect(),
+            nearest: matches[index_with_min_diff_by_value].0.name.clone(),
+        });
+
+        // series left out of the tooltip still count for the hover
+        let nearest = matches[index_with_min_diff_by_value].0;
+        let mut matches = matches;
+        matches.retain(|m| m.0.show_in_tooltip);
+        if matches.is_empty() {
+            if let Some(html) = &mut self.html {
+                html.hide();
             }
+            return;
         }
-        drop(min_diff);
+        index_with_min_diff_by_value = matches
+            .iter()
+            .position(|m| std::ptr::eq(m.0, nearest))
+            .unwrap_or_else(|| get_nearest_by_value(&matches, value));
 
         let coord_format = &content.coord_verbose_format;
         let value_format = &content.value_verbose_format;
@@ -138,24 +326,115 @@ impl Tooltip {
             .next()
             .unwrap();
 
-        let max_name_length = matches
+        let formatted_values: Vec<String> = value_format
+            .format_values(
+                matches.iter().cloned(),
+                |t| t.1.value,
+                screen_area.global_scale.get_value_min(),
+                screen_area.global_scale.get_value_max(),
+            )
+            .into_iter()
+            .zip(matches.iter())
+            .map(|(formatted_value, m)| m.0.with_unit(formatted_value))
+            .collect();
+
+        let changes: Vec<Option<(f64, Option<f64>)>> = matches
             .iter()
-            .cloned()
-            .map(|t| t.0.name.len())
-            .max()
-            .unwrap();
-        let formatted_values = value_format.format_values(
-            matches.iter().cloned(),
-            |t| t.1.value,
+            .map(|(data_set, data_point, index)| {
+                // a comparison row tells how the current value differs from it
+                if let Some(comparison) = &data_set.comparison {
+                    return matches
+                        .iter()
+                        .find(|(current, _, _)| current.name == comparison.of)
+                        .map(|(_, current, _)| {
+                            let delta = current.value - data_point.value;
+                            (
+                                delta,
+                                if data_point.value == 0.0 {
+                                    None
+                                } else {
+                                    Some(delta / data_point.value.abs() * 100.0)
+                                },
+                            )
+                        });
+                }
+                if !self.chart_config.tooltip_show_change {
+                    return None;
+                }
+                let base_index = match self.chart_config.tooltip_change_base {
+                    ChangeBase::Previous => index.checked_sub(1),
+                    ChangeBase::First => data_set
+                        .bin_search_left_bound(screen_area.scale.get_coord_min())
+                        .filter(|base_index| base_index < index),
+                };
+                base_index.map(|base_index| {
+                    let base = data_set.data_points[base_index].value;
+                    let delta = data_point.value - base;
+                    (
+                        delta,
+                        if base == 0.0 {
+                            None
+                        } else {
+                            Some(delta / base.abs() * 100.0)
+                        },
+                    )
+                })
+            })
+            .collect();
+        let mut formatted_deltas = value_format
+            .format_values(
+                changes.iter().flatten(),
+                |change| change.0,
+                screen_area.global_scale.get_value_min(),
+                screen_area.global_scale.get_value_max(),
+            )
+            .into_iter();
+        let formatted_changes: Vec<Option<String>> = changes
+            .iter()
+            .map(|change| {
+                change.map(|(delta, percent)| {
+                    format_change(formatted_deltas.next().unwrap().as_str(), delta, percent)
+                })
+            })
+            .collect();
+        let value_texts: Vec<String> = formatted_values
+            .iter()
+            .zip(formatted_changes.iter())
+            .map(
+                |(formatted_value, formatted_change)| match formatted_change {
+                    Some(formatted_change) => format!("{} {}", formatted_value, formatted_change),
+                    None => formatted_value.clone(),
+                },
+            )
+            .collect();
+
+        let (rows, collapsed) = select_tooltip_rows(
+            &matches.iter().map(|m| m.1.value).collect::<Vec<f64>>(),
+            self.chart_config.tooltip_sort,
+            self.chart_config.tooltip_hide_zero,
+            self.chart_config.tooltip_max_entries,
+            Some(index_with_min_diff_by_value),
+        );
+        let mut more_line = format_more_line(
+            &self.chart_config,
+            value_format,
+            collapsed.iter().map(|index| matches[*index].1.value),
             screen_area.global_scale.get_value_min(),
             screen_area.global_scale.get_value_max(),
         );
-        let max_formatted_value_length: usize =
-            formatted_values.iter().map(|v| v.len()).max().unwrap();
+        index_with_min_diff_by_value = rows
+            .iter()
+            .position(|index| *index == index_with_min_diff_by_value)
+            .unwrap();
+        let mut matches: Vec<(&DataSet, &DataPoint, usize)> =
+            rows.iter().map(|index| matches[*index]).collect();
+        let formatted_values = pick_rows(&formatted_values, &rows);
+        let formatted_changes = pick_rows(&formatted_changes, &rows);
+        let value_texts = pick_rows(&value_texts, &rows);
 
         let context = &screen.context;
 
-        let c_line_width = screen.apx_to_cpx(1.0);
+        let c_line_width = snap_line_width(screen.apx_to_cpx(self.chart_config.stroke_width_coeff));
         let c_padding: f64 = screen.apx_to_cpx(5.0);
         let c_additional_gap_after_heading: f64 = screen.apx_to_cpx(10.0);
         let c_gap_between_lines: f64 = screen.apx_to_cpx(2.0);
@@ -165,13 +444,54 @@ impl Tooltip {
         let c_font_size: f64 = screen.apx_to_cpx(self.chart_config.font_size_normal);
         let c_color_size: f64 = c_font_size;
         let c_expected_tooltip_shift_x: f64 = screen.apx_to_cpx(25.0).max(cx_step_size * 0.125);
-        let c_font_width = c_font_size * self.chart_config.font_width_coeff;
-        let c_heading_width = content.coord_short_verbose_len as f64 * c_font_width;
+        let c_expected_tooltip_shift_y: f64 = screen.apx_to_cpx(10.0);
+
+        // measured with the bold font, which is the widest one in use
+        context.set_font(
+            format!(
+                "bold {:.0}px {}",
+                c_font_size,
+                self.chart_config.font_monospace.as_str()
+            )
+            .as_str(),
+        );
+        let measure = |text: &str| context.measure_text(text).unwrap().width();
+        let c_heading_width = measure(formatted_coord.as_str());
+        let c_max_line_width = matches
+            .iter()
+            .zip(value_texts.iter())
+            .map(|((data_set, _, _), value_text)| {
+                measure(data_set.name.as_str()) + measure(value_text.as_str())
+            })
+            .chain(self.shared_sections.iter().flat_map(|section| {
+                section.lines.iter().map(|line| {
+                    measure(line.name.as_str()) + measure(line.formatted_value.as_str())
+                })
+            }))
+            .chain(more_line.iter().map(|line| measure(line.as_str())))
+            .chain(
+                self.shared_sections
+                    .iter()
+                    .flat_map(|section| section.more_line.iter())
+                    .map(|line| measure(line.as_str())),
+            )
+            .fold(0.0, f64::max);
+        let c_heading_width = self
+            .shared_sections
+            .iter()
+            .map(|section| measure(section.title.as_str()))
+            .fold(c_heading_width, f64::max);
+        let section_lines: usize = self
+            .shared_sections
+            .iter()
+            .map(|section| section.lines.len() + 1 + section.more_line.is_some() as usize)
+            .sum();
+        let c_section_gap = c_additional_gap_after_heading * 0.5;
 
         let mut tooltip_width = c_heading_width.max(
             c_color_size
                 + c_gap_between_colors_n_names
-                + (max_name_length + max_formatted_value_length) as f64 * c_font_width
+                + c_max_line_width
                 + c_gap_between_names_n_values,
         ) + c_padding * 2.0;
         let tooltip_min_width = self.min_width.get_value(time_us);
@@ -185,27 +505,49 @@ impl Tooltip {
             tooltip_width = tooltip_min_width;
         }
 
-        let tooltip_height = (formatted_values.len() + c_heading_lines) as f64 * c_font_size
-            + (formatted_values.len() + c_heading_lines - 1) as f64 * c_gap_between_lines
+        let rows_number = formatted_values.len() + more_line.is_some() as usize;
+        let tooltip_height = (rows_number + c_heading_lines + section_lines) as f64 * c_font_size
+            + (rows_number + c_heading_lines + section_lines - 1) as f64 * c_gap_between_lines
             + c_additional_gap_after_heading
+            + self.shared_sections.len() as f64 * c_section_gap
             + c_padding * 2.0;
         let tooltip_height = tooltip_height.min(screen_area.get_content_cheight());
 
         let bottom_cy = screen_area.bottom_cy() - c_line_width;
         let (tooltip_x, tooltip_y) = place_rect_inside(
-            pointer_cx,
-            pointer_cy,
-            tooltip_width,
-            tooltip_height,
-            c_line_width,
-            screen_area.right_cx() - c_line_width,
-            bottom_cy,
-            c_expected_tooltip_shift_x,
+            (pointer_cx, pointer_cy),
+            (tooltip_width, tooltip_height),
+            [
+                c_line_width,
+                screen_area.top_cy() + c_line_width,
+                screen_area.right_cx() - c_line_width,
+                bottom_cy,
+            ],
+            (c_expected_tooltip_shift_x, c_expected_tooltip_shift_y),
         );
 
-        let color_x = tooltip_x + c_padding;
-        let name_x = color_x + c_color_size + c_gap_between_colors_n_names;
-        let value_x = tooltip_x + tooltip_width - c_padding;
+        // the rtl layout puts colors and names on the right, values on the left
+        let rtl = self.chart_config.is_rtl();
+        let (name_align, value_align) = if rtl {
+            ("right", "left")
+        } else {
+            ("left", "right")
+        };
+        let (color_x, name_x, value_x) = if rtl {
+            let color_x = tooltip_x + tooltip_width - c_padding - c_color_size;
+            (
+                color_x,
+                color_x - c_gap_between_colors_n_names,
+                tooltip_x + c_padding,
+            )
+        } else {
+            let color_x = tooltip_x + c_padding;
+            (
+                color_x,
+                color_x + c_color_size + c_gap_between_colors_n_names,
+                tooltip_x + tooltip_width - c_padding,
+            )
+        };
         let heading_y = tooltip_y + c_padding;
         let start_y = heading_y
             + c_heading_lines as f64 * c_font_size
@@ -215,16 +557,18 @@ impl Tooltip {
         let delta_y = c_font_size + c_gap_between_lines;
         let lines_number = ((bottom_cy - start_y) / delta_y) as usize;
 
-        let hidden_lines: usize;
-        if lines_number > 0 && lines_number < matches.len() {
-            hidden_lines = matches.len() - lines_number + 1;
+        // sections of other charts are the first to go when space is short
+        if self.html.is_none() && lines_number > 0 && lines_number < rows_number {
+            let hidden_lines = matches.len() + collapsed.len() - lines_number + 1;
             matches.truncate(lines_number - 1);
-        } else {
-            hidden_lines = 0;
+            more_line = Some(self.chart_config.strings.format(
+                "tooltipHiddenLines",
+                &[("count", hidden_lines.to_string().as_str())],
+            ));
         }
 
         let transparent_color = JsValue::from_str("rgba(0, 0, 0, 0)");
-        for (index, (data_set, data_point)) in matches.iter().enumerate() {
+        for (index, (data_set, data_point, _)) in matches.iter().enumerate() {
             let color = JsValue::from_str(data_set.to_css_color(1.0).as_str());
             context.begin_path();
             context.set_line_width(screen.apx_to_cpx(self.chart_config.line_width));
@@ -248,6 +592,43 @@ impl Tooltip {
             context.stroke();
         }
 
+        if let Some(html) = &mut self.html {
+            let lines: Vec<HtmlTooltipLine> = matches
+                .iter()
+                .zip(formatted_values.iter().zip(formatted_changes.iter()))
+                .enumerate()
+                .map(
+                    |(index, ((data_set, data_point, _), (formatted_value, formatted_change)))| {
+                        HtmlTooltipLine {
+                            name: data_set.name.clone(),
+                            value: data_point.value,
+                            formatted_value: formatted_value.clone(),
+                            formatted_change: formatted_change.clone(),
+                            color: data_set.to_css_color(1.0),
+                            nearest: index == index_with_min_diff_by_value,
+                        }
+                    },
+                )
+                .collect();
+            let (anchor_cx, anchor_cy) = match self.chart_config.tooltip_position {
+                TooltipPosition::Pointer => (pointer_cx, pointer_cy),
+                TooltipPosition::Point => (
+                    screen_area.get_cx(matches[index_with_min_diff_by_value].1.coord),
+                    screen_area.get_cy(matches[index_with_min_diff_by_value].1.value),
+                ),
+            };
+            let css_px = screen.css_px_to_cpx(1.0);
+            html.show(
+                self.hover.as_ref().unwrap(),
+                formatted_coord.as_str(),
+                lines.as_slice(),
+                more_line.as_deref(),
+                &self.shared_sections,
+                (anchor_cx / css_px, anchor_cy / css_px),
+            );
+            return;
+        }
+
         let v = &self.chart_config.color_tooltip_font;
         let font_color =
             JsValue::from_str(format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3,).as_str());
@@ -256,11 +637,15 @@ impl Tooltip {
         let background_color =
             JsValue::from_str(format!("rgba({}, {}, {}, {})", v.0, v.1, v.2, v.3,).as_str());
 
+        let (box_x, box_y, box_width, box_height) = snap_rect(
+            (tooltip_x, tooltip_y, tooltip_width, tooltip_height),
+            c_line_width,
+        );
         context.set_line_width(c_line_width);
         context.set_fill_style(&background_color);
         context.set_stroke_style(&font_color);
-        context.stroke_rect(tooltip_x, tooltip_y, tooltip_width, tooltip_height);
-        context.fill_rect(tooltip_x, tooltip_y, tooltip_width, tooltip_height);
+        context.stroke_rect(box_x, box_y, box_width, box_height);
+        context.fill_rect(box_x, box_y, box_width, box_height);
 
         context.set_font(
             format!(
@@ -284,17 +669,23 @@ impl Tooltip {
 
         context.set_text_baseline("top");
 
-        for (index, ((data_set, _), formatted_value)) in matches
-            .iter()
-            .cloned()
-            .zip(formatted_values.iter())
-            .enumerate()
+        for (index, ((data_set, _, _), formatted_value)) in
+            matches.iter().cloned().zip(value_texts.iter()).enumerate()
         {
             let y = start_y + delta_y * index as f64;
             let color = JsValue::from_str(data_set.to_css_color(1.0).as_str());
 
             context.set_fill_style(&color);
             context.fill_rect(color_x, y, c_color_size, c_color_size);
+            if self.chart_config.series_patterns {
+                if let Some(hatch) =
+                    self.hatch_patterns
+                        .get(context, data_set.pattern, screen.apx_to_cpx(1.0))
+                {
+                    context.set_fill_style_canvas_pattern(hatch);
+                    context.fill_rect(color_x, y, c_color_size, c_color_size);
+                }
+            }
 
             context.set_fill_style(&font_color);
 
@@ -311,32 +702,134 @@ impl Tooltip {
                 )
                 .as_str(),
             );
-            context.set_text_align("left");
+            context.set_text_align(name_align);
             context
                 .fill_text(data_set.name.as_str(), name_x, y)
                 .unwrap();
 
-            context.set_text_align("right");
+            context.set_text_align(value_align);
             context
                 .fill_text(formatted_value.as_str(), value_x, y)
                 .unwrap();
         }
 
-        if hidden_lines > 0 {
+        if let Some(more_line) = &more_line {
             let y = start_y + delta_y * matches.len() as f64;
-            context.set_text_align("left");
+            context.set_text_align(name_align);
             context.set_fill_style(&font_color);
+            self.set_small_font(screen);
+            context.fill_text(more_line.as_str(), name_x, y).unwrap();
+        }
+
+        let mut y = start_y + delta_y * (matches.len() + more_line.is_some() as usize) as f64;
+        let font_color_str = font_color.as_string().unwrap();
+        for section in self.shared_sections.iter() {
+            y += c_section_gap;
+            if y + c_font_size > bottom_cy {
+                break;
+            }
+            context.set_fill_style_str(font_color_str.as_str());
             context.set_font(
                 format!(
-                    "{:.0}px {}",
-                    screen.apx_to_cpx(self.chart_config.font_size_small),
-                    self.chart_config.font_standard.as_str()
+                    "bold {:.0}px {}",
+                    c_font_size,
+                    self.chart_config.font_monospace.as_str()
                 )
                 .as_str(),
             );
+            context.set_text_align(name_align);
             context
-                .fill_text(format!("{} hidden", hidden_lines).as_str(), name_x, y)
+                .fill_text(section.title.as_str(), color_x, y)
                 .unwrap();
+            y += delta_y;
+            context.set_font(
+                format!(
+                    "{:.0}px {}",
+                    c_font_size,
+                    self.chart_config.font_monospace.as_str()
+                )
+                .as_str(),
+            );
+            for line in section.lines.iter() {
+                if y + c_font_size > bottom_cy {
+                    break;
+                }
+                context.set_fill_style_str(line.color.as_str());
+                context.fill_rect(color_x, y, c_color_size, c_color_size);
+                context.set_fill_style_str(font_color_str.as_str());
+                context.set_text_align(name_align);
+                context.fill_text(line.name.as_str(), name_x, y).unwrap();
+                context.set_text_align(value_align);
+                context
+                    .fill_text(line.formatted_value.as_str(), value_x, y)
+                    .unwrap();
+                y += delta_y;
+            }
+            if let Some(more_line) = &section.more_line {
+                if y + c_font_size <= bottom_cy {
+                    context.set_text_align(name_align);
+                    self.set_small_font(screen);
+                    context.fill_text(more_line.as_str(), name_x, y).unwrap();
+                    y += delta_y;
+                }
+            }
+        }
+    }
+
+    fn set_small_font(&self, screen: &Screen) {
+        screen.context.set_font(
+            format!(
+                "{:.0}px {}",
+                screen.apx_to_cpx(self.chart_config.font_size_small),
+                self.chart_config.font_standard.as_str()
+            )
+            .as_str(),
+        );
+    }
+}
+
+/// Formats "+N more" with the sum of the values collapsed by
+/// `tooltipMaxEntries`, None without any.
+pub fn format_more_line(
+    chart_config: &ChartConfig,
+    value_format: &VerboseFormat,
+    collapsed: impl Iterator<Item = f64> + Clone,
+    value_min: f64,
+    value_max: f64,
+) -> Option<String> {
+    let count = collapsed.clone().count();
+    if count == 0 {
+        return None;
+    }
+    let sum: f64 = collapsed.sum();
+    let formatted_sum = value_format
+        .format_values(Some(sum).into_iter(), |v| v, value_min, value_max)
+        .into_iter()
+        .next()
+        .unwrap();
+    Some(chart_config.strings.format(
+        "tooltipMoreLines",
+        &[
+            ("count", count.to_string().as_str()),
+            ("sum", formatted_sum.as_str()),
+        ],
+    ))
+}
+
+fn pick_rows<T: Clone>(items: &[T], rows: &[usize]) -> Vec<T> {
+    rows.iter().map(|index| items[*index].clone()).collect()
+}
+
+/// Index of the match whose value is the nearest to the pointer's.
+fn get_nearest_by_value(matches: &[(&DataSet, &DataPoint, usize)], value: f64) -> usize {
+    let mut min_diff: f64 = f64::MAX;
+    let mut index_with_min_diff_by_value: usize = 0;
+    for (index, (_, data_point, _)) in matches.iter().enumerate() {
+        let diff = (data_point.value - value).abs();
+        if min_diff > diff {
+            min_diff = diff;
+            index_with_min_diff_by_value = index;
         }
     }
+    index_with_min_diff_by_value
 }
@@ -5,6 +5,7 @@
  *
  * Copyright (C) 2023, Nikita Almakov
  */
+use crate::params::TooltipSort;
 use js_sys::Reflect;
 use wasm_bindgen::prelude::*;
 
@@ -19,6 +20,13 @@ pub fn is_click(pos1: &Option<(f64, f64)>, pos2: &Option<(f64, f64)>) -> bool {
         _ => false,
     }
 }
+/// Radius around a point where a click hits it, scaled by the hit scale.
+pub const POINT_HIT_RADIUS: f64 = 12.0;
+
+/// Whether the pointer is within `radius` of the point.
+pub fn is_point_hit(point: (f64, f64), pointer: (f64, f64), radius: f64) -> bool {
+    (point.0 - pointer.0).hypot(point.1 - pointer.1) <= radius
+}
 pub fn js_scroll_coords() -> (f64, f64) {
     let window = web_sys::window().unwrap();
     let scroll_x = Reflect::get(&window, &JsValue::from_str("scrollX"))
@@ -35,22 +43,533 @@ pub fn js_coords_to_global(x: f64, y: f64) -> (f64, f64) {
     let (scroll_x, scroll_y) = js_scroll_coords();
     (scroll_x + x, scroll_y + y)
 }
+/// The top-left corner of the element in page coordinates.
+pub fn js_element_global_position(element: &web_sys::Element) -> (f64, f64) {
+    let rect = Reflect::get(element, &JsValue::from_str("getBoundingClientRect"))
+        .unwrap()
+        .dyn_into::<js_sys::Function>()
+        .unwrap()
+        .call0(element)
+        .unwrap();
+    let x = Reflect::get(&rect, &JsValue::from_str("left"))
+        .unwrap()
+        .as_f64()
+        .unwrap();
+    let y = Reflect::get(&rect, &JsValue::from_str("top"))
+        .unwrap()
+        .as_f64()
+        .unwrap();
+    js_coords_to_global(x, y)
+}
+/// Places a `size` box next to the `anchor` point, preferring right-and-below
+/// of it; flips to the other side when the box would cross the right or the
+/// bottom edge and clamps into `bounds` (x_min, y_min, x_max, y_max) as a last
+/// resort, the top-left corner winning when the box is larger than bounds.
 pub fn place_rect_inside(
-    desired_x: f64,
-    desired_y: f64,
-    width: f64,
-    height: f64,
-    x_min: f64,
-    x_max: f64,
-    y_max: f64,
-    x_shift: f64,
+    anchor: (f64, f64),
+    size: (f64, f64),
+    bounds: [f64; 4],
+    shift: (f64, f64),
 ) -> (f64, f64) {
-    let x: f64;
-    if desired_x + width > x_max {
-        x = (desired_x - x_shift - width).max(x_min);
+    fn place_1d(anchor: f64, size: f64, min: f64, max: f64, shift: f64) -> f64 {
+        let position = if anchor + shift + size <= max || anchor - shift - size < min {
+            anchor + shift
+        } else {
+            anchor - shift - size
+        };
+        position.min(max - size).max(min)
+    }
+    let [x_min, y_min, x_max, y_max] = bounds;
+    (
+        place_1d(anchor.0, size.0, x_min, x_max, shift.0),
+        place_1d(anchor.1, size.1, y_min, y_max, shift.1),
+    )
+}
+/// Returns the smallest step N such that keeping every Nth label leaves no
+/// two neighbouring labels closer than `min_gap`.
+pub fn get_label_step(positions: &[f64], widths: &[f64], min_gap: f64) -> usize {
+    let len = positions.len();
+    for step in 1..len.max(1) {
+        if (step..len).step_by(step).all(|index| {
+            // positions decrease on a reversed axis
+            (positions[index] - positions[index - step]).abs()
+                >= (widths[index] + widths[index - step]) * 0.5 + min_gap
+        }) {
+            return step;
+        }
+    }
+    len.max(1)
+}
+/// Renders a change as "+1.2K (+3.4%)"; a percent change against a zero
+/// base is shown as a dash.
+/// Most decimals `dedup_labels` adds to tell labels apart.
+const MAX_EXTRA_DECIMALS: usize = 6;
+
+/// Resolves neighbouring labels which read the same, e.g. 1.00K for both 1000
+/// and 1001: `refine` formats the label at the index with more decimals, None
+/// where the format has no room for them; labels which still repeat the one
+/// before are dropped. Returns the labels kept with their indices.
+pub fn dedup_labels<F: Fn(usize, usize) -> Option<String>>(
+    mut labels: Vec<String>,
+    refine: F,
+) -> Vec<(usize, String)> {
+    let mut start = 0;
+    while start < labels.len() {
+        let end = start
+            + labels[start..]
+                .iter()
+                .take_while(|label| **label == labels[start])
+                .count();
+        if end - start > 1 {
+            for extra_decimals in 1..=MAX_EXTRA_DECIMALS {
+                match (start..end)
+                    .map(|index| refine(index, extra_decimals))
+                    .collect::<Option<Vec<String>>>()
+                {
+                    Some(refined) if refined.windows(2).all(|pair| pair[0] != pair[1]) => {
+                        labels.splice(start..end, refined);
+                        break;
+                    }
+                    Some(_) => {}
+                    None => break,
+                }
+            }
+        }
+        start = end;
+    }
+    let mut kept: Vec<(usize, String)> = Vec::with_capacity(labels.len());
+    for (index, label) in labels.into_iter().enumerate() {
+        if kept.last().is_none_or(|(_, last)| *last != label) {
+            kept.push((index, label));
+        }
+    }
+    kept
+}
+
+pub fn format_change(formatted_delta: &str, delta: f64, percent: Option<f64>) -> String {
+    let sign = if delta > 0.0 { "+" } else { "" };
+    match percent {
+        Some(percent) => format!("{}{} ({:+.1}%)", sign, formatted_delta, percent),
+        None => format!("{}{} (\u{2014})", sign, formatted_delta),
+    }
+}
+/// Shortens `text` and appends an ellipsis until `measure` fits it into
+/// `max_width`.
+pub fn truncate_with_ellipsis<F: Fn(&str) -> f64>(
+    text: &str,
+    max_width: f64,
+    measure: F,
+) -> String {
+    if measure(text) <= max_width {
+        return text.to_string();
+    }
+    let chars: Vec<char> = text.chars().collect();
+    for len in (0..chars.len()).rev() {
+        let candidate = format!(
+            "{}\u{2026}",
+            chars[..len].iter().collect::<String>().trim_end()
+        );
+        if measure(candidate.as_str()) <= max_width {
+            return candidate;
+        }
+    }
+    String::new()
+}
+
+#[derive(Clone)]
+pub struct LabelBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// -1.0 to be nudged up, 1.0 to be nudged down.
+    pub direction: f64,
+}
+/// Places boxes given in priority order: a box overlapping the ones placed
+/// before it is nudged in its direction by at most `max_shift` and dropped
+/// if it still overlaps or leaves `y_min..y_max`. Returns the final y.
+pub fn place_labels(
+    boxes: &[LabelBox],
+    y_min: f64,
+    y_max: f64,
+    max_shift: f64,
+) -> Vec<Option<f64>> {
+    let mut placed: Vec<(f64, f64, f64, f64)> = Vec::with_capacity(boxes.len());
+    boxes
+        .iter()
+        .map(|label| {
+            let mut y = label.y;
+            for _ in 0..=placed.len() {
+                if y < y_min || y + label.height > y_max || (y - label.y).abs() > max_shift {
+                    return None;
+                }
+                let overlapping = placed.iter().find(|(x1, y1, x2, y2)| {
+                    label.x < *x2
+                        && label.x + label.width > *x1
+                        && y < *y2
+                        && y + label.height > *y1
+                });
+                match overlapping {
+                    Some((_, y1, _, y2)) => {
+                        y = if label.direction < 0.0 {
+                            y1 - label.height
+                        } else {
+                            *y2
+                        };
+                    }
+                    None => {
+                        placed.push((label.x, y, label.x + label.width, y + label.height));
+                        return Some(y);
+                    }
+                }
+            }
+            None
+        })
+        .collect()
+}
+/// Returns when a chart hidden since the time should release its memory;
+/// None when it is visible or the delay is 0.
+pub fn get_memory_trim_time_us(hidden_since_us: Option<f64>, delay_ms: f64) -> Option<f64> {
+    hidden_since_us
+        .filter(|_| delay_ms > 0.0)
+        .map(|hidden_since_us| hidden_since_us + delay_ms * 1000.0)
+}
+/// Fits the `from..to` window into `min..max`: an overlapping window is
+/// clamped, a window outside is moved to the nearest edge keeping its width.
+pub fn fit_window(from: f64, to: f64, min: f64, max: f64) -> (f64, f64) {
+    let width = (to - from).min(max - min);
+    if to <= min {
+        (min, min + width)
+    } else if from >= max {
+        (max - width, max)
     } else {
-        x = (desired_x + x_shift).min(x_max - width);
+        (from.max(min), to.min(max))
+    }
+}
+/// Returns the factor to scale the canvas resolution by, so that the pixel
+/// ratio and the longer canvas side in physical pixels stay within the caps;
+/// 1 when uncapped.
+pub fn get_resolution_coeff(
+    pixel_ratio: f64,
+    max_pixel_ratio: Option<f64>,
+    physical_size: f64,
+    max_canvas_dimension: Option<f64>,
+) -> f64 {
+    let mut coeff: f64 = 1.0;
+    if let Some(max_pixel_ratio) = max_pixel_ratio {
+        coeff = coeff.min(max_pixel_ratio / pixel_ratio);
+    }
+    if let Some(max_canvas_dimension) = max_canvas_dimension {
+        coeff = coeff.min(max_canvas_dimension / physical_size);
+    }
+    coeff
+}
+/// Rounds the width of a hairline (grid, axis, border) to whole canvas
+/// pixels, 1 at least; canvas pixels are the capped ones under a
+/// resolution cap.
+pub fn snap_line_width(c_line_width: f64) -> f64 {
+    c_line_width.round().max(1.0)
+}
+/// Moves the position of a line across it to the canvas pixel grid, so a
+/// line of the snapped width covers whole pixels instead of blurring into
+/// the ones around: odd widths go through pixel centers, even ones along
+/// pixel edges. Series paths are left unsnapped to stay smooth.
+pub fn snap_to_pixel(c: f64, c_snapped_line_width: f64) -> f64 {
+    if c_snapped_line_width % 2.0 == 1.0 {
+        (c - 0.5).round() + 0.5
+    } else {
+        c.round()
+    }
+}
+/// Snaps the edges of a rectangle to stroke, see `snap_to_pixel`.
+pub fn snap_rect(
+    (x, y, width, height): (f64, f64, f64, f64),
+    c_snapped_line_width: f64,
+) -> (f64, f64, f64, f64) {
+    let (x0, y0) = (
+        snap_to_pixel(x, c_snapped_line_width),
+        snap_to_pixel(y, c_snapped_line_width),
+    );
+    (
+        x0,
+        y0,
+        snap_to_pixel(x + width, c_snapped_line_width) - x0,
+        snap_to_pixel(y + height, c_snapped_line_width) - y0,
+    )
+}
+/// Picks tooltip rows by their values: drops zeros if asked, orders the rest
+/// and keeps at most `max_entries`, the row at `keep` (the nearest series)
+/// always among them. Returns the kept and the collapsed indices.
+pub fn select_tooltip_rows(
+    values: &[f64],
+    sort: TooltipSort,
+    hide_zero: bool,
+    max_entries: Option<usize>,
+    keep: Option<usize>,
+) -> (Vec<usize>, Vec<usize>) {
+    let mut rows: Vec<usize> = (0..values.len())
+        .filter(|index| !hide_zero || values[*index] != 0.0 || Some(*index) == keep)
+        .collect();
+    match sort {
+        TooltipSort::SeriesOrder => {}
+        TooltipSort::ValueDesc => rows.sort_by(|a, b| values[*b].total_cmp(&values[*a])),
+        TooltipSort::ValueAsc => rows.sort_by(|a, b| values[*a].total_cmp(&values[*b])),
+    }
+    let max_entries = match max_entries {
+        Some(max_entries) if max_entries < rows.len() => max_entries.max(1),
+        _ => return (rows, Vec::new()),
     };
-    let y = desired_y.min(y_max - height);
-    (x, y)
+    let mut collapsed = rows.split_off(max_entries);
+    if let Some(position) = keep.and_then(|keep| collapsed.iter().position(|i| *i == keep)) {
+        let kept = collapsed.remove(position);
+        collapsed.insert(0, rows.pop().unwrap());
+        rows.push(kept);
+    }
+    (rows, collapsed)
+}
+#[cfg(test)]
+mod tests {
+    use crate::params::{ClientCaps, ColorScheme, TooltipSort, VerboseFormat};
+    use crate::utils::{
+        dedup_labels, fit_window, format_change, get_label_step, get_memory_trim_time_us,
+        get_resolution_coeff, is_point_hit, place_labels, place_rect_inside, select_tooltip_rows,
+        snap_line_width, snap_rect, snap_to_pixel, truncate_with_ellipsis, LabelBox,
+        POINT_HIT_RADIUS,
+    };
+
+    #[test]
+    fn test_dedup_labels() {
+        let dedup = |format: &VerboseFormat, values: &[f64]| {
+            let (min, max) = (values[0], values[values.len() - 1]);
+            let labels = format.format_values(values.iter(), |value| *value, min, max);
+            dedup_labels(labels, |index, extra_decimals| {
+                format.format_with_extra_decimals(values[index], extra_decimals, min, max)
+            })
+        };
+        let labels = |labels: &[(usize, &str)]| {
+            labels
+                .iter()
+                .map(|(index, label)| (*index, label.to_string()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            dedup(&VerboseFormat::NumberConcise, &[0.0001, 0.0002, 0.0003]),
+            labels(&[(0, "0.0001"), (1, "0.0002"), (2, "0.0003")])
+        );
+        // only the labels which collide get more decimals
+        assert_eq!(
+            dedup(&VerboseFormat::NumberConcise, &[999.0, 1000.0, 1001.0]),
+            labels(&[(0, "999"), (1, "1.000K"), (2, "1.001K")])
+        );
+        // dates have no decimals, so the repeated months go
+        let date = VerboseFormat::Date {
+            fmt_str: "%b %Y".to_string(),
+        };
+        assert_eq!(
+            dedup(&date, &[1672531200000.0, 1673740800000.0, 1675209600000.0]),
+            labels(&[(0, "Jan 2023"), (2, "Feb 2023")])
+        );
+        assert_eq!(
+            dedup_labels(vec!["a".to_string(), "a".to_string()], |_, _| None),
+            labels(&[(0, "a")])
+        );
+    }
+
+    #[test]
+    fn test_select_tooltip_rows() {
+        let values = [3.0, 0.0, 5.0, 1.0, 0.0];
+        assert_eq!(
+            select_tooltip_rows(&values, TooltipSort::SeriesOrder, false, None, None),
+            (vec![0, 1, 2, 3, 4], vec![])
+        );
+        assert_eq!(
+            select_tooltip_rows(&values, TooltipSort::ValueDesc, true, None, Some(4)),
+            (vec![2, 0, 3, 4], vec![])
+        );
+        assert_eq!(
+            select_tooltip_rows(&values, TooltipSort::ValueAsc, true, Some(2), None),
+            (vec![3, 0], vec![2])
+        );
+        // the nearest one replaces the last kept row
+        assert_eq!(
+            select_tooltip_rows(&values, TooltipSort::ValueDesc, false, Some(2), Some(1)),
+            (vec![2, 1], vec![0, 3, 4])
+        );
+        assert_eq!(
+            select_tooltip_rows(&values, TooltipSort::SeriesOrder, false, Some(5), Some(1)),
+            (vec![0, 1, 2, 3, 4], vec![])
+        );
+    }
+
+    #[test]
+    fn test_is_point_hit() {
+        let fine_pointer = ClientCaps {
+            touch_device: false,
+            device_pixel_ratio: 1.0,
+            css_to_physical_scale: 1.0,
+            reduced_motion: false,
+            color_scheme: ColorScheme::Light,
+            more_contrast: false,
+            coarse_pointer: false,
+            context_loss_events: false,
+            idle_callback: false,
+        };
+        let coarse_pointer = ClientCaps {
+            coarse_pointer: true,
+            ..fine_pointer.clone()
+        };
+        // a tap 20px from the point
+        let (point, pointer) = ((100.0, 50.0), (112.0, 66.0));
+        assert!(is_point_hit(
+            point,
+            pointer,
+            POINT_HIT_RADIUS * coarse_pointer.get_hit_scale()
+        ));
+        assert!(!is_point_hit(
+            point,
+            pointer,
+            POINT_HIT_RADIUS * fine_pointer.get_hit_scale()
+        ));
+        assert!(is_point_hit(point, (105.0, 55.0), POINT_HIT_RADIUS));
+    }
+
+    #[test]
+    fn test_get_label_step() {
+        assert_eq!(get_label_step(&[], &[], 5.0), 1);
+        assert_eq!(get_label_step(&[0.0], &[100.0], 5.0), 1);
+        assert_eq!(
+            get_label_step(&[0.0, 20.0, 40.0, 60.0], &[10.0, 10.0, 10.0, 10.0], 5.0),
+            1
+        );
+        assert_eq!(
+            get_label_step(&[0.0, 20.0, 40.0, 60.0], &[20.0, 20.0, 20.0, 20.0], 5.0),
+            2
+        );
+        assert_eq!(
+            get_label_step(&[0.0, 10.0, 20.0, 30.0], &[25.0, 25.0, 25.0, 25.0], 5.0),
+            3
+        );
+        assert_eq!(
+            get_label_step(&[60.0, 40.0, 20.0, 0.0], &[20.0, 20.0, 20.0, 20.0], 5.0),
+            2
+        );
+    }
+
+    #[test]
+    fn test_get_memory_trim_time_us() {
+        assert_eq!(get_memory_trim_time_us(None, 60000.0), None);
+        assert_eq!(get_memory_trim_time_us(Some(5e6), 0.0), None);
+        assert_eq!(get_memory_trim_time_us(Some(5e6), 1000.0), Some(6e6));
+    }
+
+    #[test]
+    fn test_fit_window() {
+        assert_eq!(fit_window(20.0, 30.0, 0.0, 100.0), (20.0, 30.0));
+        assert_eq!(fit_window(90.0, 130.0, 0.0, 100.0), (90.0, 100.0));
+        assert_eq!(fit_window(-10.0, 10.0, 0.0, 100.0), (0.0, 10.0));
+        assert_eq!(fit_window(150.0, 160.0, 0.0, 100.0), (90.0, 100.0));
+        assert_eq!(fit_window(-50.0, -40.0, 0.0, 100.0), (0.0, 10.0));
+        assert_eq!(fit_window(-500.0, -100.0, 0.0, 100.0), (0.0, 100.0));
+    }
+
+    #[test]
+    fn test_format_change() {
+        assert_eq!(format_change("1.2K", 1200.0, Some(3.45)), "+1.2K (+3.5%)");
+        assert_eq!(format_change("-5", -5.0, Some(-50.0)), "-5 (-50.0%)");
+        assert_eq!(format_change("3", 3.0, None), "+3 (\u{2014})");
+    }
+
+    #[test]
+    fn test_truncate_with_ellipsis() {
+        let measure = |text: &str| text.chars().count() as f64;
+        assert_eq!(truncate_with_ellipsis("requests", 8.0, measure), "requests");
+        assert_eq!(
+            truncate_with_ellipsis("time UTC", 6.0, measure),
+            "time\u{2026}"
+        );
+        assert_eq!(truncate_with_ellipsis("requests", 0.5, measure), "");
+    }
+
+    #[test]
+    fn test_place_rect_inside() {
+        let bounds = [0.0, 0.0, 100.0, 100.0];
+        assert_eq!(
+            place_rect_inside((10.0, 10.0), (30.0, 20.0), bounds, (5.0, 5.0)),
+            (15.0, 15.0)
+        );
+        // flipped to the left of and above the pointer
+        assert_eq!(
+            place_rect_inside((90.0, 90.0), (30.0, 20.0), bounds, (5.0, 5.0)),
+            (55.0, 65.0)
+        );
+        // does not fit on either side, clamped
+        assert_eq!(
+            place_rect_inside((50.0, 50.0), (60.0, 20.0), bounds, (5.0, 5.0)),
+            (40.0, 55.0)
+        );
+        assert_eq!(
+            place_rect_inside((50.0, 50.0), (120.0, 20.0), bounds, (5.0, 5.0)),
+            (0.0, 55.0)
+        );
+    }
+
+    #[test]
+    fn test_place_labels() {
+        let label = |x: f64, y: f64, direction: f64| LabelBox {
+            x,
+            y,
+            width: 20.0,
+            height: 10.0,
+            direction,
+        };
+        assert_eq!(
+            place_labels(
+                &[
+                    label(0.0, 50.0, -1.0),
+                    label(100.0, 50.0, -1.0),
+                    label(5.0, 45.0, -1.0),
+                    label(10.0, 55.0, 1.0),
+                    label(10.0, 55.0, -1.0),
+                ],
+                0.0,
+                100.0,
+                15.0
+            ),
+            vec![Some(50.0), Some(50.0), Some(40.0), Some(60.0), None]
+        );
+    }
+
+    #[test]
+    fn test_get_resolution_coeff() {
+        assert_eq!(get_resolution_coeff(2.0, None, 7680.0, None), 1.0);
+        assert_eq!(get_resolution_coeff(2.0, Some(1.5), 7680.0, None), 0.75);
+        // a cap above the ratio never upscales
+        assert_eq!(get_resolution_coeff(1.0, Some(1.5), 1000.0, None), 1.0);
+        assert_eq!(get_resolution_coeff(2.0, None, 8000.0, Some(4000.0)), 0.5);
+        assert_eq!(
+            get_resolution_coeff(2.0, Some(1.0), 8000.0, Some(2000.0)),
+            0.25
+        );
+    }
+
+    #[test]
+    fn test_snap_to_pixel() {
+        // a 1px line drawn at DPR 1, 1.25, 2 and at 2 capped to 1.5
+        for (pixel_ratio, expected_width) in [(1.0, 1.0), (1.25, 1.0), (2.0, 2.0), (1.5, 2.0)] {
+            let c_line_width = snap_line_width(pixel_ratio);
+            assert_eq!(c_line_width, expected_width);
+            for c in [0.0, 10.2, 10.5, 10.7, 99.99, 123.456 * pixel_ratio] {
+                let snapped = snap_to_pixel(c, c_line_width);
+                // crisp: both sides of the stroke on pixel edges
+                assert_eq!((snapped - c_line_width * 0.5).fract(), 0.0);
+                assert_eq!((snapped + c_line_width * 0.5).fract(), 0.0);
+                assert!((snapped - c).abs() <= 0.5);
+            }
+        }
+        assert_eq!(snap_line_width(0.3), 1.0);
+        assert_eq!(
+            snap_rect((10.3, 20.6, 50.0, 30.0), 1.0),
+            (10.5, 20.5, 50.0, 30.0)
+        );
+    }
 }
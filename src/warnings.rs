@@ -0,0 +1,149 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use js_sys::{Array, Object, Reflect};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Warnings kept until they are taken, older ones are dropped past it.
+const MAX_WARNINGS: usize = 100;
+
+/// A non-fatal issue with the params, config or data, e.g. a clamped initial
+/// range, or a failed callback.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartWarning {
+    pub code: &'static str,
+    pub message: String,
+    /// Where in the params or the state it comes from, e.g.
+    /// `dataSets[1]`.
+    pub path: Option<String>,
+    /// What a failed callback threw.
+    pub error: Option<JsValue>,
+}
+impl ChartWarning {
+    pub fn new(code: &'static str, message: String, path: Option<String>) -> Self {
+        Self {
+            code,
+            message,
+            path,
+            error: None,
+        }
+    }
+    /// A callback of the config threw, e.g. onHover; its path is the key of
+    /// the callback.
+    pub fn callback_failed(callback: &str, error: JsValue) -> Self {
+        let reason = match error.dyn_ref::<js_sys::Error>() {
+            Some(error) => String::from(error.message()),
+            None => error.as_string().unwrap_or_else(|| format!("{:?}", error)),
+        };
+        Self {
+            code: "CALLBACK_FAILED",
+            message: format!("{} failed: {}", callback, reason),
+            path: Some(callback.to_string()),
+            error: Some(error),
+        }
+    }
+    /// Warns of the points `sanitize_non_finite` is about to drop or clamp,
+    /// if any; gaps are not warned of.
+    pub fn non_finite(
+        name: &str,
+        coords: &[f64],
+        values: &[Option<f64>],
+        path: String,
+    ) -> Option<Self> {
+        let count = coords
+            .iter()
+            .zip(values.iter())
+            .filter(|(coord, value)| {
+                !coord.is_finite() || value.is_some_and(|value| !value.is_finite())
+            })
+            .count();
+        (count > 0).then(|| {
+            Self::new(
+                "NON_FINITE_VALUES",
+                format!("data set '{}' - {} non-finite points", name, count),
+                Some(path),
+            )
+        })
+    }
+    /// Builds {code, message, path}, plus error for a failed callback.
+    pub fn to_js(&self) -> JsValue {
+        let warning = Object::new();
+        Reflect::set(&warning, &"code".into(), &self.code.into()).unwrap();
+        Reflect::set(&warning, &"message".into(), &self.message.as_str().into()).unwrap();
+        let path = match &self.path {
+            Some(path) => JsValue::from_str(path.as_str()),
+            None => JsValue::NULL,
+        };
+        Reflect::set(&warning, &"path".into(), &path).unwrap();
+        if let Some(error) = &self.error {
+            Reflect::set(&warning, &"error".into(), error).unwrap();
+        }
+        warning.into()
+    }
+}
+
+/// Warnings of a chart, kept until `take` (getWarnings); the oldest are
+/// dropped past MAX_WARNINGS. Clones share the warnings, e.g. with a timeout.
+#[derive(Clone, Default)]
+pub struct Warnings(Rc<RefCell<VecDeque<ChartWarning>>>);
+impl Warnings {
+    pub fn push(&self, warning: ChartWarning) {
+        let mut warnings = self.0.borrow_mut();
+        if warnings.len() == MAX_WARNINGS {
+            warnings.pop_front();
+        }
+        warnings.push_back(warning);
+    }
+    pub fn extend(&self, warnings: Vec<ChartWarning>) {
+        for warning in warnings {
+            self.push(warning);
+        }
+    }
+    /// Returns the warnings since the last call.
+    pub fn take(&self) -> Vec<ChartWarning> {
+        self.0.borrow_mut().drain(..).collect()
+    }
+}
+
+pub fn warnings_to_js(warnings: &[ChartWarning]) -> JsValue {
+    warnings
+        .iter()
+        .map(|warning| warning.to_js())
+        .collect::<Array>()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::warnings::ChartWarning;
+
+    #[test]
+    fn test_non_finite_warning() {
+        let path = || "dataSets[0]".to_string();
+        assert_eq!(
+            ChartWarning::non_finite("a", &[1.0, 2.0], &[Some(1.0), None], path()),
+            None
+        );
+        assert_eq!(
+            ChartWarning::non_finite(
+                "a",
+                &[1.0, f64::NAN, 3.0],
+                &[Some(f64::INFINITY), Some(2.0), None],
+                path()
+            ),
+            Some(ChartWarning::new(
+                "NON_FINITE_VALUES",
+                "data set 'a' - 2 non-finite points".to_string(),
+                Some(path())
+            ))
+        );
+    }
+}
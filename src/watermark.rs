@@ -0,0 +1,133 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+use crate::debug::console_warn;
+use crate::params::ChartConfig;
+use crate::screen::Screen;
+
+// apx from the edges of the plot
+const WATERMARK_MARGIN: f64 = 8.0;
+
+enum WatermarkImage {
+    Loading(web_sys::HtmlImageElement),
+    Ready(web_sys::HtmlImageElement),
+    Failed,
+}
+
+enum WatermarkContent {
+    Text(String),
+    Image(WatermarkImage),
+}
+
+/// Text or an image stamped in a corner of the plot. An image is drawn once
+/// it decodes, a broken one is skipped with a console warning.
+pub struct Watermark {
+    content: WatermarkContent,
+}
+impl Watermark {
+    pub fn new(chart_config: &ChartConfig) -> Option<Self> {
+        let content = match (&chart_config.watermark_text, &chart_config.watermark_image) {
+            (Some(text), _) => WatermarkContent::Text(text.clone()),
+            (None, Some(url)) => {
+                let image = web_sys::HtmlImageElement::new().unwrap();
+                image.set_src(url.as_str());
+                WatermarkContent::Image(WatermarkImage::Loading(image))
+            }
+            (None, None) => return None,
+        };
+        Some(Self { content })
+    }
+    pub fn is_loading(&self) -> bool {
+        matches!(
+            self.content,
+            WatermarkContent::Image(WatermarkImage::Loading(_))
+        )
+    }
+    /// Checks a loading image; returns true once it is ready to be drawn.
+    pub fn poll(&mut self) -> bool {
+        let image = match &self.content {
+            WatermarkContent::Image(WatermarkImage::Loading(image)) if image.complete() => {
+                image.clone()
+            }
+            _ => return false,
+        };
+        if image.natural_width() == 0 {
+            console_warn(format!("watermark image failed to load: {}", image.src()).as_str());
+            self.content = WatermarkContent::Image(WatermarkImage::Failed);
+            false
+        } else {
+            self.content = WatermarkContent::Image(WatermarkImage::Ready(image));
+            true
+        }
+    }
+    /// Draws into the `[left, top, right, bottom]` rect of the plot.
+    pub fn draw(&self, chart_config: &ChartConfig, screen: &Screen, rect: [f64; 4]) {
+        let context = &screen.context;
+        let c_margin = screen.apx_to_cpx(WATERMARK_MARGIN);
+        let corner = chart_config.watermark_position;
+        context.save();
+        context.set_global_alpha(chart_config.watermark_opacity);
+        match &self.content {
+            WatermarkContent::Text(text) => {
+                let v = &chart_config.color_tick;
+                context.set_fill_style_str(format!("rgb({}, {}, {})", v.0, v.1, v.2).as_str());
+                context.set_font(
+                    format!(
+                        "{}px {}",
+                        screen.apx_to_cpx(
+                            chart_config.font_size_normal * chart_config.watermark_scale
+                        ),
+                        chart_config.font_standard.as_str()
+                    )
+                    .as_str(),
+                );
+                context.set_text_align(if corner.is_left() { "left" } else { "right" });
+                context.set_text_baseline(if corner.is_top() { "top" } else { "bottom" });
+                context
+                    .fill_text(
+                        text.as_str(),
+                        if corner.is_left() {
+                            rect[0] + c_margin
+                        } else {
+                            rect[2] - c_margin
+                        },
+                        if corner.is_top() {
+                            rect[1] + c_margin
+                        } else {
+                            rect[3] - c_margin
+                        },
+                    )
+                    .unwrap();
+            }
+            WatermarkContent::Image(WatermarkImage::Ready(image)) => {
+                let c_width =
+                    screen.apx_to_cpx(image.natural_width() as f64) * chart_config.watermark_scale;
+                let c_height =
+                    screen.apx_to_cpx(image.natural_height() as f64) * chart_config.watermark_scale;
+                context
+                    .draw_image_with_html_image_element_and_dw_and_dh(
+                        image,
+                        if corner.is_left() {
+                            rect[0] + c_margin
+                        } else {
+                            rect[2] - c_margin - c_width
+                        },
+                        if corner.is_top() {
+                            rect[1] + c_margin
+                        } else {
+                            rect[3] - c_margin - c_height
+                        },
+                        c_width,
+                        c_height,
+                    )
+                    .unwrap();
+            }
+            WatermarkContent::Image(_) => {}
+        }
+        context.restore();
+    }
+}
@@ -0,0 +1,748 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright (C) 2023, Nikita Almakov
+ */
+//! Browser tests, see `make test_web`; the resource counts need the
+//! debug-leak-check feature.
+#![cfg(target_arch = "wasm32")]
+use js_sys::Reflect;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+/// Appends a sized container to the body.
+fn create_container(id: &str) -> web_sys::Element {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let container = document.create_element("div").unwrap();
+    container.set_attribute("id", id).unwrap();
+    container
+        .set_attribute("style", "width: 400px; height: 300px")
+        .unwrap();
+    document.body().unwrap().append_child(&container).unwrap();
+    container
+}
+
+fn get_params(selector: &str) -> JsValue {
+    let params = js_sys::JSON::parse(
+        r#"{
+            "coordType": "number",
+            "valueType": "number",
+            "dataSets": [
+                {"name": "Foo", "coords": [1, 2, 3, 4], "values": [10, 30, 20, 40]},
+                {"name": "Bar", "coords": [1, 2, 3, 4], "values": [5, 15, 25, 10]}
+            ]
+        }"#,
+    )
+    .unwrap();
+    Reflect::set(&params, &"selector".into(), &selector.into()).unwrap();
+    params
+}
+
+/// Resolves after the timeout, giving the charts frames to draw.
+async fn sleep(ms: i32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        web_sys::window()
+            .unwrap()
+            .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms)
+            .unwrap();
+    });
+    JsFuture::from(promise).await.unwrap();
+}
+
+/// Pixels of every canvas in the container.
+fn get_pixels(container: &web_sys::Element) -> Vec<Vec<u8>> {
+    let canvases = container.query_selector_all("canvas").unwrap();
+    (0..canvases.length())
+        .map(|index| {
+            let canvas: web_sys::HtmlCanvasElement =
+                canvases.get(index).unwrap().dyn_into().unwrap();
+            let context: web_sys::CanvasRenderingContext2d = canvas
+                .get_context("2d")
+                .unwrap()
+                .unwrap()
+                .dyn_into()
+                .unwrap();
+            context
+                .get_image_data(0.0, 0.0, canvas.width() as f64, canvas.height() as f64)
+                .unwrap()
+                .data()
+                .0
+        })
+        .collect()
+}
+
+#[cfg(feature = "debug-leak-check")]
+fn get_resource_count(name: &str) -> f64 {
+    let counts = graphima::get_resource_counts().unwrap();
+    Reflect::get(&counts, &name.into())
+        .unwrap()
+        .as_f64()
+        .unwrap()
+}
+
+#[cfg(feature = "debug-leak-check")]
+#[wasm_bindgen_test]
+async fn test_create_destroy_releases_listeners() {
+    let container = create_container("listeners");
+    for _ in 0..3 {
+        let chart_id = graphima::create_main(get_params("#listeners"), JsValue::UNDEFINED).unwrap();
+        assert!(get_resource_count("listeners") > 0.0);
+        assert!(get_resource_count("closures") > 0.0);
+        graphima::destroy_main(chart_id).unwrap();
+        assert_eq!(get_resource_count("charts"), 0.0);
+        assert_eq!(get_resource_count("listeners"), 0.0);
+        // the frame and timeout closures are dropped by a timeout
+        sleep(0).await;
+        assert_eq!(get_resource_count("closures"), 0.0);
+    }
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn test_trim_memory_restores_pixels() {
+    let trimmed = create_container("trimmed");
+    let untrimmed = create_container("untrimmed");
+    let trimmed_id = graphima::create_main(get_params("#trimmed"), JsValue::UNDEFINED).unwrap();
+    let untrimmed_id = graphima::create_main(get_params("#untrimmed"), JsValue::UNDEFINED).unwrap();
+    sleep(1000).await;
+    let before = get_pixels(&trimmed);
+    assert_eq!(before, get_pixels(&untrimmed));
+
+    // out of the viewport, so the chart counts as hidden
+    trimmed
+        .set_attribute(
+            "style",
+            "width: 400px; height: 300px; position: absolute; top: -10000px",
+        )
+        .unwrap();
+    sleep(200).await;
+    graphima::trim_memory();
+    let canvas: web_sys::HtmlCanvasElement = trimmed
+        .query_selector("canvas")
+        .unwrap()
+        .unwrap()
+        .dyn_into()
+        .unwrap();
+    assert_eq!((canvas.width(), canvas.height()), (1, 1));
+
+    trimmed
+        .set_attribute("style", "width: 400px; height: 300px")
+        .unwrap();
+    sleep(1000).await;
+    assert_eq!(get_pixels(&trimmed), before);
+    assert_eq!(get_pixels(&trimmed), get_pixels(&untrimmed));
+
+    graphima::destroy_main(trimmed_id).unwrap();
+    graphima::destroy_main(untrimmed_id).unwrap();
+    trimmed.remove();
+    untrimmed.remove();
+}
+
+#[cfg(feature = "debug-leak-check")]
+#[wasm_bindgen_test]
+async fn test_create_destroy_cycles_release_resources() {
+    // performance.memory is Chrome only
+    let get_used_heap = || {
+        let performance = web_sys::window().unwrap().performance().unwrap();
+        Reflect::get(&performance, &"memory".into())
+            .ok()
+            .filter(|memory| memory.is_object())
+            .and_then(|memory| Reflect::get(&memory, &"usedJSHeapSize".into()).ok())
+            .and_then(|used| used.as_f64())
+    };
+    let container = create_container("cycles");
+    let used_heap_before = get_used_heap();
+    for _ in 0..100 {
+        let chart_id = graphima::create_main(get_params("#cycles"), JsValue::UNDEFINED).unwrap();
+        graphima::destroy_main(chart_id).unwrap();
+        sleep(0).await;
+        for name in [
+            "charts",
+            "listeners",
+            "closures",
+            "canvases",
+            "observations",
+        ] {
+            assert_eq!(get_resource_count(name), 0.0, "{}", name);
+        }
+    }
+    if let (Some(before), Some(after)) = (used_heap_before, get_used_heap()) {
+        assert!(after - before < 16.0 * 1024.0 * 1024.0);
+    }
+    container.remove();
+}
+
+/// Calls the API from onHover, which runs after the charts are released.
+#[wasm_bindgen_test]
+async fn test_api_from_on_hover() {
+    let container = create_container("on-hover");
+    let results: Rc<RefCell<Vec<Result<(), String>>>> = Rc::new(RefCell::new(Vec::new()));
+    let on_hover = {
+        let results = Rc::clone(&results);
+        Closure::<dyn FnMut(JsValue)>::new(move |detail: JsValue| {
+            if Reflect::get(&detail, &"x".into()).unwrap().is_null() {
+                return;
+            }
+            let chart_id = Reflect::get(&detail, &"chartId".into()).unwrap();
+            let range = js_sys::Array::of2(&2.0.into(), &3.0.into());
+            let mut results = results.borrow_mut();
+            results.push(graphima::set_visible_range(chart_id.clone(), range.into()));
+            results.push(graphima::redraw(chart_id.clone()));
+            results.push(graphima::destroy_main(chart_id));
+        })
+    };
+    let config = js_sys::Object::new();
+    Reflect::set(&config, &"onHover".into(), on_hover.as_ref()).unwrap();
+    let chart_id = graphima::create_main(get_params("#on-hover"), config.into()).unwrap();
+    sleep(200).await;
+    graphima::set_hover_x(chart_id, 2.5.into(), None).unwrap();
+    sleep(500).await;
+    assert_eq!(*results.borrow(), vec![Ok(()), Ok(()), Ok(())]);
+    assert_eq!(container.child_element_count(), 0);
+    container.remove();
+}
+
+/// Calls the API from onRangeChange, which setVisibleRange makes fire.
+#[wasm_bindgen_test]
+async fn test_api_from_on_range_change() {
+    let container = create_container("on-range-change");
+    let results: Rc<RefCell<Vec<Result<(), String>>>> = Rc::new(RefCell::new(Vec::new()));
+    let on_range_change = {
+        let results = Rc::clone(&results);
+        Closure::<dyn FnMut(JsValue)>::new(move |detail: JsValue| {
+            let chart_id = Reflect::get(&detail, &"chartId".into()).unwrap();
+            let range = js_sys::Array::of2(&2.0.into(), &3.0.into());
+            let mut results = results.borrow_mut();
+            results.push(graphima::set_visible_range(chart_id.clone(), range.into()));
+            results.push(graphima::redraw(chart_id));
+        })
+    };
+    let config = js_sys::Object::new();
+    Reflect::set(&config, &"onRangeChange".into(), on_range_change.as_ref()).unwrap();
+    let chart_id = graphima::create_main(get_params("#on-range-change"), config.into()).unwrap();
+    sleep(200).await;
+    let range = js_sys::Array::of2(&1.0.into(), &3.0.into());
+    graphima::set_visible_range(chart_id.clone(), range.into()).unwrap();
+    sleep(1000).await;
+    assert!(!results.borrow().is_empty());
+    assert!(results.borrow().iter().all(|result| result.is_ok()));
+    graphima::destroy_main(chart_id).unwrap();
+    container.remove();
+}
+
+/// renderTooltip runs while the chart draws, so its calls are deferred and
+/// their failures come as warnings.
+#[wasm_bindgen_test]
+async fn test_deferred_call_failure_is_a_warning() {
+    let container = create_container("deferred");
+    let chart_id = Rc::new(RefCell::new(JsValue::UNDEFINED));
+    let results: Rc<RefCell<Vec<Result<(), String>>>> = Rc::new(RefCell::new(Vec::new()));
+    let render_tooltip = {
+        let chart_id = Rc::clone(&chart_id);
+        let results = Rc::clone(&results);
+        Closure::<dyn FnMut(JsValue) -> JsValue>::new(move |_payload: JsValue| {
+            let chart_id = chart_id.borrow().clone();
+            results.borrow_mut().push(graphima::set_data_set_visible(
+                chart_id.clone(),
+                "Missing".into(),
+                false.into(),
+            ));
+            let range = js_sys::Array::of2(&2.0.into(), &3.0.into());
+            results
+                .borrow_mut()
+                .push(graphima::set_visible_range(chart_id.clone(), range.into()));
+            results.borrow_mut().push(graphima::redraw(chart_id));
+            JsValue::from_str("<b>tooltip</b>")
+        })
+    };
+    let config = js_sys::Object::new();
+    Reflect::set(&config, &"renderTooltip".into(), render_tooltip.as_ref()).unwrap();
+    chart_id.replace(graphima::create_main(get_params("#deferred"), config.into()).unwrap());
+    sleep(200).await;
+    graphima::set_hover_x(chart_id.borrow().clone(), 2.5.into(), None).unwrap();
+    sleep(500).await;
+    assert!(!results.borrow().is_empty());
+    assert!(results.borrow().iter().all(|result| result.is_ok()));
+    let warnings: js_sys::Array = graphima::get_warnings(chart_id.borrow().clone())
+        .unwrap()
+        .dyn_into()
+        .unwrap();
+    assert!(warnings.iter().any(|warning| {
+        Reflect::get(&warning, &"code".into()).unwrap() == "DEFERRED_CALL_FAILED"
+    }));
+    graphima::destroy_main(chart_id.borrow().clone()).unwrap();
+    container.remove();
+}
+
+/// Compares JSON values regardless of the order of object keys.
+fn json_equals(a: &JsValue, b: &JsValue) -> bool {
+    if js_sys::Array::is_array(a) && js_sys::Array::is_array(b) {
+        let (a, b): (js_sys::Array, js_sys::Array) = (a.clone().into(), b.clone().into());
+        return a.length() == b.length()
+            && a.iter().zip(b.iter()).all(|(a, b)| json_equals(&a, &b));
+    }
+    if a.is_object() && b.is_object() {
+        let keys = |value: &JsValue| -> Vec<String> {
+            let mut keys: Vec<String> =
+                js_sys::Object::keys(value.unchecked_ref::<js_sys::Object>())
+                    .iter()
+                    .map(|key| key.as_string().unwrap())
+                    .collect();
+            keys.sort();
+            keys
+        };
+        return keys(a) == keys(b)
+            && keys(a).iter().all(|key| {
+                json_equals(
+                    &Reflect::get(a, &key.into()).unwrap(),
+                    &Reflect::get(b, &key.into()).unwrap(),
+                )
+            });
+    }
+    a == b
+}
+
+/// Exports a zoomed chart with a hidden data set, recreates it from the
+/// definition and exports it again.
+#[wasm_bindgen_test]
+async fn test_definition_round_trip() {
+    let exported_container = create_container("exported");
+    let imported_container = create_container("imported");
+    let config = js_sys::JSON::parse(r#"{"linkGroup": "definition", "alignY": true}"#).unwrap();
+    let chart_id = graphima::create_main(get_params("#exported"), config).unwrap();
+    sleep(200).await;
+    let range = js_sys::Array::of2(&2.0.into(), &3.0.into());
+    graphima::set_visible_range(chart_id.clone(), range.into()).unwrap();
+    graphima::set_data_set_visible(chart_id.clone(), "Bar".into(), false.into()).unwrap();
+    sleep(1000).await;
+    let exported = graphima::export_definition(chart_id.clone()).unwrap();
+
+    let parsed = js_sys::JSON::parse(&exported).unwrap();
+    let get = |path: &[&str]| {
+        path.iter().fold(parsed.clone(), |value, key| {
+            Reflect::get(&value, &(*key).into()).unwrap()
+        })
+    };
+    assert_eq!(get(&["config", "alignY"]), true);
+    assert!(json_equals(
+        &get(&["state", "window"]),
+        &js_sys::JSON::parse("[2, 3]").unwrap()
+    ));
+    assert!(json_equals(
+        &get(&["state", "hiddenDataSets"]),
+        &js_sys::JSON::parse(r#"["Bar"]"#).unwrap()
+    ));
+    assert!(get(&["params", "selector"]).is_undefined());
+
+    let imported_id =
+        graphima::create_from_definition("#imported".into(), exported.clone()).unwrap();
+    sleep(1000).await;
+    let reexported = graphima::export_definition(imported_id.clone()).unwrap();
+    assert!(json_equals(
+        &parsed,
+        &js_sys::JSON::parse(&reexported).unwrap()
+    ));
+
+    graphima::destroy_main(chart_id).unwrap();
+    graphima::destroy_main(imported_id).unwrap();
+    exported_container.remove();
+    imported_container.remove();
+}
+
+#[wasm_bindgen_test]
+fn test_definition_errors() {
+    let container = create_container("invalid-definition");
+    for (definition, error) in [
+        (
+            r#"{"version":2,"params":{}}"#,
+            "unsupported definition version: 2",
+        ),
+        (r#"{"params":{}}"#, "definition.version is missing"),
+        (
+            r#"{"version":1,"params":[]}"#,
+            "not an object: definition.params",
+        ),
+        (
+            r#"{"version":1,"params":{},"state":{"previewHeight":-1}}"#,
+            "should be a non-negative number: state.previewHeight",
+        ),
+        (
+            r#"{"version":1,"params":{},"state":{"hiddenDataSets":[1]}}"#,
+            "not a string: state.hiddenDataSets[0]",
+        ),
+        (
+            r#"{"version":1,"params":{},"state":{"window":[1]}}"#,
+            "should be [from, to]: state.window",
+        ),
+    ] {
+        let result =
+            graphima::create_from_definition("#invalid-definition".into(), definition.to_string());
+        assert_eq!(
+            JsValue::from(result.unwrap_err()).as_string().unwrap(),
+            error
+        );
+    }
+    let result = graphima::create_from_definition("#invalid-definition".into(), "{".to_string());
+    let error = JsValue::from(result.unwrap_err()).as_string().unwrap();
+    assert!(
+        error.starts_with("definition is not valid JSON: "),
+        "{}",
+        error
+    );
+    container.remove();
+}
+
+/// Overrides window.devicePixelRatio and has the charts detect it again, as
+/// they do on an orientation change.
+fn set_device_pixel_ratio(ratio: f64) {
+    let window = web_sys::window().unwrap();
+    let descriptor = js_sys::Object::new();
+    Reflect::set(&descriptor, &"value".into(), &ratio.into()).unwrap();
+    Reflect::set(&descriptor, &"configurable".into(), &true.into()).unwrap();
+    js_sys::Object::define_property(&window, &"devicePixelRatio".into(), &descriptor);
+    let event_class: js_sys::Function = Reflect::get(&window, &"Event".into()).unwrap().into();
+    let screen = Reflect::get(&window, &"screen".into()).unwrap();
+    let orientation = Reflect::get(&screen, &"orientation".into()).unwrap();
+    for (target, event_name) in [
+        (orientation, "change"),
+        (window.into(), "orientationchange"),
+    ] {
+        if !target.is_object() {
+            continue;
+        }
+        let event =
+            Reflect::construct(&event_class, &js_sys::Array::of1(&event_name.into())).unwrap();
+        let dispatch: js_sys::Function = Reflect::get(&target, &"dispatchEvent".into())
+            .unwrap()
+            .into();
+        dispatch.call1(&target, &event).unwrap();
+    }
+}
+
+/// Coverage of a pixel by the red lines of `test_hairlines_are_snapped`,
+/// whether drawn over a transparent or a white background.
+fn get_red_coverage(pixel: &[u8]) -> Option<u8> {
+    match *pixel {
+        [255, 0, 0, alpha] if alpha > 0 => Some(alpha),
+        [255, g, b, 255] if g == b && g < 255 => Some(255 - g),
+        _ => None,
+    }
+}
+
+/// Coverages of the runs of consecutive covered pixels with uncolored
+/// pixels on both sides, which leaves out the parts of lines cut by a series.
+fn get_red_runs<'a>(pixels: impl Iterator<Item = &'a [u8]>) -> Vec<Vec<u8>> {
+    let is_uncolored =
+        |pixel: &[u8]| pixel[3] == 0 || (pixel[0] == pixel[1] && pixel[1] == pixel[2]);
+    let mut runs = Vec::new();
+    let mut run = Vec::new();
+    let mut after_uncolored = true;
+    for pixel in pixels {
+        match get_red_coverage(pixel) {
+            Some(coverage) => run.push(coverage),
+            None => {
+                if !run.is_empty() && after_uncolored && is_uncolored(pixel) {
+                    runs.push(std::mem::take(&mut run));
+                }
+                run.clear();
+                after_uncolored = is_uncolored(pixel);
+            }
+        }
+    }
+    if !run.is_empty() && after_uncolored {
+        runs.push(run);
+    }
+    runs
+}
+
+/// Draws red gridlines and zero line at several device pixel ratios: lines
+/// crossing the middle of a canvas have to cover whole pixels, i.e. be as
+/// wide as the snapped line width and evenly covered, rather than blur into
+/// the neighbouring column or row.
+#[wasm_bindgen_test]
+async fn test_hairlines_are_snapped() {
+    let initial_ratio = web_sys::window().unwrap().device_pixel_ratio();
+    let container = create_container("hairlines");
+    let params = get_params("#hairlines");
+    let data_sets = Reflect::get(&params, &"dataSets".into()).unwrap();
+    let first_data_set = Reflect::get(&data_sets, &0.into()).unwrap();
+    let values = js_sys::JSON::parse("[10, -30, 20, 40]").unwrap();
+    Reflect::set(&first_data_set, &"values".into(), &values).unwrap();
+    let config = js_sys::JSON::parse(
+        r#"{
+            "colorGrid": [255, 0, 0],
+            "zeroLineShow": true,
+            "zeroLineWidth": 1,
+            "colorZeroLine": [255, 0, 0, 1],
+            "colorPalette": [[0, 160, 0], [0, 100, 0]]
+        }"#,
+    )
+    .unwrap();
+    let chart_id = graphima::create_main(params, config).unwrap();
+    for ratio in [1.0, 1.25, 2.0] {
+        set_device_pixel_ratio(ratio);
+        sleep(1000).await;
+        let line_width = f64::round(ratio).max(1.0) as usize;
+        let canvases = container.query_selector_all("canvas").unwrap();
+        let mut line_count = 0;
+        let mut widths = Vec::new();
+        for (index, pixels) in get_pixels(&container).iter().enumerate() {
+            let canvas: web_sys::HtmlCanvasElement =
+                canvases.get(index as u32).unwrap().dyn_into().unwrap();
+            let (width, height) = (canvas.width() as usize, canvas.height() as usize);
+            widths.push(width);
+            let pixel = |x: usize, y: usize| &pixels[(y * width + x) * 4..][..4];
+            // rows cross the vertical lines, columns the horizontal ones;
+            // long runs are lines along the row or column
+            let rows = (height * 2 / 5..height * 3 / 5)
+                .map(|y| get_red_runs((0..width).map(|x| pixel(x, y))));
+            let columns = (width * 2 / 5..width * 3 / 5)
+                .map(|x| get_red_runs((0..height).map(|y| pixel(x, y))));
+            for run in rows.chain(columns).flatten().filter(|run| run.len() < 8) {
+                assert_eq!(run.len(), line_width, "{:?} at {}", run, ratio);
+                assert!(
+                    run.iter().all(|coverage| *coverage == run[0]),
+                    "{:?} at {}",
+                    run,
+                    ratio
+                );
+                line_count += 1;
+            }
+        }
+        assert!(
+            widths.contains(&((400.0 * ratio).round() as usize)),
+            "{:?}",
+            widths
+        );
+        assert!(line_count > 0, "no lines at {}", ratio);
+    }
+    set_device_pixel_ratio(initial_ratio);
+    graphima::destroy_main(chart_id).unwrap();
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+fn test_container_already_used() {
+    let container = create_container("reused");
+    let chart_id = graphima::create_main(get_params("#reused"), JsValue::UNDEFINED).unwrap();
+    let error = JsValue::from(
+        graphima::create_main(get_params("#reused"), JsValue::UNDEFINED).unwrap_err(),
+    );
+    assert_eq!(
+        Reflect::get(&error, &"code".into()).unwrap(),
+        "CONTAINER_ALREADY_USED"
+    );
+    assert_eq!(Reflect::get(&error, &"chartId".into()).unwrap(), chart_id);
+    assert_eq!(container.child_element_count(), 1);
+
+    let config = js_sys::JSON::parse(r#"{"containerReuse": "replace"}"#).unwrap();
+    let replacing_id = graphima::create_main(get_params("#reused"), config).unwrap();
+    assert_eq!(container.child_element_count(), 1);
+    graphima::destroy_main(replacing_id).unwrap();
+    container.remove();
+}
+
+/// Warnings are kept for getWarnings either way; returnWarnings only makes
+/// createMain return the ones of the creation.
+#[wasm_bindgen_test]
+fn test_warnings_are_kept() {
+    let container = create_container("warnings");
+    let get_codes = |warnings: JsValue| -> Vec<String> {
+        warnings
+            .dyn_into::<js_sys::Array>()
+            .unwrap()
+            .iter()
+            .map(|warning| {
+                Reflect::get(&warning, &"code".into())
+                    .unwrap()
+                    .as_string()
+                    .unwrap()
+            })
+            .collect()
+    };
+    let get_params_out_of_range = || {
+        let params = get_params("#warnings");
+        let range = js_sys::Array::of2(&0.0.into(), &2.0.into());
+        Reflect::set(&params, &"initialRange".into(), &range).unwrap();
+        params
+    };
+
+    let chart_id = graphima::create_main(get_params_out_of_range(), JsValue::UNDEFINED).unwrap();
+    let warnings = graphima::get_warnings(chart_id.clone()).unwrap();
+    assert_eq!(get_codes(warnings), vec!["INITIAL_RANGE_CLAMPED"]);
+    let warnings = graphima::get_warnings(chart_id.clone()).unwrap();
+    assert_eq!(get_codes(warnings), Vec::<String>::new());
+    graphima::destroy_main(chart_id).unwrap();
+
+    let config = js_sys::JSON::parse(r#"{"returnWarnings": true}"#).unwrap();
+    let result = graphima::create_main(get_params_out_of_range(), config).unwrap();
+    let warnings = Reflect::get(&result, &"warnings".into()).unwrap();
+    assert_eq!(get_codes(warnings), vec!["INITIAL_RANGE_CLAMPED"]);
+    let chart_id = Reflect::get(&result, &"chartId".into()).unwrap();
+    let warnings = graphima::get_warnings(chart_id.clone()).unwrap();
+    assert_eq!(get_codes(warnings), Vec::<String>::new());
+    graphima::destroy_main(chart_id).unwrap();
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn test_callback_failure_is_a_warning() {
+    let container = create_container("failing-callback");
+    let on_hover = js_sys::Function::new_with_args("detail", "throw new Error('no hover')");
+    let config = js_sys::Object::new();
+    Reflect::set(&config, &"onHover".into(), &on_hover).unwrap();
+    let chart_id = graphima::create_main(get_params("#failing-callback"), config.into()).unwrap();
+    sleep(200).await;
+    graphima::set_hover_x(chart_id.clone(), 2.5.into(), None).unwrap();
+    sleep(500).await;
+    let warnings: js_sys::Array = graphima::get_warnings(chart_id.clone())
+        .unwrap()
+        .dyn_into()
+        .unwrap();
+    let warning = warnings
+        .iter()
+        .find(|warning| Reflect::get(warning, &"code".into()).unwrap() == "CALLBACK_FAILED")
+        .unwrap();
+    assert_eq!(Reflect::get(&warning, &"path".into()).unwrap(), "onHover");
+    assert_eq!(
+        Reflect::get(&warning, &"message".into()).unwrap(),
+        "onHover failed: no hover"
+    );
+    let error = Reflect::get(&warning, &"error".into()).unwrap();
+    assert!(error.is_instance_of::<js_sys::Error>());
+    graphima::destroy_main(chart_id).unwrap();
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn test_describe_chart_failure_is_a_warning() {
+    let container = create_container("failing-description");
+    let describe_chart = js_sys::Function::new_with_args("payload", "throw 'no summary'");
+    let config = js_sys::Object::new();
+    Reflect::set(&config, &"describeChart".into(), &describe_chart).unwrap();
+    let chart_id =
+        graphima::create_main(get_params("#failing-description"), config.into()).unwrap();
+    sleep(500).await;
+    let warnings: js_sys::Array = graphima::get_warnings(chart_id.clone())
+        .unwrap()
+        .dyn_into()
+        .unwrap();
+    let warning = warnings.get(0);
+    assert_eq!(
+        Reflect::get(&warning, &"code".into()).unwrap(),
+        "CALLBACK_FAILED"
+    );
+    assert_eq!(
+        Reflect::get(&warning, &"path".into()).unwrap(),
+        "describeChart"
+    );
+    assert_eq!(
+        Reflect::get(&warning, &"error".into()).unwrap(),
+        "no summary"
+    );
+    // the default summary stays
+    let label = container
+        .first_element_child()
+        .unwrap()
+        .get_attribute("aria-label")
+        .unwrap();
+    assert!(!label.is_empty());
+    graphima::destroy_main(chart_id).unwrap();
+    container.remove();
+}
+
+/// Coord types are detected with js Date; an empty cell is a gap that
+/// survives the definition.
+#[wasm_bindgen_test]
+fn test_params_from_csv() {
+    let from_csv = |csv: &str| graphima::params_from_csv(csv.to_string(), JsValue::UNDEFINED);
+    let get_coord_type = |csv: &str| {
+        Reflect::get(&from_csv(csv).unwrap(), &"coordType".into())
+            .unwrap()
+            .as_string()
+            .unwrap()
+    };
+    assert_eq!(get_coord_type("x,a\n1,1\n2.5,2"), "number");
+    assert_eq!(get_coord_type("x,a\n2020-01-01,1\n2020-01-02,2"), "date");
+    assert_eq!(
+        get_coord_type("x,a\n2020-01-01,1\n2020-01-01T12:30:00Z,2"),
+        "datetime"
+    );
+    assert_eq!(
+        from_csv("x,a\n1,1\nfoo,2").unwrap_err(),
+        "line 3: neither a number nor a date: 'foo'"
+    );
+
+    let container = create_container("csv");
+    let params = from_csv("x,a,b\n2020-01-01,1,\n2020-01-02,,2\n2020-01-03,3,3").unwrap();
+    let data_sets = Reflect::get(&params, &"dataSets".into()).unwrap();
+    let data_set = Reflect::get(&data_sets, &0.into()).unwrap();
+    assert!(json_equals(
+        &Reflect::get(&data_set, &"coords".into()).unwrap(),
+        &js_sys::JSON::parse("[1577836800000, 1577923200000, 1578009600000]").unwrap()
+    ));
+    let values = js_sys::JSON::parse("[1, null, 3]").unwrap();
+    assert!(json_equals(
+        &Reflect::get(&data_set, &"values".into()).unwrap(),
+        &values
+    ));
+    Reflect::set(&params, &"selector".into(), &"#csv".into()).unwrap();
+    let chart_id = graphima::create_main(params, JsValue::UNDEFINED).unwrap();
+    let definition = graphima::export_definition(chart_id.clone()).unwrap();
+    let definition = js_sys::JSON::parse(definition.as_str()).unwrap();
+    let params = Reflect::get(&definition, &"params".into()).unwrap();
+    let data_sets = Reflect::get(&params, &"dataSets".into()).unwrap();
+    let data_set = Reflect::get(&data_sets, &0.into()).unwrap();
+    assert!(json_equals(
+        &Reflect::get(&data_set, &"values".into()).unwrap(),
+        &values
+    ));
+    graphima::destroy_main(chart_id).unwrap();
+    container.remove();
+}
+
+/// The line breaks at a null value: the middle column, between the points
+/// around it, is green only without the gap.
+#[wasm_bindgen_test]
+async fn test_gaps_break_the_line() {
+    let mut green_counts = Vec::new();
+    for values in ["[10, 20, 20, 20, 10]", "[10, 20, null, 20, 10]"] {
+        let container = create_container("gaps");
+        let params = get_params("#gaps");
+        let data_set = js_sys::Object::new();
+        Reflect::set(&data_set, &"name".into(), &"Foo".into()).unwrap();
+        let coords = js_sys::JSON::parse("[1, 2, 3, 4, 5]").unwrap();
+        Reflect::set(&data_set, &"coords".into(), &coords).unwrap();
+        let values = js_sys::JSON::parse(values).unwrap();
+        Reflect::set(&data_set, &"values".into(), &values).unwrap();
+        let data_sets = js_sys::Array::of1(&data_set);
+        Reflect::set(&params, &"dataSets".into(), &data_sets).unwrap();
+        let config = js_sys::JSON::parse(r#"{"colorPalette": [[0, 160, 0]]}"#).unwrap();
+        let chart_id = graphima::create_main(params, config).unwrap();
+        sleep(500).await;
+        let canvases = container.query_selector_all("canvas").unwrap();
+        let mut green_count = 0;
+        for (index, pixels) in get_pixels(&container).iter().enumerate() {
+            let canvas: web_sys::HtmlCanvasElement =
+                canvases.get(index as u32).unwrap().dyn_into().unwrap();
+            let (width, height) = (canvas.width() as usize, canvas.height() as usize);
+            green_count += (0..height)
+                .map(|y| &pixels[(y * width + width / 2) * 4..][..4])
+                .filter(|p| p[1] > 100 && p[0] < 80 && p[2] < 80 && p[3] > 0)
+                .count();
+        }
+        green_counts.push(green_count);
+        graphima::destroy_main(chart_id).unwrap();
+        container.remove();
+    }
+    assert!(green_counts[0] > 0, "{:?}", green_counts);
+    assert_eq!(green_counts[1], 0, "{:?}", green_counts);
+}